@@ -0,0 +1,68 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_derive::Deserialize;
+use serde_json::{json};
+use std::ops::Deref;
+use chrono::{DateTime, NaiveDate, Utc};
+use log::{error};
+
+mod models;
+use crate::models::{Activity, deadline_offset};
+
+#[derive(Debug, Deserialize)]
+struct ActivityPayload {
+    name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    location: String,
+    date: NaiveDate,
+    capacity: u32,
+    #[serde(default)]
+    cost_cents: u32,
+    signup_deadline: DateTime<Utc>
+}
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let body = request.body().deref();
+    let payload : ActivityPayload = match serde_json::from_slice(body) {
+        Ok(payload) => payload,
+        Err(error) => return Ok(respond(400, json!({"message": format!("Invalid activity: {}", error)}).to_string()))
+    };
+
+    let activity = match Activity::new(payload.name, payload.date, payload.capacity, payload.cost_cents, payload.signup_deadline, deadline_offset()) {
+        Ok(activity) => Activity {
+            description: payload.description.trim().to_string(),
+            location: payload.location.trim().to_string(),
+            ..activity
+        },
+        Err(message) => return Ok(respond(400, json!({"message": message}).to_string()))
+    };
+
+    Ok(match activity.create() {
+        Ok(activity) => respond(200, json!(activity).to_string()),
+        Err(err) => {
+            error!("Could not create activity {:?}: {}", activity, err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
@@ -0,0 +1,80 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::{json};
+use log::{error};
+use uuid::Uuid;
+
+mod models;
+use crate::models::{Household, ActivitySignup, ActivityError};
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// Signs one guest up for an activity from their household's RSVP link.
+/// When it's full they join the waitlist instead.
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let uuid : Uuid = match Uuid::parse_str(path_parameters.get("id").unwrap()) {
+        Ok(uuid) => uuid,
+        Err(_error) => return Ok(respond(404, json!({"message": "No household was found with that id"}).to_string()))
+    };
+    let activity_id : Uuid = match Uuid::parse_str(path_parameters.get("activity_id").unwrap()) {
+        Ok(activity_id) => activity_id,
+        Err(_error) => return Ok(respond(404, json!({"message": ActivityError::ActivityNotFound.to_string()}).to_string()))
+    };
+    let rsvp_id = path_parameters.get("rsvp_id").unwrap();
+
+    let rsvp = match Household::get(uuid) {
+        Ok(rsvps) => match rsvps.into_iter().find(|rsvp| rsvp.id == rsvp_id) {
+            Some(rsvp) => rsvp,
+            None => return Ok(respond(404, json!({"message": "No guest in this household was found with that id"}).to_string()))
+        },
+        Err(err) => {
+            error!("Could not look up household {}: {}", uuid, err);
+            return Ok(respond(500, json!({"message": "Something went wrong!"}).to_string()))
+        }
+    };
+
+    Ok(match ActivitySignup::join(activity_id, &rsvp) {
+        Ok(signup) => respond(200, json!(signup).to_string()),
+        Err(ActivityError::ActivityNotFound) => respond(404, json!({"message": ActivityError::ActivityNotFound.to_string()}).to_string()),
+        Err(ActivityError::Closed(activity)) => respond(403, json!({"message": ActivityError::Closed(activity).to_string()}).to_string()),
+        Err(error @ ActivityError::NotAttending(_)) => respond(422, json!({"message": error.to_string()}).to_string()),
+        Err(err) => {
+            error!("Could not sign {} up for activity {}: {:?}", rsvp.id, activity_id, err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lambda_http::Body;
+
+    #[test]
+    #[ignore]
+    fn activity_join_handler_handles() {
+
+        let mut request = Request::new(Body::default());
+        *request.uri_mut() = "https://api.slswedding.com/household/3eb28445-7698-4a00-b071-49da8eaac944/activities/5ba2b0fc-5fa1-4bd6-9d1c-2b6f3d3c8a57/9c1e7a3e-3f0e-4a4e-8f55-0d5e8b1f2a61".parse().unwrap();
+
+        handler(request, Context::default()).expect("Expected an OK response");
+    }
+}
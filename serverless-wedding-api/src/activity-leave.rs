@@ -0,0 +1,64 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::{json};
+use log::{error};
+use uuid::Uuid;
+
+mod models;
+use crate::models::{Household, ActivitySignup, ActivityError};
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// Takes one guest off an activity. Their spot goes to whoever has been on
+/// the waitlist longest.
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let uuid : Uuid = match Uuid::parse_str(path_parameters.get("id").unwrap()) {
+        Ok(uuid) => uuid,
+        Err(_error) => return Ok(respond(404, json!({"message": "No household was found with that id"}).to_string()))
+    };
+    let activity_id : Uuid = match Uuid::parse_str(path_parameters.get("activity_id").unwrap()) {
+        Ok(activity_id) => activity_id,
+        Err(_error) => return Ok(respond(404, json!({"message": ActivityError::ActivityNotFound.to_string()}).to_string()))
+    };
+    let rsvp_id = path_parameters.get("rsvp_id").unwrap();
+
+    match Household::get(uuid) {
+        Ok(ref rsvps) if rsvps.iter().any(|rsvp| rsvp.id == rsvp_id) => {},
+        Ok(_rsvps) => {
+            return Ok(respond(404, json!({"message": "No guest in this household was found with that id"}).to_string()))
+        },
+        Err(err) => {
+            error!("Could not look up household {}: {}", uuid, err);
+            return Ok(respond(500, json!({"message": "Something went wrong!"}).to_string()))
+        }
+    }
+
+    Ok(match ActivitySignup::leave(activity_id, rsvp_id) {
+        Ok((Some(signup), _promoted)) => respond(200, json!(signup).to_string()),
+        Ok((None, _promoted)) => respond(404, json!({"message": "That guest isn't signed up for this activity"}).to_string()),
+        Err(ActivityError::ActivityNotFound) => respond(404, json!({"message": ActivityError::ActivityNotFound.to_string()}).to_string()),
+        Err(ActivityError::Closed(activity)) => respond(403, json!({"message": ActivityError::Closed(activity).to_string()}).to_string()),
+        Err(err) => {
+            error!("Could not take {} off activity {}: {:?}", rsvp_id, activity_id, err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
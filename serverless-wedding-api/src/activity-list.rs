@@ -0,0 +1,47 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::{json};
+use chrono::Utc;
+use log::{error};
+
+mod models;
+use crate::models::{Activity, ActivityStatus};
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+/// Every activity by date, with the spots left and whether sign ups have
+/// closed.
+fn handler(
+    _: Request,
+    _: Context,
+) -> Result<impl IntoResponse, HandlerError> {
+    let now = Utc::now();
+
+    Ok(match Activity::all() {
+        Ok(activities) => {
+            let statuses : Vec<ActivityStatus> = activities.into_iter()
+                .map(|activity| activity.status(now))
+                .collect();
+
+            http::Response::builder()
+                .header("Access-Control-Allow-Origin", "*")
+                .status(200)
+                .body(json!(statuses).to_string())
+                .unwrap()
+        },
+        Err(err) => {
+            error!("Could not list activities: {}", err);
+            http::Response::builder()
+                .header("Access-Control-Allow-Origin", "*")
+                .status(500)
+                .body(json!({"message": "Something went wrong!"}).to_string())
+                .unwrap()
+        }
+    })
+}
@@ -0,0 +1,46 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::json;
+use log::{error};
+use uuid::Uuid;
+
+mod models;
+use crate::models::{Roster, ActivityError};
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// The guests confirmed for an activity and its waitlist, each in sign up
+/// order, with what the group owes.
+fn handler(
+    request: Request,
+    _: Context,
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let activity_id : Uuid = match Uuid::parse_str(path_parameters.get("id").unwrap()) {
+        Ok(activity_id) => activity_id,
+        Err(_error) => return Ok(respond(404, json!({"message": ActivityError::ActivityNotFound.to_string()}).to_string()))
+    };
+
+    Ok(match Roster::get(activity_id) {
+        Ok(roster) => respond(200, json!(roster).to_string()),
+        Err(ActivityError::ActivityNotFound) => respond(404, json!({"message": ActivityError::ActivityNotFound.to_string()}).to_string()),
+        Err(err) => {
+            error!("Could not build the roster for activity {}: {}", activity_id, err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
@@ -0,0 +1,40 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::json;
+use log::{error};
+
+mod models;
+use crate::models::ArrivalsReport;
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+/// When attending guests arrive and leave, by day and airport, and who
+/// hasn't told us yet.
+fn handler(
+    _request: Request,
+    _: Context,
+) -> Result<impl IntoResponse, HandlerError> {
+    Ok(match ArrivalsReport::get() {
+        Ok(report) => {
+            http::Response::builder()
+                .header("Access-Control-Allow-Origin", "*")
+                .status(200)
+                .body(json!(report).to_string())
+                .unwrap()
+        },
+        Err(err) => {
+            error!("Could not build the arrivals report: {}", err);
+            http::Response::builder()
+                .header("Access-Control-Allow-Origin", "*")
+                .status(500)
+                .body(json!({"message": "Something went wrong!"}).to_string())
+                .unwrap()
+        }
+    })
+}
@@ -0,0 +1,82 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt, Body};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::json;
+use uuid::Uuid;
+use log::{error};
+
+mod models;
+mod pdf;
+mod cards;
+use crate::cards::CardKind;
+use crate::pdf::PaperSize;
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn error_response(status: u16, message: &str) -> http::Response<Body> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(Body::from(json!({"message": message}).to_string()))
+        .unwrap()
+}
+
+/// Escort cards, place cards or the servers' table sheets for an event, as
+/// a PDF ready to print.
+fn handler(
+    request: Request,
+    _: Context,
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let event_id : Uuid = match Uuid::parse_str(path_parameters.get("id").unwrap()) {
+        Ok(event_id) => event_id,
+        Err(_error) => return Ok(error_response(404, "No event was found with that id"))
+    };
+
+    let kind : CardKind = match path_parameters.get("kind").unwrap().parse() {
+        Ok(kind) => kind,
+        Err(message) => return Ok(error_response(404, &message))
+    };
+
+    let paper : PaperSize = match request.query_string_parameters().get("paper") {
+        Some(paper) => match paper.parse() {
+            Ok(paper) => paper,
+            Err(message) => return Ok(error_response(400, &message))
+        },
+        None => PaperSize::LETTER
+    };
+
+    Ok(match cards::for_event(event_id, kind, paper) {
+        Ok(bytes) => http::Response::builder()
+            .header("Access-Control-Allow-Origin", "*")
+            .header("Content-Type", "application/pdf")
+            .header("Content-Disposition", format!("inline; filename=\"{}\"", kind.file_name()))
+            .status(200)
+            .body(Body::from(bytes))
+            .unwrap(),
+        Err(err) => {
+            error!("Could not print {} for {}: {}", kind.file_name(), event_id, err);
+            error_response(500, "Something went wrong!")
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[ignore]
+    fn cards_handler_handles() {
+
+        let mut request = Request::new(Body::default());
+        *request.uri_mut() = "https://api.slswedding.com/admin/events/3eb28445-7698-4a00-b071-49da8eaac944/cards/place?paper=a4".parse().unwrap();
+
+        handler(request, Context::default()).expect("Expected an OK response");
+    }
+}
@@ -0,0 +1,291 @@
+use std::error::Error;
+use std::str::FromStr;
+use printpdf::{PdfLayerReference, Mm, Color, Rgb};
+use uuid::Uuid;
+
+use crate::models::{SeatingChart, Menu};
+use crate::pdf::{
+    PaperSize,
+    Fonts,
+    text_width,
+    fit,
+    centered,
+    rectangle,
+    new_document,
+    next_page,
+    finish
+};
+
+/// Business card sized, which most escort and place card stock is
+const CARD_WIDTH : f64 = 89.0;
+const CARD_HEIGHT : f64 = 51.0;
+const MARGIN : f64 = 10.0;
+const GAP : f64 = 4.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CardKind {
+    /// Guest name and table, for the table at the entrance
+    Escort,
+    /// Guest name and meal, set at each seat
+    Place,
+    /// One sheet per table listing each seat's meal and allergies, for servers
+    Tables
+}
+
+impl CardKind {
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            CardKind::Escort => "escort-cards.pdf",
+            CardKind::Place => "place-cards.pdf",
+            CardKind::Tables => "table-sheets.pdf"
+        }
+    }
+}
+
+impl FromStr for CardKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<CardKind, String> {
+        match value.to_lowercase().as_str() {
+            "escort" => Ok(CardKind::Escort),
+            "place" => Ok(CardKind::Place),
+            "tables" => Ok(CardKind::Tables),
+            other => Err(format!("Unsupported card kind: {}. Use escort, place or tables", other))
+        }
+    }
+}
+
+/// A seated guest, with their meal spelled out from the menu.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrintGuest {
+    pub name: String,
+    pub table: String,
+    pub meal_code: String,
+    pub meal_label: String,
+    pub dietary_restrictions: String,
+    pub allergies: String,
+    pub severe_allergy: bool
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrintTable {
+    pub name: String,
+    pub capacity: u32,
+    pub guests: Vec<PrintGuest>
+}
+
+/// The chart's tables as they'll be printed. Guests who have since declined
+/// are left off.
+pub fn from_chart(chart: &SeatingChart, menu: &Menu) -> Vec<PrintTable> {
+    chart.tables.iter()
+        .map(|view| PrintTable {
+            name: view.table.name.clone(),
+            capacity: view.table.capacity,
+            guests: view.guests.iter()
+                .filter(|guest| guest.attending)
+                .map(|guest| PrintGuest {
+                    name: guest.name.clone(),
+                    table: view.table.name.clone(),
+                    meal_code: guest.food_preference.clone(),
+                    meal_label: menu.meals().into_iter()
+                        .find(|item| item.code == guest.food_preference)
+                        .map_or(guest.food_preference.clone(), |item| item.label.clone()),
+                    dietary_restrictions: match guest.dietary_restrictions_other.as_str() {
+                        "" => guest.dietary_restrictions.clone(),
+                        other => format!("{}: {}", guest.dietary_restrictions, other)
+                    },
+                    allergies: guest.allergy_summary.clone(),
+                    severe_allergy: guest.severe_allergy
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Where cards go on a page: the bottom left corner of each, top row first.
+/// Cards bigger than the paper fill one page each.
+fn card_slots(paper: PaperSize) -> (f64, f64, Vec<(f64, f64)>) {
+    let columns = ((paper.width - 2.0 * MARGIN + GAP) / (CARD_WIDTH + GAP)).floor() as usize;
+    let rows = ((paper.height - 2.0 * MARGIN + GAP) / (CARD_HEIGHT + GAP)).floor() as usize;
+
+    if columns == 0 || rows == 0 {
+        return (paper.width, paper.height, vec!((0.0, 0.0)));
+    }
+
+    let mut slots = vec!();
+    for row in 0..rows {
+        for column in 0..columns {
+            slots.push((
+                MARGIN + column as f64 * (CARD_WIDTH + GAP),
+                paper.height - MARGIN - CARD_HEIGHT - row as f64 * (CARD_HEIGHT + GAP)
+            ));
+        }
+    }
+    (CARD_WIDTH, CARD_HEIGHT, slots)
+}
+
+/// Lays out one card per guest, with cut lines, drawing each with `draw`.
+fn cards<F>(title: &str, guests: &[&PrintGuest], paper: PaperSize, draw: F) -> Result<Vec<u8>, String>
+    where F: Fn(&PdfLayerReference, &Fonts, &PrintGuest, f64, f64, f64, f64)
+{
+    let (document, mut layer, fonts) = new_document(title, paper)?;
+    let (width, height, slots) = card_slots(paper);
+
+    for (index, guest) in guests.iter().enumerate() {
+        if index > 0 && index % slots.len() == 0 {
+            layer = next_page(&document, paper);
+        }
+        let (x, y) = slots[index % slots.len()];
+
+        layer.set_outline_color(Color::Rgb(Rgb::new(0.8, 0.8, 0.8, None)));
+        layer.set_outline_thickness(0.25);
+        rectangle(&layer, x, y, width, height);
+        draw(&layer, &fonts, guest, x, y, width, height);
+    }
+
+    finish(document)
+}
+
+fn all_guests(tables: &[PrintTable]) -> Vec<&PrintGuest> {
+    tables.iter().flat_map(|table| table.guests.iter()).collect()
+}
+
+/// Escort cards in alphabetical order, so guests can find their name.
+pub fn escort_cards(tables: &[PrintTable], paper: PaperSize) -> Result<Vec<u8>, String> {
+    let mut guests = all_guests(tables);
+    guests.sort_by_key(|guest| guest.name.to_lowercase());
+
+    cards("Escort Cards", &guests, paper, |layer, fonts, guest, x, y, width, height| {
+        centered(layer, &guest.name, 18.0, x + width / 2.0, y + height * 0.55, width - 8.0, &fonts.bold);
+        centered(layer, &guest.table, 12.0, x + width / 2.0, y + height * 0.25, width - 8.0, &fonts.regular);
+    })
+}
+
+/// Place cards grouped by table, each with the guest's meal code in a box
+/// for the servers.
+pub fn place_cards(tables: &[PrintTable], paper: PaperSize) -> Result<Vec<u8>, String> {
+    let guests = all_guests(tables);
+
+    cards("Place Cards", &guests, paper, |layer, fonts, guest, x, y, width, height| {
+        centered(layer, &guest.name, 20.0, x + width / 2.0, y + height * 0.5, width - 8.0, &fonts.bold);
+        centered(layer, &guest.meal_label, 9.0, x + width / 2.0, y + height * 0.3, width - 8.0, &fonts.regular);
+
+        let badge = fit(&guest.meal_code.to_uppercase(), 8.0, 30.0);
+        let badge_width = text_width(&guest.meal_code.to_uppercase(), badge) + 3.0;
+        layer.set_outline_color(Color::Rgb(Rgb::new(0.0, 0.0, 0.0, None)));
+        rectangle(layer, x + width - badge_width - 4.0, y + 3.0, badge_width, 5.0);
+        layer.use_text(guest.meal_code.to_uppercase(), badge, Mm(x + width - badge_width - 2.5), Mm(y + 4.5), &fonts.bold);
+        if guest.severe_allergy {
+            layer.use_text("ALLERGY", 8.0, Mm(x + 4.0), Mm(y + 4.5), &fonts.bold);
+        }
+    })
+}
+
+/// One sheet per table for the servers, listing each guest's meal and any
+/// allergies, with severe allergies called out.
+pub fn table_sheets(tables: &[PrintTable], paper: PaperSize) -> Result<Vec<u8>, String> {
+    let (document, mut layer, fonts) = new_document("Table Sheets", paper)?;
+    let line_height = 7.0;
+    let width = paper.width - 2.0 * MARGIN;
+
+    for (index, table) in tables.iter().enumerate() {
+        if index > 0 {
+            layer = next_page(&document, paper);
+        }
+
+        let mut y = paper.height - MARGIN - 10.0;
+        layer.use_text(format!("{} - {} of {} seats", table.name, table.guests.len(), table.capacity), 18.0, Mm(MARGIN), Mm(y), &fonts.bold);
+        y -= line_height * 2.0;
+
+        for (seat, guest) in table.guests.iter().enumerate() {
+            if y < MARGIN + line_height {
+                layer = next_page(&document, paper);
+                y = paper.height - MARGIN - 10.0;
+                layer.use_text(format!("{} (continued)", table.name), 14.0, Mm(MARGIN), Mm(y), &fonts.bold);
+                y -= line_height * 2.0;
+            }
+
+            let line = format!("{}. {}  -  {}", seat + 1, guest.name, guest.meal_label);
+            layer.use_text(line.as_str(), fit(&line, 12.0, width), Mm(MARGIN), Mm(y), &fonts.regular);
+            y -= line_height * 0.8;
+
+            let mut notes = vec!();
+            if !guest.dietary_restrictions.eq_ignore_ascii_case("none") {
+                notes.push(format!("Diet: {}", guest.dietary_restrictions));
+            }
+            if !guest.allergies.is_empty() {
+                notes.push(format!("Allergies: {}", guest.allergies));
+            }
+            if !notes.is_empty() {
+                let font = if guest.severe_allergy { &fonts.bold } else { &fonts.regular };
+                let prefix = if guest.severe_allergy { "SEVERE ALLERGY - " } else { "" };
+                let note = format!("{}{}", prefix, notes.join("; "));
+                layer.use_text(note.as_str(), fit(&note, 10.0, width - 6.0), Mm(MARGIN + 6.0), Mm(y), font);
+                y -= line_height * 0.8;
+            }
+            y -= line_height * 0.4;
+        }
+    }
+
+    finish(document)
+}
+
+pub fn render(kind: CardKind, tables: &[PrintTable], paper: PaperSize) -> Result<Vec<u8>, String> {
+    match kind {
+        CardKind::Escort => escort_cards(tables, paper),
+        CardKind::Place => place_cards(tables, paper),
+        CardKind::Tables => table_sheets(tables, paper)
+    }
+}
+
+/// Renders `kind` for an event from its current seating chart.
+pub fn for_event(event_id: Uuid, kind: CardKind, paper: PaperSize) -> Result<Vec<u8>, Box<Error>> {
+    let chart = SeatingChart::get(event_id)?;
+    let menu = Menu::get()?;
+
+    Ok(render(kind, &from_chart(&chart, &menu), paper)?)
+}
+
+
+#[cfg(test)]
+mod cards_tests {
+
+    use super::*;
+
+    fn guest(name: &str, table: &str, severe_allergy: bool) -> PrintGuest {
+        PrintGuest {
+            name: name.to_string(),
+            table: table.to_string(),
+            meal_code: "4course".to_string(),
+            meal_label: "Four-Course Meal".to_string(),
+            dietary_restrictions: "None".to_string(),
+            allergies: if severe_allergy { "shellfish (severe)".to_string() } else { String::new() },
+            severe_allergy
+        }
+    }
+
+    fn tables() -> Vec<PrintTable> {
+        vec!(PrintTable {
+            name: "Table 1".to_string(),
+            capacity: 8,
+            guests: (0..30).map(|i| guest(&format!("Guest {}", i), "Table 1", i % 7 == 0)).collect()
+        })
+    }
+
+    #[test]
+    fn test_card_slots() {
+        let (_width, _height, slots) = card_slots(PaperSize::LETTER);
+        assert_eq!(slots.len(), 8);
+
+        let (width, height, slots) = card_slots(PaperSize { width: 89.0, height: 51.0 });
+        assert_eq!((width, height, slots.len()), (89.0, 51.0, 1));
+    }
+
+    #[test]
+    fn test_render_pdfs() {
+        for kind in &[CardKind::Escort, CardKind::Place, CardKind::Tables] {
+            let bytes = render(*kind, &tables(), PaperSize::A4).unwrap();
+            assert!(bytes.starts_with(b"%PDF"));
+        }
+    }
+}
@@ -0,0 +1,49 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::{json};
+use chrono::Duration;
+use log::{error};
+
+mod models;
+use crate::models::{CarpoolEntry, CarpoolMatches, DEFAULT_WINDOW_MINUTES};
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// A guest's carpool entry and the ride they're matched to so far, if any.
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let rsvp_id = path_parameters.get("id").unwrap();
+
+    let entries = match CarpoolEntry::all() {
+        Ok(entries) => entries,
+        Err(err) => {
+            error!("Could not list carpool entries: {}", err);
+            return Ok(respond(500, json!({"message": "Something went wrong!"}).to_string()))
+        }
+    };
+
+    let entry = match entries.iter().find(|entry| entry.rsvp_id == rsvp_id) {
+        Some(entry) => entry.clone(),
+        None => return Ok(respond(404, json!({"message": "That guest hasn't joined a carpool"}).to_string()))
+    };
+
+    let matches = CarpoolMatches::new(&entries, Duration::minutes(DEFAULT_WINDOW_MINUTES));
+    Ok(respond(200, json!({"entry": entry, "ride": matches.ride_for(rsvp_id)}).to_string()))
+}
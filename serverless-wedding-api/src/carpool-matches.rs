@@ -0,0 +1,48 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::{json};
+use chrono::Duration;
+use log::{error};
+
+mod models;
+use crate::models::{CarpoolMatches, DEFAULT_WINDOW_MINUTES};
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// Every driver with the riders matched to them, and the riders still
+/// without a ride. `?window_minutes` widens or narrows how far apart
+/// arrivals can be.
+fn handler(
+    request: Request,
+    _: Context,
+) -> Result<impl IntoResponse, HandlerError> {
+    let window_minutes = match request.query_string_parameters().get("window_minutes") {
+        Some(minutes) => match minutes.parse::<i64>() {
+            Ok(minutes) if minutes >= 0 => minutes,
+            _ => return Ok(respond(400, json!({"message": "window_minutes must be a whole number of minutes"}).to_string()))
+        },
+        None => DEFAULT_WINDOW_MINUTES
+    };
+
+    Ok(match CarpoolMatches::get(Duration::minutes(window_minutes)) {
+        Ok(matches) => respond(200, json!(matches).to_string()),
+        Err(err) => {
+            error!("Could not match carpools: {}", err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
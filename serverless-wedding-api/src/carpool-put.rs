@@ -0,0 +1,93 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_derive::Deserialize;
+use serde_json::{json};
+use std::ops::Deref;
+use log::{error};
+use uuid::Uuid;
+use rusoto_dynamodb::QueryError;
+
+mod models;
+use crate::models::{RSVP, CarpoolEntry, CarpoolRole};
+
+#[derive(Debug, Deserialize)]
+struct CarpoolPayload {
+    role: CarpoolRole,
+    seats: u32
+}
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// Opts a guest in to carpooling from the airport as a driver with seats to
+/// spare or a rider who needs them. Their arrival comes from their travel
+/// plans, so they need to fill those in first.
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let uuid : Uuid = match Uuid::parse_str(path_parameters.get("id").unwrap()) {
+        Ok(uuid) => uuid,
+        Err(_error) => return Ok(respond(404, json!({"message": "No RSVP was found with that id"}).to_string()))
+    };
+
+    let body = request.body().deref();
+    let payload : CarpoolPayload = match serde_json::from_slice(body) {
+        Ok(payload) => payload,
+        Err(error) => return Ok(respond(400, json!({"message": format!("Invalid carpool: {}", error)}).to_string()))
+    };
+
+    let rsvp = match RSVP::get(uuid) {
+        Ok(rsvp) => rsvp,
+        Err(QueryError::ResourceNotFound(_)) => {
+            return Ok(respond(404, json!({"message": "No RSVP was found with that id"}).to_string()))
+        },
+        Err(err) => {
+            error!("Could not look up RSVP {}: {}", uuid, err);
+            return Ok(respond(500, json!({"message": "Something went wrong!"}).to_string()))
+        }
+    };
+
+    let entry = match CarpoolEntry::new(&rsvp, payload.role, payload.seats) {
+        Ok(entry) => entry,
+        Err(message) => return Ok(respond(400, json!({"message": message}).to_string()))
+    };
+
+    Ok(match entry.save() {
+        Ok(entry) => respond(200, json!(entry).to_string()),
+        Err(err) => {
+            error!("Could not save carpool entry {:?}: {}", entry, err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lambda_http::Body;
+
+    #[test]
+    #[ignore]
+    fn carpool_handler_handles() {
+
+        let mut request = Request::new(Body::from(json!({"role": "driver", "seats": 3}).to_string()));
+        *request.uri_mut() = "https://api.slswedding.com/rsvp/3eb28445-7698-4a00-b071-49da8eaac944/carpool".parse().unwrap();
+
+        handler(request, Context::default()).expect("Expected an OK response");
+    }
+}
@@ -0,0 +1,51 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::json;
+use log::{error};
+
+mod models;
+use crate::models::CatererExport;
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn handler(
+    request: Request,
+    _: Context,
+) -> Result<impl IntoResponse, HandlerError> {
+    let query = request.query_string_parameters();
+    let as_csv = query.get("format") == Some("csv");
+
+    let export = CatererExport::get(query.get("event"));
+    let body = export.and_then(|export| {
+        if as_csv {
+            export.guests_csv()
+        } else {
+            Ok(json!(export).to_string())
+        }
+    });
+
+    Ok(match body {
+        Ok(body) => {
+            http::Response::builder()
+                .header("Access-Control-Allow-Origin", "*")
+                .header("Content-Type", if as_csv { "text/csv" } else { "application/json" })
+                .status(200)
+                .body(body)
+                .unwrap()
+        },
+        Err(err) => {
+            error!("Could not build the caterer export: {}", err);
+            http::Response::builder()
+                .header("Access-Control-Allow-Origin", "*")
+                .status(500)
+                .body(json!({"message": "Something went wrong!"}).to_string())
+                .unwrap()
+        }
+    })
+}
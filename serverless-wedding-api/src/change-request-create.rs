@@ -0,0 +1,100 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_derive::Deserialize;
+use serde_json::{json};
+use std::ops::Deref;
+use chrono::Utc;
+use uuid::Uuid;
+use log::{error};
+
+mod models;
+use crate::models::{RSVP, ChangeRequest, ChangeSet, Menu};
+
+#[derive(Debug, Deserialize)]
+struct ChangeRequestPayload {
+    changes: ChangeSet,
+    #[serde(default)]
+    reason: Option<String>
+}
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let uuid : Uuid = Uuid::parse_str(
+        path_parameters.get("id").unwrap()
+    ).unwrap();
+
+    let body = request.body().deref();
+    let payload : ChangeRequestPayload = match serde_json::from_slice(body) {
+        Ok(payload) => payload,
+        Err(error) => {
+            return Ok(respond(400, json!({"message": format!("Invalid change request: {}", error)}).to_string()))
+        }
+    };
+
+    if payload.changes.is_empty() {
+        return Ok(respond(400, json!({"message": "A change request needs at least one change"}).to_string()));
+    }
+
+    let rsvp = match RSVP::get(uuid) {
+        Ok(rsvp) => rsvp,
+        Err(_error) => return Ok(respond(404, json!({"message": "No RSVP was found with that id"}).to_string()))
+    };
+
+    // Turn away changes that couldn't be applied once approved
+    let menu = match Menu::get() {
+        Ok(menu) => menu,
+        Err(error) => {
+            error!("Could not load the menu: {}", error);
+            return Ok(respond(500, json!({"message": "Something went wrong!"}).to_string()))
+        }
+    };
+    if let Err(error) = payload.changes.check(&rsvp, &menu) {
+        return Ok(respond(400, json!({"message": error.to_string()}).to_string()));
+    }
+
+    Ok(match ChangeRequest::new(&rsvp, payload.changes, payload.reason, Utc::now()).create() {
+        Ok(change_request) => respond(200, json!(change_request).to_string()),
+        Err(_error) => respond(500, json!({"message": "Something went wrong!"}).to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lambda_http::{Body};
+
+    #[test]
+    #[ignore]
+    fn change_request_create_handler_handles() {
+
+        let payload = r#"{
+            "changes": {
+                "food_preference": "pizza"
+            },
+            "reason": "Turns out I'm a pizza person"
+        }"#;
+
+        let request = Request::new(Body::from(payload));
+
+        handler(request, Context::default()).expect("expected Ok(_) value");
+    }
+}
@@ -0,0 +1,66 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_derive::Deserialize;
+use serde_json::{json};
+use std::ops::Deref;
+use uuid::Uuid;
+
+mod models;
+use crate::models::{ChangeRequest, DecisionError};
+
+#[derive(Debug, Default, Deserialize)]
+struct DecisionPayload {
+    #[serde(default)]
+    note: Option<String>
+}
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let uuid : Uuid = Uuid::parse_str(
+        path_parameters.get("id").unwrap()
+    ).unwrap();
+
+    let approve = match path_parameters.get("decision") {
+        Some("approve") => true,
+        Some("reject") => false,
+        _ => return Ok(respond(404, json!({"message": "Change requests can only be approved or rejected"}).to_string()))
+    };
+
+    // The note is optional, so an empty body is fine
+    let body = request.body().deref();
+    let payload : DecisionPayload = if body.is_empty() {
+        DecisionPayload::default()
+    } else {
+        match serde_json::from_slice(body) {
+            Ok(payload) => payload,
+            Err(error) => return Ok(respond(400, json!({"message": format!("Invalid decision: {}", error)}).to_string()))
+        }
+    };
+
+    Ok(match ChangeRequest::decide(uuid, approve, payload.note) {
+        Ok(change_request) => respond(200, json!(change_request).to_string()),
+        Err(DecisionError::NotFound) => respond(404, json!({"message": DecisionError::NotFound.to_string()}).to_string()),
+        Err(error @ DecisionError::AlreadyDecided(_)) => respond(409, json!({"message": error.to_string()}).to_string()),
+        Err(error @ DecisionError::Patch(_)) => respond(422, json!({"message": error.to_string()}).to_string()),
+        Err(DecisionError::Update(_error)) => respond(500, json!({"message": "Something went wrong!"}).to_string())
+    })
+}
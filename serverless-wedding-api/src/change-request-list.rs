@@ -0,0 +1,57 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::{json};
+use log::{error};
+
+mod models;
+use crate::models::{ChangeRequest, ChangeRequestStatus};
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn handler(
+    request: Request,
+    _: Context,
+) -> Result<impl IntoResponse, HandlerError> {
+    // Optionally narrow the list with ?status=pending|approved|rejected
+    let status : Option<ChangeRequestStatus> = match request.query_string_parameters().get("status") {
+        Some(status) => match serde_json::from_value(json!(status)) {
+            Ok(status) => Some(status),
+            Err(_error) => {
+                return Ok(http::Response::builder()
+                    .header("Access-Control-Allow-Origin", "*")
+                    .status(400)
+                    .body(json!({"message": format!("Unknown status: {}", status)}).to_string())
+                    .unwrap())
+            }
+        },
+        None => None
+    };
+
+    Ok(match ChangeRequest::all() {
+        Ok(requests) => {
+            let requests : Vec<ChangeRequest> = requests.into_iter()
+                .filter(|request| status.map_or(true, |status| request.status == status))
+                .collect();
+
+            http::Response::builder()
+                .header("Access-Control-Allow-Origin", "*")
+                .status(200)
+                .body(json!(requests).to_string())
+                .unwrap()
+        },
+        Err(err) => {
+            error!("Could not list change requests: {}", err);
+            http::Response::builder()
+                .header("Access-Control-Allow-Origin", "*")
+                .status(500)
+                .body(json!({"message": "Something went wrong!"}).to_string())
+                .unwrap()
+        }
+    })
+}
@@ -0,0 +1,111 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_derive::Deserialize;
+use serde_json::{json};
+use std::env;
+use std::ops::Deref;
+use log::{error};
+use uuid::Uuid;
+
+mod models;
+mod payments;
+use crate::models::{Household, RegistryItem, Contribution, RegistryError, Money};
+use crate::payments::CheckoutRequest;
+
+#[derive(Debug, Deserialize)]
+struct CheckoutPayload {
+    household_id: Uuid,
+    /// A decimal string like "50.00", in the item's currency
+    amount: String,
+    #[serde(default)]
+    message: String
+}
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// Starts paying towards a registry item. The guest is sent to the
+/// returned url, and the contribution is recorded when the provider's
+/// webhook says the payment went through.
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let item_id : Uuid = match Uuid::parse_str(path_parameters.get("id").unwrap()) {
+        Ok(item_id) => item_id,
+        Err(_error) => return Ok(respond(404, json!({"message": RegistryError::ItemNotFound.to_string()}).to_string()))
+    };
+
+    let body = request.body().deref();
+    let payload : CheckoutPayload = match serde_json::from_slice(body) {
+        Ok(payload) => payload,
+        Err(error) => return Ok(respond(400, json!({"message": format!("Invalid contribution: {}", error)}).to_string()))
+    };
+
+    let item = match RegistryItem::get(item_id) {
+        Ok(item) => item,
+        Err(_err) => return Ok(respond(404, json!({"message": RegistryError::ItemNotFound.to_string()}).to_string()))
+    };
+
+    match Household::get(payload.household_id) {
+        Ok(ref rsvps) if rsvps.is_empty() => {
+            return Ok(respond(404, json!({"message": "No household was found with that id"}).to_string()))
+        },
+        Ok(_rsvps) => {},
+        Err(err) => {
+            error!("Could not look up household {}: {}", payload.household_id, err);
+            return Ok(respond(500, json!({"message": "Something went wrong!"}).to_string()))
+        }
+    }
+
+    // Checked the same way as a recorded contribution, so the webhook
+    // can't be refused later
+    let amount = match Money::parse(&payload.amount, &item.currency) {
+        Ok(amount) => amount,
+        Err(err) => return Ok(respond(400, json!({"message": err.to_string()}).to_string()))
+    };
+    let contribution = match Contribution::new(&item, payload.household_id.to_string(), amount, payload.message) {
+        Ok(contribution) => contribution,
+        Err(message) => return Ok(respond(400, json!({"message": message}).to_string()))
+    };
+
+    let provider = match payments::from_env() {
+        Ok(provider) => provider,
+        Err(err) => {
+            error!("Could not set up the payment provider: {}", err);
+            return Ok(respond(500, json!({"message": "Something went wrong!"}).to_string()))
+        }
+    };
+
+    let base_url = env::var("RSVP_BASE_URL").unwrap_or_else(|_| String::from("https://slswedding.com"));
+    let checkout_request = CheckoutRequest {
+        item_id: item.id.clone(),
+        item_title: item.title.clone(),
+        household_id: contribution.household_id,
+        amount: contribution.amount,
+        message: contribution.message,
+        return_url: format!("{}/registry", base_url.trim_end_matches('/'))
+    };
+
+    Ok(match provider.create_checkout(&checkout_request) {
+        Ok(checkout) => respond(200, json!(checkout).to_string()),
+        Err(err) => {
+            error!("Could not start a {} checkout for {:?}: {}", provider.name(), checkout_request, err);
+            respond(502, json!({"message": "We couldn't reach the payment provider. Please try again."}).to_string())
+        }
+    })
+}
@@ -0,0 +1,40 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::json;
+use log::{error};
+
+mod models;
+use crate::models::Conflict;
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+/// Guests whose meal clashes with their dietary restriction, to follow up
+/// with before the final count.
+fn handler(
+    _request: Request,
+    _: Context,
+) -> Result<impl IntoResponse, HandlerError> {
+    Ok(match Conflict::all() {
+        Ok(conflicts) => {
+            http::Response::builder()
+                .header("Access-Control-Allow-Origin", "*")
+                .status(200)
+                .body(json!(conflicts).to_string())
+                .unwrap()
+        },
+        Err(err) => {
+            error!("Could not build the conflicts report: {}", err);
+            http::Response::builder()
+                .header("Access-Control-Allow-Origin", "*")
+                .status(500)
+                .body(json!({"message": "Something went wrong!"}).to_string())
+                .unwrap()
+        }
+    })
+}
@@ -0,0 +1,110 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_derive::Deserialize;
+use serde_json::{json};
+use std::ops::Deref;
+use log::{error};
+use uuid::Uuid;
+
+mod models;
+use crate::models::{Household, RegistryItem, Contribution, RegistryError, Money};
+
+#[derive(Debug, Deserialize)]
+struct ContributionPayload {
+    household_id: Uuid,
+    /// A decimal string like "50.00"
+    amount: String,
+    currency: Option<String>,
+    #[serde(default)]
+    message: String
+}
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// Records a gift towards a registry item, e.g. a check that came with a
+/// card. Returns the item with its progress afterwards.
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let item_id : Uuid = match Uuid::parse_str(path_parameters.get("id").unwrap()) {
+        Ok(item_id) => item_id,
+        Err(_error) => return Ok(respond(404, json!({"message": RegistryError::ItemNotFound.to_string()}).to_string()))
+    };
+
+    let body = request.body().deref();
+    let payload : ContributionPayload = match serde_json::from_slice(body) {
+        Ok(payload) => payload,
+        Err(error) => return Ok(respond(400, json!({"message": format!("Invalid contribution: {}", error)}).to_string()))
+    };
+
+    let item = match RegistryItem::get(item_id) {
+        Ok(item) => item,
+        Err(_err) => return Ok(respond(404, json!({"message": RegistryError::ItemNotFound.to_string()}).to_string()))
+    };
+
+    match Household::get(payload.household_id) {
+        Ok(ref rsvps) if rsvps.is_empty() => {
+            return Ok(respond(404, json!({"message": "No household was found with that id"}).to_string()))
+        },
+        Ok(_rsvps) => {},
+        Err(err) => {
+            error!("Could not look up household {}: {}", payload.household_id, err);
+            return Ok(respond(500, json!({"message": "Something went wrong!"}).to_string()))
+        }
+    }
+
+    let currency = payload.currency.unwrap_or_else(|| item.currency.clone());
+    let amount = match Money::parse(&payload.amount, &currency) {
+        Ok(amount) => amount,
+        Err(err) => return Ok(respond(400, json!({"message": err.to_string()}).to_string()))
+    };
+    let contribution = match Contribution::new(&item, payload.household_id.to_string(), amount, payload.message) {
+        Ok(contribution) => contribution,
+        Err(message) => return Ok(respond(400, json!({"message": message}).to_string()))
+    };
+
+    Ok(match contribution.record() {
+        Ok(progress) => respond(200, json!({"contribution": contribution, "item": progress}).to_string()),
+        Err(RegistryError::ItemNotFound) => respond(404, json!({"message": RegistryError::ItemNotFound.to_string()}).to_string()),
+        Err(err) => {
+            error!("Could not record contribution {:?}: {:?}", contribution, err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lambda_http::Body;
+
+    #[test]
+    #[ignore]
+    fn contribution_handler_handles() {
+
+        let mut request = Request::new(Body::from(json!({
+            "household_id": "3eb28445-7698-4a00-b071-49da8eaac944",
+            "amount": "50.00",
+            "message": "Have fun!"
+        }).to_string()));
+        *request.uri_mut() = "https://api.slswedding.com/admin/registry/5ba2b0fc-5fa1-4bd6-9d1c-2b6f3d3c8a57/contributions".parse().unwrap();
+
+        handler(request, Context::default()).expect("Expected an OK response");
+    }
+}
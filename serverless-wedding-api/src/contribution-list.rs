@@ -0,0 +1,44 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::json;
+use log::{error};
+use uuid::Uuid;
+
+mod models;
+use crate::models::{Contribution, RegistryError};
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// Everything given towards a registry item, oldest first.
+fn handler(
+    request: Request,
+    _: Context,
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let item_id : Uuid = match Uuid::parse_str(path_parameters.get("id").unwrap()) {
+        Ok(item_id) => item_id,
+        Err(_error) => return Ok(respond(404, json!({"message": RegistryError::ItemNotFound.to_string()}).to_string()))
+    };
+
+    Ok(match Contribution::for_item(&item_id.to_string()) {
+        Ok(contributions) => respond(200, json!(contributions).to_string()),
+        Err(err) => {
+            error!("Could not list contributions to {}: {}", item_id, err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
@@ -0,0 +1,115 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_derive::Deserialize;
+use serde_json::{json};
+use std::ops::Deref;
+use log::{error};
+
+mod models;
+mod payments;
+use crate::models::{Contribution, RegistryError, Money};
+use crate::payments::RefundRequest;
+
+#[derive(Debug, Default, Deserialize)]
+struct RefundPayload {
+    /// A decimal string like "25.00". Refunds whatever is left when left out.
+    amount: Option<String>
+}
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// Refunds a contribution paid through the payment provider, in full or
+/// in part, and takes it off the item's total.
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let item_id = path_parameters.get("id").unwrap();
+    let contribution_id = path_parameters.get("contribution_id").unwrap();
+
+    let body = request.body().deref();
+    let payload : RefundPayload = if body.is_empty() {
+        RefundPayload::default()
+    } else {
+        match serde_json::from_slice(body) {
+            Ok(payload) => payload,
+            Err(error) => return Ok(respond(400, json!({"message": format!("Invalid refund: {}", error)}).to_string()))
+        }
+    };
+
+    let contribution = match Contribution::get(item_id, contribution_id) {
+        Ok(contribution) => contribution,
+        Err(RegistryError::ContributionNotFound) => {
+            return Ok(respond(404, json!({"message": RegistryError::ContributionNotFound.to_string()}).to_string()))
+        },
+        Err(err) => {
+            error!("Could not look up contribution {}: {}", contribution_id, err);
+            return Ok(respond(500, json!({"message": "Something went wrong!"}).to_string()))
+        }
+    };
+
+    let payment_id = match contribution.payment_id {
+        Some(ref payment_id) => payment_id.clone(),
+        None => return Ok(respond(400, json!({"message": "Contributions recorded by hand have to be refunded by hand"}).to_string()))
+    };
+    let refundable = match contribution.refundable() {
+        Ok(refundable) => refundable,
+        Err(err) => {
+            error!("Could not work out what's left of contribution {}: {}", contribution.id, err);
+            return Ok(respond(500, json!({"message": "Something went wrong!"}).to_string()))
+        }
+    };
+    let amount = match payload.amount.map(|amount| Money::parse(&amount, refundable.currency())) {
+        Some(Ok(amount)) => amount,
+        Some(Err(err)) => return Ok(respond(400, json!({"message": err.to_string()}).to_string())),
+        None => refundable.clone()
+    };
+    if !amount.is_positive() || amount > refundable {
+        return Ok(respond(400, json!({"message": format!("Only {} can be refunded", refundable)}).to_string()));
+    }
+
+    let provider = match payments::from_env() {
+        Ok(provider) => provider,
+        Err(err) => {
+            error!("Could not set up the payment provider: {}", err);
+            return Ok(respond(500, json!({"message": "Something went wrong!"}).to_string()))
+        }
+    };
+
+    let refund = match provider.refund(&RefundRequest {
+        item_id: contribution.item_id.clone(),
+        payment_id,
+        amount
+    }) {
+        Ok(refund) => refund,
+        Err(err) => {
+            error!("The {} provider could not refund contribution {}: {}", provider.name(), contribution.id, err);
+            return Ok(respond(502, json!({"message": err.to_string()}).to_string()))
+        }
+    };
+
+    Ok(match contribution.record_refund(&refund.id, &refund.amount) {
+        Ok(progress) => respond(200, json!({"refund": refund, "item": progress}).to_string()),
+        // The provider's webhook got here first
+        Err(RegistryError::AlreadyRecorded) => respond(200, json!({"refund": refund}).to_string()),
+        Err(err) => {
+            error!("Refund {:?} went through but could not be recorded: {:?}", refund, err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
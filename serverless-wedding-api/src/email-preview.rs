@@ -0,0 +1,79 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::json;
+use log::{error};
+use uuid::Uuid;
+
+mod models;
+use crate::models::{EmailTemplate, EmailContext, TemplateKind, EmailError};
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// Renders a guest email for a real household without sending it, so the
+/// couple can check a template before it goes out. Takes the household as
+/// `?household_id=`.
+fn handler(
+    request: Request,
+    _: Context,
+) -> Result<impl IntoResponse, HandlerError> {
+    let kind = match TemplateKind::parse(request.path_parameters().get("kind").unwrap()) {
+        Some(kind) => kind,
+        None => return Ok(respond(404, json!({"message": "No email template was found with that name"}).to_string()))
+    };
+    let uuid : Uuid = match request.query_string_parameters().get("household_id").map(|id| Uuid::parse_str(id)) {
+        Some(Ok(uuid)) => uuid,
+        Some(Err(_error)) => return Ok(respond(404, json!({"message": "No household was found with that id"}).to_string())),
+        None => return Ok(respond(400, json!({"message": "Pick a household to preview with ?household_id="}).to_string()))
+    };
+
+    let context = match EmailContext::get(uuid) {
+        Ok(ref context) if context.guests.is_empty() => {
+            return Ok(respond(404, json!({"message": "No household was found with that id"}).to_string()))
+        },
+        Ok(context) => context,
+        Err(err) => {
+            error!("Could not look up household {} to preview an email: {}", uuid, err);
+            return Ok(respond(500, json!({"message": "Something went wrong!"}).to_string()))
+        }
+    };
+
+    let rendered = EmailTemplate::get(kind).and_then(|template| template.render(&context));
+    Ok(match rendered {
+        Ok(email) => respond(200, json!(email).to_string()),
+        Err(EmailError::Template(message)) => respond(400, json!({"message": message}).to_string()),
+        Err(err) => {
+            error!("Could not preview the {} email for household {}: {}", kind.as_str(), uuid, err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lambda_http::Body;
+
+    #[test]
+    #[ignore]
+    fn email_preview_handler_handles() {
+
+        let mut request = Request::new(Body::from(""));
+        *request.uri_mut() = "https://api.slswedding.com/admin/email-templates/invitation/preview?household_id=3eb28445-7698-4a00-b071-49da8eaac944".parse().unwrap();
+
+        handler(request, Context::default()).expect("Expected an OK response");
+    }
+}
@@ -0,0 +1,123 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::json;
+use log::{info, error};
+use uuid::Uuid;
+use chrono::Utc;
+
+mod models;
+mod notify;
+use crate::models::{RSVP, Household, Event, Menu, EmailTemplate, EmailContext, TemplateKind, EmailError, local_deadline};
+use crate::notify::Notification;
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// Sends a guest email to everyone in a household with an address, through
+/// whichever channels its kind is routed to. Sending an invitation or a
+/// reminder marks each guest's RSVP as having had one.
+fn handler(
+    request: Request,
+    _: Context,
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let uuid : Uuid = match Uuid::parse_str(path_parameters.get("id").unwrap()) {
+        Ok(uuid) => uuid,
+        Err(_error) => return Ok(respond(404, json!({"message": "No household was found with that id"}).to_string()))
+    };
+    let kind = match TemplateKind::parse(path_parameters.get("kind").unwrap()) {
+        Some(kind) => kind,
+        None => return Ok(respond(404, json!({"message": "No email template was found with that name"}).to_string()))
+    };
+
+    let rsvps = match Household::get(uuid) {
+        Ok(ref rsvps) if rsvps.is_empty() => {
+            return Ok(respond(404, json!({"message": "No household was found with that id"}).to_string()))
+        },
+        Ok(rsvps) => rsvps,
+        Err(err) => {
+            error!("Could not look up household {}: {}", uuid, err);
+            return Ok(respond(500, json!({"message": "Something went wrong!"}).to_string()))
+        }
+    };
+    let context = match (Household::record(uuid), Event::all(), local_deadline()) {
+        (Ok(household), Ok(events), Ok(deadline)) => EmailContext::new(&household, &rsvps, &events, deadline),
+        (Err(err), _, _) => {
+            error!("Could not look up household {}: {}", uuid, err);
+            return Ok(respond(500, json!({"message": "Something went wrong!"}).to_string()))
+        },
+        (_, Err(err), _) => {
+            error!("Could not load events: {}", err);
+            return Ok(respond(500, json!({"message": "Something went wrong!"}).to_string()))
+        },
+        (_, _, Err(err)) => {
+            error!("Could not read the RSVP deadline: {}", err);
+            return Ok(respond(500, json!({"message": "Something went wrong!"}).to_string()))
+        }
+    };
+
+    let email = match EmailTemplate::get(kind).and_then(|template| template.render(&context)) {
+        Ok(ref email) if email.to.is_empty() => {
+            return Ok(respond(400, json!({"message": EmailError::NoRecipients.to_string()}).to_string()))
+        },
+        Ok(email) => email,
+        Err(EmailError::Template(message)) => return Ok(respond(400, json!({"message": message}).to_string())),
+        Err(err) => {
+            error!("Could not render the {} email for household {}: {}", kind.as_str(), uuid, err);
+            return Ok(respond(500, json!({"message": "Something went wrong!"}).to_string()))
+        }
+    };
+
+    let sent = notify::from_env().and_then(|notifiers| notifiers.notify(&Notification::email(&email)));
+    if let Err(err) = sent {
+        error!("Could not send the {} email to household {}: {}", kind.as_str(), uuid, err);
+        return Ok(respond(502, json!({"message": err.to_string()}).to_string()));
+    }
+    info!("Sent the {} email to {:?}", kind.as_str(), email.to);
+
+    // Reminders are also counted, so the reminder job knows about ones
+    // sent by hand
+    for rsvp in &rsvps {
+        let marked = match kind {
+            TemplateKind::Invitation => Uuid::parse_str(&rsvp.id)
+                .map_err(|err| err.to_string())
+                .and_then(|id| RSVP::apply(id, json!({"invitation_submitted": true}), &Menu::default()).map(|_| ()).map_err(|err| err.to_string())),
+            TemplateKind::Reminder => rsvp.record_reminder(Utc::now()).map_err(|err| err.to_string()),
+            TemplateKind::Confirmation => Ok(())
+        };
+        if let Err(err) = marked {
+            error!("Sent the {} email but could not mark RSVP {}: {}", kind.as_str(), rsvp.id, err);
+        }
+    }
+
+    Ok(respond(200, json!(email).to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lambda_http::Body;
+
+    #[test]
+    #[ignore]
+    fn email_send_handler_handles() {
+
+        let mut request = Request::new(Body::from(""));
+        *request.uri_mut() = "https://api.slswedding.com/admin/household/3eb28445-7698-4a00-b071-49da8eaac944/emails/invitation".parse().unwrap();
+
+        handler(request, Context::default()).expect("Expected an OK response");
+    }
+}
@@ -0,0 +1,54 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::json;
+use log::{error};
+
+mod models;
+use crate::models::EmailTemplate;
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// Every guest email template, with the defaults standing in for any the
+/// couple hasn't edited.
+fn handler(
+    _: Request,
+    _: Context,
+) -> Result<impl IntoResponse, HandlerError> {
+    Ok(match EmailTemplate::all() {
+        Ok(templates) => respond(200, json!(templates).to_string()),
+        Err(err) => {
+            error!("Could not list email templates: {}", err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lambda_http::Body;
+
+    #[test]
+    #[ignore]
+    fn email_template_list_handler_handles() {
+
+        let mut request = Request::new(Body::from(""));
+        *request.uri_mut() = "https://api.slswedding.com/admin/email-templates".parse().unwrap();
+
+        handler(request, Context::default()).expect("Expected an OK response");
+    }
+}
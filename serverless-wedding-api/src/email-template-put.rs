@@ -0,0 +1,87 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_derive::Deserialize;
+use serde_json::json;
+use std::ops::Deref;
+use log::{error};
+
+mod models;
+use crate::models::{EmailTemplate, TemplateKind, EmailError};
+
+#[derive(Debug, Deserialize)]
+struct TemplatePayload {
+    subject: String,
+    #[serde(default)]
+    html: String,
+    text: String
+}
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// Replaces a guest email template. Templates that don't parse are
+/// refused, so nothing broken is ever sent.
+fn handler(
+    request: Request,
+    _: Context,
+) -> Result<impl IntoResponse, HandlerError> {
+    let kind = match TemplateKind::parse(request.path_parameters().get("kind").unwrap()) {
+        Some(kind) => kind,
+        None => return Ok(respond(404, json!({"message": "No email template was found with that name"}).to_string()))
+    };
+
+    let body = request.body().deref();
+    let payload : TemplatePayload = match serde_json::from_slice(body) {
+        Ok(payload) => payload,
+        Err(error) => return Ok(respond(400, json!({"message": format!("Invalid email template: {}", error)}).to_string()))
+    };
+
+    let template = EmailTemplate {
+        kind,
+        subject: payload.subject,
+        html: payload.html,
+        text: payload.text,
+        updated_at: None
+    };
+
+    Ok(match template.save() {
+        Ok(template) => respond(200, json!(template).to_string()),
+        Err(EmailError::Template(message)) => respond(400, json!({"message": message}).to_string()),
+        Err(err) => {
+            error!("Could not save the {} email template: {}", kind.as_str(), err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lambda_http::Body;
+
+    #[test]
+    #[ignore]
+    fn email_template_put_handler_handles() {
+
+        let mut request = Request::new(Body::from(json!({
+            "subject": "Will we see you there?",
+            "text": "Dear {{salutation}}, please RSVP at {{rsvp_link}}"
+        }).to_string()));
+        *request.uri_mut() = "https://api.slswedding.com/admin/email-templates/reminder".parse().unwrap();
+
+        handler(request, Context::default()).expect("Expected an OK response");
+    }
+}
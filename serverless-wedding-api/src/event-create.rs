@@ -0,0 +1,83 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_derive::Deserialize;
+use serde_json::{json};
+use std::ops::Deref;
+use chrono::NaiveDate;
+
+mod models;
+use crate::models::Event;
+
+#[derive(Debug, Deserialize)]
+struct EventPayload {
+    name: String,
+    date: NaiveDate,
+    venue: String,
+    capacity: u32
+}
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let body = request.body().deref();
+    let payload : EventPayload = match serde_json::from_slice(body) {
+        Ok(payload) => payload,
+        Err(error) => {
+            return Ok(http::Response::builder()
+                .header("Access-Control-Allow-Origin", "*")
+                .status(400)
+                .body(json!({"message": format!("Invalid event: {}", error)}).to_string())
+                .unwrap())
+        }
+    };
+
+    let event = Event::new(payload.name, payload.date, payload.venue, payload.capacity);
+
+    Ok(match event.create() {
+        Ok(response) => {
+            http::Response::builder()
+                .header("Access-Control-Allow-Origin", "*")
+                .status(200)
+                .body(json!(response).to_string())
+                .unwrap()
+        },
+        Err(_error) => {
+            http::Response::builder()
+                .header("Access-Control-Allow-Origin", "*")
+                .status(500)
+                .body(json!({"message": "Something went wrong!"}).to_string())
+                .unwrap()
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lambda_http::{Body};
+
+    #[test]
+    #[ignore]
+    fn event_create_handler_handles() {
+
+        let payload = r#"{
+            "name": "The Bash",
+            "date": "2019-10-12",
+            "venue": "Williamsburg",
+            "capacity": 80
+        }"#;
+
+        let request = Request::new(Body::from(payload));
+
+        handler(request, Context::default()).expect("expected Ok(_) value");
+    }
+}
@@ -0,0 +1,93 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_derive::Deserialize;
+use serde_json::{json};
+use std::ops::Deref;
+use log::{error};
+use uuid::Uuid;
+
+mod models;
+use crate::models::{Event, RSVP};
+
+/// Guests to invite, by RSVP id
+#[derive(Debug, Deserialize)]
+struct InvitePayload {
+    rsvp_ids: Vec<Uuid>
+}
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let event_id : Uuid = Uuid::parse_str(
+        path_parameters.get("id").unwrap()
+    ).unwrap();
+
+    let body = request.body().deref();
+    let payload : InvitePayload = match serde_json::from_slice(body) {
+        Ok(payload) => payload,
+        Err(error) => {
+            return Ok(respond(400, json!({"message": format!("Invalid invitation list: {}", error)}).to_string()))
+        }
+    };
+
+    let event = match Event::get(event_id) {
+        Ok(event) => event,
+        Err(_error) => return Ok(respond(404, json!({"message": "No event was found with that id"}).to_string()))
+    };
+
+    let mut invited : Vec<RSVP> = vec!();
+    for rsvp_id in payload.rsvp_ids {
+        let rsvp = match RSVP::get(rsvp_id) {
+            Ok(rsvp) => rsvp,
+            Err(_error) => {
+                return Ok(respond(404, json!({"message": format!("No RSVP was found with id {}", rsvp_id)}).to_string()))
+            }
+        };
+
+        if let Err(err) = rsvp.invite(&event.id) {
+            error!("Could not invite {} to {}: {:?}", rsvp.id, event.id, err);
+            return Ok(respond(500, json!({"message": "Something went wrong!"}).to_string()));
+        }
+
+        invited.push(RSVP::get(rsvp_id).unwrap());
+    }
+
+    Ok(respond(200, json!({"event": event, "invited": invited}).to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lambda_http::{Body};
+
+    #[test]
+    #[ignore]
+    fn event_invite_handler_handles() {
+
+        let payload = r#"{
+            "rsvp_ids": ["955e9465-d9cc-43cc-96ac-0fe00fc75d0e"]
+        }"#;
+
+        let request = Request::new(Body::from(payload));
+
+        handler(request, Context::default()).expect("expected Ok(_) value");
+    }
+}
@@ -0,0 +1,128 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_derive::Deserialize;
+use serde_json::{json};
+use std::ops::Deref;
+use chrono::{NaiveDate, Utc};
+use log::{error};
+use uuid::Uuid;
+
+mod models;
+use crate::models::{Household, Event, Gift, GiftKind, Money, default_currency};
+
+#[derive(Debug, Deserialize)]
+struct GiftPayload {
+    kind: GiftKind,
+    description: String,
+    /// A decimal string like "150.00", so it never passes through a float
+    value: Option<String>,
+    currency: Option<String>,
+    event_id: Option<Uuid>,
+    /// Today when left out
+    received_on: Option<NaiveDate>,
+    #[serde(default)]
+    notes: String
+}
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// Adds a gift from a household to the ledger, with its thank-you note
+/// still to write.
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let household_id : Uuid = match Uuid::parse_str(path_parameters.get("id").unwrap()) {
+        Ok(household_id) => household_id,
+        Err(_error) => return Ok(respond(404, json!({"message": "No household was found with that id"}).to_string()))
+    };
+
+    let body = request.body().deref();
+    let payload : GiftPayload = match serde_json::from_slice(body) {
+        Ok(payload) => payload,
+        Err(error) => return Ok(respond(400, json!({"message": format!("Invalid gift: {}", error)}).to_string()))
+    };
+
+    match Household::get(household_id) {
+        Ok(ref rsvps) if rsvps.is_empty() => {
+            return Ok(respond(404, json!({"message": "No household was found with that id"}).to_string()))
+        },
+        Ok(_rsvps) => {},
+        Err(err) => {
+            error!("Could not look up household {}: {}", household_id, err);
+            return Ok(respond(500, json!({"message": "Something went wrong!"}).to_string()))
+        }
+    }
+
+    if let Some(event_id) = payload.event_id {
+        if Event::get(event_id).is_err() {
+            return Ok(respond(404, json!({"message": "No event was found with that id"}).to_string()));
+        }
+    }
+
+    let currency = payload.currency.unwrap_or_else(default_currency);
+    let value = match payload.value.map(|value| Money::parse(&value, &currency)) {
+        Some(Ok(value)) => Some(value),
+        Some(Err(err)) => return Ok(respond(400, json!({"message": err.to_string()}).to_string())),
+        None => None
+    };
+
+    let gift = match Gift::new(
+        household_id.to_string(),
+        payload.kind,
+        payload.description,
+        value,
+        payload.event_id.map(|event_id| event_id.to_string()),
+        payload.received_on.unwrap_or_else(|| Utc::now().naive_utc().date())
+    ) {
+        Ok(gift) => Gift {
+            notes: payload.notes.trim().to_string(),
+            ..gift
+        },
+        Err(message) => return Ok(respond(400, json!({"message": message}).to_string()))
+    };
+
+    Ok(match gift.create() {
+        Ok(gift) => respond(200, json!(gift).to_string()),
+        Err(err) => {
+            error!("Could not record gift {:?}: {}", gift, err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lambda_http::Body;
+
+    #[test]
+    #[ignore]
+    fn gift_create_handler_handles() {
+
+        let mut request = Request::new(Body::from(json!({
+            "kind": "cash",
+            "description": "Check in a card",
+            "value": "150.00",
+            "received_on": "2019-09-21"
+        }).to_string()));
+        *request.uri_mut() = "https://api.slswedding.com/admin/household/3eb28445-7698-4a00-b071-49da8eaac944/gifts".parse().unwrap();
+
+        handler(request, Context::default()).expect("Expected an OK response");
+    }
+}
@@ -0,0 +1,47 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::json;
+use log::{error};
+use uuid::Uuid;
+
+mod models;
+use crate::models::Gift;
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// The gift ledger, in the order gifts came in. `?household_id=` narrows
+/// it to one household.
+fn handler(
+    request: Request,
+    _: Context,
+) -> Result<impl IntoResponse, HandlerError> {
+    let gifts = match request.query_string_parameters().get("household_id") {
+        Some(household_id) => match Uuid::parse_str(household_id) {
+            Ok(household_id) => Gift::for_household(&household_id.to_string()).map_err(|err| err.to_string()),
+            Err(_error) => return Ok(respond(404, json!({"message": "No household was found with that id"}).to_string()))
+        },
+        None => Gift::all().map_err(|err| err.to_string())
+    };
+
+    Ok(match gifts {
+        Ok(gifts) => respond(200, json!(gifts).to_string()),
+        Err(err) => {
+            error!("Could not list gifts: {}", err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
@@ -0,0 +1,77 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_derive::Deserialize;
+use serde_json::{json};
+use std::ops::Deref;
+use chrono::{NaiveDate, Utc};
+use log::{error};
+
+mod models;
+use crate::models::{Gift, GiftError, ThankYouStatus};
+
+#[derive(Debug, Deserialize)]
+struct ThankYouPayload {
+    status: ThankYouStatus,
+    /// Today when left out
+    on: Option<NaiveDate>
+}
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// Marks the thank-you note for a gift written or mailed, or back to
+/// pending if it was marked by mistake.
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let household_id = path_parameters.get("id").unwrap();
+    let gift_id = path_parameters.get("gift_id").unwrap();
+
+    let body = request.body().deref();
+    let payload : ThankYouPayload = match serde_json::from_slice(body) {
+        Ok(payload) => payload,
+        Err(error) => return Ok(respond(400, json!({"message": format!("Invalid thank-you: {}", error)}).to_string()))
+    };
+    let on = payload.on.unwrap_or_else(|| Utc::now().naive_utc().date());
+
+    Ok(match Gift::set_thank_you(household_id, gift_id, payload.status, on) {
+        Ok(gift) => respond(200, json!(gift).to_string()),
+        Err(GiftError::NotFound) => respond(404, json!({"message": GiftError::NotFound.to_string()}).to_string()),
+        Err(GiftError::Invalid(message)) => respond(400, json!({"message": message}).to_string()),
+        Err(err) => {
+            error!("Could not update the thank-you for gift {}: {}", gift_id, err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lambda_http::Body;
+
+    #[test]
+    #[ignore]
+    fn gift_thank_you_handler_handles() {
+
+        let mut request = Request::new(Body::from(json!({"status": "mailed", "on": "2019-10-03"}).to_string()));
+        *request.uri_mut() = "https://api.slswedding.com/admin/household/3eb28445-7698-4a00-b071-49da8eaac944/gifts/5ba2b0fc-5fa1-4bd6-9d1c-2b6f3d3c8a57/thank-you".parse().unwrap();
+
+        handler(request, Context::default()).expect("Expected an OK response");
+    }
+}
@@ -0,0 +1,77 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_derive::Deserialize;
+use serde_json::{json};
+use std::ops::Deref;
+use chrono::NaiveDate;
+use log::{error};
+
+mod models;
+use crate::models::Hotel;
+
+#[derive(Debug, Deserialize)]
+struct HotelPayload {
+    name: String,
+    address: String,
+    #[serde(default)]
+    phone: String,
+    #[serde(default)]
+    url: String,
+    #[serde(default)]
+    booking_code: String,
+    block_size: u32,
+    rate_cents: u32,
+    release_date: NaiveDate
+}
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let body = request.body().deref();
+    let payload : HotelPayload = match serde_json::from_slice(body) {
+        Ok(payload) => payload,
+        Err(error) => return Ok(respond(400, json!({"message": format!("Invalid hotel: {}", error)}).to_string()))
+    };
+
+    if payload.name.trim().is_empty() || payload.block_size == 0 {
+        return Ok(respond(400, json!({"message": "Hotels need a name and at least one room in the block"}).to_string()));
+    }
+
+    let hotel = Hotel {
+        phone: payload.phone.trim().to_string(),
+        url: payload.url.trim().to_string(),
+        booking_code: payload.booking_code.trim().to_string(),
+        ..Hotel::new(
+            payload.name.trim().to_string(),
+            payload.address.trim().to_string(),
+            payload.block_size,
+            payload.rate_cents,
+            payload.release_date
+        )
+    };
+
+    Ok(match hotel.create() {
+        Ok(hotel) => respond(200, json!(hotel).to_string()),
+        Err(err) => {
+            error!("Could not create hotel {:?}: {}", hotel, err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
@@ -0,0 +1,47 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::{json};
+use chrono::Utc;
+use log::{error};
+
+mod models;
+use crate::models::{Hotel, HotelStatus, deadline_offset, local_date};
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+/// The hotels holding room blocks, with how many rooms are left in each
+/// and a warning when a block is nearly gone.
+fn handler(
+    _: Request,
+    _: Context,
+) -> Result<impl IntoResponse, HandlerError> {
+    let today = local_date(Utc::now(), deadline_offset());
+
+    Ok(match Hotel::all() {
+        Ok(hotels) => {
+            let statuses : Vec<HotelStatus> = hotels.into_iter()
+                .map(|hotel| hotel.status(today))
+                .collect();
+
+            http::Response::builder()
+                .header("Access-Control-Allow-Origin", "*")
+                .status(200)
+                .body(json!(statuses).to_string())
+                .unwrap()
+        },
+        Err(err) => {
+            error!("Could not list hotels: {}", err);
+            http::Response::builder()
+                .header("Access-Control-Allow-Origin", "*")
+                .status(500)
+                .body(json!({"message": "Something went wrong!"}).to_string())
+                .unwrap()
+        }
+    })
+}
@@ -0,0 +1,100 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_derive::Deserialize;
+use serde_json::{json};
+use std::ops::Deref;
+use log::{error};
+use uuid::Uuid;
+
+mod models;
+use crate::models::{Household, Address};
+
+#[derive(Debug, Deserialize)]
+struct AddressPayload {
+    salutation: Option<String>,
+    address: Address
+}
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// Guests set their household's mailing address from their RSVP link. This
+/// stays open after the RSVP deadline since thank-you cards go out later.
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let uuid : Uuid = match Uuid::parse_str(path_parameters.get("id").unwrap()) {
+        Ok(uuid) => uuid,
+        Err(_error) => return Ok(respond(404, json!({"message": "No household was found with that id"}).to_string()))
+    };
+
+    let body = request.body().deref();
+    let payload : AddressPayload = match serde_json::from_slice(body) {
+        Ok(payload) => payload,
+        Err(error) => return Ok(respond(400, json!({"message": format!("Invalid address: {}", error)}).to_string()))
+    };
+
+    let address = match payload.address.normalize() {
+        Ok(address) => address,
+        Err(error) => return Ok(respond(400, json!({"message": error.to_string()}).to_string()))
+    };
+
+    match Household::get(uuid) {
+        Ok(ref rsvps) if rsvps.is_empty() => {
+            return Ok(respond(404, json!({"message": "No household was found with that id"}).to_string()))
+        },
+        Ok(_rsvps) => {},
+        Err(err) => {
+            error!("Could not look up household {}: {}", uuid, err);
+            return Ok(respond(500, json!({"message": "Something went wrong!"}).to_string()))
+        }
+    }
+
+    Ok(match Household::set_address(uuid, payload.salutation, &address) {
+        Ok(household) => respond(200, json!(household).to_string()),
+        Err(err) => {
+            error!("Could not set the address for {}: {:?}", uuid, err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lambda_http::Body;
+
+    #[test]
+    #[ignore]
+    fn address_handler_handles() {
+
+        let mut request = Request::new(Body::from(json!({
+            "salutation": "The Price Family",
+            "address": {
+                "line1": "12 Main St",
+                "city": "Springfield",
+                "region": "IL",
+                "postal_code": "62704",
+                "country": "US"
+            }
+        }).to_string()));
+        *request.uri_mut() = "https://api.slswedding.com/household/3eb28445-7698-4a00-b071-49da8eaac944/address".parse().unwrap();
+
+        handler(request, Context::default()).expect("Expected an OK response");
+    }
+}
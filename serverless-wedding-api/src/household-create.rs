@@ -3,6 +3,7 @@ extern crate simple_logger;
 
 use lambda_http::{lambda, IntoResponse, http, Request};
 use lambda_runtime::{error::HandlerError, Context};
+use serde_derive::Deserialize;
 use serde_json::{json};
 use std::ops::Deref;
 use log::{error};
@@ -10,6 +11,19 @@ use log::{error};
 mod models;
 use crate::models::{Household, Person};
 
+/// Either a bare list of people, or the people along with how many
+/// plus-ones the household may bring.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum HouseholdPayload {
+    People(Vec<Person>),
+    Household {
+        people: Vec<Person>,
+        #[serde(default)]
+        plus_ones: u32
+    }
+}
+
 fn main() {
     simple_logger::init_with_level(log::Level::Info).unwrap();
     lambda!(handler)
@@ -20,9 +34,19 @@ fn handler(
     _: Context
 ) -> Result<impl IntoResponse, HandlerError> {
     let body = request.body().deref();
-    let people : Vec<Person> = serde_json::from_slice(body).unwrap();
+    let (people, plus_ones) = match serde_json::from_slice(body) {
+        Ok(HouseholdPayload::People(people)) => (people, 0),
+        Ok(HouseholdPayload::Household { people, plus_ones }) => (people, plus_ones),
+        Err(error) => {
+            return Ok(http::Response::builder()
+                .header("Access-Control-Allow-Origin", "*")
+                .status(400)
+                .body(json!({"message": format!("Invalid household: {}", error)}).to_string())
+                .unwrap())
+        }
+    };
 
-    Ok(match Household::create(people) {
+    Ok(match Household::create_with_plus_ones(people, plus_ones) {
         Ok(response) => {
             http::Response::builder()
                 .header("Access-Control-Allow-Origin", "*")
@@ -63,4 +87,27 @@ mod tests {
 
         handler(request, Context::default()).expect("expected Ok(_) value");
     }
+
+    #[test]
+    fn household_payload_accepts_both_shapes() {
+        let people : HouseholdPayload = serde_json::from_str(r#"[
+            {"email_address": "example@email.com", "name": "Blaine Price"}
+        ]"#).unwrap();
+        match people {
+            HouseholdPayload::People(people) => assert_eq!(people.len(), 1),
+            _ => assert!(false)
+        }
+
+        let household : HouseholdPayload = serde_json::from_str(r#"{
+            "people": [{"email_address": "example@email.com", "name": "Blaine Price"}],
+            "plus_ones": 1
+        }"#).unwrap();
+        match household {
+            HouseholdPayload::Household { people, plus_ones } => {
+                assert_eq!(people.len(), 1);
+                assert_eq!(plus_ones, 1);
+            },
+            _ => assert!(false)
+        }
+    }
 }
@@ -2,19 +2,51 @@ extern crate log;
 extern crate simple_logger;
 
 use std::error::Error;
+use std::collections::{HashSet};
 use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
 use lambda_runtime::{error::HandlerError, Context};
 use serde_json::{json, Value};
 use uuid::Uuid;
 
 mod models;
-use crate::models::Household;
+use crate::models::{Household, Event, LodgingChoice, ShuttleSignup, ActivitySignup, local_deadline};
 
 fn main() {
     simple_logger::init_with_level(log::Level::Info).unwrap();
     lambda!(handler)
 }
 
+/// The household's RSVPs, household-level details, lodging, shuttle seats
+/// and activities, along with only the events someone in the household is
+/// invited to and the RSVP deadline.
+fn household_with_events(uuid: Uuid) -> Result<Value, Box<Error>> {
+    let rsvps = Household::get(uuid)?;
+    let details = Household::record(uuid)?;
+    let lodging = LodgingChoice::for_household(&uuid.to_string())?;
+    let shuttles = ShuttleSignup::for_household(&uuid.to_string())?;
+    let activities = ActivitySignup::for_household(&uuid.to_string())?;
+    let deadline = local_deadline()?;
+
+    let invited : HashSet<&String> = rsvps.iter()
+        .flat_map(|rsvp| rsvp.invitations.keys())
+        .collect();
+
+    let events = Event::all()?
+        .into_iter()
+        .filter(|event| invited.contains(&event.id))
+        .collect::<Vec<Event>>();
+
+    Ok(json!({
+        "household": rsvps,
+        "details": details,
+        "lodging": lodging,
+        "shuttles": shuttles,
+        "activities": activities,
+        "events": events,
+        "deadline": deadline
+    }))
+}
+
 fn handler(
     request: Request,
     _: Context,
@@ -24,12 +56,12 @@ fn handler(
         path_parameters.get("id").unwrap()
     ).unwrap();
 
-    Ok(match Household::get(uuid) {
-        Ok(rsvps) => {
+    Ok(match household_with_events(uuid) {
+        Ok(household) => {
             http::Response::builder()
                 .header("Access-Control-Allow-Origin", "*")
                 .status(200)
-                .body(json!(rsvps).to_string())
+                .body(household.to_string())
                 .unwrap()
         },
         Err(err) => {
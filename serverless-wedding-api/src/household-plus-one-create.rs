@@ -0,0 +1,75 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::{json};
+use std::ops::Deref;
+use log::{error};
+use uuid::Uuid;
+
+mod models;
+use crate::models::{PlusOne, PlusOneError};
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let uuid : Uuid = Uuid::parse_str(
+        path_parameters.get("id").unwrap()
+    ).unwrap();
+
+    let body = request.body().deref();
+    let plus_one : PlusOne = match serde_json::from_slice(body) {
+        Ok(plus_one) => plus_one,
+        Err(error) => return Ok(respond(400, json!({"message": format!("Invalid plus-one: {}", error)}).to_string()))
+    };
+
+    Ok(match plus_one.add(uuid) {
+        Ok(rsvp) => respond(200, json!(rsvp).to_string()),
+        Err(error @ PlusOneError::Invalid(_)) => respond(400, json!({"message": error.to_string()}).to_string()),
+        Err(error @ PlusOneError::DeadlinePassed(_)) => respond(403, json!({"message": error.to_string()}).to_string()),
+        Err(error @ PlusOneError::HouseholdNotFound) => respond(404, json!({"message": error.to_string()}).to_string()),
+        Err(error @ PlusOneError::AllowanceExhausted) => respond(409, json!({"message": error.to_string()}).to_string()),
+        Err(error @ PlusOneError::NameTaken(_)) => respond(409, json!({"message": error.to_string()}).to_string()),
+        Err(error) => {
+            error!("Could not add a plus-one to household {}: {:?}", uuid, error);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lambda_http::{Body};
+
+    #[test]
+    #[ignore]
+    fn plus_one_handler_handles() {
+
+        let payload = r#"{
+            "name": "Ling Ling",
+            "food_preference": "pizza",
+            "dietary_restrictions": "vegetarian"
+        }"#;
+
+        let request = Request::new(Body::from(payload));
+
+        handler(request, Context::default()).expect("expected Ok(_) value");
+    }
+}
@@ -0,0 +1,59 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use rusoto_dynamodb::UpdateItemError;
+use serde_derive::Deserialize;
+use serde_json::{json};
+use std::ops::Deref;
+use log::{error};
+use uuid::Uuid;
+
+mod models;
+use crate::models::Household;
+
+#[derive(Debug, Deserialize)]
+struct AllowancePayload {
+    allowed: u32
+}
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let uuid : Uuid = Uuid::parse_str(
+        path_parameters.get("id").unwrap()
+    ).unwrap();
+
+    let body = request.body().deref();
+    let payload : AllowancePayload = match serde_json::from_slice(body) {
+        Ok(payload) => payload,
+        Err(error) => return Ok(respond(400, json!({"message": format!("Invalid allowance: {}", error)}).to_string()))
+    };
+
+    Ok(match Household::set_plus_ones_allowed(uuid, payload.allowed) {
+        Ok(household) => respond(200, json!(household).to_string()),
+        Err(UpdateItemError::ConditionalCheckFailed(_)) => {
+            respond(409, json!({"message": "Guests have already named more plus-ones than that"}).to_string())
+        },
+        Err(err) => {
+            error!("Could not set the plus-one allowance for {}: {:?}", uuid, err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
@@ -0,0 +1,105 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt, Body};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::json;
+use uuid::Uuid;
+use log::{error};
+
+mod models;
+mod qr;
+use crate::models::Household;
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn error_response(status: u16, message: &str) -> http::Response<Body> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(Body::from(json!({"message": message}).to_string()))
+        .unwrap()
+}
+
+fn options(request: &Request) -> Result<qr::Options, String> {
+    let query = request.query_string_parameters();
+    let mut options = qr::Options::default();
+
+    if let Some(format) = query.get("format") {
+        options.format = format.parse()?;
+    }
+    if let Some(level) = query.get("ec") {
+        options.error_correction = qr::parse_error_correction(level)?;
+    }
+    if let Some(size) = query.get("size") {
+        let size : u32 = size.parse()
+            .map_err(|_| format!("Invalid size: {}", size))?;
+        options = options.with_size(size);
+    }
+
+    Ok(options)
+}
+
+fn handler(
+    request: Request,
+    _: Context,
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let uuid : Uuid = Uuid::parse_str(
+        path_parameters.get("id").unwrap()
+    ).unwrap();
+
+    let options = match options(&request) {
+        Ok(options) => options,
+        Err(message) => return Ok(error_response(400, &message))
+    };
+
+    let rsvps = match Household::get(uuid) {
+        Ok(rsvps) => rsvps,
+        Err(_err) => return Ok(error_response(500, "Something went wrong!"))
+    };
+
+    if rsvps.is_empty() {
+        return Ok(error_response(404, "No household was found with that id"));
+    }
+
+    let link = Household::rsvp_link(&uuid.to_string());
+
+    Ok(match qr::render(&link, &options) {
+        Ok(bytes) => {
+            let body = match options.format {
+                qr::Format::Svg => Body::from(String::from_utf8(bytes).unwrap()),
+                qr::Format::Png => Body::from(bytes)
+            };
+
+            http::Response::builder()
+                .header("Access-Control-Allow-Origin", "*")
+                .header("Content-Type", options.format.content_type())
+                .status(200)
+                .body(body)
+                .unwrap()
+        },
+        Err(err) => {
+            error!("Could not render a QR code for {}: {}", link, err);
+            error_response(500, "Something went wrong!")
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[ignore]
+    fn qr_handler_handles() {
+
+        let mut request = Request::new(Body::default());
+        *request.uri_mut() = "https://api.slswedding.com/household/3eb28445-7698-4a00-b071-49da8eaac944/qr?format=png&size=512&ec=H".parse().unwrap();
+
+        handler(request, Context::default()).expect("Expected an OK response");
+    }
+}
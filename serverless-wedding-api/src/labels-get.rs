@@ -0,0 +1,84 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt, Body};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::json;
+use log::{error};
+
+mod models;
+mod pdf;
+mod labels;
+use crate::labels::LabelLayout;
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn error_response(status: u16, message: &str) -> http::Response<Body> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(Body::from(json!({"message": message}).to_string()))
+        .unwrap()
+}
+
+fn options(request: &Request) -> Result<(LabelLayout, usize), String> {
+    let query = request.query_string_parameters();
+
+    let layout : LabelLayout = match query.get("layout") {
+        Some(layout) => layout.parse()?,
+        None => "5160".parse()?
+    };
+    let skip = match query.get("skip") {
+        Some(skip) => skip.parse().map_err(|_| format!("Invalid skip: {}", skip))?,
+        None => 0
+    };
+    let skip = layout.check_skip(skip)?;
+
+    Ok((layout, skip))
+}
+
+/// Address labels for every household that has given a mailing address.
+/// How many households haven't is sent back in `X-Missing-Addresses`.
+fn handler(
+    request: Request,
+    _: Context,
+) -> Result<impl IntoResponse, HandlerError> {
+    let (layout, skip) = match options(&request) {
+        Ok(options) => options,
+        Err(message) => return Ok(error_response(400, &message))
+    };
+
+    Ok(match labels::for_all(layout, skip) {
+        Ok((bytes, missing)) => http::Response::builder()
+            .header("Access-Control-Allow-Origin", "*")
+            .header("Access-Control-Expose-Headers", "X-Missing-Addresses")
+            .header("Content-Type", "application/pdf")
+            .header("Content-Disposition", "inline; filename=\"address-labels.pdf\"")
+            .header("X-Missing-Addresses", missing.len().to_string())
+            .status(200)
+            .body(Body::from(bytes))
+            .unwrap(),
+        Err(err) => {
+            error!("Could not print address labels: {}", err);
+            error_response(500, "Something went wrong!")
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[ignore]
+    fn labels_handler_handles() {
+
+        let mut request = Request::new(Body::default());
+        *request.uri_mut() = "https://api.slswedding.com/admin/labels?layout=l7163&skip=3".parse().unwrap();
+
+        handler(request, Context::default()).expect("Expected an OK response");
+    }
+}
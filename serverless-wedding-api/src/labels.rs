@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::str::FromStr;
+use printpdf::Mm;
+
+use crate::models::{Household, RSVP, Address};
+use crate::pdf::{PaperSize, PT, fit, new_document, next_page, finish};
+
+const PADDING : f64 = 3.0;
+
+/// A sheet of address labels. Measurements are in millimeters, from the
+/// top left corner of the sheet.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LabelLayout {
+    pub name: &'static str,
+    pub paper: PaperSize,
+    pub columns: usize,
+    pub rows: usize,
+    pub width: f64,
+    pub height: f64,
+    pub left: f64,
+    pub top: f64,
+    pub column_pitch: f64,
+    pub row_pitch: f64
+}
+
+pub const LAYOUTS : &[LabelLayout] = &[
+    // Avery 5160, 30 per sheet
+    LabelLayout { name: "5160", paper: PaperSize::LETTER, columns: 3, rows: 10, width: 66.675, height: 25.4, left: 4.7625, top: 12.7, column_pitch: 69.85, row_pitch: 25.4 },
+    // Avery 5162, 14 per sheet
+    LabelLayout { name: "5162", paper: PaperSize::LETTER, columns: 2, rows: 7, width: 101.6, height: 33.867, left: 3.969, top: 21.167, column_pitch: 104.775, row_pitch: 33.867 },
+    // Avery 5163, 10 per sheet
+    LabelLayout { name: "5163", paper: PaperSize::LETTER, columns: 2, rows: 5, width: 101.6, height: 50.8, left: 3.969, top: 12.7, column_pitch: 104.775, row_pitch: 50.8 },
+    // Avery L7160, 21 per A4 sheet
+    LabelLayout { name: "l7160", paper: PaperSize::A4, columns: 3, rows: 7, width: 63.5, height: 38.1, left: 7.2, top: 15.15, column_pitch: 66.0, row_pitch: 38.1 },
+    // Avery L7163, 14 per A4 sheet
+    LabelLayout { name: "l7163", paper: PaperSize::A4, columns: 2, rows: 7, width: 99.1, height: 38.1, left: 4.65, top: 15.15, column_pitch: 101.6, row_pitch: 38.1 }
+];
+
+impl LabelLayout {
+    pub fn per_sheet(&self) -> usize {
+        self.columns * self.rows
+    }
+
+    /// Checks how many labels are already used on the first sheet. A whole
+    /// sheet or more would only print blank pages.
+    pub fn check_skip(&self, skip: usize) -> Result<usize, String> {
+        if skip >= self.per_sheet() {
+            return Err(format!("Only {} labels fit on a {} sheet, so skip must be less than that", self.per_sheet(), self.name.to_uppercase()));
+        }
+        Ok(skip)
+    }
+
+    /// Bottom left corner of the label at `index` on its sheet, in PDF
+    /// coordinates. Labels fill each row left to right, top row first.
+    pub fn position(&self, index: usize) -> (f64, f64) {
+        let slot = index % self.per_sheet();
+        let (row, column) = (slot / self.columns, slot % self.columns);
+        (
+            self.left + column as f64 * self.column_pitch,
+            self.paper.height - self.top - row as f64 * self.row_pitch - self.height
+        )
+    }
+}
+
+impl FromStr for LabelLayout {
+    type Err = String;
+
+    /// An Avery product number, e.g. "5160" or "L7163".
+    fn from_str(value: &str) -> Result<LabelLayout, String> {
+        let name = value.trim().to_lowercase();
+        let name = name.trim_start_matches("avery").trim();
+        LAYOUTS.iter()
+            .find(|layout| layout.name == name)
+            .cloned()
+            .ok_or_else(|| format!(
+                "Unsupported label sheet: {}. Use one of {}",
+                value,
+                LAYOUTS.iter().map(|layout| layout.name.to_uppercase()).collect::<Vec<String>>().join(", ")
+            ))
+    }
+}
+
+/// One label's lines, salutation first, for each household with an address,
+/// plus the names of households still missing one.
+pub fn from_households(households: &[(Household, Vec<RSVP>)], home_country: &str) -> (Vec<Vec<String>>, Vec<String>) {
+    let mut labels = vec!();
+    let mut missing = vec!();
+
+    for (household, rsvps) in households {
+        match household.address {
+            Some(ref address) => {
+                let mut lines = vec!(household.salutation_for(rsvps));
+                lines.extend(address.lines(home_country));
+                labels.push(lines);
+            },
+            None => missing.push(household.salutation_for(rsvps))
+        }
+    }
+
+    (labels, missing)
+}
+
+/// Draws one label per entry in `labels`, starting `skip` labels into the
+/// first sheet so a partly used sheet can go back through the printer.
+pub fn render(labels: &[Vec<String>], layout: LabelLayout, skip: usize) -> Result<Vec<u8>, String> {
+    let skip = layout.check_skip(skip)?;
+    let (document, mut layer, fonts) = new_document("Address Labels", layout.paper)?;
+    let width = layout.width - 2.0 * PADDING;
+
+    for (offset, lines) in labels.iter().enumerate() {
+        let index = offset + skip;
+        if index > 0 && index % layout.per_sheet() == 0 {
+            layer = next_page(&document, layout.paper);
+        }
+        let (x, y) = layout.position(index);
+
+        // Shrink long addresses to fit the label's height, then center
+        // the block vertically
+        let size = (11.0f64).min((layout.height - 2.0 * PADDING) / (lines.len() as f64 * 1.2 * PT));
+        let line_height = size * 1.2 * PT;
+        let mut baseline = y + layout.height / 2.0 + lines.len() as f64 * line_height / 2.0 - size * PT;
+
+        for (number, line) in lines.iter().enumerate() {
+            let font = if number == 0 { &fonts.bold } else { &fonts.regular };
+            layer.use_text(line.as_str(), fit(line, size, width), Mm(x + PADDING), Mm(baseline), font);
+            baseline -= line_height;
+        }
+    }
+
+    finish(document)
+}
+
+/// Labels for every household with an address on `layout`, and the names of
+/// the households that still need to send one.
+pub fn for_all(layout: LabelLayout, skip: usize) -> Result<(Vec<u8>, Vec<String>), Box<Error>> {
+    let mut records : HashMap<String, Household> = Household::records()?
+        .into_iter()
+        .map(|household| (household.id.clone(), household))
+        .collect();
+
+    let households : Vec<(Household, Vec<RSVP>)> = Household::list()?
+        .into_iter()
+        .map(|rsvps| {
+            let id = rsvps[0].household_id.clone();
+            let household = records.remove(&id).unwrap_or_else(|| Household {
+                id,
+                ..Household::default()
+            });
+            (household, rsvps)
+        })
+        .collect();
+
+    let (labels, missing) = from_households(&households, &Address::home_country());
+    Ok((render(&labels, layout, skip)?, missing))
+}
+
+
+#[cfg(test)]
+mod labels_tests {
+
+    use super::*;
+
+    #[test]
+    fn test_layout_from_str() {
+        assert_eq!("5160".parse::<LabelLayout>().unwrap().per_sheet(), 30);
+        assert_eq!("Avery L7163".parse::<LabelLayout>().unwrap().per_sheet(), 14);
+        assert!("1234".parse::<LabelLayout>().is_err());
+    }
+
+    #[test]
+    fn test_layout_check_skip() {
+        let layout = "5160".parse::<LabelLayout>().unwrap();
+
+        assert_eq!(layout.check_skip(29), Ok(29));
+        assert!(layout.check_skip(30).is_err());
+        assert!(layout.check_skip(usize::max_value()).is_err());
+        assert!(render(&[], layout, 30).is_err());
+    }
+
+    #[test]
+    fn test_layouts_fit_their_paper() {
+        for layout in LAYOUTS {
+            let (x, y) = layout.position(layout.per_sheet() - 1);
+            assert!(x + layout.width <= layout.paper.width, "{} is too wide", layout.name);
+            assert!(y >= 0.0, "{} is too tall", layout.name);
+            assert_eq!(layout.position(layout.per_sheet()), layout.position(0));
+        }
+    }
+
+    #[test]
+    fn test_render_labels() {
+        let labels : Vec<Vec<String>> = (0..40)
+            .map(|i| vec!(format!("The Family {}", i), "12 Main St".to_string(), "Springfield, IL 62704".to_string()))
+            .collect();
+        let bytes = render(&labels, "5160".parse().unwrap(), 5).unwrap();
+        assert!(bytes.starts_with(b"%PDF"));
+    }
+}
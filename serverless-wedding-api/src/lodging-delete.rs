@@ -0,0 +1,41 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::{json};
+use log::{error};
+
+mod models;
+use crate::models::LodgingChoice;
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// Cancels a household's rooms, giving them back to the block.
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let household_id = path_parameters.get("id").unwrap();
+
+    Ok(match LodgingChoice::remove(household_id) {
+        Ok(Some(choice)) => respond(200, json!(choice).to_string()),
+        Ok(None) => respond(404, json!({"message": "That household hasn't booked any rooms"}).to_string()),
+        Err(err) => {
+            error!("Could not cancel lodging for household {}: {:?}", household_id, err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
@@ -0,0 +1,104 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_derive::Deserialize;
+use serde_json::{json};
+use std::ops::Deref;
+use chrono::NaiveDate;
+use log::{error};
+use uuid::Uuid;
+
+mod models;
+use crate::models::{Household, LodgingChoice, LodgingError};
+
+#[derive(Debug, Deserialize)]
+struct LodgingPayload {
+    hotel_id: String,
+    rooms: u32,
+    check_in: NaiveDate,
+    check_out: NaiveDate,
+    #[serde(default)]
+    notes: String
+}
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// Guests book rooms in a hotel's block from their RSVP link.
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let uuid : Uuid = match Uuid::parse_str(path_parameters.get("id").unwrap()) {
+        Ok(uuid) => uuid,
+        Err(_error) => return Ok(respond(404, json!({"message": "No household was found with that id"}).to_string()))
+    };
+
+    let body = request.body().deref();
+    let payload : LodgingPayload = match serde_json::from_slice(body) {
+        Ok(payload) => payload,
+        Err(error) => return Ok(respond(400, json!({"message": format!("Invalid lodging: {}", error)}).to_string()))
+    };
+
+    match Household::get(uuid) {
+        Ok(ref rsvps) if rsvps.is_empty() => {
+            return Ok(respond(404, json!({"message": "No household was found with that id"}).to_string()))
+        },
+        Ok(_rsvps) => {},
+        Err(err) => {
+            error!("Could not look up household {}: {}", uuid, err);
+            return Ok(respond(500, json!({"message": "Something went wrong!"}).to_string()))
+        }
+    }
+
+    let choice = match LodgingChoice::new(uuid.to_string(), payload.hotel_id, payload.rooms, payload.check_in, payload.check_out, payload.notes) {
+        Ok(choice) => choice,
+        Err(message) => return Ok(respond(400, json!({"message": message}).to_string()))
+    };
+
+    Ok(match choice.choose() {
+        Ok(chosen) => respond(200, json!(chosen).to_string()),
+        Err(LodgingError::HotelNotFound) => respond(404, json!({"message": LodgingError::HotelNotFound.to_string()}).to_string()),
+        Err(LodgingError::Released(hotel)) => respond(403, json!({"message": LodgingError::Released(hotel).to_string()}).to_string()),
+        Err(LodgingError::BlockFull(hotel)) => respond(409, json!({"message": LodgingError::BlockFull(hotel).to_string()}).to_string()),
+        Err(LodgingError::Changed) => respond(409, json!({"message": LodgingError::Changed.to_string()}).to_string()),
+        Err(err) => {
+            error!("Could not book lodging for household {}: {:?}", uuid, err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lambda_http::Body;
+
+    #[test]
+    #[ignore]
+    fn lodging_handler_handles() {
+
+        let mut request = Request::new(Body::from(json!({
+            "hotel_id": "5ba2b0fc-5fa1-4bd6-9d1c-2b6f3d3c8a57",
+            "rooms": 1,
+            "check_in": "2019-09-20",
+            "check_out": "2019-09-22"
+        }).to_string()));
+        *request.uri_mut() = "https://api.slswedding.com/household/3eb28445-7698-4a00-b071-49da8eaac944/lodging".parse().unwrap();
+
+        handler(request, Context::default()).expect("Expected an OK response");
+    }
+}
@@ -0,0 +1,60 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::{json};
+use log::{error};
+
+mod models;
+use crate::models::Menu;
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// The active meal and dietary options, optionally only those served at
+/// ?event=<id>.
+fn handler(
+    request: Request,
+    _: Context,
+) -> Result<impl IntoResponse, HandlerError> {
+    let query_string_parameters = request.query_string_parameters();
+    let event = query_string_parameters.get("event");
+
+    Ok(match Menu::get() {
+        Ok(menu) => {
+            let menu = menu.available(event);
+            respond(200, json!({
+                "meals": menu.meals(),
+                "dietary_restrictions": menu.diets()
+            }).to_string())
+        },
+        Err(err) => {
+            error!("Could not load the menu: {}", err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lambda_http::Body;
+
+    #[test]
+    #[ignore]
+    fn menu_handler_handles() {
+        let request = Request::new(Body::default());
+        handler(request, Context::default()).expect("Expected an OK response");
+    }
+}
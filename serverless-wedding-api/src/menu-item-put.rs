@@ -0,0 +1,53 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::{json};
+use std::ops::Deref;
+use log::{error};
+
+mod models;
+use crate::models::MenuItem;
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// Creates or replaces the menu item with the code in the path. Items are
+/// retired by sending `"active": false`.
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let code = path_parameters.get("code").unwrap().to_string();
+
+    let body = request.body().deref();
+    let mut item : MenuItem = match serde_json::from_slice(body) {
+        Ok(item) => item,
+        Err(error) => return Ok(respond(400, json!({"message": format!("Invalid menu item: {}", error)}).to_string()))
+    };
+    item.code = code;
+
+    if let Err(message) = item.validate() {
+        return Ok(respond(400, json!({"message": message}).to_string()));
+    }
+
+    Ok(match item.save() {
+        Ok(item) => respond(200, json!(item).to_string()),
+        Err(err) => {
+            error!("Could not save menu item {}: {}", item.code, err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
@@ -0,0 +1,548 @@
+use serde_derive::{Serialize, Deserialize};
+use std::vec::{Vec};
+use std::collections::{HashMap};
+use std::env;
+use std::fmt;
+use uuid::Uuid;
+use chrono::{DateTime, FixedOffset, NaiveDate, TimeZone, Utc};
+use log::{info, error};
+use serde_dynamodb;
+use rusoto_core::Region;
+use rusoto_dynamodb::{
+    DynamoDb,
+    AttributeValue,
+    DynamoDbClient,
+    GetItemInput,
+    GetItemError,
+    PutItemInput,
+    PutItemError,
+    DeleteItemInput,
+    DeleteItemError,
+    QueryInput,
+    QueryError,
+    UpdateItemInput,
+    UpdateItemError,
+    ScanError
+};
+
+use crate::models::{RSVP};
+use super::scan::scan_table;
+use super::attributes::{Counter, string_value};
+
+/// An optional outing guests can join, like horseback riding the day
+/// before. `booked` counts confirmed spots.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Activity {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub location: String,
+    pub date: NaiveDate,
+    pub capacity: u32,
+    /// Per person, in cents. Zero for free activities.
+    #[serde(default)]
+    pub cost_cents: u32,
+    pub signup_deadline: DateTime<Utc>,
+    #[serde(default)]
+    pub booked: u32
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignupStatus {
+    Confirmed,
+    Waitlisted
+}
+
+impl SignupStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SignupStatus::Confirmed => "confirmed",
+            SignupStatus::Waitlisted => "waitlisted"
+        }
+    }
+}
+
+/// One guest on an activity, keyed on the activity and RSVP so each guest
+/// signs up once.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActivitySignup {
+    pub activity_id: String,
+    pub rsvp_id: String,
+    pub household_id: String,
+    pub name: String,
+    pub status: SignupStatus,
+    pub signed_up_at: DateTime<Utc>
+}
+
+/// An activity as guests see it.
+#[derive(Debug, Clone, Serialize)]
+pub struct ActivityStatus {
+    #[serde(flatten)]
+    pub activity: Activity,
+    pub spots_left: u32,
+    pub closed: bool
+}
+
+/// Who's going on an activity and who's waiting, in the order they
+/// signed up.
+#[derive(Debug, Clone, Serialize)]
+pub struct Roster {
+    pub activity: Activity,
+    pub confirmed: Vec<ActivitySignup>,
+    pub waitlist: Vec<ActivitySignup>,
+    /// What the confirmed guests owe altogether, in cents
+    pub total_cost_cents: u32
+}
+
+#[derive(Debug)]
+pub enum ActivityError {
+    ActivityNotFound,
+    Closed(Activity),
+    NotAttending(String),
+    Get(GetItemError),
+    Query(QueryError),
+    Update(UpdateItemError),
+    Put(PutItemError),
+    Delete(DeleteItemError)
+}
+
+impl fmt::Display for ActivityError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ActivityError::ActivityNotFound => write!(f, "No activity was found with that id"),
+            ActivityError::Closed(activity) => write!(f, "Sign ups for {} have closed", activity.name),
+            ActivityError::NotAttending(name) => write!(f, "{} hasn't said they're coming to the wedding", name),
+            ActivityError::Get(error) => write!(f, "{}", error),
+            ActivityError::Query(error) => write!(f, "{}", error),
+            ActivityError::Update(error) => write!(f, "{}", error),
+            ActivityError::Put(error) => write!(f, "{}", error),
+            ActivityError::Delete(error) => write!(f, "{}", error)
+        }
+    }
+}
+
+fn activity_key(activity_id: &str) -> HashMap<String, AttributeValue> {
+    let mut key = HashMap::new();
+    key.insert(String::from("id"), string_value(activity_id));
+    key
+}
+
+fn signup_key(activity_id: &str, rsvp_id: &str) -> HashMap<String, AttributeValue> {
+    let mut key = HashMap::new();
+    key.insert(String::from("activity_id"), string_value(activity_id));
+    key.insert(String::from("rsvp_id"), string_value(rsvp_id));
+    key
+}
+
+/// Signups in the order they came in.
+fn in_order(signups: &[ActivitySignup], status: SignupStatus) -> Vec<ActivitySignup> {
+    let mut matching : Vec<ActivitySignup> = signups.iter()
+        .filter(|signup| signup.status == status)
+        .cloned()
+        .collect();
+    matching.sort_by(|a, b| (a.signed_up_at, &a.name).cmp(&(b.signed_up_at, &b.name)));
+    matching
+}
+
+/// Whoever has been on the waitlist longest.
+pub fn next_in_line(signups: &[ActivitySignup]) -> Option<ActivitySignup> {
+    in_order(signups, SignupStatus::Waitlisted).into_iter().next()
+}
+
+impl Activity {
+    /// A new activity on `date`, a day in the couple's time zone at
+    /// `offset`. Sign ups have to close before that day begins.
+    pub fn new(name: String, date: NaiveDate, capacity: u32, cost_cents: u32, signup_deadline: DateTime<Utc>, offset: FixedOffset) -> Result<Activity, String> {
+        if name.trim().is_empty() || capacity == 0 {
+            return Err(String::from("Activities need a name and at least one spot"));
+        }
+        if signup_deadline >= offset.from_local_datetime(&date.and_hms(0, 0, 0)).unwrap() {
+            return Err(String::from("Sign ups must close before the activity"));
+        }
+
+        Ok(Activity {
+            id: Uuid::new_v4().to_string(),
+            name: name.trim().to_string(),
+            description: String::new(),
+            location: String::new(),
+            date,
+            capacity,
+            cost_cents,
+            signup_deadline,
+            booked: 0
+        })
+    }
+
+    pub fn spots_left(&self) -> u32 {
+        self.capacity.saturating_sub(self.booked)
+    }
+
+    pub fn is_closed(&self, now: DateTime<Utc>) -> bool {
+        now > self.signup_deadline
+    }
+
+    pub fn status(self, now: DateTime<Utc>) -> ActivityStatus {
+        ActivityStatus {
+            spots_left: self.spots_left(),
+            closed: self.is_closed(now),
+            activity: self
+        }
+    }
+
+    pub fn create(&self) -> Result<Activity, PutItemError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let put_item_input = PutItemInput {
+            table_name: env::var("ACTIVITY_TABLE_NAME").unwrap(),
+            item: serde_dynamodb::to_hashmap(self).unwrap(),
+            ..PutItemInput::default()
+        };
+
+        client.put_item(put_item_input).sync()?;
+        info!("Created activity {:?}", self);
+        Ok(self.clone())
+    }
+
+    pub fn get(uuid: Uuid) -> Result<Activity, GetItemError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let get_item_input = GetItemInput {
+            table_name: env::var("ACTIVITY_TABLE_NAME").unwrap(),
+            key: activity_key(&uuid.to_string()),
+            ..GetItemInput::default()
+        };
+
+        match client.get_item(get_item_input).sync()?.item {
+            Some(item) => Ok(serde_dynamodb::from_hashmap(item).unwrap()),
+            None => Err(GetItemError::ResourceNotFound(format!("No activity with id {}", uuid)))
+        }
+    }
+
+    /// Every activity, by date.
+    pub fn all() -> Result<Vec<Activity>, ScanError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+        let mut activities : Vec<Activity> = scan_table(&client, env::var("ACTIVITY_TABLE_NAME").unwrap())?;
+        activities.sort_by(|a, b| (a.date, &a.name).cmp(&(b.date, &b.name)));
+        Ok(activities)
+    }
+
+    /// The spots confirmed guests hold.
+    fn booked(activity_id: &str) -> Counter {
+        Counter {
+            table_name: env::var("ACTIVITY_TABLE_NAME").unwrap(),
+            key: activity_key(activity_id),
+            count: "booked",
+            limit: "capacity"
+        }
+    }
+}
+
+impl ActivitySignup {
+    /// Everyone signed up for an activity, confirmed or waiting.
+    pub fn for_activity(activity_id: &str) -> Result<Vec<ActivitySignup>, QueryError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let mut values = HashMap::new();
+        values.insert(String::from(":activity_id"), string_value(activity_id));
+
+        let query_input = QueryInput {
+            table_name: env::var("ACTIVITY_SIGNUP_TABLE_NAME").unwrap(),
+            key_condition_expression: Some(String::from("activity_id = :activity_id")),
+            expression_attribute_values: Some(values),
+            ..QueryInput::default()
+        };
+
+        Ok(client.query(query_input).sync()?
+            .items
+            .unwrap_or_default()
+            .into_iter()
+            .map(|item| serde_dynamodb::from_hashmap(item).unwrap())
+            .collect())
+    }
+
+    /// Every activity anyone in a household is signed up for.
+    pub fn for_household(household_id: &str) -> Result<Vec<ActivitySignup>, ScanError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+        let mut signups : Vec<ActivitySignup> = scan_table(&client, env::var("ACTIVITY_SIGNUP_TABLE_NAME").unwrap())?;
+        signups.retain(|signup| signup.household_id == household_id);
+        Ok(signups)
+    }
+
+    fn get(client: &DynamoDbClient, activity_id: &str, rsvp_id: &str) -> Result<Option<ActivitySignup>, GetItemError> {
+        let get_item_input = GetItemInput {
+            table_name: env::var("ACTIVITY_SIGNUP_TABLE_NAME").unwrap(),
+            key: signup_key(activity_id, rsvp_id),
+            ..GetItemInput::default()
+        };
+
+        Ok(client.get_item(get_item_input).sync()?
+            .item
+            .map(|item| serde_dynamodb::from_hashmap(item).unwrap()))
+    }
+
+    /// Signs a guest up, taking a spot if one is left and joining the
+    /// waitlist if not. Only guests coming to something can sign up.
+    /// Signing up twice, even at the same moment, returns the existing
+    /// signup.
+    pub fn join(activity_id: Uuid, rsvp: &RSVP) -> Result<ActivitySignup, ActivityError> {
+        let activity = Activity::get(activity_id).map_err(|_| ActivityError::ActivityNotFound)?;
+        if activity.is_closed(Utc::now()) {
+            return Err(ActivityError::Closed(activity));
+        }
+        if !rsvp.is_attending_any() {
+            return Err(ActivityError::NotAttending(rsvp.name.clone()));
+        }
+
+        let client = DynamoDbClient::new(Region::UsEast1);
+        if let Some(existing) = ActivitySignup::get(&client, &activity.id, &rsvp.id).map_err(ActivityError::Get)? {
+            return Ok(existing);
+        }
+
+        // Nobody jumps the queue, even if a spot opened before the waitlist
+        // was promoted
+        let waiting = next_in_line(&ActivitySignup::for_activity(&activity.id).map_err(ActivityError::Query)?).is_some();
+        let status = if waiting {
+            SignupStatus::Waitlisted
+        } else {
+            match Activity::booked(&activity.id).reserve(&client, activity.capacity, 1) {
+                Ok(()) => SignupStatus::Confirmed,
+                Err(UpdateItemError::ConditionalCheckFailed(_)) => SignupStatus::Waitlisted,
+                Err(error) => return Err(ActivityError::Update(error))
+            }
+        };
+
+        let signup = ActivitySignup {
+            activity_id: activity.id.clone(),
+            rsvp_id: rsvp.id.clone(),
+            household_id: rsvp.household_id.clone(),
+            name: rsvp.name.clone(),
+            status,
+            signed_up_at: Utc::now()
+        };
+
+        let put_item_input = PutItemInput {
+            table_name: env::var("ACTIVITY_SIGNUP_TABLE_NAME").unwrap(),
+            item: serde_dynamodb::to_hashmap(&signup).unwrap(),
+            condition_expression: Some(String::from("attribute_not_exists(rsvp_id)")),
+            ..PutItemInput::default()
+        };
+
+        if let Err(error) = client.put_item(put_item_input).sync() {
+            if status == SignupStatus::Confirmed {
+                if let Err(release_error) = Activity::booked(&activity.id).release(&client, 1) {
+                    error!("Could not release a spot on activity {}: {:?}", activity.id, release_error);
+                }
+            }
+            return match error {
+                // Signed up twice at once, e.g. a double click
+                PutItemError::ConditionalCheckFailed(message) => ActivitySignup::get(&client, &activity.id, &rsvp.id)
+                    .map_err(ActivityError::Get)?
+                    .ok_or(ActivityError::Put(PutItemError::ConditionalCheckFailed(message))),
+                error => Err(ActivityError::Put(error))
+            };
+        }
+
+        info!("{} is {} for {}", signup.name, status.as_str(), activity.name);
+
+        // Catch up a waitlist that a failed promotion left behind
+        if status == SignupStatus::Waitlisted {
+            let promoted = ActivitySignup::promote(&client, activity_id)?;
+            if let Some(promoted) = promoted.into_iter().find(|promoted| promoted.rsvp_id == signup.rsvp_id) {
+                return Ok(promoted);
+            }
+        }
+
+        Ok(signup)
+    }
+
+    /// Takes a guest off an activity. When they held a spot it goes to the
+    /// waitlist. Returns the removed signup and anyone promoted.
+    pub fn leave(activity_id: Uuid, rsvp_id: &str) -> Result<(Option<ActivitySignup>, Vec<ActivitySignup>), ActivityError> {
+        let activity = Activity::get(activity_id).map_err(|_| ActivityError::ActivityNotFound)?;
+        if activity.is_closed(Utc::now()) {
+            return Err(ActivityError::Closed(activity));
+        }
+
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let delete_item_input = DeleteItemInput {
+            table_name: env::var("ACTIVITY_SIGNUP_TABLE_NAME").unwrap(),
+            key: signup_key(&activity.id, rsvp_id),
+            return_values: Some(String::from("ALL_OLD")),
+            ..DeleteItemInput::default()
+        };
+
+        let removed : Option<ActivitySignup> = client.delete_item(delete_item_input).sync()
+            .map_err(ActivityError::Delete)?
+            .attributes
+            .map(|item| serde_dynamodb::from_hashmap(item).unwrap());
+
+        match removed {
+            Some(ref removed) if removed.status == SignupStatus::Confirmed => {
+                Activity::booked(&activity.id).release(&client, 1).map_err(ActivityError::Update)?;
+                info!("{} gave up their spot on {}", removed.name, activity.name);
+                let promoted = ActivitySignup::promote(&client, activity_id)?;
+                Ok((Some(removed.clone()), promoted))
+            },
+            removed => Ok((removed, vec!()))
+        }
+    }
+
+    /// Moves guests off the waitlist, longest waiting first, while spots
+    /// are open.
+    fn promote(client: &DynamoDbClient, activity_id: Uuid) -> Result<Vec<ActivitySignup>, ActivityError> {
+        let mut promoted = vec!();
+
+        loop {
+            let activity = Activity::get(activity_id).map_err(ActivityError::Get)?;
+            if activity.spots_left() == 0 {
+                break;
+            }
+            let signups = ActivitySignup::for_activity(&activity.id).map_err(ActivityError::Query)?;
+            let next = match next_in_line(&signups) {
+                Some(next) => next,
+                None => break
+            };
+
+            match Activity::booked(&activity.id).reserve(client, activity.capacity, 1) {
+                Ok(()) => {},
+                Err(UpdateItemError::ConditionalCheckFailed(_)) => break,
+                Err(error) => return Err(ActivityError::Update(error))
+            }
+
+            match next.set_status(client, SignupStatus::Waitlisted, SignupStatus::Confirmed) {
+                Ok(()) => {
+                    info!("{} moved off the waitlist for {}", next.name, activity.name);
+                    promoted.push(ActivitySignup { status: SignupStatus::Confirmed, ..next });
+                },
+                // They left the waitlist while we were promoting them
+                Err(UpdateItemError::ConditionalCheckFailed(_)) => {
+                    Activity::booked(&activity.id).release(client, 1).map_err(ActivityError::Update)?;
+                },
+                Err(error) => {
+                    if let Err(release_error) = Activity::booked(&activity.id).release(client, 1) {
+                        error!("Could not release a spot on activity {}: {:?}", activity.id, release_error);
+                    }
+                    return Err(ActivityError::Update(error));
+                }
+            }
+        }
+
+        Ok(promoted)
+    }
+
+    /// The condition on the current status means a guest who just left
+    /// isn't written back.
+    fn set_status(&self, client: &DynamoDbClient, from: SignupStatus, to: SignupStatus) -> Result<(), UpdateItemError> {
+        let mut names = HashMap::new();
+        names.insert(String::from("#status"), String::from("status"));
+
+        let mut values = HashMap::new();
+        values.insert(String::from(":from"), string_value(from.as_str()));
+        values.insert(String::from(":to"), string_value(to.as_str()));
+
+        client.update_item(UpdateItemInput {
+            table_name: env::var("ACTIVITY_SIGNUP_TABLE_NAME").unwrap(),
+            key: signup_key(&self.activity_id, &self.rsvp_id),
+            update_expression: Some(String::from("SET #status = :to")),
+            condition_expression: Some(String::from("#status = :from")),
+            expression_attribute_names: Some(names),
+            expression_attribute_values: Some(values),
+            ..UpdateItemInput::default()
+        }).sync()?;
+
+        Ok(())
+    }
+}
+
+impl Roster {
+    pub fn new(activity: Activity, signups: &[ActivitySignup]) -> Roster {
+        let signups : Vec<ActivitySignup> = signups.iter()
+            .filter(|signup| signup.activity_id == activity.id)
+            .cloned()
+            .collect();
+        let confirmed = in_order(&signups, SignupStatus::Confirmed);
+
+        Roster {
+            total_cost_cents: confirmed.len() as u32 * activity.cost_cents,
+            waitlist: in_order(&signups, SignupStatus::Waitlisted),
+            confirmed,
+            activity
+        }
+    }
+
+    pub fn get(activity_id: Uuid) -> Result<Roster, ActivityError> {
+        let activity = Activity::get(activity_id).map_err(|_| ActivityError::ActivityNotFound)?;
+        let signups = ActivitySignup::for_activity(&activity.id).map_err(ActivityError::Query)?;
+        Ok(Roster::new(activity, &signups))
+    }
+}
+
+
+#[cfg(test)]
+mod activity_tests {
+
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn signup(activity: &Activity, name: &str, status: SignupStatus, minutes: i64) -> ActivitySignup {
+        ActivitySignup {
+            activity_id: activity.id.clone(),
+            rsvp_id: Uuid::new_v4().to_string(),
+            household_id: Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            status,
+            signed_up_at: Utc.ymd(2019, 8, 1).and_hms(12, 0, 0) + Duration::minutes(minutes)
+        }
+    }
+
+    #[test]
+    fn test_activity_new() {
+        let deadline = Utc.ymd(2019, 9, 1).and_hms(23, 59, 59);
+        let activity = Activity::new(" Horseback Riding ".to_string(), NaiveDate::from_ymd(2019, 9, 20), 8, 6500, deadline, FixedOffset::east(0)).unwrap();
+        assert_eq!(activity.name, "Horseback Riding");
+        assert!(!activity.is_closed(deadline));
+        assert!(activity.is_closed(deadline + Duration::seconds(1)));
+
+        let status = Activity { booked: 8, ..activity }.status(deadline);
+        assert_eq!(status.spots_left, 0);
+        assert!(!status.closed);
+
+        assert!(Activity::new("Horseback Riding".to_string(), NaiveDate::from_ymd(2019, 9, 20), 0, 0, deadline, FixedOffset::east(0)).is_err());
+        assert!(Activity::new("Horseback Riding".to_string(), NaiveDate::from_ymd(2019, 8, 20), 8, 0, deadline, FixedOffset::east(0)).is_err());
+    }
+
+    #[test]
+    fn test_activity_new_closes_before_the_day() {
+        let denver = FixedOffset::west(6 * 3600);
+        let new = |deadline| Activity::new("Trail Ride".to_string(), NaiveDate::from_ymd(2019, 9, 20), 8, 0, deadline, denver);
+
+        // Midnight on the 20th in Denver is 6am UTC
+        assert!(new(Utc.ymd(2019, 9, 20).and_hms(5, 59, 59)).is_ok());
+        assert!(new(Utc.ymd(2019, 9, 20).and_hms(6, 0, 0)).is_err());
+        assert!(new(Utc.ymd(2019, 9, 20).and_hms(15, 0, 0)).is_err());
+    }
+
+    #[test]
+    fn test_roster_orders_signups() {
+        let deadline = Utc.ymd(2019, 9, 1).and_hms(23, 59, 59);
+        let activity = Activity::new("Trail Ride".to_string(), NaiveDate::from_ymd(2019, 9, 20), 2, 6500, deadline, FixedOffset::east(0)).unwrap();
+        let signups = vec!(
+            signup(&activity, "Ling Ling", SignupStatus::Waitlisted, 30),
+            signup(&activity, "Cynthia Young", SignupStatus::Confirmed, 10),
+            signup(&activity, "Blaine Price", SignupStatus::Confirmed, 0),
+            signup(&activity, "Dana Scully", SignupStatus::Waitlisted, 20)
+        );
+
+        assert_eq!(next_in_line(&signups).unwrap().name, "Dana Scully");
+
+        let roster = Roster::new(activity, &signups);
+        let names = |signups: &[ActivitySignup]| signups.iter().map(|signup| signup.name.clone()).collect::<Vec<String>>();
+        assert_eq!(names(&roster.confirmed), vec!("Blaine Price", "Cynthia Young"));
+        assert_eq!(names(&roster.waitlist), vec!("Dana Scully", "Ling Ling"));
+        assert_eq!(roster.total_cost_cents, 13000);
+    }
+}
@@ -0,0 +1,240 @@
+use serde_derive::{Serialize, Deserialize};
+use std::env;
+use std::fmt;
+
+/// How a country orders the last lines of an address.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Layout {
+    /// "Springfield, IL 62704"
+    CityRegionPostal,
+    /// "75007 Paris"
+    PostalCity,
+    /// "London" then "SW1A 1AA" on its own line
+    CityThenPostal,
+    /// "00184 Roma RM"
+    PostalCityRegion
+}
+
+struct CountryFormat {
+    code: &'static str,
+    name: &'static str,
+    layout: Layout,
+    region_required: bool,
+    /// Postal code shapes, where 9 is any digit and A any letter. Empty means
+    /// any postal code, or none, is accepted.
+    postal_codes: &'static [&'static str]
+}
+
+const COUNTRIES : &[CountryFormat] = &[
+    CountryFormat { code: "US", name: "UNITED STATES", layout: Layout::CityRegionPostal, region_required: true, postal_codes: &["99999", "99999-9999"] },
+    CountryFormat { code: "CA", name: "CANADA", layout: Layout::CityRegionPostal, region_required: true, postal_codes: &["A9A 9A9"] },
+    CountryFormat { code: "AU", name: "AUSTRALIA", layout: Layout::CityRegionPostal, region_required: true, postal_codes: &["9999"] },
+    CountryFormat { code: "MX", name: "MEXICO", layout: Layout::PostalCityRegion, region_required: true, postal_codes: &["99999"] },
+    CountryFormat { code: "GB", name: "UNITED KINGDOM", layout: Layout::CityThenPostal, region_required: false, postal_codes: &[] },
+    CountryFormat { code: "IE", name: "IRELAND", layout: Layout::CityThenPostal, region_required: false, postal_codes: &[] },
+    CountryFormat { code: "DE", name: "GERMANY", layout: Layout::PostalCity, region_required: false, postal_codes: &["99999"] },
+    CountryFormat { code: "FR", name: "FRANCE", layout: Layout::PostalCity, region_required: false, postal_codes: &["99999"] },
+    CountryFormat { code: "ES", name: "SPAIN", layout: Layout::PostalCity, region_required: false, postal_codes: &["99999"] },
+    CountryFormat { code: "IT", name: "ITALY", layout: Layout::PostalCityRegion, region_required: false, postal_codes: &["99999"] },
+    CountryFormat { code: "NL", name: "NETHERLANDS", layout: Layout::PostalCity, region_required: false, postal_codes: &["9999 AA"] },
+    CountryFormat { code: "BE", name: "BELGIUM", layout: Layout::PostalCity, region_required: false, postal_codes: &["9999"] },
+    CountryFormat { code: "CH", name: "SWITZERLAND", layout: Layout::PostalCity, region_required: false, postal_codes: &["9999"] },
+    CountryFormat { code: "AT", name: "AUSTRIA", layout: Layout::PostalCity, region_required: false, postal_codes: &["9999"] },
+    CountryFormat { code: "DK", name: "DENMARK", layout: Layout::PostalCity, region_required: false, postal_codes: &["9999"] },
+    CountryFormat { code: "SE", name: "SWEDEN", layout: Layout::PostalCity, region_required: false, postal_codes: &["999 99"] },
+    CountryFormat { code: "NO", name: "NORWAY", layout: Layout::PostalCity, region_required: false, postal_codes: &["9999"] },
+    CountryFormat { code: "PT", name: "PORTUGAL", layout: Layout::PostalCity, region_required: false, postal_codes: &["9999-999"] },
+    CountryFormat { code: "JP", name: "JAPAN", layout: Layout::CityRegionPostal, region_required: true, postal_codes: &["999-9999"] },
+    CountryFormat { code: "NZ", name: "NEW ZEALAND", layout: Layout::CityRegionPostal, region_required: false, postal_codes: &["9999"] }
+];
+
+fn country_format(code: &str) -> Option<&'static CountryFormat> {
+    COUNTRIES.iter().find(|format| format.code == code)
+}
+
+fn matches_shape(value: &str, shape: &str) -> bool {
+    value.chars().count() == shape.chars().count() && value.chars().zip(shape.chars()).all(|(c, s)| match s {
+        '9' => c.is_ascii_digit(),
+        'A' => c.is_ascii_alphabetic(),
+        literal => c == literal
+    })
+}
+
+#[derive(Debug, PartialEq)]
+pub enum AddressError {
+    Missing(&'static str),
+    InvalidCountry(String),
+    InvalidPostalCode(String)
+}
+
+impl fmt::Display for AddressError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AddressError::Missing(field) => write!(f, "The address is missing its {}", field),
+            AddressError::InvalidCountry(country) => write!(f, "{} is not a two letter country code", country),
+            AddressError::InvalidPostalCode(code) => write!(f, "{} is not a valid postal code for that country", code)
+        }
+    }
+}
+
+/// A postal address. `region` is the state, province, prefecture or county,
+/// and `country` an ISO 3166 two letter code.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Address {
+    pub line1: String,
+    #[serde(default)]
+    pub line2: String,
+    pub city: String,
+    #[serde(default)]
+    pub region: String,
+    #[serde(default)]
+    pub postal_code: String,
+    pub country: String
+}
+
+impl Address {
+    /// The country mail is sent from, which is left off labels. Set with
+    /// `HOME_COUNTRY`.
+    pub fn home_country() -> String {
+        env::var("HOME_COUNTRY")
+            .ok()
+            .filter(|country| !country.is_empty())
+            .unwrap_or_else(|| String::from("US"))
+            .to_uppercase()
+    }
+
+    /// Trims every field, upper cases the country and postal code, and checks
+    /// the address against what's known about the country's format.
+    /// Countries we have no format for only need a street and city.
+    pub fn normalize(&self) -> Result<Address, AddressError> {
+        let mut address = Address {
+            line1: self.line1.trim().to_string(),
+            line2: self.line2.trim().to_string(),
+            city: self.city.trim().to_string(),
+            region: self.region.trim().to_string(),
+            postal_code: self.postal_code.trim().to_uppercase(),
+            country: self.country.trim().to_uppercase()
+        };
+
+        if address.line1.is_empty() {
+            return Err(AddressError::Missing("street address"));
+        }
+        if address.city.is_empty() {
+            return Err(AddressError::Missing("city"));
+        }
+        if address.country.len() != 2 || !address.country.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(AddressError::InvalidCountry(address.country));
+        }
+
+        if let Some(format) = country_format(&address.country) {
+            if format.region_required && address.region.is_empty() {
+                return Err(AddressError::Missing("state or province"));
+            }
+            if format.code == "US" || format.code == "CA" {
+                address.region = address.region.to_uppercase();
+            }
+            if !format.postal_codes.is_empty() {
+                if address.postal_code.is_empty() {
+                    return Err(AddressError::Missing("postal code"));
+                }
+                if !format.postal_codes.iter().any(|shape| matches_shape(&address.postal_code, shape)) {
+                    return Err(AddressError::InvalidPostalCode(address.postal_code));
+                }
+            }
+        }
+
+        Ok(address)
+    }
+
+    /// The address as it's written on an envelope, in the country's order.
+    /// The country is named on the last line unless it's `home_country`.
+    pub fn lines(&self, home_country: &str) -> Vec<String> {
+        let mut lines = vec!(self.line1.clone());
+        if !self.line2.is_empty() {
+            lines.push(self.line2.clone());
+        }
+
+        let format = country_format(&self.country);
+        let layout = format.map_or(Layout::CityRegionPostal, |format| format.layout);
+        let join = |parts: &[&str]| parts.iter()
+            .filter(|part| !part.is_empty())
+            .cloned()
+            .collect::<Vec<&str>>()
+            .join(" ");
+
+        match layout {
+            Layout::CityRegionPostal => {
+                let city = match self.region.as_str() {
+                    "" => self.city.clone(),
+                    _ => format!("{},", self.city)
+                };
+                lines.push(join(&[&city, &self.region, &self.postal_code]));
+            },
+            Layout::PostalCity => lines.push(join(&[&self.postal_code, &self.city])),
+            Layout::PostalCityRegion => lines.push(join(&[&self.postal_code, &self.city, &self.region])),
+            Layout::CityThenPostal => {
+                lines.push(join(&[&self.city, &self.region]));
+                if !self.postal_code.is_empty() {
+                    lines.push(self.postal_code.clone());
+                }
+            }
+        }
+
+        if !self.country.eq_ignore_ascii_case(home_country) {
+            lines.push(format.map_or(self.country.clone(), |format| format.name.to_string()));
+        }
+
+        lines
+    }
+}
+
+
+#[cfg(test)]
+mod address_tests {
+
+    use super::*;
+
+    fn address(line1: &str, city: &str, region: &str, postal_code: &str, country: &str) -> Address {
+        Address {
+            line1: line1.to_string(),
+            city: city.to_string(),
+            region: region.to_string(),
+            postal_code: postal_code.to_string(),
+            country: country.to_string(),
+            ..Address::default()
+        }
+    }
+
+    #[test]
+    fn test_normalize() {
+        let normalized = address(" 12 Main St ", "Springfield", "il", "62704", "us").normalize().unwrap();
+        assert_eq!(normalized.line1, "12 Main St");
+        assert_eq!(normalized.region, "IL");
+        assert_eq!(normalized.country, "US");
+
+        assert_eq!(address("12 Main St", "Springfield", "", "62704", "US").normalize(), Err(AddressError::Missing("state or province")));
+        assert_eq!(address("12 Main St", "Springfield", "IL", "627", "US").normalize(), Err(AddressError::InvalidPostalCode("627".to_string())));
+        assert_eq!(address("12 Main St", "Springfield", "IL", "62704", "USA").normalize(), Err(AddressError::InvalidCountry("USA".to_string())));
+        assert!(address("1 Rue", "Ottawa", "ON", "k1a 0b1", "CA").normalize().is_ok());
+        // Countries without a known format only need a street and city
+        assert!(address("Calle 1", "Bogotá", "", "", "CO").normalize().is_ok());
+    }
+
+    #[test]
+    fn test_lines() {
+        let domestic = address("12 Main St", "Springfield", "IL", "62704", "US");
+        assert_eq!(domestic.lines("US"), vec!("12 Main St", "Springfield, IL 62704"));
+
+        let french = address("7 Rue de Grenelle", "Paris", "", "75007", "FR");
+        assert_eq!(french.lines("US"), vec!("7 Rue de Grenelle", "75007 Paris", "FRANCE"));
+
+        let british = Address {
+            line2: "Flat 2".to_string(),
+            ..address("10 Downing St", "London", "", "SW1A 2AA", "GB")
+        };
+        assert_eq!(british.lines("GB"), vec!("10 Downing St", "Flat 2", "London", "SW1A 2AA"));
+
+        let unknown = address("Calle 1", "Bogotá", "", "", "CO");
+        assert_eq!(unknown.lines("US"), vec!("Calle 1", "Bogotá", "CO"));
+    }
+}
@@ -0,0 +1,187 @@
+use serde_derive::{Serialize, Deserialize};
+use std::vec::{Vec};
+use std::collections::{HashSet};
+use serde_json::{Value};
+
+use crate::models::{RSVP};
+
+/// The major food allergens, as codes. Anything else a guest enters is
+/// kept as a custom allergen.
+pub const MAJOR_ALLERGENS : [&str; 9] = [
+    "milk",
+    "eggs",
+    "fish",
+    "shellfish",
+    "tree_nuts",
+    "peanuts",
+    "wheat",
+    "soy",
+    "sesame"
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Mild,
+    Moderate,
+    Severe
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Mild => "mild",
+            Severity::Moderate => "moderate",
+            Severity::Severe => "severe"
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Allergy {
+    pub allergen: String,
+    pub severity: Severity,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>
+}
+
+impl Allergy {
+    /// Whether the allergen is one the guest typed in rather than a major allergen
+    pub fn is_custom(&self) -> bool {
+        !MAJOR_ALLERGENS.contains(&self.allergen.as_str())
+    }
+
+    pub fn is_severe(&self) -> bool {
+        self.severity == Severity::Severe
+    }
+
+    /// e.g. "tree nuts (severe)"
+    pub fn label(&self) -> String {
+        format!("{} ({})", self.allergen.replace("_", " "), self.severity.as_str())
+    }
+}
+
+/// Matches major allergens however they were typed, so "Tree nuts" and
+/// "tree-nuts" both become `tree_nuts`. Custom allergens are only trimmed.
+fn normalize_allergen(allergen: &str) -> String {
+    let code = allergen.trim()
+        .to_lowercase()
+        .replace(" ", "_")
+        .replace("-", "_");
+
+    if MAJOR_ALLERGENS.contains(&code.as_str()) {
+        code
+    } else {
+        allergen.trim().to_string()
+    }
+}
+
+/// Parses and normalizes the `allergies` list from a PATCH payload.
+pub fn parse_allergies(value: &Value) -> Result<Vec<Allergy>, String> {
+    let allergies : Vec<Allergy> = serde_json::from_value(value.clone())
+        .map_err(|err| format!("Allergies must be a list of allergens with a severity of mild, moderate or severe: {}", err))?;
+
+    let mut seen = HashSet::new();
+    let mut normalized = vec!();
+    for allergy in allergies {
+        let allergen = normalize_allergen(&allergy.allergen);
+        if allergen.is_empty() {
+            return Err(String::from("Please name the allergen"));
+        }
+        if !seen.insert(allergen.to_lowercase()) {
+            return Err(format!("{} is listed more than once", allergen));
+        }
+
+        normalized.push(Allergy {
+            allergen,
+            severity: allergy.severity,
+            notes: allergy.notes
+                .map(|notes| notes.trim().to_string())
+                .filter(|notes| !notes.is_empty())
+        });
+    }
+
+    Ok(normalized)
+}
+
+/// A compact description for exports, most severe first, e.g.
+/// "shellfish (severe); cilantro (mild)".
+pub fn describe_allergies(allergies: &[Allergy]) -> String {
+    let mut allergies : Vec<&Allergy> = allergies.iter().collect();
+    allergies.sort_by(|a, b| b.severity.cmp(&a.severity));
+    allergies.iter()
+        .map(|allergy| allergy.label())
+        .collect::<Vec<String>>()
+        .join("; ")
+}
+
+/// A severe allergy called out on its own so it can't be missed in a list
+/// of meal choices.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SevereAllergy {
+    pub rsvp_id: String,
+    pub household_id: String,
+    pub name: String,
+    pub allergen: String,
+    pub notes: Option<String>
+}
+
+impl SevereAllergy {
+    pub fn collect<'a, I: IntoIterator<Item = &'a RSVP>>(rsvps: I) -> Vec<SevereAllergy> {
+        let mut severe = vec!();
+        for rsvp in rsvps {
+            for allergy in rsvp.allergies.iter().filter(|allergy| allergy.is_severe()) {
+                severe.push(SevereAllergy {
+                    rsvp_id: rsvp.id.clone(),
+                    household_id: rsvp.household_id.clone(),
+                    name: rsvp.name.clone(),
+                    allergen: allergy.allergen.replace("_", " "),
+                    notes: allergy.notes.clone()
+                });
+            }
+        }
+        severe
+    }
+}
+
+#[cfg(test)]
+mod allergy_tests {
+
+    use super::*;
+    use serde_json::{json};
+
+    #[test]
+    fn test_parse_allergies_normalizes() {
+        let allergies = parse_allergies(&json!([
+            {"allergen": "Tree nuts", "severity": "severe"},
+            {"allergen": " cilantro ", "severity": "mild", "notes": "  "}
+        ])).unwrap();
+
+        assert_eq!(allergies[0].allergen, "tree_nuts");
+        assert!(!allergies[0].is_custom());
+        assert!(allergies[0].is_severe());
+        assert_eq!(allergies[1].allergen, "cilantro");
+        assert!(allergies[1].is_custom());
+        assert_eq!(allergies[1].notes, None);
+    }
+
+    #[test]
+    fn test_parse_allergies_rejects_bad_input() {
+        assert!(parse_allergies(&json!([{"allergen": "peanuts", "severity": "deadly"}])).is_err());
+        assert!(parse_allergies(&json!([{"allergen": " ", "severity": "mild"}])).is_err());
+        assert!(parse_allergies(&json!([
+            {"allergen": "peanuts", "severity": "mild"},
+            {"allergen": "Peanuts", "severity": "severe"}
+        ])).is_err());
+    }
+
+    #[test]
+    fn test_describe_allergies() {
+        let allergies = parse_allergies(&json!([
+            {"allergen": "cilantro", "severity": "mild"},
+            {"allergen": "shellfish", "severity": "severe"}
+        ])).unwrap();
+
+        assert_eq!(describe_allergies(&allergies), "shellfish (severe); cilantro (mild)");
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::{HashMap};
+use rusoto_dynamodb::{
+    DynamoDb,
+    AttributeValue,
+    DynamoDbClient,
+    UpdateItemInput,
+    UpdateItemError
+};
+
+pub fn string_value(value: &str) -> AttributeValue {
+    AttributeValue {
+        s: Some(value.to_string()),
+        ..Default::default()
+    }
+}
+
+pub fn number_value(value: u32) -> AttributeValue {
+    AttributeValue {
+        n: Some(value.to_string()),
+        ..Default::default()
+    }
+}
+
+/// A count kept on an item against a limit on the same item, like the
+/// rooms booked out of a hotel block. It's only moved by conditional
+/// updates, so it never goes past the limit or below zero however many
+/// guests claim at once.
+pub struct Counter {
+    pub table_name: String,
+    pub key: HashMap<String, AttributeValue>,
+    /// The attribute holding the count, e.g. "booked"
+    pub count: &'static str,
+    /// The attribute holding the limit, e.g. "capacity"
+    pub limit: &'static str
+}
+
+impl Counter {
+    /// Adds `amount`, failing with `ConditionalCheckFailed` if that would
+    /// take the count past `limit`, or if the stored limit is no longer
+    /// `limit`. The highest count that leaves room is worked out here since
+    /// DynamoDB conditions can't add.
+    pub fn reserve(&self, client: &DynamoDbClient, limit: u32, amount: u32) -> Result<(), UpdateItemError> {
+        if amount == 0 || amount > limit {
+            return Err(UpdateItemError::ConditionalCheckFailed(format!("Only {} can be claimed", limit)));
+        }
+
+        let mut names = HashMap::new();
+        names.insert(String::from("#count"), self.count.to_string());
+        names.insert(String::from("#limit"), self.limit.to_string());
+
+        let mut values = HashMap::new();
+        values.insert(String::from(":amount"), number_value(amount));
+        values.insert(String::from(":zero"), number_value(0));
+        values.insert(String::from(":limit"), number_value(limit));
+        values.insert(String::from(":most"), number_value(limit - amount));
+
+        client.update_item(UpdateItemInput {
+            table_name: self.table_name.clone(),
+            key: self.key.clone(),
+            update_expression: Some(String::from("SET #count = if_not_exists(#count, :zero) + :amount")),
+            condition_expression: Some(String::from("#limit = :limit AND (attribute_not_exists(#count) OR #count <= :most)")),
+            expression_attribute_names: Some(names),
+            expression_attribute_values: Some(values),
+            ..UpdateItemInput::default()
+        }).sync()?;
+
+        Ok(())
+    }
+
+    /// Gives back `amount` claimed earlier.
+    pub fn release(&self, client: &DynamoDbClient, amount: u32) -> Result<(), UpdateItemError> {
+        let mut names = HashMap::new();
+        names.insert(String::from("#count"), self.count.to_string());
+
+        let mut values = HashMap::new();
+        values.insert(String::from(":amount"), number_value(amount));
+
+        client.update_item(UpdateItemInput {
+            table_name: self.table_name.clone(),
+            key: self.key.clone(),
+            update_expression: Some(String::from("SET #count = #count - :amount")),
+            condition_expression: Some(String::from("#count >= :amount")),
+            expression_attribute_names: Some(names),
+            expression_attribute_values: Some(values),
+            ..UpdateItemInput::default()
+        }).sync()?;
+
+        Ok(())
+    }
+}
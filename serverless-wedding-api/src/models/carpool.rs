@@ -0,0 +1,270 @@
+use serde_derive::{Serialize, Deserialize};
+use std::vec::{Vec};
+use std::collections::{HashMap};
+use std::env;
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use log::{info};
+use serde_dynamodb;
+use rusoto_core::Region;
+use rusoto_dynamodb::{
+    DynamoDb,
+    AttributeValue,
+    DynamoDbClient,
+    PutItemInput,
+    PutItemError,
+    DeleteItemInput,
+    DeleteItemError,
+    ScanError
+};
+
+use crate::models::{RSVP};
+use super::scan::scan_table;
+
+/// How far apart a driver's and rider's arrivals can be by default.
+pub const DEFAULT_WINDOW_MINUTES : i64 = 90;
+
+/// The most seats a driver can offer.
+const MAX_SEATS : u32 = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CarpoolRole {
+    Driver,
+    Rider
+}
+
+/// A guest who opted in to carpooling from the airport. The airport and
+/// arrival are copied from their travel plans when they opt in.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CarpoolEntry {
+    pub rsvp_id: String,
+    pub household_id: String,
+    pub name: String,
+    /// Shared with the guests they're matched with
+    pub email_address: String,
+    pub role: CarpoolRole,
+    /// Seats a driver has free, or a rider needs
+    pub seats: u32,
+    pub airport: String,
+    pub arrival: NaiveDateTime,
+    pub updated_at: DateTime<Utc>
+}
+
+/// A driver and the riders matched to them.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Ride {
+    pub driver: CarpoolEntry,
+    pub riders: Vec<CarpoolEntry>,
+    pub seats_left: u32
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CarpoolMatches {
+    pub rides: Vec<Ride>,
+    /// Riders no driver could take
+    pub unmatched: Vec<CarpoolEntry>
+}
+
+fn rsvp_key(rsvp_id: &str) -> HashMap<String, AttributeValue> {
+    let mut key = HashMap::new();
+    key.insert(String::from("rsvp_id"), AttributeValue {
+        s: Some(rsvp_id.to_string()),
+        ..Default::default()
+    });
+    key
+}
+
+impl CarpoolEntry {
+    /// Opts a guest in. They need to have told us when and where they land.
+    pub fn new(rsvp: &RSVP, role: CarpoolRole, seats: u32) -> Result<CarpoolEntry, String> {
+        let arrival = rsvp.travel.arrival
+            .ok_or_else(|| String::from("Add your arrival time before joining a carpool"))?;
+        if rsvp.travel.airport.is_empty() {
+            return Err(String::from("Add your arrival airport before joining a carpool"));
+        }
+        if seats == 0 || seats > MAX_SEATS {
+            return Err(format!("Seats must be between 1 and {}", MAX_SEATS));
+        }
+
+        Ok(CarpoolEntry {
+            rsvp_id: rsvp.id.clone(),
+            household_id: rsvp.household_id.clone(),
+            name: rsvp.name.clone(),
+            email_address: rsvp.email_address.clone(),
+            role,
+            seats,
+            airport: rsvp.travel.airport.clone(),
+            arrival,
+            updated_at: Utc::now()
+        })
+    }
+
+    pub fn save(&self) -> Result<CarpoolEntry, PutItemError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let put_item_input = PutItemInput {
+            table_name: env::var("CARPOOL_TABLE_NAME").unwrap(),
+            item: serde_dynamodb::to_hashmap(self).unwrap(),
+            ..PutItemInput::default()
+        };
+
+        client.put_item(put_item_input).sync()?;
+        info!("{} joined the carpool as a {:?}", self.name, self.role);
+        Ok(self.clone())
+    }
+
+    /// Opts a guest out, returning their entry if they had one.
+    pub fn remove(rsvp_id: &str) -> Result<Option<CarpoolEntry>, DeleteItemError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let delete_item_input = DeleteItemInput {
+            table_name: env::var("CARPOOL_TABLE_NAME").unwrap(),
+            key: rsvp_key(rsvp_id),
+            return_values: Some(String::from("ALL_OLD")),
+            ..DeleteItemInput::default()
+        };
+
+        Ok(client.delete_item(delete_item_input).sync()?
+            .attributes
+            .map(|item| serde_dynamodb::from_hashmap(item).unwrap()))
+    }
+
+    pub fn all() -> Result<Vec<CarpoolEntry>, ScanError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+        scan_table(&client, env::var("CARPOOL_TABLE_NAME").unwrap())
+    }
+}
+
+impl CarpoolMatches {
+    /// Pairs riders with drivers landing at the same airport within
+    /// `window` of them. Riders are placed in arrival order, each with the
+    /// driver closest in time who still has room, preferring the fuller car
+    /// when two are equally close. Nobody is matched with their own household.
+    pub fn new(entries: &[CarpoolEntry], window: Duration) -> CarpoolMatches {
+        let mut rides : Vec<Ride> = entries.iter()
+            .filter(|entry| entry.role == CarpoolRole::Driver)
+            .map(|driver| Ride {
+                driver: driver.clone(),
+                riders: vec!(),
+                seats_left: driver.seats
+            })
+            .collect();
+        rides.sort_by(|a, b| (a.driver.arrival, &a.driver.name).cmp(&(b.driver.arrival, &b.driver.name)));
+
+        let mut riders : Vec<&CarpoolEntry> = entries.iter()
+            .filter(|entry| entry.role == CarpoolRole::Rider)
+            .collect();
+        riders.sort_by(|a, b| (a.arrival, &a.name).cmp(&(b.arrival, &b.name)));
+
+        let mut unmatched = vec!();
+        for rider in riders {
+            let best = rides.iter_mut()
+                .filter(|ride| {
+                    ride.driver.airport == rider.airport &&
+                    ride.driver.household_id != rider.household_id &&
+                    ride.seats_left >= rider.seats &&
+                    (ride.driver.arrival - rider.arrival).num_seconds().abs() <= window.num_seconds()
+                })
+                .min_by_key(|ride| ((ride.driver.arrival - rider.arrival).num_seconds().abs(), ride.seats_left));
+
+            match best {
+                Some(ride) => {
+                    ride.seats_left -= rider.seats;
+                    ride.riders.push(rider.clone());
+                },
+                None => unmatched.push(rider.clone())
+            }
+        }
+
+        CarpoolMatches { rides, unmatched }
+    }
+
+    pub fn get(window: Duration) -> Result<CarpoolMatches, ScanError> {
+        Ok(CarpoolMatches::new(&CarpoolEntry::all()?, window))
+    }
+
+    /// The ride a guest is part of, as the driver or a rider.
+    pub fn ride_for(&self, rsvp_id: &str) -> Option<&Ride> {
+        self.rides.iter().find(|ride| {
+            ride.driver.rsvp_id == rsvp_id || ride.riders.iter().any(|rider| rider.rsvp_id == rsvp_id)
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod carpool_tests {
+
+    use super::*;
+    use chrono::NaiveDate;
+    use crate::models::{Person, Travel};
+
+    fn entry(name: &str, household_id: &str, role: CarpoolRole, seats: u32, airport: &str, hour: u32, minute: u32) -> CarpoolEntry {
+        let mut rsvp = RSVP::new(
+            Person {
+                name: name.to_string(),
+                email_address: "email@example.com".to_string(),
+                ..Person::default()
+            },
+            household_id.to_string()
+        );
+        rsvp.travel = Travel {
+            arrival: Some(NaiveDate::from_ymd(2019, 9, 20).and_hms(hour, minute, 0)),
+            airport: airport.to_string(),
+            ..Travel::default()
+        };
+        CarpoolEntry::new(&rsvp, role, seats).unwrap()
+    }
+
+    fn entry_travel() -> Travel {
+        Travel {
+            arrival: Some(NaiveDate::from_ymd(2019, 9, 20).and_hms(12, 0, 0)),
+            airport: "DEN".to_string(),
+            ..Travel::default()
+        }
+    }
+
+    #[test]
+    fn test_carpool_entry_needs_travel() {
+        let rsvp = RSVP::new(
+            Person {
+                name: "Blaine Price".to_string(),
+                email_address: "email@example.com".to_string(),
+                ..Person::default()
+            },
+            "household".to_string()
+        );
+        assert!(CarpoolEntry::new(&rsvp, CarpoolRole::Rider, 1).is_err());
+        assert!(CarpoolEntry::new(&RSVP { travel: entry_travel(), ..rsvp.clone() }, CarpoolRole::Driver, 0).is_err());
+        assert!(CarpoolEntry::new(&RSVP { travel: entry_travel(), ..rsvp }, CarpoolRole::Driver, 3).is_ok());
+    }
+
+    #[test]
+    fn test_carpool_matches() {
+        let entries = vec!(
+            entry("Early Driver", "a", CarpoolRole::Driver, 2, "DEN", 12, 0),
+            entry("Late Driver", "b", CarpoolRole::Driver, 3, "DEN", 16, 0),
+            entry("Early Rider", "c", CarpoolRole::Rider, 2, "DEN", 12, 45),
+            entry("Another Early Rider", "d", CarpoolRole::Rider, 1, "DEN", 13, 0),
+            entry("Late Rider", "e", CarpoolRole::Rider, 1, "DEN", 15, 30),
+            entry("Springs Rider", "f", CarpoolRole::Rider, 1, "COS", 12, 0),
+            entry("Own Household", "b", CarpoolRole::Rider, 1, "DEN", 16, 0)
+        );
+
+        let matches = CarpoolMatches::new(&entries, Duration::minutes(DEFAULT_WINDOW_MINUTES));
+
+        let early = &matches.rides[0];
+        assert_eq!(early.driver.name, "Early Driver");
+        assert_eq!(early.riders.iter().map(|rider| rider.name.as_str()).collect::<Vec<&str>>(), vec!("Early Rider"));
+        assert_eq!(early.seats_left, 0);
+
+        let late = &matches.rides[1];
+        assert_eq!(late.riders.iter().map(|rider| rider.name.as_str()).collect::<Vec<&str>>(), vec!("Late Rider"));
+
+        let unmatched : Vec<&str> = matches.unmatched.iter().map(|rider| rider.name.as_str()).collect();
+        assert_eq!(unmatched, vec!("Springs Rider", "Another Early Rider", "Own Household"));
+
+        assert_eq!(matches.ride_for(&entries[4].rsvp_id).unwrap().driver.name, "Late Driver");
+        assert!(matches.ride_for(&entries[5].rsvp_id).is_none());
+    }
+}
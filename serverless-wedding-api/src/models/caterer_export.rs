@@ -0,0 +1,193 @@
+use serde_derive::{Serialize};
+use std::vec::{Vec};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+
+use crate::models::{RSVP, Event, Household, AgeCategory, AgeBreakdown, SevereAllergy, describe_allergies};
+
+/// How many of each meal to prepare for an event, split by age since kids'
+/// meals are priced differently.
+#[derive(Debug, Clone, Serialize)]
+pub struct MealCount {
+    pub event: String,
+    pub food_preference: String,
+    #[serde(flatten)]
+    pub by_age: AgeBreakdown,
+    pub total: usize
+}
+
+/// One attending guest at one event.
+#[derive(Debug, Clone, Serialize)]
+pub struct CatererRow {
+    pub event: String,
+    pub date: String,
+    pub household: String,
+    pub name: String,
+    /// "SEVERE ALLERGY" when any allergen is severe, so it stands out in a spreadsheet
+    pub allergy_alert: String,
+    pub allergies: String,
+    pub age_category: AgeCategory,
+    pub food_preference: String,
+    pub dietary_restrictions: String,
+    pub dietary_restrictions_other: String
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CatererExport {
+    pub meals: Vec<MealCount>,
+    pub severe_allergies: Vec<SevereAllergy>,
+    pub guests: Vec<CatererRow>
+}
+
+impl CatererExport {
+    /// Builds the export for everyone attending `events`.
+    pub fn new(rsvps: &[RSVP], events: &[Event]) -> CatererExport {
+        let mut households : HashMap<&str, Vec<RSVP>> = HashMap::new();
+        for rsvp in rsvps {
+            households.entry(rsvp.household_id.as_str())
+                .or_insert_with(Vec::new)
+                .push(rsvp.clone());
+        }
+
+        let mut meals = vec!();
+        let mut guests = vec!();
+        let mut attending_any : Vec<&RSVP> = vec!();
+
+        for event in events {
+            let mut attending : Vec<&RSVP> = rsvps.iter()
+                .filter(|rsvp| {
+                    rsvp.invitations.get(&event.id)
+                        .map_or(false, |invitation| invitation.attending == Some(true))
+                })
+                .collect();
+            attending.sort_by(|a, b| (&a.household_id, &a.name).cmp(&(&b.household_id, &b.name)));
+
+            for rsvp in &attending {
+                if !attending_any.iter().any(|seen| seen.id == rsvp.id) {
+                    attending_any.push(rsvp);
+                }
+            }
+
+            let mut by_meal : BTreeMap<&str, AgeBreakdown> = BTreeMap::new();
+            for rsvp in &attending {
+                by_meal.entry(rsvp.food_preference.as_str())
+                    .or_insert_with(AgeBreakdown::default)
+                    .add(rsvp.age_category);
+
+                guests.push(CatererRow {
+                    event: event.name.clone(),
+                    date: event.date.to_string(),
+                    household: Household::display_name(&households[rsvp.household_id.as_str()]),
+                    name: rsvp.name.clone(),
+                    allergy_alert: if rsvp.allergies.iter().any(|allergy| allergy.is_severe()) {
+                        String::from("SEVERE ALLERGY")
+                    } else {
+                        String::from("")
+                    },
+                    allergies: describe_allergies(&rsvp.allergies),
+                    age_category: rsvp.age_category,
+                    food_preference: rsvp.food_preference.clone(),
+                    dietary_restrictions: rsvp.dietary_restrictions.clone(),
+                    dietary_restrictions_other: rsvp.dietary_restrictions_other.trim().to_string()
+                });
+            }
+
+            for (food_preference, by_age) in by_meal {
+                meals.push(MealCount {
+                    event: event.name.clone(),
+                    food_preference: food_preference.to_string(),
+                    total: by_age.total(),
+                    by_age
+                });
+            }
+        }
+
+        CatererExport {
+            meals,
+            severe_allergies: SevereAllergy::collect(attending_any),
+            guests
+        }
+    }
+
+    /// The export for one event, or for every event when `event_id` is `None`.
+    pub fn get(event_id: Option<&str>) -> Result<CatererExport, Box<Error>> {
+        let rsvps = RSVP::all()?;
+        let events : Vec<Event> = Event::all()?
+            .into_iter()
+            .filter(|event| event_id.map_or(true, |id| event.id == id))
+            .collect();
+
+        Ok(CatererExport::new(&rsvps, &events))
+    }
+
+    /// The guest list as CSV, one row per guest per event.
+    pub fn guests_csv(&self) -> Result<String, Box<Error>> {
+        let mut writer = csv::Writer::from_writer(vec!());
+        for row in &self.guests {
+            writer.serialize(row)?;
+        }
+
+        let bytes = writer.into_inner().map_err(|err| err.into_error())?;
+        Ok(String::from_utf8(bytes)?)
+    }
+}
+
+
+#[cfg(test)]
+mod caterer_export_tests {
+
+    use super::*;
+    use chrono::NaiveDate;
+    use serde_json::{json};
+    use crate::models::{Person, Invitation, parse_allergies};
+
+    fn attending(name: &str, event: &Event, age_category: AgeCategory, food_preference: &str) -> RSVP {
+        let mut rsvp = RSVP::new(
+            Person {
+                name: name.to_string(),
+                email_address: "email@example.com".to_string(),
+                events: vec!(event.id.clone()),
+                age_category
+            },
+            "household".to_string()
+        );
+        rsvp.food_preference = food_preference.to_string();
+        rsvp.invitations.insert(event.id.clone(), Invitation { attending: Some(true) });
+        rsvp
+    }
+
+    #[test]
+    fn test_caterer_export_counts_by_age() {
+        let wedding = Event::new("Wedding".to_string(), NaiveDate::from_ymd(2019, 9, 21), "Estes Park".to_string(), 100);
+        let rsvps = vec!(
+            attending("Blaine Price", &wedding, AgeCategory::Adult, "4course"),
+            attending("Cynthia Young", &wedding, AgeCategory::Adult, "4course"),
+            attending("Ling Ling", &wedding, AgeCategory::Child, "kids"),
+            attending("Baby Price", &wedding, AgeCategory::Infant, "none")
+        );
+
+        let export = CatererExport::new(&rsvps, &[wedding]);
+
+        assert_eq!(export.guests.len(), 4);
+        assert!(export.severe_allergies.is_empty());
+        assert_eq!(export.meals.len(), 3);
+        assert_eq!(export.meals[0].food_preference, "4course");
+        assert_eq!(export.meals[0].by_age, AgeBreakdown { adults: 2, children: 0, infants: 0 });
+        assert_eq!(export.meals[1].food_preference, "kids");
+        assert_eq!(export.meals[1].by_age.children, 1);
+    }
+
+    #[test]
+    fn test_caterer_export_csv() {
+        let wedding = Event::new("Wedding".to_string(), NaiveDate::from_ymd(2019, 9, 21), "Estes Park".to_string(), 100);
+        let mut ling = attending("Ling Ling", &wedding, AgeCategory::Child, "kids");
+        ling.allergies = parse_allergies(&json!([{"allergen": "peanuts", "severity": "severe"}])).unwrap();
+        let rsvps = vec!(ling);
+
+        let csv = CatererExport::new(&rsvps, &[wedding]).guests_csv().unwrap();
+        let mut lines = csv.lines();
+
+        assert_eq!(lines.next(), Some("event,date,household,name,allergy_alert,allergies,age_category,food_preference,dietary_restrictions,dietary_restrictions_other"));
+        assert_eq!(lines.next(), Some("Wedding,2019-09-21,Ling Ling,Ling Ling,SEVERE ALLERGY,peanuts (severe),child,kids,None,"));
+    }
+}
@@ -0,0 +1,332 @@
+use serde_derive::{Serialize, Deserialize};
+use std::vec::{Vec};
+use std::collections::{HashMap};
+use std::env;
+use std::fmt;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use log::{info, error};
+use serde_dynamodb;
+use rusoto_core::Region;
+use rusoto_dynamodb::{
+    DynamoDb,
+    AttributeValue,
+    DynamoDbClient,
+    GetItemInput,
+    GetItemError,
+    PutItemInput,
+    PutItemError,
+    UpdateItemInput,
+    UpdateItemError,
+    ScanError
+};
+
+use crate::models::{RSVP, Invitation, PatchError, AgeCategory, Allergy, Menu};
+use super::scan::scan_table;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeRequestStatus {
+    Pending,
+    Approved,
+    Rejected
+}
+
+impl ChangeRequestStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ChangeRequestStatus::Pending => "pending",
+            ChangeRequestStatus::Approved => "approved",
+            ChangeRequestStatus::Rejected => "rejected"
+        }
+    }
+}
+
+/// The edits a guest would like made to their RSVP. Mirrors the fields
+/// `RSVP::patch` accepts from guests.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChangeSet {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attending: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dietary_restrictions: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dietary_restrictions_other: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub food_preference: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub invitations: Option<HashMap<String, Invitation>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub age_category: Option<AgeCategory>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allergies: Option<Vec<Allergy>>
+}
+
+impl ChangeSet {
+    pub fn is_empty(&self) -> bool {
+        *self == ChangeSet::default()
+    }
+
+    /// Runs the checks approving the change would, so a request that could
+    /// never be applied is turned away when the guest makes it.
+    pub fn check(&self, rsvp: &RSVP, menu: &Menu) -> Result<(), PatchError> {
+        rsvp.check(&serde_json::to_value(self).unwrap(), menu)
+    }
+}
+
+/// A guest's request to change their RSVP after the deadline, which the
+/// couple approves or rejects. Decided requests are kept as a log of what
+/// was changed and when.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeRequest {
+    pub id: String,
+    pub rsvp_id: String,
+    pub household_id: String,
+    pub name: String,
+    pub changes: ChangeSet,
+    #[serde(default)]
+    pub reason: Option<String>,
+    pub status: ChangeRequestStatus,
+    pub requested_at: DateTime<Utc>,
+    #[serde(default)]
+    pub decided_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub decision_note: Option<String>
+}
+
+#[derive(Debug)]
+pub enum DecisionError {
+    NotFound,
+    AlreadyDecided(ChangeRequestStatus),
+    Patch(PatchError),
+    Update(UpdateItemError)
+}
+
+impl fmt::Display for DecisionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecisionError::NotFound => write!(f, "No change request was found with that id"),
+            DecisionError::AlreadyDecided(status) => write!(f, "This change request was already {}", status.as_str()),
+            DecisionError::Patch(error) => write!(f, "The change could not be applied: {}", error),
+            DecisionError::Update(error) => write!(f, "{}", error)
+        }
+    }
+}
+
+impl ChangeRequest {
+    pub fn new(rsvp: &RSVP, changes: ChangeSet, reason: Option<String>, now: DateTime<Utc>) -> ChangeRequest {
+        ChangeRequest {
+            id: Uuid::new_v4().to_string(),
+            rsvp_id: rsvp.id.clone(),
+            household_id: rsvp.household_id.clone(),
+            name: rsvp.name.clone(),
+            changes,
+            reason,
+            status: ChangeRequestStatus::Pending,
+            requested_at: now,
+            decided_at: None,
+            decision_note: None
+        }
+    }
+
+    pub fn create(&self) -> Result<ChangeRequest, PutItemError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let put_item_input = PutItemInput {
+            table_name: env::var("CHANGE_REQUEST_TABLE_NAME").unwrap(),
+            item: serde_dynamodb::to_hashmap(self).unwrap(),
+            ..PutItemInput::default()
+        };
+
+        client.put_item(put_item_input).sync()?;
+        info!("{} requested a change to RSVP {}: {:?}", self.name, self.rsvp_id, self.changes);
+        Ok(self.clone())
+    }
+
+    pub fn get(uuid: Uuid) -> Result<ChangeRequest, GetItemError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let mut key = HashMap::new();
+        key.insert(String::from("id"), AttributeValue {
+            s: Some(uuid.to_string()),
+            ..Default::default()
+        });
+
+        let get_item_input = GetItemInput {
+            table_name: env::var("CHANGE_REQUEST_TABLE_NAME").unwrap(),
+            key,
+            ..GetItemInput::default()
+        };
+
+        match client.get_item(get_item_input).sync()?.item {
+            Some(item) => Ok(serde_dynamodb::from_hashmap(item).unwrap()),
+            None => Err(GetItemError::ResourceNotFound(format!("No change request with id {}", uuid)))
+        }
+    }
+
+    /// Every change request, oldest first.
+    pub fn all() -> Result<Vec<ChangeRequest>, ScanError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+        let mut requests : Vec<ChangeRequest> = scan_table(&client, env::var("CHANGE_REQUEST_TABLE_NAME").unwrap())?;
+        requests.sort_by(|a, b| a.requested_at.cmp(&b.requested_at));
+        Ok(requests)
+    }
+
+    /// Moves a pending request to `status`. The condition on the current
+    /// status means two admins deciding at once can't both win.
+    fn set_status(&self, from: ChangeRequestStatus, to: ChangeRequestStatus, note: Option<String>, now: Option<DateTime<Utc>>) -> Result<(), UpdateItemError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let mut key = HashMap::new();
+        key.insert(String::from("id"), AttributeValue {
+            s: Some(self.id.clone()),
+            ..Default::default()
+        });
+
+        let mut names = HashMap::new();
+        names.insert(String::from("#status"), String::from("status"));
+
+        let mut values = HashMap::new();
+        values.insert(String::from(":from"), AttributeValue {
+            s: Some(from.as_str().to_string()),
+            ..Default::default()
+        });
+        values.insert(String::from(":to"), AttributeValue {
+            s: Some(to.as_str().to_string()),
+            ..Default::default()
+        });
+        values.insert(String::from(":decided_at"), match now {
+            Some(now) => AttributeValue { s: Some(now.to_rfc3339()), ..Default::default() },
+            None => AttributeValue { null: Some(true), ..Default::default() }
+        });
+        values.insert(String::from(":note"), match note {
+            Some(note) => AttributeValue { s: Some(note), ..Default::default() },
+            None => AttributeValue { null: Some(true), ..Default::default() }
+        });
+
+        client.update_item(UpdateItemInput {
+            key,
+            update_expression: Some(String::from("SET #status = :to, decided_at = :decided_at, decision_note = :note")),
+            condition_expression: Some(String::from("#status = :from")),
+            expression_attribute_names: Some(names),
+            expression_attribute_values: Some(values),
+            table_name: env::var("CHANGE_REQUEST_TABLE_NAME").unwrap(),
+            ..Default::default()
+        }).sync()?;
+
+        Ok(())
+    }
+
+    /// Approves or rejects a pending request. Approved changes are applied
+    /// to the RSVP, bypassing the deadline.
+    pub fn decide(uuid: Uuid, approve: bool, note: Option<String>) -> Result<ChangeRequest, DecisionError> {
+        let request = ChangeRequest::get(uuid).map_err(|_| DecisionError::NotFound)?;
+        if request.status != ChangeRequestStatus::Pending {
+            return Err(DecisionError::AlreadyDecided(request.status));
+        }
+
+        let status = if approve { ChangeRequestStatus::Approved } else { ChangeRequestStatus::Rejected };
+
+        // Claim the request before touching the RSVP so it's applied once
+        match request.set_status(ChangeRequestStatus::Pending, status, note.clone(), Some(Utc::now())) {
+            Ok(()) => {},
+            Err(UpdateItemError::ConditionalCheckFailed(_)) => {
+                let current = ChangeRequest::get(uuid).map_err(|_| DecisionError::NotFound)?;
+                return Err(DecisionError::AlreadyDecided(current.status));
+            },
+            Err(error) => return Err(DecisionError::Update(error))
+        }
+
+        if approve {
+            let rsvp_id = Uuid::parse_str(&request.rsvp_id).map_err(|_| DecisionError::NotFound)?;
+            let payload = serde_json::to_value(&request.changes).unwrap();
+
+            let applied = Menu::get()
+                .map_err(PatchError::Menu)
+                .and_then(|menu| RSVP::apply(rsvp_id, payload, &menu));
+            if let Err(error) = applied {
+                error!("Could not apply change request {}, returning it to pending: {}", request.id, error);
+                request.set_status(status, ChangeRequestStatus::Pending, None, None)
+                    .map_err(DecisionError::Update)?;
+                return Err(DecisionError::Patch(error));
+            }
+
+            info!("Applied change request {} to RSVP {}: {:?}", request.id, request.rsvp_id, request.changes);
+        } else {
+            info!("Rejected change request {} for RSVP {}", request.id, request.rsvp_id);
+        }
+
+        ChangeRequest::get(uuid).map_err(|_| DecisionError::NotFound)
+    }
+}
+
+
+#[cfg(test)]
+mod change_request_tests {
+
+    use super::*;
+    use serde_json::{json};
+    use crate::models::{Person};
+
+    #[test]
+    fn test_change_request_new() {
+        let rsvp = RSVP::new(
+            Person {
+                name: "Blaine Price".to_string(),
+                email_address: "email@example.com".to_string(),
+                ..Person::default()
+            },
+            Uuid::new_v4().to_string()
+        );
+
+        let changes = ChangeSet {
+            food_preference: Some("pizza".to_string()),
+            ..ChangeSet::default()
+        };
+
+        let request = ChangeRequest::new(&rsvp, changes, Some("Changed my mind".to_string()), Utc::now());
+        assert_eq!(request.status, ChangeRequestStatus::Pending);
+        assert_eq!(request.rsvp_id, rsvp.id);
+        assert_eq!(request.decided_at, None);
+    }
+
+    #[test]
+    fn test_change_set_as_patch_payload() {
+        let changes : ChangeSet = serde_json::from_value(json!({
+            "attending": false,
+            "dietary_restrictions": "vegan"
+        })).unwrap();
+
+        assert!(!changes.is_empty());
+        assert!(ChangeSet::default().is_empty());
+        assert_eq!(serde_json::to_value(&changes).unwrap(), json!({
+            "attending": false,
+            "dietary_restrictions": "vegan"
+        }));
+    }
+
+    #[test]
+    fn test_change_set_check() {
+        let rsvp = RSVP::new(
+            Person {
+                name: "Blaine Price".to_string(),
+                email_address: "email@example.com".to_string(),
+                events: vec!("wedding".to_string()),
+                ..Person::default()
+            },
+            Uuid::new_v4().to_string()
+        );
+        let menu = Menu::new(serde_json::from_value(json!([
+            {"code": "4course", "kind": "meal", "label": "Four-Course Meal"},
+            {"code": "vegan", "kind": "diet", "label": "Vegan"}
+        ])).unwrap());
+        let changes = |changes| serde_json::from_value::<ChangeSet>(changes).unwrap();
+
+        assert!(changes(json!({"invitations": {"wedding": {"attending": true}}})).check(&rsvp, &menu).is_ok());
+        assert!(changes(json!({"food_preference": "4course", "dietary_restrictions": "vegan"})).check(&rsvp, &menu).is_ok());
+        assert!(changes(json!({"invitations": {"bash": {"attending": true}}})).check(&rsvp, &menu).is_err());
+        assert!(changes(json!({"invitations": {"wedding": {}}})).check(&rsvp, &menu).is_err());
+        assert!(changes(json!({"food_preference": "sushi"})).check(&rsvp, &menu).is_err());
+        assert!(changes(json!({"dietary_restrictions": "paleo"})).check(&rsvp, &menu).is_err());
+    }
+}
@@ -0,0 +1,88 @@
+use std::env;
+use chrono::{DateTime, FixedOffset, NaiveDate, Utc};
+use log::{error};
+
+/// The RSVP deadline from `RSVP_DEADLINE`, an RFC 3339 timestamp such as
+/// `2019-08-01T23:59:59-06:00`. Returns `None` when no deadline is set, and
+/// an error when it can't be read, so a typo never turns the deadline off.
+pub fn rsvp_deadline() -> Result<Option<DateTime<Utc>>, String> {
+    Ok(local_deadline()?.map(|deadline| deadline.with_timezone(&Utc)))
+}
+
+/// The offset the deadline was written with, taken to be the couple's local
+/// time. UTC when no deadline is set, or when it can't be read.
+pub fn deadline_offset() -> FixedOffset {
+    match local_deadline() {
+        Ok(deadline) => deadline.map_or_else(|| FixedOffset::east(0), |deadline| *deadline.offset()),
+        Err(message) => {
+            error!("Using UTC as the local time zone: {}", message);
+            FixedOffset::east(0)
+        }
+    }
+}
+
+/// The RSVP deadline at the offset it was written with, for showing to
+/// guests.
+pub fn local_deadline() -> Result<Option<DateTime<FixedOffset>>, String> {
+    parse_deadline(&env::var("RSVP_DEADLINE").unwrap_or_default())
+}
+
+fn parse_deadline(value: &str) -> Result<Option<DateTime<FixedOffset>>, String> {
+    if value.trim().is_empty() {
+        return Ok(None);
+    }
+
+    DateTime::parse_from_rfc3339(value.trim())
+        .map(Some)
+        .map_err(|err| format!("RSVP_DEADLINE {:?} is not an RFC 3339 timestamp: {}", value, err))
+}
+
+/// The couple's calendar date at `now`, for anything that changes on a
+/// given day, like a room block's release date.
+pub fn local_date(now: DateTime<Utc>, offset: FixedOffset) -> NaiveDate {
+    now.with_timezone(&offset).date().naive_local()
+}
+
+pub fn deadline_has_passed(deadline: Option<DateTime<Utc>>, now: DateTime<Utc>) -> bool {
+    match deadline {
+        Some(deadline) => now > deadline,
+        None => false
+    }
+}
+
+
+#[cfg(test)]
+mod deadline_tests {
+
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_deadline_has_passed() {
+        let deadline = Utc.ymd(2019, 8, 1).and_hms(23, 59, 59);
+
+        assert!(!deadline_has_passed(Some(deadline), Utc.ymd(2019, 8, 1).and_hms(12, 0, 0)));
+        assert!(deadline_has_passed(Some(deadline), Utc.ymd(2019, 8, 2).and_hms(0, 0, 0)));
+        assert!(!deadline_has_passed(None, Utc.ymd(2030, 1, 1).and_hms(0, 0, 0)));
+    }
+
+    #[test]
+    fn test_local_date() {
+        let denver = FixedOffset::west(6 * 3600);
+
+        assert_eq!(local_date(Utc.ymd(2019, 8, 2).and_hms(3, 0, 0), denver), NaiveDate::from_ymd(2019, 8, 1));
+        assert_eq!(local_date(Utc.ymd(2019, 8, 2).and_hms(6, 0, 0), denver), NaiveDate::from_ymd(2019, 8, 2));
+        assert_eq!(local_date(Utc.ymd(2019, 8, 2).and_hms(3, 0, 0), FixedOffset::east(0)), NaiveDate::from_ymd(2019, 8, 2));
+    }
+
+    #[test]
+    fn test_parse_deadline() {
+        assert_eq!(
+            parse_deadline("2019-08-01T23:59:59-06:00"),
+            Ok(Some(FixedOffset::west(6 * 3600).ymd(2019, 8, 1).and_hms(23, 59, 59)))
+        );
+        assert_eq!(parse_deadline(" "), Ok(None));
+        assert!(parse_deadline("August 1, 2019").is_err());
+        assert!(parse_deadline("2019-08-01").is_err());
+    }
+}
@@ -0,0 +1,442 @@
+use serde_derive::{Serialize, Deserialize};
+use std::vec::{Vec};
+use std::collections::{HashMap};
+use std::env;
+use std::fmt;
+use std::error::Error;
+use uuid::Uuid;
+use chrono::{DateTime, FixedOffset, Utc};
+use log::{info};
+use serde_dynamodb;
+use handlebars::{Handlebars, Template, no_escape};
+use rusoto_core::Region;
+use rusoto_dynamodb::{
+    DynamoDb,
+    AttributeValue,
+    DynamoDbClient,
+    GetItemInput,
+    GetItemError,
+    PutItemInput,
+    PutItemError,
+    ScanError
+};
+
+use crate::models::{RSVP, Household, Event};
+use super::scan::scan_table;
+use super::deadline::local_deadline;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TemplateKind {
+    /// Asks a household to RSVP
+    Invitation,
+    /// Asks again when they haven't
+    Reminder,
+    /// Thanks them for responding, listing what they said
+    Confirmation
+}
+
+pub const TEMPLATE_KINDS : [TemplateKind; 3] = [
+    TemplateKind::Invitation,
+    TemplateKind::Reminder,
+    TemplateKind::Confirmation
+];
+
+impl TemplateKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TemplateKind::Invitation => "invitation",
+            TemplateKind::Reminder => "reminder",
+            TemplateKind::Confirmation => "confirmation"
+        }
+    }
+
+    pub fn parse(kind: &str) -> Option<TemplateKind> {
+        TEMPLATE_KINDS.iter().cloned().find(|template_kind| template_kind.as_str() == kind)
+    }
+}
+
+/// An email the couple can edit. Each part is a Handlebars template
+/// rendered with an `EmailContext`. Kinds that were never edited use the
+/// built in defaults.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmailTemplate {
+    pub kind: TemplateKind,
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+    /// When the couple last saved it. Unset for the defaults.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>
+}
+
+/// One guest in the household, as templates see them.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GuestContext {
+    pub name: String,
+    pub email_address: String,
+    /// Whether they've answered at least one invitation
+    pub responded: bool,
+    /// Whether they're coming to anything
+    pub attending: bool,
+    pub food_preference: String
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EventContext {
+    pub name: String,
+    /// e.g. "September 14, 2019"
+    pub date: String,
+    pub venue: String
+}
+
+/// Everything a template can use, e.g. `{{salutation}}`, `{{rsvp_link}}`
+/// or `{{#each guests}}{{name}}{{/each}}`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EmailContext {
+    pub household_id: String,
+    /// How the household is addressed, e.g. "The Price Family"
+    pub salutation: String,
+    pub rsvp_link: String,
+    /// The deadline in the couple's time zone, e.g. "August 1, 2019", or
+    /// null when there's no deadline
+    pub deadline: Option<String>,
+    pub guests: Vec<GuestContext>,
+    /// The events anyone in the household is invited to, soonest first
+    pub events: Vec<EventContext>
+}
+
+/// A template filled in for one household, ready to send.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RenderedEmail {
+    pub kind: TemplateKind,
+    pub to: Vec<String>,
+    pub subject: String,
+    pub html: String,
+    pub text: String
+}
+
+#[derive(Debug)]
+pub enum EmailError {
+    /// The template doesn't parse or render
+    Template(String),
+    /// No one in the household has an email address
+    NoRecipients,
+    Get(GetItemError),
+    Put(PutItemError),
+    Scan(ScanError)
+}
+
+impl fmt::Display for EmailError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EmailError::Template(message) => write!(f, "{}", message),
+            EmailError::NoRecipients => write!(f, "No one in that household has an email address"),
+            EmailError::Get(error) => write!(f, "{}", error),
+            EmailError::Put(error) => write!(f, "{}", error),
+            EmailError::Scan(error) => write!(f, "{}", error)
+        }
+    }
+}
+
+const INVITATION_TEXT : &str = "Dear {{salutation}},
+
+We would love for you to celebrate with us!
+{{#each events}}
+{{name}}, {{date}} at {{venue}}{{/each}}
+
+Please let us know if you can make it{{#if deadline}} by {{deadline}}{{/if}}:
+{{rsvp_link}}
+";
+
+const INVITATION_HTML : &str = "<p>Dear {{salutation}},</p>
+<p>We would love for you to celebrate with us!</p>
+<ul>{{#each events}}<li><strong>{{name}}</strong>, {{date}} at {{venue}}</li>{{/each}}</ul>
+<p>Please let us know if you can make it{{#if deadline}} by {{deadline}}{{/if}}.</p>
+<p><a href=\"{{rsvp_link}}\">RSVP here</a></p>
+";
+
+const REMINDER_TEXT : &str = "Dear {{salutation}},
+
+We haven't heard back from you yet and would love to know if you can make it.
+{{#if deadline}}RSVPs close on {{deadline}}. {{/if}}You can respond here:
+{{rsvp_link}}
+";
+
+const REMINDER_HTML : &str = "<p>Dear {{salutation}},</p>
+<p>We haven't heard back from you yet and would love to know if you can make it.</p>
+<p>{{#if deadline}}RSVPs close on {{deadline}}. {{/if}}<a href=\"{{rsvp_link}}\">Respond here</a>.</p>
+";
+
+const CONFIRMATION_TEXT : &str = "Dear {{salutation}},
+
+Thank you for your RSVP! Here's what we have:
+{{#each guests}}
+{{name}}: {{#if attending}}attending{{else}}not attending{{/if}}{{/each}}
+
+You can make changes{{#if deadline}} until {{deadline}}{{/if}} here:
+{{rsvp_link}}
+";
+
+const CONFIRMATION_HTML : &str = "<p>Dear {{salutation}},</p>
+<p>Thank you for your RSVP! Here's what we have:</p>
+<ul>{{#each guests}}<li>{{name}}: {{#if attending}}attending{{else}}not attending{{/if}}</li>{{/each}}</ul>
+<p>You can make changes{{#if deadline}} until {{deadline}}{{/if}} <a href=\"{{rsvp_link}}\">here</a>.</p>
+";
+
+fn kind_key(kind: TemplateKind) -> HashMap<String, AttributeValue> {
+    let mut key = HashMap::new();
+    key.insert(String::from("kind"), AttributeValue {
+        s: Some(kind.as_str().to_string()),
+        ..Default::default()
+    });
+    key
+}
+
+impl EmailTemplate {
+    /// The email sent when the couple hasn't written their own.
+    pub fn default_for(kind: TemplateKind) -> EmailTemplate {
+        let (subject, html, text) = match kind {
+            TemplateKind::Invitation => ("You're invited!", INVITATION_HTML, INVITATION_TEXT),
+            TemplateKind::Reminder => ("Will we see you there?", REMINDER_HTML, REMINDER_TEXT),
+            TemplateKind::Confirmation => ("Thanks for your RSVP", CONFIRMATION_HTML, CONFIRMATION_TEXT)
+        };
+
+        EmailTemplate {
+            kind,
+            subject: subject.to_string(),
+            html: html.to_string(),
+            text: text.to_string(),
+            updated_at: None
+        }
+    }
+
+    /// Checks every part parses, so a broken template is caught when it's
+    /// saved rather than when it's sent.
+    pub fn check(&self) -> Result<(), EmailError> {
+        if self.subject.trim().is_empty() {
+            return Err(EmailError::Template(String::from("Emails need a subject")));
+        }
+        if self.text.trim().is_empty() {
+            return Err(EmailError::Template(String::from("Emails need a plain text body")));
+        }
+        for (part, source) in &[("subject", &self.subject), ("html", &self.html), ("text", &self.text)] {
+            Template::compile(source.as_str())
+                .map_err(|error| EmailError::Template(format!("The {} doesn't parse: {}", part, error)))?;
+        }
+        Ok(())
+    }
+
+    /// Fills the template in for a household. Only the HTML is escaped;
+    /// the subject and text are sent as plain text.
+    pub fn render(&self, context: &EmailContext) -> Result<RenderedEmail, EmailError> {
+        let mut plain = Handlebars::new();
+        plain.register_escape_fn(no_escape);
+        let html = Handlebars::new();
+
+        let render = |registry: &Handlebars, part: &str, source: &str| registry.render_template(source, context)
+            .map_err(|error| EmailError::Template(format!("The {} doesn't render: {}", part, error)));
+
+        Ok(RenderedEmail {
+            kind: self.kind,
+            to: context.recipients(),
+            subject: render(&plain, "subject", &self.subject)?.trim().to_string(),
+            html: render(&html, "html", &self.html)?,
+            text: render(&plain, "text", &self.text)?
+        })
+    }
+
+    /// The couple's version of the template, or the default.
+    pub fn get(kind: TemplateKind) -> Result<EmailTemplate, EmailError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let get_item_input = GetItemInput {
+            table_name: env::var("EMAIL_TEMPLATE_TABLE_NAME").unwrap(),
+            key: kind_key(kind),
+            ..GetItemInput::default()
+        };
+
+        match client.get_item(get_item_input).sync().map_err(EmailError::Get)?.item {
+            Some(item) => Ok(serde_dynamodb::from_hashmap(item).unwrap()),
+            None => Ok(EmailTemplate::default_for(kind))
+        }
+    }
+
+    /// Every kind, edited or not.
+    pub fn all() -> Result<Vec<EmailTemplate>, EmailError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+        let saved : Vec<EmailTemplate> = scan_table(&client, env::var("EMAIL_TEMPLATE_TABLE_NAME").unwrap())
+            .map_err(EmailError::Scan)?;
+
+        Ok(TEMPLATE_KINDS.iter()
+            .map(|kind| saved.iter()
+                .find(|template| template.kind == *kind)
+                .cloned()
+                .unwrap_or_else(|| EmailTemplate::default_for(*kind)))
+            .collect())
+    }
+
+    pub fn save(&self) -> Result<EmailTemplate, EmailError> {
+        self.check()?;
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let template = EmailTemplate {
+            updated_at: Some(Utc::now()),
+            ..self.clone()
+        };
+        let put_item_input = PutItemInput {
+            table_name: env::var("EMAIL_TEMPLATE_TABLE_NAME").unwrap(),
+            item: serde_dynamodb::to_hashmap(&template).unwrap(),
+            ..PutItemInput::default()
+        };
+
+        client.put_item(put_item_input).sync().map_err(EmailError::Put)?;
+        info!("Saved the {} email template", template.kind.as_str());
+        Ok(template)
+    }
+}
+
+impl EmailContext {
+    pub fn new(household: &Household, rsvps: &[RSVP], events: &[Event], deadline: Option<DateTime<FixedOffset>>) -> EmailContext {
+        let mut rsvps = rsvps.to_vec();
+        rsvps.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut invited : Vec<&Event> = events.iter()
+            .filter(|event| rsvps.iter().any(|rsvp| rsvp.invitations.contains_key(&event.id)))
+            .collect();
+        invited.sort_by(|a, b| (a.date, &a.name).cmp(&(b.date, &b.name)));
+
+        EmailContext {
+            household_id: household.id.clone(),
+            salutation: household.salutation_for(&rsvps),
+            rsvp_link: Household::rsvp_link(&household.id),
+            deadline: deadline.map(|deadline| deadline.format("%B %-d, %Y").to_string()),
+            guests: rsvps.iter()
+                .map(|rsvp| GuestContext {
+                    name: rsvp.name.clone(),
+                    email_address: rsvp.email_address.trim().to_string(),
+                    responded: rsvp.has_responded(),
+                    attending: rsvp.is_attending_any(),
+                    food_preference: rsvp.food_preference.trim().to_string()
+                })
+                .collect(),
+            events: invited.iter()
+                .map(|event| EventContext {
+                    name: event.name.clone(),
+                    date: event.date.format("%B %-d, %Y").to_string(),
+                    venue: event.venue.clone()
+                })
+                .collect()
+        }
+    }
+
+    /// Looks up a household's guests, its record and the events. A
+    /// household no one is in comes back with no guests.
+    pub fn get(uuid: Uuid) -> Result<EmailContext, Box<Error>> {
+        let rsvps = Household::get(uuid)?;
+        let household = Household::record(uuid)?;
+        let events = Event::all()?;
+        Ok(EmailContext::new(&household, &rsvps, &events, local_deadline()?))
+    }
+
+    /// Each guest's address, once.
+    pub fn recipients(&self) -> Vec<String> {
+        let mut recipients : Vec<String> = vec!();
+        for guest in &self.guests {
+            let address = guest.email_address.to_lowercase();
+            if address.contains('@') && !recipients.contains(&address) {
+                recipients.push(address);
+            }
+        }
+        recipients
+    }
+}
+
+
+#[cfg(test)]
+mod email_tests {
+
+    use super::*;
+    use chrono::{NaiveDate, TimeZone};
+    use crate::models::{Person, Invitation};
+
+    fn context() -> EmailContext {
+        let people = vec!(
+            Person {
+                name: "Jane Doe".to_string(),
+                email_address: "Jane@Example.com ".to_string(),
+                events: vec!("estes-park".to_string()),
+                ..Person::default()
+            },
+            Person {
+                name: "John Doe".to_string(),
+                email_address: "jane@example.com".to_string(),
+                events: vec!("estes-park".to_string()),
+                ..Person::default()
+            }
+        );
+        let mut rsvps = Household::new(people);
+        rsvps[0].invitations.insert("estes-park".to_string(), Invitation { attending: Some(true) });
+        let household = Household {
+            id: rsvps[0].household_id.clone(),
+            salutation: "The Does & <Friends>".to_string(),
+            ..Household::default()
+        };
+        let events = vec!(
+            Event {
+                id: "estes-park".to_string(),
+                ..Event::new("Estes Park Wedding".to_string(), NaiveDate::from_ymd(2019, 9, 14), "Della Terra".to_string(), 120)
+            },
+            Event::new("Williamsburg Bash".to_string(), NaiveDate::from_ymd(2019, 10, 5), "Brooklyn".to_string(), 80)
+        );
+
+        EmailContext::new(&household, &rsvps, &events, Some(FixedOffset::west(6 * 3600).ymd(2019, 8, 1).and_hms(23, 59, 59)))
+    }
+
+    #[test]
+    fn test_email_context() {
+        let context = context();
+        assert_eq!(context.rsvp_link, format!("https://slswedding.com/rsvp/{}", context.household_id));
+        assert_eq!(context.deadline, Some("August 1, 2019".to_string()));
+        assert_eq!(context.events.len(), 1);
+        assert_eq!(context.events[0].date, "September 14, 2019");
+        assert!(context.guests[0].responded && context.guests[0].attending);
+        assert!(!context.guests[1].responded);
+        assert_eq!(context.recipients(), vec!("jane@example.com"));
+    }
+
+    #[test]
+    fn test_render_defaults() {
+        let context = context();
+        for kind in TEMPLATE_KINDS.iter() {
+            let email = EmailTemplate::default_for(*kind).render(&context).unwrap();
+            assert_eq!(email.to, vec!("jane@example.com"));
+            assert!(email.text.starts_with("Dear The Does & <Friends>,"));
+            assert!(email.html.starts_with("<p>Dear The Does &amp; &lt;Friends&gt;,</p>"));
+            assert!(email.text.contains(&context.rsvp_link));
+            assert!(email.html.contains(&context.rsvp_link));
+        }
+
+        let invitation = EmailTemplate::default_for(TemplateKind::Invitation).render(&context).unwrap();
+        assert!(invitation.text.contains("Estes Park Wedding, September 14, 2019 at Della Terra"));
+        assert!(invitation.text.contains("by August 1, 2019"));
+
+        let confirmation = EmailTemplate::default_for(TemplateKind::Confirmation).render(&context).unwrap();
+        assert!(confirmation.text.contains("Jane Doe: attending\nJohn Doe: not attending"));
+    }
+
+    #[test]
+    fn test_check_template() {
+        let template = EmailTemplate {
+            subject: "Hi {{salutation}}".to_string(),
+            ..EmailTemplate::default_for(TemplateKind::Reminder)
+        };
+        assert!(template.check().is_ok());
+        assert_eq!(template.render(&context()).unwrap().subject, "Hi The Does & <Friends>");
+
+        let broken = EmailTemplate { text: "{{#each guests}}{{name}}".to_string(), ..template.clone() };
+        assert!(broken.check().is_err());
+        let blank = EmailTemplate { subject: " ".to_string(), ..template };
+        assert!(blank.check().is_err());
+    }
+}
@@ -0,0 +1,123 @@
+use serde_derive::{Serialize, Deserialize};
+use std::vec::{Vec};
+use std::collections::{HashMap};
+use std::env;
+use uuid::Uuid;
+use chrono::NaiveDate;
+use log::{info, error};
+use serde_dynamodb;
+use rusoto_core::Region;
+use rusoto_dynamodb::{
+    DynamoDb,
+    AttributeValue,
+    DynamoDbClient,
+    GetItemInput,
+    GetItemError,
+    PutItemInput,
+    PutItemError,
+    ScanError
+};
+
+use super::scan::scan_table;
+
+/// Something guests are invited to, such as the Estes Park wedding or the
+/// Williamsburg Bash. Invitations to an event are stored on each RSVP.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    pub id: String,
+    pub name: String,
+    pub date: NaiveDate,
+    pub venue: String,
+    pub capacity: u32
+}
+
+impl Event {
+    pub fn new(name: String, date: NaiveDate, venue: String, capacity: u32) -> Event {
+        Event {
+            id: Uuid::new_v4().to_string(),
+            name,
+            date,
+            venue,
+            capacity
+        }
+    }
+
+    pub fn create(&self) -> Result<Event, PutItemError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let put_item_input = PutItemInput {
+            table_name: env::var("EVENT_TABLE_NAME").unwrap(),
+            item: serde_dynamodb::to_hashmap(self).unwrap(),
+            ..PutItemInput::default()
+        };
+
+        info!("Creating event {:?}", self);
+
+        match client.put_item(put_item_input).sync() {
+            Ok(_result) => Ok(self.clone()),
+            Err(error) => {
+                error!("There was an error creating the event {}", error);
+                Err(error)
+            }
+        }
+    }
+
+    pub fn get(uuid: Uuid) -> Result<Event, GetItemError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let mut key = HashMap::new();
+        key.insert(String::from("id"), AttributeValue {
+            s: Some(uuid.to_string()),
+            ..Default::default()
+        });
+
+        let get_item_input = GetItemInput {
+            table_name: env::var("EVENT_TABLE_NAME").unwrap(),
+            key,
+            ..GetItemInput::default()
+        };
+
+        match client.get_item(get_item_input).sync()?.item {
+            Some(item) => Ok(serde_dynamodb::from_hashmap(item).unwrap()),
+            None => Err(GetItemError::ResourceNotFound(format!("No event with id {}", uuid)))
+        }
+    }
+
+    /// Every event, soonest first.
+    pub fn all() -> Result<Vec<Event>, ScanError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+        let mut events : Vec<Event> = scan_table(&client, env::var("EVENT_TABLE_NAME").unwrap())?;
+        events.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.name.cmp(&b.name)));
+        Ok(events)
+    }
+}
+
+
+#[cfg(test)]
+mod event_tests {
+
+    use super::*;
+
+    #[test]
+    fn test_event_new() {
+        let date = NaiveDate::from_ymd(2019, 9, 21);
+        let event = Event::new("Wedding".to_string(), date, "Estes Park".to_string(), 120);
+
+        assert!(Uuid::parse_str(&event.id).is_ok());
+        assert_eq!(event.date, date);
+        assert_eq!(event.capacity, 120);
+    }
+
+    #[test]
+    fn test_event_serializes_date() {
+        let event = Event::new(
+            "The Bash".to_string(),
+            NaiveDate::from_ymd(2019, 10, 12),
+            "Williamsburg".to_string(),
+            80
+        );
+
+        let item = serde_dynamodb::to_hashmap(&event).unwrap();
+        assert_eq!(item["date"].s, Some("2019-10-12".to_string()));
+    }
+}
@@ -0,0 +1,484 @@
+use serde_derive::{Serialize, Deserialize};
+use std::vec::{Vec};
+use std::collections::{HashMap};
+use std::env;
+use std::error::Error;
+use std::fmt;
+use uuid::Uuid;
+use chrono::{DateTime, NaiveDate, Utc};
+use log::{info};
+use serde_dynamodb;
+use rusoto_core::Region;
+use rusoto_dynamodb::{
+    DynamoDb,
+    AttributeValue,
+    DynamoDbClient,
+    GetItemInput,
+    GetItemError,
+    PutItemInput,
+    PutItemError,
+    QueryInput,
+    QueryError,
+    UpdateItemInput,
+    UpdateItemError,
+    ScanError
+};
+
+use crate::models::{RSVP, Household, Event, Address, Money, default_locale};
+use super::scan::scan_table;
+use super::attributes::string_value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GiftKind {
+    /// Something wrapped, off the registry or not
+    Item,
+    /// Cash or a check
+    Cash
+}
+
+/// Where the thank-you note for a gift is up to.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThankYouStatus {
+    Pending,
+    Written,
+    Mailed
+}
+
+impl ThankYouStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ThankYouStatus::Pending => "pending",
+            ThankYouStatus::Written => "written",
+            ThankYouStatus::Mailed => "mailed"
+        }
+    }
+}
+
+/// A gift received from a household, at one of the events or in the mail.
+/// Keyed on the household, so a household's gifts can be queried together.
+/// Registry contributions are tracked separately.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Gift {
+    pub household_id: String,
+    pub id: String,
+    pub kind: GiftKind,
+    pub description: String,
+    /// What cash came to, or roughly what an item is worth
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Money>,
+    /// The event it was given at. Gifts that came in the mail have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_id: Option<String>,
+    pub received_on: NaiveDate,
+    pub thank_you: ThankYouStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub written_on: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mailed_on: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+    pub created_at: DateTime<Utc>
+}
+
+#[derive(Debug)]
+pub enum GiftError {
+    NotFound,
+    Invalid(String),
+    Get(GetItemError),
+    Update(UpdateItemError)
+}
+
+impl fmt::Display for GiftError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GiftError::NotFound => write!(f, "No gift was found with that id"),
+            GiftError::Invalid(message) => write!(f, "{}", message),
+            GiftError::Get(error) => write!(f, "{}", error),
+            GiftError::Update(error) => write!(f, "{}", error)
+        }
+    }
+}
+
+fn date_value(date: Option<NaiveDate>) -> AttributeValue {
+    match date {
+        Some(date) => string_value(&date.to_string()),
+        None => AttributeValue { null: Some(true), ..Default::default() }
+    }
+}
+
+fn gift_key(household_id: &str, gift_id: &str) -> HashMap<String, AttributeValue> {
+    let mut key = HashMap::new();
+    key.insert(String::from("household_id"), string_value(household_id));
+    key.insert(String::from("id"), string_value(gift_id));
+    key
+}
+
+impl Gift {
+    pub fn new(household_id: String, kind: GiftKind, description: String, value: Option<Money>, event_id: Option<String>, received_on: NaiveDate) -> Result<Gift, String> {
+        if description.trim().is_empty() {
+            return Err(String::from("Describe the gift so the note can mention it"));
+        }
+        if let Some(ref value) = value {
+            if !value.is_positive() {
+                return Err(String::from("Leave the value out if it isn't known"));
+            }
+        }
+        if kind == GiftKind::Cash && value.is_none() {
+            return Err(String::from("Cash gifts need an amount"));
+        }
+
+        Ok(Gift {
+            household_id,
+            id: Uuid::new_v4().to_string(),
+            kind,
+            description: description.trim().to_string(),
+            value,
+            event_id,
+            received_on,
+            thank_you: ThankYouStatus::Pending,
+            written_on: None,
+            mailed_on: None,
+            notes: String::new(),
+            created_at: Utc::now()
+        })
+    }
+
+    /// The gift with its note moved to `status` on `on`. Mailing a note
+    /// that was never marked written counts it as written the same day, and
+    /// going back to an earlier status clears the later dates.
+    pub fn with_thank_you(self, status: ThankYouStatus, on: NaiveDate) -> Result<Gift, String> {
+        if on < self.received_on {
+            return Err(format!("The gift wasn't received until {}", self.received_on));
+        }
+
+        let (written_on, mailed_on) = match status {
+            ThankYouStatus::Pending => (None, None),
+            ThankYouStatus::Written => (Some(on), None),
+            ThankYouStatus::Mailed => {
+                let written_on = self.written_on.filter(|written_on| *written_on <= on).unwrap_or(on);
+                (Some(written_on), Some(on))
+            }
+        };
+
+        Ok(Gift {
+            thank_you: status,
+            written_on,
+            mailed_on,
+            ..self
+        })
+    }
+
+    pub fn create(&self) -> Result<Gift, PutItemError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let put_item_input = PutItemInput {
+            table_name: env::var("GIFT_TABLE_NAME").unwrap(),
+            item: serde_dynamodb::to_hashmap(self).unwrap(),
+            ..PutItemInput::default()
+        };
+
+        client.put_item(put_item_input).sync()?;
+        info!("Recorded gift {:?}", self);
+        Ok(self.clone())
+    }
+
+    pub fn get(household_id: &str, gift_id: &str) -> Result<Gift, GiftError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let get_item_input = GetItemInput {
+            table_name: env::var("GIFT_TABLE_NAME").unwrap(),
+            key: gift_key(household_id, gift_id),
+            ..GetItemInput::default()
+        };
+
+        match client.get_item(get_item_input).sync().map_err(GiftError::Get)?.item {
+            Some(item) => Ok(serde_dynamodb::from_hashmap(item).unwrap()),
+            None => Err(GiftError::NotFound)
+        }
+    }
+
+    /// Every gift from a household, in the order they came in.
+    pub fn for_household(household_id: &str) -> Result<Vec<Gift>, QueryError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let mut values = HashMap::new();
+        values.insert(String::from(":household_id"), string_value(household_id));
+
+        let query_input = QueryInput {
+            table_name: env::var("GIFT_TABLE_NAME").unwrap(),
+            key_condition_expression: Some(String::from("household_id = :household_id")),
+            expression_attribute_values: Some(values),
+            ..QueryInput::default()
+        };
+
+        let mut gifts : Vec<Gift> = client.query(query_input).sync()?
+            .items
+            .unwrap_or_default()
+            .into_iter()
+            .map(|item| serde_dynamodb::from_hashmap(item).unwrap())
+            .collect();
+        gifts.sort_by(|a, b| (a.received_on, a.created_at).cmp(&(b.received_on, b.created_at)));
+        Ok(gifts)
+    }
+
+    /// The whole ledger, in the order gifts came in.
+    pub fn all() -> Result<Vec<Gift>, ScanError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+        let mut gifts : Vec<Gift> = scan_table(&client, env::var("GIFT_TABLE_NAME").unwrap())?;
+        gifts.sort_by(|a, b| (a.received_on, a.created_at).cmp(&(b.received_on, b.created_at)));
+        Ok(gifts)
+    }
+
+    /// Moves the gift's thank-you note to `status`, returning the gift
+    /// afterwards.
+    pub fn set_thank_you(household_id: &str, gift_id: &str, status: ThankYouStatus, on: NaiveDate) -> Result<Gift, GiftError> {
+        let gift = Gift::get(household_id, gift_id)?
+            .with_thank_you(status, on)
+            .map_err(GiftError::Invalid)?;
+
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let mut names = HashMap::new();
+        names.insert(String::from("#thank_you"), String::from("thank_you"));
+
+        let mut values = HashMap::new();
+        values.insert(String::from(":thank_you"), string_value(gift.thank_you.as_str()));
+        values.insert(String::from(":written_on"), date_value(gift.written_on));
+        values.insert(String::from(":mailed_on"), date_value(gift.mailed_on));
+
+        let update = client.update_item(UpdateItemInput {
+            table_name: env::var("GIFT_TABLE_NAME").unwrap(),
+            key: gift_key(household_id, gift_id),
+            update_expression: Some(String::from("SET #thank_you = :thank_you, written_on = :written_on, mailed_on = :mailed_on")),
+            condition_expression: Some(String::from("attribute_exists(id)")),
+            expression_attribute_names: Some(names),
+            expression_attribute_values: Some(values),
+            ..UpdateItemInput::default()
+        }).sync();
+
+        match update {
+            Ok(_) => {},
+            Err(UpdateItemError::ConditionalCheckFailed(_)) => return Err(GiftError::NotFound),
+            Err(error) => return Err(GiftError::Update(error))
+        }
+
+        info!("Thank-you note for gift {} from household {} is {}", gift.id, gift.household_id, gift.thank_you.as_str());
+        Ok(gift)
+    }
+}
+
+/// A gift still waiting on its note being mailed, with where to send it.
+#[derive(Debug, Clone, Serialize)]
+pub struct ThankYouRow {
+    pub household_id: String,
+    pub household: String,
+    /// The envelope's lines joined with commas. Blank when the household
+    /// hasn't given an address.
+    pub mailing_address: String,
+    pub gift_id: String,
+    pub description: String,
+    /// Formatted for the note, e.g. "$150.00"
+    pub value: String,
+    pub event: String,
+    pub received_on: NaiveDate,
+    pub status: ThankYouStatus,
+    pub written_on: Option<NaiveDate>,
+    pub days_waiting: i64
+}
+
+/// Every thank-you note still to write or mail, oldest gift first.
+#[derive(Debug, Clone, Serialize)]
+pub struct ThankYouReport {
+    pub to_write: usize,
+    pub to_mail: usize,
+    /// Households owed a note that have no mailing address yet
+    pub missing_addresses: Vec<String>,
+    pub rows: Vec<ThankYouRow>
+}
+
+impl ThankYouReport {
+    pub fn new(gifts: &[Gift], households: &[Household], rsvps: &[RSVP], events: &[Event], today: NaiveDate, home_country: &str, locale: &str) -> ThankYouReport {
+        let records : HashMap<&str, &Household> = households.iter()
+            .map(|household| (household.id.as_str(), household))
+            .collect();
+        let mut guests : HashMap<&str, Vec<RSVP>> = HashMap::new();
+        for rsvp in rsvps {
+            guests.entry(rsvp.household_id.as_str())
+                .or_insert_with(Vec::new)
+                .push(rsvp.clone());
+        }
+        let event_names : HashMap<&str, &str> = events.iter()
+            .map(|event| (event.id.as_str(), event.name.as_str()))
+            .collect();
+
+        let mut rows : Vec<ThankYouRow> = gifts.iter()
+            .filter(|gift| gift.thank_you != ThankYouStatus::Mailed)
+            .map(|gift| {
+                let mut members = guests.get(gift.household_id.as_str()).cloned().unwrap_or_default();
+                members.sort_by(|a, b| a.name.cmp(&b.name));
+                let record = records.get(gift.household_id.as_str());
+                let address : Option<&Address> = record.and_then(|household| household.address.as_ref());
+
+                ThankYouRow {
+                    household_id: gift.household_id.clone(),
+                    household: record.map_or_else(|| Household::display_name(&members), |household| household.salutation_for(&members)),
+                    mailing_address: address.map_or(String::new(), |address| address.lines(home_country).join(", ")),
+                    gift_id: gift.id.clone(),
+                    description: gift.description.clone(),
+                    value: gift.value.as_ref().map_or(String::new(), |value| value.format(locale)),
+                    event: gift.event_id.as_ref()
+                        .and_then(|event_id| event_names.get(event_id.as_str()))
+                        .map_or(String::new(), |name| name.to_string()),
+                    received_on: gift.received_on,
+                    status: gift.thank_you,
+                    written_on: gift.written_on,
+                    days_waiting: (today - gift.received_on).num_days()
+                }
+            })
+            .collect();
+        rows.sort_by(|a, b| (a.received_on, &a.household).cmp(&(b.received_on, &b.household)));
+
+        let mut missing_addresses : Vec<String> = rows.iter()
+            .filter(|row| row.mailing_address.is_empty())
+            .map(|row| row.household.clone())
+            .collect();
+        missing_addresses.sort();
+        missing_addresses.dedup();
+
+        ThankYouReport {
+            to_write: rows.iter().filter(|row| row.status == ThankYouStatus::Pending).count(),
+            to_mail: rows.iter().filter(|row| row.status == ThankYouStatus::Written).count(),
+            missing_addresses,
+            rows
+        }
+    }
+
+    pub fn get() -> Result<ThankYouReport, Box<Error>> {
+        let gifts = Gift::all()?;
+        let households = Household::records()?;
+        let rsvps = RSVP::all()?;
+        let events = Event::all()?;
+        Ok(ThankYouReport::new(
+            &gifts,
+            &households,
+            &rsvps,
+            &events,
+            Utc::now().naive_utc().date(),
+            &Address::home_country(),
+            &default_locale()
+        ))
+    }
+
+    pub fn csv(&self) -> Result<String, Box<Error>> {
+        let mut writer = csv::Writer::from_writer(vec!());
+        for row in &self.rows {
+            writer.serialize(row)?;
+        }
+
+        let bytes = writer.into_inner().map_err(|err| err.into_error())?;
+        Ok(String::from_utf8(bytes)?)
+    }
+}
+
+
+#[cfg(test)]
+mod gift_tests {
+
+    use super::*;
+    use crate::models::{Person};
+
+    fn usd(amount: &str) -> Option<Money> {
+        Some(Money::parse(amount, "USD").unwrap())
+    }
+
+    fn gift(household_id: &str, description: &str, received_on: NaiveDate) -> Gift {
+        Gift::new(household_id.to_string(), GiftKind::Item, description.to_string(), None, None, received_on).unwrap()
+    }
+
+    #[test]
+    fn test_gift_new() {
+        let received_on = NaiveDate::from_ymd(2019, 9, 21);
+        let check = Gift::new("household".to_string(), GiftKind::Cash, " Check ".to_string(), usd("150"), None, received_on).unwrap();
+        assert_eq!(check.description, "Check");
+        assert_eq!(check.thank_you, ThankYouStatus::Pending);
+
+        assert!(Gift::new("household".to_string(), GiftKind::Cash, "Card".to_string(), None, None, received_on).is_err());
+        assert!(Gift::new("household".to_string(), GiftKind::Item, "Vase".to_string(), usd("0"), None, received_on).is_err());
+        assert!(Gift::new("household".to_string(), GiftKind::Item, " ".to_string(), None, None, received_on).is_err());
+    }
+
+    #[test]
+    fn test_gift_with_thank_you() {
+        let received_on = NaiveDate::from_ymd(2019, 9, 21);
+        let vase = gift("household", "Vase", received_on);
+
+        let written = vase.clone().with_thank_you(ThankYouStatus::Written, NaiveDate::from_ymd(2019, 10, 1)).unwrap();
+        let mailed = written.with_thank_you(ThankYouStatus::Mailed, NaiveDate::from_ymd(2019, 10, 3)).unwrap();
+        assert_eq!(mailed.written_on, Some(NaiveDate::from_ymd(2019, 10, 1)));
+        assert_eq!(mailed.mailed_on, Some(NaiveDate::from_ymd(2019, 10, 3)));
+
+        let mailed = vase.clone().with_thank_you(ThankYouStatus::Mailed, NaiveDate::from_ymd(2019, 10, 3)).unwrap();
+        assert_eq!(mailed.written_on, Some(NaiveDate::from_ymd(2019, 10, 3)));
+
+        let pending = mailed.with_thank_you(ThankYouStatus::Pending, NaiveDate::from_ymd(2019, 10, 4)).unwrap();
+        assert_eq!((pending.written_on, pending.mailed_on), (None, None));
+
+        assert!(vase.with_thank_you(ThankYouStatus::Written, NaiveDate::from_ymd(2019, 9, 1)).is_err());
+    }
+
+    #[test]
+    fn test_thank_you_report() {
+        let rsvp = |name: &str, household_id: &str| RSVP::new(
+            Person {
+                name: name.to_string(),
+                email_address: format!("{}@example.com", name.to_lowercase().replace(" ", ".")),
+                ..Person::default()
+            },
+            household_id.to_string()
+        );
+        let rsvps = vec!(rsvp("Blaine Price", "price"), rsvp("Cynthia Young", "price"), rsvp("Ling Ling", "ling"));
+        let households = vec!(Household {
+            id: "price".to_string(),
+            salutation: "The Price Family".to_string(),
+            address: Some(Address {
+                line1: "1 Main St".to_string(),
+                city: "Springfield".to_string(),
+                region: "IL".to_string(),
+                postal_code: "62704".to_string(),
+                country: "US".to_string(),
+                ..Address::default()
+            }),
+            ..Household::default()
+        });
+        let wedding = Event::new("Wedding".to_string(), NaiveDate::from_ymd(2019, 9, 21), "Estes Park".to_string(), 100);
+
+        let check = Gift {
+            event_id: Some(wedding.id.clone()),
+            ..Gift::new("price".to_string(), GiftKind::Cash, "Check".to_string(), usd("150"), None, NaiveDate::from_ymd(2019, 9, 21)).unwrap()
+        };
+        let vase = gift("ling", "Vase", NaiveDate::from_ymd(2019, 9, 14))
+            .with_thank_you(ThankYouStatus::Written, NaiveDate::from_ymd(2019, 9, 30))
+            .unwrap();
+        let mailed = gift("price", "Toaster", NaiveDate::from_ymd(2019, 9, 1))
+            .with_thank_you(ThankYouStatus::Mailed, NaiveDate::from_ymd(2019, 9, 30))
+            .unwrap();
+
+        let report = ThankYouReport::new(&[check, vase, mailed], &households, &rsvps, &[wedding], NaiveDate::from_ymd(2019, 10, 1), "US", "en-US");
+        assert_eq!(report.rows.len(), 2);
+        assert_eq!((report.to_write, report.to_mail), (1, 1));
+        assert_eq!(report.missing_addresses, vec!("Ling Ling".to_string()));
+
+        assert_eq!(report.rows[0].description, "Vase");
+        assert_eq!(report.rows[0].days_waiting, 17);
+        assert_eq!(report.rows[1].household, "The Price Family");
+        assert_eq!(report.rows[1].mailing_address, "1 Main St, Springfield, IL 62704");
+        assert_eq!(report.rows[1].value, "$150.00");
+        assert_eq!(report.rows[1].event, "Wedding");
+
+        let csv = report.csv().unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("household_id,household,mailing_address,gift_id,description,value,event,received_on,status,written_on,days_waiting"));
+    }
+}
@@ -1,5 +1,6 @@
+use serde_derive::{Serialize, Deserialize};
 use std::vec::{Vec};
-use std::collections::{HashMap};
+use std::collections::{HashMap, BTreeMap};
 use std::env;
 use uuid::Uuid;
 use log::{error};
@@ -13,13 +14,37 @@ use rusoto_dynamodb::{
     DynamoDbClient,
     WriteRequest,
     BatchWriteItemInput,
-    BatchWriteItemError
+    BatchWriteItemError,
+    GetItemInput,
+    GetItemError,
+    UpdateItemInput,
+    UpdateItemError,
+    ScanError
 };
 use serde_dynamodb;
 
-use crate::models::{RSVP, Person};
+use crate::models::{RSVP, Person, Menu, Address};
+use super::scan::scan_table;
 
-pub struct Household;
+/// Household-level details, stored apart from the RSVPs that make up the
+/// household. Households created before this record existed have none, and
+/// are treated as having the defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Household {
+    pub id: String,
+    /// How many unnamed companions the household may bring
+    #[serde(default)]
+    pub plus_ones_allowed: u32,
+    /// How many of those companions guests have already named
+    #[serde(default)]
+    pub plus_ones_used: u32,
+    /// How the household is addressed on envelopes, e.g. "The Price Family".
+    /// Guests' names are used when it's blank.
+    #[serde(default)]
+    pub salutation: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<Address>
+}
 
 impl Household {
     pub fn new(people: Vec<Person>) -> Vec<RSVP> {
@@ -34,9 +59,25 @@ impl Household {
     }
 
     pub fn create(people: Vec<Person>) -> Result<Vec<RSVP>, BatchWriteItemError> {
-        let rsvps = Household::new(people); 
+        Household::create_with_plus_ones(people, 0)
+    }
+
+    pub fn create_with_plus_ones(people: Vec<Person>, plus_ones_allowed: u32) -> Result<Vec<RSVP>, BatchWriteItemError> {
+        let mut rsvps = Household::new(people); 
         let client = DynamoDbClient::new(Region::UsEast1);
 
+        // Start everyone on the menu's default meal. If the menu can't be
+        // read, guests are left to choose their own.
+        match Menu::get() {
+            Ok(menu) => {
+                for rsvp in &mut rsvps {
+                    let events : Vec<String> = rsvp.invitations.keys().cloned().collect();
+                    rsvp.food_preference = menu.default_meal(rsvp.age_category, &events);
+                }
+            },
+            Err(error) => error!("Could not load the menu for new household: {}", error)
+        }
+
         let mut put_requests : Vec<WriteRequest> = vec!();
         for rsvp in &rsvps {
             put_requests.push(
@@ -52,6 +93,23 @@ impl Household {
         let mut request_items : HashMap<String, Vec<WriteRequest>> = HashMap::new();
         request_items.insert(env::var("RSVP_TABLE_NAME").unwrap(), put_requests);
 
+        if let Some(rsvp) = rsvps.first() {
+            let household = Household {
+                id: rsvp.household_id.clone(),
+                plus_ones_allowed,
+                ..Household::default()
+            };
+
+            request_items.insert(env::var("HOUSEHOLD_TABLE_NAME").unwrap(), vec!(
+                WriteRequest {
+                    put_request: Some(PutRequest {
+                        item: serde_dynamodb::to_hashmap(&household).unwrap()
+                    }),
+                    ..WriteRequest::default()
+                }
+            ));
+        }
+
         let batch_write_request_input = BatchWriteItemInput {
             request_items: request_items,
             ..BatchWriteItemInput::default()
@@ -104,6 +162,156 @@ impl Household {
             }
         }
     }
+
+    /// Primary key of the household record
+    pub(crate) fn record_key(uuid: &str) -> HashMap<String, AttributeValue> {
+        let mut key = HashMap::new();
+        key.insert(String::from("id"), AttributeValue {
+            s: Some(uuid.to_string()),
+            ..Default::default()
+        });
+        key
+    }
+
+    /// The household-level record, or the defaults if none was stored.
+    pub fn record(uuid: Uuid) -> Result<Household, GetItemError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let get_item_input = GetItemInput {
+            table_name: env::var("HOUSEHOLD_TABLE_NAME").unwrap(),
+            key: Household::record_key(&uuid.to_string()),
+            ..GetItemInput::default()
+        };
+
+        match client.get_item(get_item_input).sync()?.item {
+            Some(item) => Ok(serde_dynamodb::from_hashmap(item).unwrap()),
+            None => Ok(Household {
+                id: uuid.to_string(),
+                ..Household::default()
+            })
+        }
+    }
+
+    /// Changes how many plus-ones a household may bring. The allowance can't
+    /// drop below the number of companions guests have already named.
+    pub fn set_plus_ones_allowed(uuid: Uuid, allowed: u32) -> Result<Household, UpdateItemError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let mut values = HashMap::new();
+        values.insert(String::from(":allowed"), AttributeValue {
+            n: Some(allowed.to_string()),
+            ..Default::default()
+        });
+        values.insert(String::from(":zero"), AttributeValue {
+            n: Some(String::from("0")),
+            ..Default::default()
+        });
+
+        let update_item_input = UpdateItemInput {
+            table_name: env::var("HOUSEHOLD_TABLE_NAME").unwrap(),
+            key: Household::record_key(&uuid.to_string()),
+            update_expression: Some(String::from("SET plus_ones_allowed = :allowed, plus_ones_used = if_not_exists(plus_ones_used, :zero)")),
+            condition_expression: Some(String::from("attribute_not_exists(plus_ones_used) OR plus_ones_used <= :allowed")),
+            expression_attribute_values: Some(values),
+            return_values: Some(String::from("ALL_NEW")),
+            ..UpdateItemInput::default()
+        };
+
+        let attributes = client.update_item(update_item_input).sync()?
+            .attributes
+            .unwrap_or_default();
+        Ok(serde_dynamodb::from_hashmap(attributes).unwrap())
+    }
+
+    /// Sets where the household's mail goes and, when given, how it's
+    /// addressed. The address should already be normalized.
+    pub fn set_address(uuid: Uuid, salutation: Option<String>, address: &Address) -> Result<Household, UpdateItemError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let mut values = HashMap::new();
+        values.insert(String::from(":address"), AttributeValue {
+            m: Some(serde_dynamodb::to_hashmap(address).unwrap()),
+            ..Default::default()
+        });
+
+        let mut update_expression = String::from("SET address = :address");
+        if let Some(salutation) = salutation {
+            update_expression.push_str(", salutation = :salutation");
+            values.insert(String::from(":salutation"), AttributeValue {
+                s: Some(salutation.trim().to_string()),
+                ..Default::default()
+            });
+        }
+
+        let update_item_input = UpdateItemInput {
+            table_name: env::var("HOUSEHOLD_TABLE_NAME").unwrap(),
+            key: Household::record_key(&uuid.to_string()),
+            update_expression: Some(update_expression),
+            expression_attribute_values: Some(values),
+            return_values: Some(String::from("ALL_NEW")),
+            ..UpdateItemInput::default()
+        };
+
+        let attributes = client.update_item(update_item_input).sync()?
+            .attributes
+            .unwrap_or_default();
+        Ok(serde_dynamodb::from_hashmap(attributes).unwrap())
+    }
+
+    /// Every stored household record. Households created before records
+    /// existed won't be among them.
+    pub fn records() -> Result<Vec<Household>, ScanError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+        scan_table(&client, env::var("HOUSEHOLD_TABLE_NAME").unwrap())
+    }
+
+    /// Every household in the table, as groups of RSVPs sharing a
+    /// household_id, ordered by household_id so batch output is stable.
+    pub fn list() -> Result<Vec<Vec<RSVP>>, ScanError> {
+        let mut households : BTreeMap<String, Vec<RSVP>> = BTreeMap::new();
+        for rsvp in RSVP::all()? {
+            households.entry(rsvp.household_id.clone())
+                .or_insert_with(Vec::new)
+                .push(rsvp);
+        }
+
+        Ok(households.into_iter()
+            .map(|(_, mut rsvps)| {
+                rsvps.sort_by(|a, b| a.name.cmp(&b.name));
+                rsvps
+            })
+            .collect())
+    }
+
+    /// A human readable name for the household built from its guests,
+    /// e.g. "Blaine Price & Cynthia Young".
+    pub fn display_name(rsvps: &[RSVP]) -> String {
+        let names : Vec<&str> = rsvps.iter()
+            .map(|rsvp| rsvp.name.as_str())
+            .collect();
+
+        match names.split_last() {
+            None => String::from(""),
+            Some((last, [])) => last.to_string(),
+            Some((last, rest)) => format!("{} & {}", rest.join(", "), last)
+        }
+    }
+
+    /// How the household is addressed on mail: its salutation, or its
+    /// guests' names when it has none.
+    pub fn salutation_for(&self, rsvps: &[RSVP]) -> String {
+        match self.salutation.trim() {
+            "" => Household::display_name(rsvps),
+            salutation => salutation.to_string()
+        }
+    }
+
+    /// The link guests follow to RSVP, rooted at `RSVP_BASE_URL`.
+    pub fn rsvp_link(household_id: &str) -> String {
+        let base_url = env::var("RSVP_BASE_URL")
+            .unwrap_or_else(|_| String::from("https://slswedding.com"));
+        format!("{}/rsvp/{}", base_url.trim_end_matches('/'), household_id)
+    }
 }
 
 
@@ -117,11 +325,13 @@ mod household_tests {
         let people : Vec<Person> = vec!(
             Person {
                 email_address: "1example@email.com".to_string(),
-                name: "person 1".to_string()
+                name: "person 1".to_string(),
+                ..Person::default()
             },
             Person {
                 email_address: "2example@email.com".to_string(),
-                name: "person 2".to_string()
+                name: "person 2".to_string(),
+                ..Person::default()
             }
         );
 
@@ -134,11 +344,13 @@ mod household_tests {
         let people : Vec<Person> = vec!(
             Person {
                 email_address: "1example@email.com".to_string(),
-                name: "person 1".to_string()
+                name: "person 1".to_string(),
+                ..Person::default()
             },
             Person {
                 email_address: "2example@email.com".to_string(),
-                name: "person 2".to_string()
+                name: "person 2".to_string(),
+                ..Person::default()
             }
         );
 
@@ -152,4 +364,30 @@ mod household_tests {
         let rsvps = Household::get(uuid).unwrap();
         assert_eq!(rsvps.len(), 2);
     }
+
+    #[test]
+    fn test_household_display_name() {
+        let household_id = Uuid::new_v4().to_string();
+        let rsvps : Vec<RSVP> = vec!("Blaine Price", "Cynthia Young", "Ling Ling")
+            .into_iter()
+            .map(|name| RSVP::new(
+                Person {
+                    email_address: "example@email.com".to_string(),
+                    name: name.to_string(),
+                    ..Person::default()
+                },
+                household_id.clone()
+            ))
+            .collect();
+
+        assert_eq!(Household::display_name(&rsvps[..1]), "Blaine Price");
+        assert_eq!(Household::display_name(&rsvps[..2]), "Blaine Price & Cynthia Young");
+        assert_eq!(Household::display_name(&rsvps), "Blaine Price, Cynthia Young & Ling Ling");
+        assert_eq!(Household::display_name(&[]), "");
+
+        let mut household = Household::default();
+        assert_eq!(household.salutation_for(&rsvps[..2]), "Blaine Price & Cynthia Young");
+        household.salutation = "The Price Family".to_string();
+        assert_eq!(household.salutation_for(&rsvps[..2]), "The Price Family");
+    }
 }
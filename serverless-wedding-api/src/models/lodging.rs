@@ -0,0 +1,572 @@
+use serde_derive::{Serialize, Deserialize};
+use std::vec::{Vec};
+use std::collections::{HashMap};
+use std::env;
+use std::fmt;
+use std::error::Error;
+use uuid::Uuid;
+use chrono::{DateTime, NaiveDate, Utc};
+use log::{info, warn, error};
+use serde_dynamodb;
+use rusoto_core::Region;
+use rusoto_dynamodb::{
+    DynamoDb,
+    AttributeValue,
+    DynamoDbClient,
+    GetItemInput,
+    GetItemError,
+    PutItemInput,
+    PutItemError,
+    DeleteItemInput,
+    DeleteItemError,
+    UpdateItemError,
+    ScanError
+};
+
+use crate::models::{RSVP, Household};
+use super::scan::scan_table;
+use super::attributes::{Counter, string_value, number_value};
+use super::deadline::{deadline_offset, local_date};
+
+/// A block counts as nearly full once this share of its rooms is booked.
+const NEARLY_FULL_PERCENT : u32 = 80;
+
+/// A hotel holding a block of rooms for guests. `booked` counts the rooms
+/// households have claimed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hotel {
+    pub id: String,
+    pub name: String,
+    pub address: String,
+    #[serde(default)]
+    pub phone: String,
+    #[serde(default)]
+    pub url: String,
+    /// The code guests give the hotel to get the block rate
+    #[serde(default)]
+    pub booking_code: String,
+    pub block_size: u32,
+    /// Nightly rate in cents
+    pub rate_cents: u32,
+    /// Unclaimed rooms go back to the hotel after this day
+    pub release_date: NaiveDate,
+    #[serde(default)]
+    pub booked: u32
+}
+
+/// Where a household is staying. Keyed on the household, so each has at
+/// most one choice.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LodgingChoice {
+    pub household_id: String,
+    pub hotel_id: String,
+    pub rooms: u32,
+    pub check_in: NaiveDate,
+    pub check_out: NaiveDate,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+    pub updated_at: DateTime<Utc>
+}
+
+/// A household's lodging along with anything they should know about the
+/// block, like it being nearly full.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChosenLodging {
+    #[serde(flatten)]
+    pub choice: LodgingChoice,
+    pub hotel: Hotel,
+    pub warnings: Vec<String>
+}
+
+/// A hotel as guests see it, with what's left in the block.
+#[derive(Debug, Clone, Serialize)]
+pub struct HotelStatus {
+    #[serde(flatten)]
+    pub hotel: Hotel,
+    pub remaining: u32,
+    pub released: bool,
+    pub warning: Option<String>
+}
+
+#[derive(Debug)]
+pub enum LodgingError {
+    HotelNotFound,
+    HouseholdNotFound,
+    Invalid(String),
+    Released(Hotel),
+    BlockFull(Hotel),
+    /// Another change to the household's choice was saved first
+    Changed,
+    Get(GetItemError),
+    Scan(ScanError),
+    Update(UpdateItemError),
+    Put(PutItemError),
+    Delete(DeleteItemError)
+}
+
+impl fmt::Display for LodgingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LodgingError::HotelNotFound => write!(f, "No hotel was found with that id"),
+            LodgingError::HouseholdNotFound => write!(f, "No household was found with that id"),
+            LodgingError::Invalid(message) => write!(f, "{}", message),
+            LodgingError::Released(hotel) => write!(
+                f,
+                "The room block at {} was released on {}. Please book with the hotel directly.",
+                hotel.name,
+                hotel.release_date.format("%B %-d, %Y")
+            ),
+            LodgingError::BlockFull(hotel) => write!(
+                f,
+                "The room block at {} only has {} rooms left",
+                hotel.name,
+                hotel.remaining()
+            ),
+            LodgingError::Changed => write!(f, "Your lodging was changed while this was being saved. Please try again."),
+            LodgingError::Get(error) => write!(f, "{}", error),
+            LodgingError::Scan(error) => write!(f, "{}", error),
+            LodgingError::Update(error) => write!(f, "{}", error),
+            LodgingError::Put(error) => write!(f, "{}", error),
+            LodgingError::Delete(error) => write!(f, "{}", error)
+        }
+    }
+}
+
+fn hotel_key(hotel_id: &str) -> HashMap<String, AttributeValue> {
+    let mut key = HashMap::new();
+    key.insert(String::from("id"), string_value(hotel_id));
+    key
+}
+
+fn choice_key(household_id: &str) -> HashMap<String, AttributeValue> {
+    let mut key = HashMap::new();
+    key.insert(String::from("household_id"), string_value(household_id));
+    key
+}
+
+impl Hotel {
+    pub fn new(name: String, address: String, block_size: u32, rate_cents: u32, release_date: NaiveDate) -> Hotel {
+        Hotel {
+            id: Uuid::new_v4().to_string(),
+            name,
+            address,
+            phone: String::new(),
+            url: String::new(),
+            booking_code: String::new(),
+            block_size,
+            rate_cents,
+            release_date,
+            booked: 0
+        }
+    }
+
+    pub fn remaining(&self) -> u32 {
+        self.block_size.saturating_sub(self.booked)
+    }
+
+    pub fn is_released(&self, today: NaiveDate) -> bool {
+        today > self.release_date
+    }
+
+    pub fn is_nearly_full(&self) -> bool {
+        self.block_size > 0 && self.booked * 100 >= self.block_size * NEARLY_FULL_PERCENT
+    }
+
+    /// What to tell guests looking at this block.
+    pub fn warning(&self, today: NaiveDate) -> Option<String> {
+        if self.is_released(today) {
+            Some(format!("The room block at {} has been released", self.name))
+        } else if self.remaining() == 0 {
+            Some(format!("The room block at {} is full", self.name))
+        } else if self.is_nearly_full() {
+            Some(format!(
+                "Only {} of {} rooms are left at {}. Book by {} for the block rate.",
+                self.remaining(),
+                self.block_size,
+                self.name,
+                self.release_date.format("%B %-d")
+            ))
+        } else {
+            None
+        }
+    }
+
+    pub fn status(self, today: NaiveDate) -> HotelStatus {
+        HotelStatus {
+            remaining: self.remaining(),
+            released: self.is_released(today),
+            warning: self.warning(today),
+            hotel: self
+        }
+    }
+
+    pub fn create(&self) -> Result<Hotel, PutItemError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let put_item_input = PutItemInput {
+            table_name: env::var("HOTEL_TABLE_NAME").unwrap(),
+            item: serde_dynamodb::to_hashmap(self).unwrap(),
+            ..PutItemInput::default()
+        };
+
+        client.put_item(put_item_input).sync()?;
+        info!("Created hotel {:?}", self);
+        Ok(self.clone())
+    }
+
+    pub fn get(uuid: Uuid) -> Result<Hotel, GetItemError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let get_item_input = GetItemInput {
+            table_name: env::var("HOTEL_TABLE_NAME").unwrap(),
+            key: hotel_key(&uuid.to_string()),
+            ..GetItemInput::default()
+        };
+
+        match client.get_item(get_item_input).sync()?.item {
+            Some(item) => Ok(serde_dynamodb::from_hashmap(item).unwrap()),
+            None => Err(GetItemError::ResourceNotFound(format!("No hotel with id {}", uuid)))
+        }
+    }
+
+    /// Every hotel, by name.
+    pub fn all() -> Result<Vec<Hotel>, ScanError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+        let mut hotels : Vec<Hotel> = scan_table(&client, env::var("HOTEL_TABLE_NAME").unwrap())?;
+        hotels.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(hotels)
+    }
+
+    /// The rooms households have claimed out of the block.
+    fn booked(hotel_id: &str) -> Counter {
+        Counter {
+            table_name: env::var("HOTEL_TABLE_NAME").unwrap(),
+            key: hotel_key(hotel_id),
+            count: "booked",
+            limit: "block_size"
+        }
+    }
+}
+
+impl LodgingChoice {
+    pub fn new(household_id: String, hotel_id: String, rooms: u32, check_in: NaiveDate, check_out: NaiveDate, notes: String) -> Result<LodgingChoice, String> {
+        if rooms == 0 {
+            return Err(String::from("Book at least one room"));
+        }
+        if check_out <= check_in {
+            return Err(String::from("Check out must be after check in"));
+        }
+
+        Ok(LodgingChoice {
+            household_id,
+            hotel_id,
+            rooms,
+            check_in,
+            check_out,
+            notes: notes.trim().to_string(),
+            updated_at: Utc::now()
+        })
+    }
+
+    pub fn nights(&self) -> i64 {
+        (self.check_out - self.check_in).num_days()
+    }
+
+    pub fn for_household(household_id: &str) -> Result<Option<LodgingChoice>, GetItemError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let get_item_input = GetItemInput {
+            table_name: env::var("LODGING_CHOICE_TABLE_NAME").unwrap(),
+            key: choice_key(household_id),
+            ..GetItemInput::default()
+        };
+
+        Ok(client.get_item(get_item_input).sync()?
+            .item
+            .map(|item| serde_dynamodb::from_hashmap(item).unwrap()))
+    }
+
+    pub fn all() -> Result<Vec<LodgingChoice>, ScanError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+        scan_table(&client, env::var("LODGING_CHOICE_TABLE_NAME").unwrap())
+    }
+
+    /// Books the household into a hotel's block, replacing any earlier
+    /// choice. Only the difference in rooms is claimed when they stay at
+    /// the same hotel, and rooms at a previous hotel are given back. If the
+    /// choice changes underneath, the rooms claimed are given back and it
+    /// fails with `Changed`.
+    pub fn choose(&self) -> Result<ChosenLodging, LodgingError> {
+        let hotel_id = Uuid::parse_str(&self.hotel_id).map_err(|_| LodgingError::HotelNotFound)?;
+        let hotel = Hotel::get(hotel_id).map_err(|_| LodgingError::HotelNotFound)?;
+        let today = local_date(Utc::now(), deadline_offset());
+
+        let client = DynamoDbClient::new(Region::UsEast1);
+        let existing = LodgingChoice::for_household(&self.household_id).map_err(LodgingError::Get)?;
+
+        let (claim, give_back) = match &existing {
+            Some(existing) if existing.hotel_id == hotel.id => {
+                if self.rooms >= existing.rooms {
+                    (self.rooms - existing.rooms, None)
+                } else {
+                    (0, Some((existing.hotel_id.clone(), existing.rooms - self.rooms)))
+                }
+            },
+            Some(existing) => (self.rooms, Some((existing.hotel_id.clone(), existing.rooms))),
+            None => (self.rooms, None)
+        };
+
+        if claim > 0 {
+            if hotel.is_released(today) {
+                return Err(LodgingError::Released(hotel));
+            }
+            match Hotel::booked(&hotel.id).reserve(&client, hotel.block_size, claim) {
+                Ok(()) => {},
+                Err(UpdateItemError::ConditionalCheckFailed(_)) => {
+                    // Report the block as it is now, not as it was read
+                    let current = Hotel::get(hotel_id).unwrap_or(hotel);
+                    return Err(LodgingError::BlockFull(current));
+                },
+                Err(error) => return Err(LodgingError::Update(error))
+            }
+        }
+
+        // The rooms claimed and given back are worked out from the choice
+        // read above, so only save over that same choice
+        let mut values = HashMap::new();
+        let condition_expression = match &existing {
+            Some(existing) => {
+                values.insert(String::from(":hotel_id"), string_value(&existing.hotel_id));
+                values.insert(String::from(":rooms"), number_value(existing.rooms));
+                String::from("hotel_id = :hotel_id AND rooms = :rooms")
+            },
+            None => String::from("attribute_not_exists(household_id)")
+        };
+        let put_item_input = PutItemInput {
+            table_name: env::var("LODGING_CHOICE_TABLE_NAME").unwrap(),
+            item: serde_dynamodb::to_hashmap(self).unwrap(),
+            condition_expression: Some(condition_expression),
+            expression_attribute_values: if values.is_empty() { None } else { Some(values) },
+            ..PutItemInput::default()
+        };
+
+        if let Err(error) = client.put_item(put_item_input).sync() {
+            if claim > 0 {
+                if let Err(release_error) = Hotel::booked(&hotel.id).release(&client, claim) {
+                    error!("Could not release {} rooms at hotel {}: {:?}", claim, hotel.id, release_error);
+                }
+            }
+            return Err(match error {
+                PutItemError::ConditionalCheckFailed(_) => LodgingError::Changed,
+                error => LodgingError::Put(error)
+            });
+        }
+
+        if let Some((hotel_id, rooms)) = give_back {
+            if let Err(release_error) = Hotel::booked(&hotel_id).release(&client, rooms) {
+                error!("Could not release {} rooms at hotel {}: {:?}", rooms, hotel_id, release_error);
+            }
+        }
+
+        let hotel = Hotel::get(hotel_id).map_err(LodgingError::Get)?;
+        let warnings : Vec<String> = hotel.warning(today).into_iter().collect();
+        if hotel.is_nearly_full() {
+            warn!("The block at {} has {} of {} rooms left", hotel.name, hotel.remaining(), hotel.block_size);
+        }
+
+        info!("Booked household {} into {} rooms at {}", self.household_id, self.rooms, hotel.name);
+        Ok(ChosenLodging {
+            choice: self.clone(),
+            hotel,
+            warnings
+        })
+    }
+
+    /// Removes a household's choice, giving its rooms back to the block.
+    pub fn remove(household_id: &str) -> Result<Option<LodgingChoice>, LodgingError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let delete_item_input = DeleteItemInput {
+            table_name: env::var("LODGING_CHOICE_TABLE_NAME").unwrap(),
+            key: choice_key(household_id),
+            return_values: Some(String::from("ALL_OLD")),
+            ..DeleteItemInput::default()
+        };
+
+        let removed : Option<LodgingChoice> = client.delete_item(delete_item_input).sync()
+            .map_err(LodgingError::Delete)?
+            .attributes
+            .map(|item| serde_dynamodb::from_hashmap(item).unwrap());
+
+        if let Some(removed) = &removed {
+            Hotel::booked(&removed.hotel_id).release(&client, removed.rooms)
+                .map_err(LodgingError::Update)?;
+            info!("Household {} gave up {} rooms at hotel {}", household_id, removed.rooms, removed.hotel_id);
+        }
+
+        Ok(removed)
+    }
+}
+
+/// One household's booking, as the hotel needs it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RoomingRow {
+    pub household: String,
+    pub guests: String,
+    pub email_address: String,
+    pub rooms: u32,
+    pub check_in: NaiveDate,
+    pub check_out: NaiveDate,
+    pub nights: i64,
+    pub notes: String
+}
+
+/// Everyone booked into a hotel's block, to send to the hotel.
+#[derive(Debug, Clone, Serialize)]
+pub struct RoomingList {
+    pub hotel: Hotel,
+    pub rows: Vec<RoomingRow>
+}
+
+impl RoomingList {
+    /// Lists the guests coming from each household booked at `hotel`, by
+    /// check in date.
+    pub fn new(hotel: Hotel, choices: &[LodgingChoice], rsvps: &[RSVP]) -> RoomingList {
+        let mut households : HashMap<&str, Vec<&RSVP>> = HashMap::new();
+        for rsvp in rsvps {
+            households.entry(rsvp.household_id.as_str())
+                .or_insert_with(Vec::new)
+                .push(rsvp);
+        }
+
+        let mut rows : Vec<RoomingRow> = choices.iter()
+            .filter(|choice| choice.hotel_id == hotel.id)
+            .map(|choice| {
+                let mut members : Vec<RSVP> = households.get(choice.household_id.as_str())
+                    .map(|members| members.iter().map(|rsvp| (*rsvp).clone()).collect())
+                    .unwrap_or_default();
+                members.sort_by(|a, b| a.name.cmp(&b.name));
+                let attending : Vec<RSVP> = members.iter()
+                    .filter(|rsvp| rsvp.is_attending_any())
+                    .cloned()
+                    .collect();
+
+                RoomingRow {
+                    household: Household::display_name(&members),
+                    guests: Household::display_name(if attending.is_empty() { &members } else { &attending }),
+                    email_address: members.first().map_or(String::new(), |rsvp| rsvp.email_address.clone()),
+                    rooms: choice.rooms,
+                    check_in: choice.check_in,
+                    check_out: choice.check_out,
+                    nights: choice.nights(),
+                    notes: choice.notes.clone()
+                }
+            })
+            .collect();
+        rows.sort_by(|a, b| (a.check_in, &a.household).cmp(&(b.check_in, &b.household)));
+
+        RoomingList { hotel, rows }
+    }
+
+    pub fn get(hotel_id: Uuid) -> Result<RoomingList, LodgingError> {
+        let hotel = Hotel::get(hotel_id).map_err(|error| match error {
+            GetItemError::ResourceNotFound(_) => LodgingError::HotelNotFound,
+            error => LodgingError::Get(error)
+        })?;
+        let choices = LodgingChoice::all().map_err(LodgingError::Scan)?;
+        let rsvps = RSVP::all().map_err(LodgingError::Scan)?;
+        Ok(RoomingList::new(hotel, &choices, &rsvps))
+    }
+
+    pub fn csv(&self) -> Result<String, Box<Error>> {
+        let mut writer = csv::Writer::from_writer(vec!());
+        for row in &self.rows {
+            writer.serialize(row)?;
+        }
+
+        let bytes = writer.into_inner().map_err(|err| err.into_error())?;
+        Ok(String::from_utf8(bytes)?)
+    }
+}
+
+
+#[cfg(test)]
+mod lodging_tests {
+
+    use super::*;
+    use crate::models::{Person};
+
+    fn hotel(block_size: u32, booked: u32) -> Hotel {
+        Hotel {
+            booked,
+            ..Hotel::new(
+                "The Stanley Hotel".to_string(),
+                "333 E Wonderview Ave, Estes Park, CO 80517".to_string(),
+                block_size,
+                22900,
+                NaiveDate::from_ymd(2019, 8, 20)
+            )
+        }
+    }
+
+    fn guest(name: &str, household_id: &str, attending: bool) -> RSVP {
+        let mut rsvp = RSVP::new(
+            Person {
+                name: name.to_string(),
+                email_address: format!("{}@example.com", name.to_lowercase().replace(" ", ".")),
+                ..Person::default()
+            },
+            household_id.to_string()
+        );
+        rsvp.attending = attending;
+        rsvp
+    }
+
+    #[test]
+    fn test_hotel_warnings() {
+        let before_release = NaiveDate::from_ymd(2019, 8, 1);
+        assert_eq!(hotel(10, 7).warning(before_release), None);
+        assert!(hotel(10, 8).is_nearly_full());
+        assert!(hotel(10, 8).warning(before_release).unwrap().starts_with("Only 2 of 10 rooms"));
+        assert_eq!(hotel(10, 10).warning(before_release), Some("The room block at The Stanley Hotel is full".to_string()));
+        assert!(hotel(10, 0).is_released(NaiveDate::from_ymd(2019, 8, 21)));
+        assert!(!hotel(10, 0).is_released(NaiveDate::from_ymd(2019, 8, 20)));
+    }
+
+    #[test]
+    fn test_lodging_choice_new() {
+        let check_in = NaiveDate::from_ymd(2019, 9, 20);
+        let choice = LodgingChoice::new("household".to_string(), "hotel".to_string(), 1, check_in, NaiveDate::from_ymd(2019, 9, 22), " late ".to_string()).unwrap();
+        assert_eq!(choice.nights(), 2);
+        assert_eq!(choice.notes, "late");
+        assert!(LodgingChoice::new("household".to_string(), "hotel".to_string(), 0, check_in, NaiveDate::from_ymd(2019, 9, 22), String::new()).is_err());
+        assert!(LodgingChoice::new("household".to_string(), "hotel".to_string(), 1, check_in, check_in, String::new()).is_err());
+    }
+
+    #[test]
+    fn test_rooming_list() {
+        let stanley = hotel(10, 2);
+        let rsvps = vec!(
+            guest("Cynthia Young", "price", true),
+            guest("Blaine Price", "price", true),
+            guest("Ling Ling", "ling", false),
+            guest("Elsewhere", "elsewhere", true)
+        );
+        let choices = vec!(
+            LodgingChoice::new("price".to_string(), stanley.id.clone(), 1, NaiveDate::from_ymd(2019, 9, 20), NaiveDate::from_ymd(2019, 9, 22), String::new()).unwrap(),
+            LodgingChoice::new("ling".to_string(), stanley.id.clone(), 1, NaiveDate::from_ymd(2019, 9, 19), NaiveDate::from_ymd(2019, 9, 22), String::new()).unwrap(),
+            LodgingChoice::new("elsewhere".to_string(), "another hotel".to_string(), 1, NaiveDate::from_ymd(2019, 9, 19), NaiveDate::from_ymd(2019, 9, 22), String::new()).unwrap()
+        );
+
+        let list = RoomingList::new(stanley, &choices, &rsvps);
+        assert_eq!(list.rows.len(), 2);
+        assert_eq!(list.rows[0].household, "Ling Ling");
+        assert_eq!(list.rows[1].guests, "Blaine Price & Cynthia Young");
+        assert_eq!(list.rows[1].email_address, "blaine.price@example.com");
+
+        let csv = list.csv().unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("household,guests,email_address,rooms,check_in,check_out,nights,notes"));
+        assert_eq!(lines.next(), Some("Ling Ling,Ling Ling,ling.ling@example.com,1,2019-09-19,2019-09-22,3,"));
+    }
+}
@@ -0,0 +1,415 @@
+use serde_derive::{Serialize, Deserialize};
+use std::vec::{Vec};
+use std::env;
+use std::error::Error;
+use log::{info};
+use serde_dynamodb;
+use rusoto_core::Region;
+use rusoto_dynamodb::{
+    DynamoDb,
+    DynamoDbClient,
+    PutItemInput,
+    PutItemError,
+    ScanError
+};
+
+use crate::models::{AgeCategory, RSVP};
+use super::scan::scan_table;
+
+/// Stored when nothing on the menu applies, e.g. for infants or before the
+/// menu has been set up.
+pub const NO_MEAL : &str = "none";
+
+/// The dietary restriction that comes with free-text details in
+/// `dietary_restrictions_other`, so it's always allowed.
+pub const OTHER_DIET : &str = "other";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MenuKind {
+    /// A `food_preference` option
+    Meal,
+    /// A `dietary_restrictions` option
+    Diet
+}
+
+/// One option on the menu. Items are retired by marking them inactive
+/// rather than deleting them, so guests who already chose them still show
+/// up correctly in exports.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MenuItem {
+    #[serde(default)]
+    pub code: String,
+    pub kind: MenuKind,
+    pub label: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Events the item is served at. Empty means every event.
+    #[serde(default)]
+    pub events: Vec<String>,
+    #[serde(default = "default_active")]
+    pub active: bool,
+    /// Only guests with the child age category may choose it
+    #[serde(default)]
+    pub children_only: bool,
+    /// The meal new guests start with
+    #[serde(default)]
+    pub default: bool,
+    /// Codes of the items of the other kind this can't be combined with,
+    /// e.g. a steak dinner lists `vegetarian`. A rule on either item counts.
+    #[serde(default)]
+    pub conflicts_with: Vec<String>
+}
+
+fn default_active() -> bool {
+    true
+}
+
+impl MenuItem {
+    /// Checks an item submitted through the admin API.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.code.trim().is_empty() || self.code.contains(char::is_whitespace) {
+            return Err(String::from("Menu codes can't be empty or contain spaces"));
+        }
+        if self.label.trim().is_empty() {
+            return Err(String::from("Please give the item a label"));
+        }
+        if self.kind == MenuKind::Diet && (self.children_only || self.default) {
+            return Err(String::from("Only meals can be children-only or the default"));
+        }
+        if self.default && self.children_only {
+            return Err(String::from("The default meal can't be children-only"));
+        }
+        Ok(())
+    }
+
+    /// Whether the item is served at any of `events`
+    pub fn served_at(&self, events: &[String]) -> bool {
+        self.events.is_empty() || events.is_empty() || self.events.iter().any(|event| events.contains(event))
+    }
+
+    /// Creates the item, or replaces the one with the same code.
+    pub fn save(&self) -> Result<MenuItem, PutItemError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let put_item_input = PutItemInput {
+            table_name: env::var("MENU_TABLE_NAME").unwrap(),
+            item: serde_dynamodb::to_hashmap(self).unwrap(),
+            ..PutItemInput::default()
+        };
+
+        client.put_item(put_item_input).sync()?;
+        info!("Saved menu item {:?}", self);
+        Ok(self.clone())
+    }
+}
+
+/// The meals and dietary restrictions on the original RSVP card.
+fn standard_items() -> Vec<MenuItem> {
+    let standard = |code: &str, kind: MenuKind, label: &str| MenuItem {
+        code: code.to_string(),
+        kind,
+        label: label.to_string(),
+        description: None,
+        events: vec!(),
+        active: true,
+        children_only: false,
+        default: false,
+        conflicts_with: vec!()
+    };
+
+    vec!(
+        MenuItem { default: true, ..standard("4course", MenuKind::Meal, "Four-Course Meal") },
+        standard("pizza", MenuKind::Meal, "Pizza"),
+        standard("pescatarian", MenuKind::Diet, "Pescatarian - Vegetables & Fish"),
+        standard("vegetarian", MenuKind::Diet, "Vegetarian - No meat, please"),
+        standard("vegan", MenuKind::Diet, "Vegan - No Animal Products"),
+        standard("gluten-free", MenuKind::Diet, "Gluten-Free - No Bread")
+    )
+}
+
+/// A guest whose meal clashes with their own dietary restriction.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Conflict {
+    pub rsvp_id: String,
+    pub household_id: String,
+    pub name: String,
+    pub food_preference: String,
+    pub dietary_restrictions: String,
+    pub message: String
+}
+
+impl Conflict {
+    /// Every guest coming to something whose choices clash.
+    pub fn all() -> Result<Vec<Conflict>, Box<Error>> {
+        let rsvps = RSVP::all()?;
+        let menu = Menu::get()?;
+        Ok(menu.conflicts(rsvps.iter().filter(|rsvp| rsvp.is_attending_any())))
+    }
+}
+
+/// The meal and dietary options guests choose from.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Menu {
+    pub items: Vec<MenuItem>
+}
+
+impl Menu {
+    pub fn new(mut items: Vec<MenuItem>) -> Menu {
+        items.sort_by(|a, b| (a.kind, &a.label).cmp(&(b.kind, &b.label)));
+        Menu { items }
+    }
+
+    /// The whole catalog, including inactive items. Until any meals or
+    /// dietary restrictions have been added, the standard ones are served
+    /// in their place.
+    pub fn get() -> Result<Menu, ScanError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+        let items : Vec<MenuItem> = scan_table(&client, env::var("MENU_TABLE_NAME").unwrap())?;
+        Ok(Menu::new(items).or_standard())
+    }
+
+    /// Fills in the options guests had before the menu was managed through
+    /// the API, for each kind the catalog has nothing of yet.
+    pub fn or_standard(mut self) -> Menu {
+        let missing = |kind: MenuKind| !self.items.iter().any(|item| item.kind == kind);
+        let standard : Vec<MenuItem> = standard_items().into_iter()
+            .filter(|item| missing(item.kind))
+            .collect();
+        self.items.extend(standard);
+        Menu::new(self.items)
+    }
+
+    /// What guests may pick from, optionally only what's served at `event`.
+    pub fn available(&self, event: Option<&str>) -> Menu {
+        Menu {
+            items: self.items.iter()
+                .filter(|item| item.active)
+                .filter(|item| event.map_or(true, |event| item.served_at(&[event.to_string()])))
+                .cloned()
+                .collect()
+        }
+    }
+
+    pub fn meals(&self) -> Vec<&MenuItem> {
+        self.items.iter().filter(|item| item.kind == MenuKind::Meal).collect()
+    }
+
+    pub fn diets(&self) -> Vec<&MenuItem> {
+        self.items.iter().filter(|item| item.kind == MenuKind::Diet).collect()
+    }
+
+    fn find(&self, kind: MenuKind, code: &str) -> Option<&MenuItem> {
+        self.items.iter().find(|item| item.kind == kind && item.code == code)
+    }
+
+    /// Checks a meal choice for a guest invited to `events`. Before any
+    /// meals have been added, any choice is accepted.
+    pub fn check_meal(&self, code: &str, age_category: AgeCategory, events: &[String]) -> Result<(), String> {
+        if self.meals().is_empty() || code.eq_ignore_ascii_case(NO_MEAL) {
+            return Ok(());
+        }
+
+        let item = match self.find(MenuKind::Meal, code) {
+            Some(item) if item.active => item,
+            _ => return Err(format!("{} is not on the menu", code))
+        };
+
+        if !item.served_at(events) {
+            return Err(format!("{} is not served at any event you're invited to", item.label));
+        }
+        if item.children_only && age_category != AgeCategory::Child {
+            return Err(format!("{} is only available to children", item.label));
+        }
+        Ok(())
+    }
+
+    /// Checks a dietary restriction choice. Before any have been added, any
+    /// choice is accepted.
+    pub fn check_diet(&self, code: &str) -> Result<(), String> {
+        if self.diets().is_empty() || code.eq_ignore_ascii_case(NO_MEAL) || code == OTHER_DIET {
+            return Ok(());
+        }
+
+        match self.find(MenuKind::Diet, code) {
+            Some(item) if item.active => Ok(()),
+            _ => Err(format!("{} is not a dietary restriction we know about", code))
+        }
+    }
+
+    /// Why a meal and dietary restriction can't be combined, if they can't.
+    pub fn conflict(&self, food_preference: &str, dietary_restrictions: &str) -> Option<String> {
+        let meal = self.find(MenuKind::Meal, food_preference)?;
+        let diet = self.find(MenuKind::Diet, dietary_restrictions)?;
+
+        if meal.conflicts_with.contains(&diet.code) || diet.conflicts_with.contains(&meal.code) {
+            Some(format!("{} may not suit a {} diet", meal.label, diet.label.to_lowercase()))
+        } else {
+            None
+        }
+    }
+
+    pub fn conflicts<'a, I: IntoIterator<Item = &'a RSVP>>(&self, rsvps: I) -> Vec<Conflict> {
+        rsvps.into_iter()
+            .filter_map(|rsvp| {
+                self.conflict(&rsvp.food_preference, &rsvp.dietary_restrictions)
+                    .map(|message| Conflict {
+                        rsvp_id: rsvp.id.clone(),
+                        household_id: rsvp.household_id.clone(),
+                        name: rsvp.name.clone(),
+                        food_preference: rsvp.food_preference.clone(),
+                        dietary_restrictions: rsvp.dietary_restrictions.clone(),
+                        message
+                    })
+            })
+            .collect()
+    }
+
+    /// The meal a new guest starts with.
+    pub fn default_meal(&self, age_category: AgeCategory, events: &[String]) -> String {
+        let meals : Vec<&MenuItem> = self.meals().into_iter()
+            .filter(|item| item.active && item.served_at(events))
+            .collect();
+
+        if age_category == AgeCategory::Child {
+            if let Some(item) = meals.iter().find(|item| item.children_only) {
+                return item.code.clone();
+            }
+        }
+
+        match meals.iter().find(|item| item.default) {
+            Some(item) if age_category != AgeCategory::Infant => item.code.clone(),
+            _ => String::from(NO_MEAL)
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod menu_tests {
+
+    use super::*;
+    use crate::models::{Person};
+
+    fn item(code: &str, kind: MenuKind) -> MenuItem {
+        MenuItem {
+            code: code.to_string(),
+            kind,
+            label: code.to_string(),
+            description: None,
+            events: vec!(),
+            active: true,
+            children_only: false,
+            default: false,
+            conflicts_with: vec!()
+        }
+    }
+
+    fn menu() -> Menu {
+        let mut four_course = item("4course", MenuKind::Meal);
+        four_course.default = true;
+        let mut kids = item("kids", MenuKind::Meal);
+        kids.children_only = true;
+        let mut pizza = item("pizza", MenuKind::Meal);
+        pizza.events = vec!("bash".to_string());
+        let mut gluten_free = item("gluten-free", MenuKind::Diet);
+        gluten_free.conflicts_with = vec!("pizza".to_string());
+        let mut paleo = item("paleo", MenuKind::Diet);
+        paleo.active = false;
+
+        Menu::new(vec!(four_course, kids, pizza, gluten_free, item("vegan", MenuKind::Diet), paleo))
+    }
+
+    #[test]
+    fn test_menu_check_meal() {
+        let menu = menu();
+        let wedding = vec!("wedding".to_string());
+
+        assert!(menu.check_meal("4course", AgeCategory::Adult, &wedding).is_ok());
+        assert!(menu.check_meal("kids", AgeCategory::Child, &wedding).is_ok());
+        assert!(menu.check_meal("kids", AgeCategory::Adult, &wedding).is_err());
+        assert!(menu.check_meal("kids", AgeCategory::Infant, &wedding).is_err());
+        assert!(menu.check_meal("pizza", AgeCategory::Adult, &wedding).is_err());
+        assert!(menu.check_meal("pizza", AgeCategory::Adult, &["bash".to_string()]).is_ok());
+        assert!(menu.check_meal("sushi", AgeCategory::Adult, &wedding).is_err());
+        assert!(Menu::default().check_meal("sushi", AgeCategory::Adult, &wedding).is_ok());
+    }
+
+    #[test]
+    fn test_menu_check_diet() {
+        let menu = menu();
+
+        assert!(menu.check_diet("vegan").is_ok());
+        assert!(menu.check_diet("None").is_ok());
+        assert!(menu.check_diet("other").is_ok());
+        assert!(menu.check_diet("paleo").is_err());
+    }
+
+    #[test]
+    fn test_menu_default_meal() {
+        let menu = menu();
+
+        assert_eq!(menu.default_meal(AgeCategory::Adult, &[]), "4course");
+        assert_eq!(menu.default_meal(AgeCategory::Child, &[]), "kids");
+        assert_eq!(menu.default_meal(AgeCategory::Infant, &[]), NO_MEAL);
+        assert_eq!(Menu::default().default_meal(AgeCategory::Adult, &[]), NO_MEAL);
+    }
+
+    #[test]
+    fn test_menu_or_standard() {
+        let empty = Menu::default().or_standard();
+        let meals : Vec<&str> = empty.meals().iter().map(|item| item.code.as_str()).collect();
+        assert_eq!(meals, vec!("4course", "pizza"));
+        assert_eq!(empty.default_meal(AgeCategory::Adult, &[]), "4course");
+        assert!(empty.check_meal("sushi", AgeCategory::Adult, &[]).is_err());
+        assert!(empty.check_diet("vegan").is_ok());
+
+        let diets_only = Menu::new(vec!(item("paleo", MenuKind::Diet))).or_standard();
+        let diets : Vec<&str> = diets_only.diets().iter().map(|item| item.code.as_str()).collect();
+        assert_eq!(diets, vec!("paleo"));
+        assert_eq!(diets_only.meals().len(), 2);
+
+        assert_eq!(menu().or_standard().items, menu().items);
+    }
+
+    #[test]
+    fn test_menu_available() {
+        let available = menu().available(Some("wedding"));
+        let codes : Vec<&str> = available.items.iter().map(|item| item.code.as_str()).collect();
+
+        assert_eq!(codes, vec!("4course", "kids", "gluten-free", "vegan"));
+    }
+
+    #[test]
+    fn test_menu_conflicts() {
+        let menu = menu();
+
+        assert_eq!(menu.conflict("pizza", "gluten-free"), Some("pizza may not suit a gluten-free diet".to_string()));
+        assert_eq!(menu.conflict("4course", "gluten-free"), None);
+        assert_eq!(menu.conflict("pizza", "None"), None);
+
+        let mut rsvp = RSVP::new(
+            Person {
+                name: "Blaine Price".to_string(),
+                email_address: "email@example.com".to_string(),
+                ..Person::default()
+            },
+            "household".to_string()
+        );
+        rsvp.food_preference = "pizza".to_string();
+        rsvp.dietary_restrictions = "gluten-free".to_string();
+
+        let conflicts = menu.conflicts(vec!(&rsvp));
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].rsvp_id, rsvp.id);
+    }
+
+    #[test]
+    fn test_menu_item_validate() {
+        let mut diet = item("vegan", MenuKind::Diet);
+        assert!(diet.validate().is_ok());
+        diet.default = true;
+        assert!(diet.validate().is_err());
+        assert!(item("four course", MenuKind::Meal).validate().is_err());
+    }
+}
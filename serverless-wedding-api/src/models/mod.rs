@@ -1,9 +1,57 @@
 mod rsvp;
+mod rsvp_change;
 mod household;
 mod person;
+mod event;
+mod summary;
+mod deadline;
+mod change_request;
+mod plus_one;
+mod caterer_export;
+mod allergy;
+mod menu;
+mod seating;
+mod seating_solver;
+mod address;
+mod travel;
+mod lodging;
+mod shuttle;
+mod carpool;
+mod activity;
+mod registry;
+mod reservation;
+mod money;
+mod gift;
+mod email;
+mod reminder;
+mod attributes;
+mod scan;
 
 pub use self::{
-    rsvp::RSVP,
+    rsvp::{RSVP, PatchedRSVP, Invitation, PatchError},
+    rsvp_change::{StreamEvent, StreamRecord, RsvpChange, RsvpDiff, FieldChange},
     household::Household,
-    person::Person
+    person::{Person, AgeCategory},
+    event::Event,
+    summary::{Summary, EventSummary, AgeBreakdown},
+    deadline::{rsvp_deadline, local_deadline, deadline_offset, local_date, deadline_has_passed},
+    change_request::{ChangeRequest, ChangeSet, ChangeRequestStatus, DecisionError},
+    plus_one::{PlusOne, PlusOneError},
+    caterer_export::{CatererExport, MealCount, CatererRow},
+    allergy::{Allergy, Severity, SevereAllergy, MAJOR_ALLERGENS, parse_allergies, describe_allergies},
+    menu::{Menu, MenuItem, MenuKind, Conflict},
+    seating::{SeatingTable, SeatAssignment, SeatingChart, SeatingConstraint, SeatingError},
+    seating_solver::{SeatingProblem, SeatingPlan, SolverBudget, ConstraintKind, DEFAULT_EVALUATIONS, solve},
+    address::{Address, AddressError},
+    travel::{Travel, ArrivalsReport},
+    lodging::{Hotel, HotelStatus, LodgingChoice, ChosenLodging, LodgingError, RoomingList},
+    shuttle::{ShuttleRun, ShuttleSignup, ShuttleStatus, ShuttleError, ShuttleManifest},
+    carpool::{CarpoolEntry, CarpoolRole, CarpoolMatches, Ride, DEFAULT_WINDOW_MINUTES},
+    activity::{Activity, ActivityStatus, ActivitySignup, SignupStatus, ActivityError, Roster},
+    registry::{RegistryItem, ItemKind, Contribution, ItemProgress, RegistryError, default_currency},
+    reservation::{Reservation, ReservationStatus, ReservationError, Availability, reservation_hours},
+    money::{Money, MoneyError, parse_currency, default_locale},
+    gift::{Gift, GiftKind, ThankYouStatus, GiftError, ThankYouReport},
+    email::{EmailTemplate, TemplateKind, EmailContext, RenderedEmail, EmailError, TEMPLATE_KINDS},
+    reminder::{ReminderSchedule, ReminderPlan, ReminderTarget, SkippedHousehold, ReminderError, parse_quiet_hours, parse_utc_offset}
 };
\ No newline at end of file
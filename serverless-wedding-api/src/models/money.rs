@@ -0,0 +1,383 @@
+use std::cmp::Ordering;
+use std::env;
+use std::fmt;
+use std::str::FromStr;
+use serde::{Serialize, Serializer, Deserializer};
+use serde::de::Error as DeError;
+use serde::ser::SerializeStruct;
+use serde_derive::Deserialize;
+use rust_decimal::Decimal;
+
+/// The locale amounts are formatted for when none is given, from `LOCALE`.
+pub fn default_locale() -> String {
+    env::var("LOCALE").unwrap_or_else(|_| String::from("en-US"))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MoneyError {
+    InvalidCurrency(String),
+    InvalidAmount(String),
+    /// More decimal places than the currency has, like 1.005 dollars
+    TooPrecise(String, String),
+    CurrencyMismatch(String, String),
+    Overflow
+}
+
+impl fmt::Display for MoneyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoneyError::InvalidCurrency(currency) => write!(f, "{} is not a three letter currency code", currency),
+            MoneyError::InvalidAmount(amount) => write!(f, "{} is not an amount of money", amount),
+            MoneyError::TooPrecise(amount, currency) => write!(f, "{} has too many decimal places for {}", amount, currency),
+            MoneyError::CurrencyMismatch(a, b) => write!(f, "Can't combine {} with {}", a, b),
+            MoneyError::Overflow => write!(f, "That amount is too large")
+        }
+    }
+}
+
+/// Upper cases a currency code and checks it looks like one.
+pub fn parse_currency(currency: &str) -> Result<String, MoneyError> {
+    let code = currency.trim().to_uppercase();
+    if code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic()) {
+        Ok(code)
+    } else {
+        Err(MoneyError::InvalidCurrency(currency.to_string()))
+    }
+}
+
+/// How many decimal places a currency is counted in, per ISO 4217.
+pub fn minor_units(currency: &str) -> u32 {
+    match currency {
+        "JPY" | "KRW" | "VND" | "CLP" | "ISK" | "UGX" | "PYG" => 0,
+        "BHD" | "KWD" | "OMR" | "JOD" | "TND" | "IQD" | "LYD" => 3,
+        _ => 2
+    }
+}
+
+/// An exact amount in one currency. Always held at the currency's number
+/// of decimal places, so "129.9" dollars is kept and shown as 129.90.
+///
+/// In JSON and DynamoDB it's `{"amount": "129.90", "currency": "USD"}`,
+/// with the amount as a string so it never passes through a float.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Money {
+    amount: Decimal,
+    currency: String
+}
+
+impl Money {
+    fn at_scale(amount: Decimal, currency: String) -> Result<Money, MoneyError> {
+        let units = minor_units(&currency);
+        if amount.round_dp(units) != amount {
+            return Err(MoneyError::TooPrecise(amount.to_string(), currency));
+        }
+        let mut amount = amount;
+        amount.rescale(units);
+        Ok(Money { amount, currency })
+    }
+
+    pub fn new(amount: Decimal, currency: &str) -> Result<Money, MoneyError> {
+        Money::at_scale(amount, parse_currency(currency)?)
+    }
+
+    /// Reads an amount like "129.99" or "1,234.50". Commas are taken as
+    /// thousands separators.
+    pub fn parse(amount: &str, currency: &str) -> Result<Money, MoneyError> {
+        let cleaned : String = amount.trim().chars().filter(|c| *c != ',').collect();
+        let decimal = Decimal::from_str(&cleaned)
+            .map_err(|_| MoneyError::InvalidAmount(amount.to_string()))?;
+        Money::new(decimal, currency)
+    }
+
+    pub fn zero(currency: &str) -> Result<Money, MoneyError> {
+        Money::new(Decimal::new(0, 0), currency)
+    }
+
+    /// From a count of the currency's smallest unit, e.g. cents.
+    pub fn from_minor(minor: i64, currency: &str) -> Result<Money, MoneyError> {
+        let currency = parse_currency(currency)?;
+        let amount = Decimal::from_i128_with_scale(i128::from(minor), minor_units(&currency));
+        Ok(Money { amount, currency })
+    }
+
+    /// A count of the currency's smallest unit, for payment providers.
+    pub fn to_minor(&self) -> i64 {
+        self.amount.mantissa() as i64
+    }
+
+    pub fn amount(&self) -> Decimal {
+        self.amount
+    }
+
+    pub fn currency(&self) -> &str {
+        &self.currency
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.amount.is_zero()
+    }
+
+    pub fn is_positive(&self) -> bool {
+        !self.amount.is_zero() && !self.amount.is_sign_negative()
+    }
+
+    fn same_currency(&self, other: &Money) -> Result<(), MoneyError> {
+        if self.currency == other.currency {
+            Ok(())
+        } else {
+            Err(MoneyError::CurrencyMismatch(self.currency.clone(), other.currency.clone()))
+        }
+    }
+
+    pub fn checked_add(&self, other: &Money) -> Result<Money, MoneyError> {
+        self.same_currency(other)?;
+        let amount = self.amount.checked_add(other.amount).ok_or(MoneyError::Overflow)?;
+        Money::at_scale(amount, self.currency.clone())
+    }
+
+    pub fn checked_sub(&self, other: &Money) -> Result<Money, MoneyError> {
+        self.same_currency(other)?;
+        let amount = self.amount.checked_sub(other.amount).ok_or(MoneyError::Overflow)?;
+        Money::at_scale(amount, self.currency.clone())
+    }
+
+    /// Adds up amounts that must all be in `currency`.
+    pub fn sum<'a, I>(currency: &str, amounts: I) -> Result<Money, MoneyError>
+        where I: IntoIterator<Item = &'a Money>
+    {
+        amounts.into_iter().fold(Money::zero(currency), |total, amount| total?.checked_add(amount))
+    }
+
+    /// This amount less `other`, stopping at zero.
+    pub fn saturating_sub(&self, other: &Money) -> Result<Money, MoneyError> {
+        let difference = self.checked_sub(other)?;
+        if difference.amount.is_sign_negative() {
+            Money::zero(&self.currency)
+        } else {
+            Ok(difference)
+        }
+    }
+
+    /// Whole percent of `goal` this amount makes up, rounded down and
+    /// capped at 100.
+    pub fn percent_of(&self, goal: &Money) -> Result<u32, MoneyError> {
+        self.same_currency(goal)?;
+        if !goal.is_positive() || !self.is_positive() {
+            return Ok(0);
+        }
+        let percent = (self.amount.mantissa() * 100) / goal.amount.mantissa();
+        Ok(percent.min(100) as u32)
+    }
+
+    /// The amount as people in `locale` write it, e.g. "$1,234.50" for
+    /// en-US or "1.234,50 €" for de-DE.
+    pub fn format(&self, locale: &str) -> String {
+        let style = LocaleStyle::for_locale(locale);
+        let symbol = symbol(&self.currency, &style.region);
+
+        let digits = self.amount.abs().to_string();
+        let (whole, fraction) = match digits.find('.') {
+            Some(index) => (&digits[..index], Some(&digits[index + 1..])),
+            None => (digits.as_str(), None)
+        };
+
+        let mut grouped = String::new();
+        for (index, digit) in whole.chars().enumerate() {
+            if index > 0 && (whole.len() - index) % 3 == 0 {
+                grouped.push_str(style.group);
+            }
+            grouped.push(digit);
+        }
+        if let Some(fraction) = fraction {
+            grouped.push_str(style.decimal);
+            grouped.push_str(fraction);
+        }
+
+        let sign = if self.amount.is_sign_negative() && !self.amount.is_zero() { "-" } else { "" };
+        let space = if style.space || symbol.len() == 3 && symbol.chars().all(|c| c.is_ascii_uppercase()) { "\u{a0}" } else { "" };
+        if style.symbol_first {
+            format!("{}{}{}{}", sign, symbol, space, grouped)
+        } else {
+            format!("{}{}{}{}", sign, grouped, space, symbol)
+        }
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.amount, self.currency)
+    }
+}
+
+/// Only amounts in the same currency can be compared.
+impl PartialOrd for Money {
+    fn partial_cmp(&self, other: &Money) -> Option<Ordering> {
+        if self.currency == other.currency {
+            self.amount.partial_cmp(&other.amount)
+        } else {
+            None
+        }
+    }
+}
+
+impl Serialize for Money {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        let mut state = serializer.serialize_struct("Money", 2)?;
+        state.serialize_field("amount", &self.amount.to_string())?;
+        state.serialize_field("currency", &self.currency)?;
+        state.end()
+    }
+}
+
+#[derive(Deserialize)]
+struct RawMoney {
+    amount: String,
+    currency: String
+}
+
+impl<'de> serde::Deserialize<'de> for Money {
+    fn deserialize<D>(deserializer: D) -> Result<Money, D::Error>
+        where D: Deserializer<'de>
+    {
+        let raw = <RawMoney as serde::Deserialize>::deserialize(deserializer)?;
+        Money::parse(&raw.amount, &raw.currency).map_err(D::Error::custom)
+    }
+}
+
+/// How a locale writes numbers and where the currency symbol goes.
+struct LocaleStyle {
+    region: String,
+    group: &'static str,
+    decimal: &'static str,
+    symbol_first: bool,
+    space: bool
+}
+
+impl LocaleStyle {
+    /// Falls back on the language when the region isn't known, and on
+    /// en-US when neither is.
+    fn for_locale(locale: &str) -> LocaleStyle {
+        let locale = locale.replace('_', "-");
+        let mut parts = locale.split('-');
+        let language = parts.next().unwrap_or("en").to_lowercase();
+        let region = parts.next().map(|region| region.to_uppercase()).unwrap_or_default();
+
+        let style = |group, decimal, symbol_first, space, default_region: &str| LocaleStyle {
+            region: if region.is_empty() { default_region.to_string() } else { region.clone() },
+            group,
+            decimal,
+            symbol_first,
+            space
+        };
+
+        match (language.as_str(), region.as_str()) {
+            ("de", "CH") => style("\u{2019}", ".", true, true, "CH"),
+            ("de", _) => style(".", ",", false, true, "DE"),
+            ("es", "MX") => style(",", ".", true, false, "MX"),
+            ("es", _) => style(".", ",", false, true, "ES"),
+            ("it", _) => style(".", ",", false, true, "IT"),
+            ("pt", _) => style(".", ",", true, true, "BR"),
+            ("nl", _) => style(".", ",", true, true, "NL"),
+            ("fr", "CH") => style("\u{202f}", ".", false, true, "CH"),
+            ("fr", _) => style("\u{202f}", ",", false, true, "FR"),
+            ("ja", _) => style(",", ".", true, false, "JP"),
+            ("zh", _) => style(",", ".", true, false, "CN"),
+            ("en", _) => style(",", ".", true, false, "US"),
+            _ => LocaleStyle {
+                region: String::from("US"),
+                group: ",",
+                decimal: ".",
+                symbol_first: true,
+                space: false
+            }
+        }
+    }
+}
+
+/// The symbol for `currency` as written in `region`. Dollars and yen are
+/// marked with their country outside of home.
+fn symbol(currency: &str, region: &str) -> String {
+    let home = |home_region: &str, local: &str, abroad: &str| {
+        if region == home_region { local.to_string() } else { abroad.to_string() }
+    };
+
+    match currency {
+        "USD" => home("US", "$", "US$"),
+        "CAD" => home("CA", "$", "CA$"),
+        "AUD" => home("AU", "$", "A$"),
+        "NZD" => home("NZ", "$", "NZ$"),
+        "MXN" => home("MX", "$", "MX$"),
+        "JPY" => home("JP", "¥", "JP¥"),
+        "CNY" => home("CN", "¥", "CN¥"),
+        "BRL" => String::from("R$"),
+        "EUR" => String::from("€"),
+        "GBP" => String::from("£"),
+        "INR" => String::from("₹"),
+        other => other.to_string()
+    }
+}
+
+
+#[cfg(test)]
+mod money_tests {
+
+    use super::*;
+    use serde_json::{json};
+
+    fn usd(amount: &str) -> Money {
+        Money::parse(amount, "USD").unwrap()
+    }
+
+    #[test]
+    fn test_parse_money() {
+        assert_eq!(usd("129.9").amount().to_string(), "129.90");
+        assert_eq!(usd("1,234.5").to_minor(), 123450);
+        assert_eq!(Money::parse("500", "jpy").unwrap().to_minor(), 500);
+        assert_eq!(Money::from_minor(21888, "USD").unwrap(), usd("218.88"));
+
+        assert_eq!(Money::parse("1.005", "USD"), Err(MoneyError::TooPrecise("1.005".to_string(), "USD".to_string())));
+        assert_eq!(Money::parse("12.5", "JPY"), Err(MoneyError::TooPrecise("12.5".to_string(), "JPY".to_string())));
+        assert!(Money::parse("twelve", "USD").is_err());
+        assert!(Money::parse("12", "dollars").is_err());
+    }
+
+    #[test]
+    fn test_money_arithmetic_is_exact() {
+        // 0.1 + 0.2 is 0.30000000000000004 as floats
+        let amounts = vec!(usd("0.10"), usd("0.20"), usd("129.99"));
+        assert_eq!(Money::sum("USD", &amounts).unwrap(), usd("130.29"));
+        assert_eq!(usd("50").checked_sub(&usd("20.01")).unwrap(), usd("29.99"));
+        assert_eq!(usd("20").saturating_sub(&usd("50")).unwrap(), usd("0"));
+        assert_eq!(usd("109.44").percent_of(&usd("218.88")).unwrap(), 50);
+        assert_eq!(usd("500").percent_of(&usd("218.88")).unwrap(), 100);
+        assert!(usd("20.01") > usd("20"));
+
+        let euros = Money::parse("10", "EUR").unwrap();
+        assert_eq!(usd("10").checked_add(&euros), Err(MoneyError::CurrencyMismatch("USD".to_string(), "EUR".to_string())));
+        assert_eq!(usd("10").partial_cmp(&euros), None);
+    }
+
+    #[test]
+    fn test_money_serde() {
+        let money = usd("129.9");
+        assert_eq!(serde_json::to_value(&money).unwrap(), json!({"amount": "129.90", "currency": "USD"}));
+        assert_eq!(serde_json::from_value::<Money>(json!({"amount": "129.90", "currency": "usd"})).unwrap(), money);
+        assert!(serde_json::from_value::<Money>(json!({"amount": 129.9, "currency": "USD"})).is_err());
+        assert!(serde_json::from_value::<Money>(json!({"amount": "1.999", "currency": "USD"})).is_err());
+    }
+
+    #[test]
+    fn test_format_money() {
+        assert_eq!(usd("1234.5").format("en-US"), "$1,234.50");
+        assert_eq!(usd("-5").format("en-US"), "-$5.00");
+        assert_eq!(usd("1234.5").format("en-CA"), "US$1,234.50");
+        assert_eq!(Money::parse("1234567.5", "EUR").unwrap().format("de-DE"), "1.234.567,50\u{a0}€");
+        assert_eq!(Money::parse("1234.5", "EUR").unwrap().format("fr"), "1\u{202f}234,50\u{a0}€");
+        assert_eq!(Money::parse("1234", "JPY").unwrap().format("ja-JP"), "¥1,234");
+        assert_eq!(Money::parse("99", "GBP").unwrap().format("en_GB"), "£99.00");
+        assert_eq!(Money::parse("99", "SEK").unwrap().format("en-US"), "SEK\u{a0}99.00");
+        assert_eq!(usd("12").format("xx"), "$12.00");
+    }
+}
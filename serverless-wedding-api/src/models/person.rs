@@ -1,7 +1,26 @@
 use serde_derive::{Serialize, Deserialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AgeCategory {
+    Adult,
+    Child,
+    Infant
+}
+
+impl Default for AgeCategory {
+    fn default() -> AgeCategory {
+        AgeCategory::Adult
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Person {
     pub email_address: String,
-    pub name: String
-}
\ No newline at end of file
+    pub name: String,
+    /// Ids of the events this guest is invited to
+    #[serde(default)]
+    pub events: Vec<String>,
+    #[serde(default)]
+    pub age_category: AgeCategory
+}
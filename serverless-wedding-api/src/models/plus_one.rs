@@ -0,0 +1,258 @@
+use serde_derive::{Deserialize};
+use std::vec::{Vec};
+use std::collections::{HashMap, BTreeSet};
+use std::env;
+use std::fmt;
+use uuid::Uuid;
+use chrono::{DateTime, FixedOffset, Utc};
+use log::{info, error};
+use serde_dynamodb;
+use rusoto_core::Region;
+use rusoto_dynamodb::{
+    DynamoDb,
+    AttributeValue,
+    DynamoDbClient,
+    PutItemInput,
+    PutItemError,
+    UpdateItemInput,
+    UpdateItemError,
+    ScanError
+};
+
+use crate::models::{RSVP, Person, Household, AgeCategory, Menu};
+use super::deadline::{local_deadline, deadline_has_passed};
+
+/// A companion named by a guest to fill one of their household's plus-ones.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PlusOne {
+    pub name: String,
+    #[serde(default)]
+    pub email_address: String,
+    #[serde(default)]
+    pub food_preference: Option<String>,
+    #[serde(default)]
+    pub dietary_restrictions: Option<String>,
+    #[serde(default)]
+    pub dietary_restrictions_other: Option<String>,
+    #[serde(default)]
+    pub age_category: AgeCategory
+}
+
+#[derive(Debug)]
+pub enum PlusOneError {
+    Invalid(String),
+    /// Shown at the offset the deadline was set with
+    DeadlinePassed(DateTime<FixedOffset>),
+    Config(String),
+    HouseholdNotFound,
+    AllowanceExhausted,
+    NameTaken(String),
+    Menu(ScanError),
+    Update(UpdateItemError),
+    Put(PutItemError)
+}
+
+impl fmt::Display for PlusOneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlusOneError::Invalid(message) => write!(f, "{}", message),
+            PlusOneError::DeadlinePassed(deadline) => write!(f, "RSVPs closed on {}", deadline.format("%B %-d, %Y")),
+            PlusOneError::Config(message) => write!(f, "{}", message),
+            PlusOneError::HouseholdNotFound => write!(f, "No household was found with that id"),
+            PlusOneError::AllowanceExhausted => write!(f, "This household has no plus-ones left"),
+            PlusOneError::NameTaken(name) => write!(f, "{} is already on this household's RSVP", name),
+            PlusOneError::Menu(error) => write!(f, "Could not load the menu: {}", error),
+            PlusOneError::Update(error) => write!(f, "{}", error),
+            PlusOneError::Put(error) => write!(f, "{}", error)
+        }
+    }
+}
+
+/// Moves the household's used plus-one count by `delta`. Claiming a slot
+/// is conditional on one being free, so two guests filling in the last
+/// plus-one at once can't both succeed.
+fn adjust_plus_ones_used(client: &DynamoDbClient, household_id: &str, delta: i32) -> Result<(), UpdateItemError> {
+    let mut values = HashMap::new();
+    values.insert(String::from(":delta"), AttributeValue {
+        n: Some(delta.to_string()),
+        ..Default::default()
+    });
+
+    let condition_expression = if delta > 0 {
+        String::from("plus_ones_used < plus_ones_allowed")
+    } else {
+        String::from("plus_ones_used >= :min")
+    };
+    if delta <= 0 {
+        values.insert(String::from(":min"), AttributeValue {
+            n: Some((-delta).to_string()),
+            ..Default::default()
+        });
+    }
+
+    client.update_item(UpdateItemInput {
+        table_name: env::var("HOUSEHOLD_TABLE_NAME").unwrap(),
+        key: Household::record_key(household_id),
+        update_expression: Some(String::from("SET plus_ones_used = plus_ones_used + :delta")),
+        condition_expression: Some(condition_expression),
+        expression_attribute_values: Some(values),
+        ..UpdateItemInput::default()
+    }).sync()?;
+
+    Ok(())
+}
+
+impl PlusOne {
+    /// The companion's RSVP. They're invited to every event anyone in the
+    /// household is invited to, and are assumed to be coming.
+    pub fn to_rsvp(&self, household: &[RSVP]) -> RSVP {
+        let events : BTreeSet<String> = household.iter()
+            .flat_map(|rsvp| rsvp.invitations.keys().cloned())
+            .collect();
+
+        let mut rsvp = RSVP::new(
+            Person {
+                name: self.name.trim().to_string(),
+                email_address: self.email_address.clone(),
+                events: events.into_iter().collect(),
+                age_category: self.age_category
+            },
+            household[0].household_id.clone()
+        );
+
+        rsvp.attending = true;
+        rsvp.plus_one = true;
+        if let Some(food_preference) = &self.food_preference {
+            rsvp.food_preference = food_preference.clone();
+        }
+        if let Some(dietary_restrictions) = &self.dietary_restrictions {
+            rsvp.dietary_restrictions = dietary_restrictions.clone();
+        }
+        if let Some(dietary_restrictions_other) = &self.dietary_restrictions_other {
+            rsvp.dietary_restrictions_other = dietary_restrictions_other.clone();
+        }
+
+        rsvp
+    }
+
+    /// Adds the companion to the household as a full RSVP, using up one of
+    /// the household's plus-ones.
+    pub fn add(self, household_id: Uuid) -> Result<RSVP, PlusOneError> {
+        if let Some(deadline) = local_deadline().map_err(PlusOneError::Config)? {
+            if deadline_has_passed(Some(deadline.with_timezone(&Utc)), Utc::now()) {
+                return Err(PlusOneError::DeadlinePassed(deadline));
+            }
+        }
+
+        if self.name.trim().is_empty() {
+            return Err(PlusOneError::Invalid(String::from("Please tell us your plus-one's name")));
+        }
+
+        let household : Vec<RSVP> = Household::get(household_id)
+            .map_err(|_| PlusOneError::HouseholdNotFound)?;
+        if household.is_empty() {
+            return Err(PlusOneError::HouseholdNotFound);
+        }
+
+        let menu = Menu::get().map_err(PlusOneError::Menu)?;
+        let mut rsvp = self.to_rsvp(&household);
+        let events : Vec<String> = rsvp.invitations.keys().cloned().collect();
+        if self.food_preference.is_none() {
+            rsvp.food_preference = menu.default_meal(rsvp.age_category, &events);
+        }
+        menu.check_meal(&rsvp.food_preference, rsvp.age_category, &events)
+            .map_err(PlusOneError::Invalid)?;
+        menu.check_diet(&rsvp.dietary_restrictions)
+            .map_err(PlusOneError::Invalid)?;
+        if household.iter().any(|guest| guest.name == rsvp.name) {
+            return Err(PlusOneError::NameTaken(rsvp.name));
+        }
+
+        let client = DynamoDbClient::new(Region::UsEast1);
+        match adjust_plus_ones_used(&client, &rsvp.household_id, 1) {
+            Ok(()) => {},
+            Err(UpdateItemError::ConditionalCheckFailed(_)) => return Err(PlusOneError::AllowanceExhausted),
+            Err(error) => return Err(PlusOneError::Update(error))
+        }
+
+        // RSVPs are keyed on household and name, so refuse to overwrite a
+        // guest who shares the companion's name
+        let put_item_input = PutItemInput {
+            table_name: env::var("RSVP_TABLE_NAME").unwrap(),
+            item: serde_dynamodb::to_hashmap(&rsvp).unwrap(),
+            condition_expression: Some(String::from("attribute_not_exists(household_id)")),
+            ..PutItemInput::default()
+        };
+
+        match client.put_item(put_item_input).sync() {
+            Ok(_result) => {
+                info!("Added plus-one {} to household {}", rsvp.id, rsvp.household_id);
+                Ok(rsvp)
+            },
+            Err(error) => {
+                if let Err(release_error) = adjust_plus_ones_used(&client, &rsvp.household_id, -1) {
+                    error!("Could not release a plus-one for household {}: {:?}", rsvp.household_id, release_error);
+                }
+
+                match error {
+                    PutItemError::ConditionalCheckFailed(_) => Err(PlusOneError::NameTaken(rsvp.name)),
+                    error => Err(PlusOneError::Put(error))
+                }
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod plus_one_tests {
+
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_deadline_passed_message() {
+        let deadline = FixedOffset::west(6 * 3600).ymd(2019, 8, 1).and_hms(23, 59, 59);
+
+        assert_eq!(PlusOneError::DeadlinePassed(deadline).to_string(), "RSVPs closed on August 1, 2019");
+    }
+
+    #[test]
+    fn test_plus_one_to_rsvp() {
+        let household_id = Uuid::new_v4().to_string();
+        let household = vec!(
+            RSVP::new(
+                Person {
+                    name: "Blaine Price".to_string(),
+                    email_address: "email@example.com".to_string(),
+                    events: vec!("wedding".to_string()),
+                    ..Person::default()
+                },
+                household_id.clone()
+            ),
+            RSVP::new(
+                Person {
+                    name: "Cynthia Young".to_string(),
+                    email_address: "email@example.com".to_string(),
+                    events: vec!("wedding".to_string(), "bash".to_string()),
+                    ..Person::default()
+                },
+                household_id.clone()
+            )
+        );
+
+        let plus_one = PlusOne {
+            name: " Ling Ling ".to_string(),
+            food_preference: Some("pizza".to_string()),
+            ..PlusOne::default()
+        };
+
+        let rsvp = plus_one.to_rsvp(&household);
+        assert_eq!(rsvp.name, "Ling Ling");
+        assert_eq!(rsvp.household_id, household_id);
+        assert_eq!(rsvp.food_preference, "pizza");
+        assert!(rsvp.plus_one);
+        assert!(rsvp.attending);
+        assert_eq!(rsvp.invitations.len(), 2);
+    }
+}
@@ -0,0 +1,704 @@
+use serde_derive::{Serialize, Deserialize};
+use std::vec::{Vec};
+use std::collections::{HashMap};
+use std::env;
+use std::fmt;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use log::{info, error};
+use serde_dynamodb;
+use rusoto_core::Region;
+use rusoto_dynamodb::{
+    DynamoDb,
+    AttributeValue,
+    DynamoDbClient,
+    GetItemInput,
+    GetItemError,
+    PutItemInput,
+    PutItemError,
+    QueryInput,
+    QueryError,
+    UpdateItemInput,
+    UpdateItemError,
+    ScanError
+};
+
+use super::scan::scan_table;
+use super::attributes::{Counter, string_value};
+use super::money::{Money, MoneyError, parse_currency};
+use super::reservation::{Reservation, Availability};
+
+/// The currency items are priced in when none is given, from `CURRENCY`.
+pub fn default_currency() -> String {
+    env::var("CURRENCY").unwrap_or_else(|_| String::from("USD"))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ItemKind {
+    /// Guests chip in money towards it
+    Fund,
+    /// Guests buy it themselves, reserving it here first
+    Physical
+}
+
+impl Default for ItemKind {
+    fn default() -> Self { ItemKind::Fund }
+}
+
+/// Something on the registry, like airfare or a dinner out that guests
+/// contribute towards, or a physical gift they buy themselves. What's been
+/// raised is always added up from its contributions, so it's exact and
+/// can't drift from what was actually given.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegistryItem {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    /// Path or URL of the picture shown with the item
+    #[serde(default)]
+    pub image: String,
+    /// What the item costs. Open ended items, like a lucky envelope, have
+    /// no goal and are never funded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal: Option<Money>,
+    /// ISO 4217 code, e.g. "USD"
+    pub currency: String,
+    /// When contributions first reached the goal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub funded_at: Option<DateTime<Utc>>,
+    /// Items are listed lowest first
+    #[serde(default)]
+    pub position: u32,
+    #[serde(default)]
+    pub kind: ItemKind,
+    /// Where to buy a physical item
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub url: String,
+    /// Roughly what a physical item costs, for guests' reference
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub price: Option<Money>,
+    /// How many of a physical item the couple wants
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quantity: Option<u32>,
+    /// How many of a physical item are reserved, held or bought. Only ever
+    /// changed with conditional writes, so it can't go over `quantity`.
+    #[serde(default)]
+    pub claimed: u32
+}
+
+/// A gift towards a registry item. Keyed on the item, so an item's
+/// contributions can be queried together. Contributions paid through the
+/// payment provider use the payment's id, so each payment is recorded once.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Contribution {
+    pub item_id: String,
+    pub id: String,
+    pub household_id: String,
+    pub amount: Money,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub message: String,
+    pub created_at: DateTime<Utc>,
+    /// Set when it came through the payment provider, rather than being
+    /// recorded by hand
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payment_id: Option<String>,
+    /// How much has been given back, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refunded: Option<Money>,
+    /// The provider's refunds already counted, so none is counted twice
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub refund_ids: Vec<String>
+}
+
+/// An item as guests see it, with how close it is to its goal.
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemProgress {
+    #[serde(flatten)]
+    pub item: RegistryItem,
+    pub raised: Money,
+    pub remaining: Option<Money>,
+    /// Whole percent of the goal raised, capped at 100
+    pub percent: Option<u32>,
+    pub funded: bool,
+    /// What's left of a physical item
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub availability: Option<Availability>
+}
+
+#[derive(Debug)]
+pub enum RegistryError {
+    ItemNotFound,
+    ContributionNotFound,
+    Invalid(String),
+    AlreadyRecorded,
+    Money(MoneyError),
+    Get(GetItemError),
+    Update(UpdateItemError),
+    Put(PutItemError),
+    Query(QueryError),
+    Scan(ScanError)
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RegistryError::ItemNotFound => write!(f, "No registry item was found with that id"),
+            RegistryError::ContributionNotFound => write!(f, "No contribution was found with that id"),
+            RegistryError::Invalid(message) => write!(f, "{}", message),
+            RegistryError::AlreadyRecorded => write!(f, "That has already been recorded"),
+            RegistryError::Money(error) => write!(f, "{}", error),
+            RegistryError::Get(error) => write!(f, "{}", error),
+            RegistryError::Update(error) => write!(f, "{}", error),
+            RegistryError::Put(error) => write!(f, "{}", error),
+            RegistryError::Query(error) => write!(f, "{}", error),
+            RegistryError::Scan(error) => write!(f, "{}", error)
+        }
+    }
+}
+
+/// How many times a refund is retried when other refunds keep landing first
+const REFUND_ATTEMPTS : u32 = 5;
+
+fn item_key(item_id: &str) -> HashMap<String, AttributeValue> {
+    let mut key = HashMap::new();
+    key.insert(String::from("id"), string_value(item_id));
+    key
+}
+
+fn contribution_key(item_id: &str, contribution_id: &str) -> HashMap<String, AttributeValue> {
+    let mut key = HashMap::new();
+    key.insert(String::from("item_id"), string_value(item_id));
+    key.insert(String::from("id"), string_value(contribution_id));
+    key
+}
+
+impl ItemProgress {
+    /// Adds up what's left of each of the item's contributions after
+    /// refunds. Contributions to other items are skipped, so everything
+    /// given can be passed in.
+    pub fn new(item: RegistryItem, contributions: &[Contribution]) -> Result<ItemProgress, MoneyError> {
+        let given = contributions.iter()
+            .filter(|contribution| contribution.item_id == item.id)
+            .map(|contribution| contribution.refundable())
+            .collect::<Result<Vec<Money>, MoneyError>>()?;
+        let raised = Money::sum(&item.currency, &given)?;
+
+        let (remaining, percent, funded) = match item.goal {
+            Some(ref goal) => {
+                let remaining = goal.saturating_sub(&raised)?;
+                let funded = remaining.is_zero();
+                (Some(remaining), Some(raised.percent_of(goal)?), funded)
+            },
+            None => (None, None, false)
+        };
+
+        Ok(ItemProgress {
+            item,
+            raised,
+            remaining,
+            percent,
+            funded,
+            availability: None
+        })
+    }
+
+    /// Adds what's left of a physical item from its reservations. It's
+    /// funded once every one has been bought.
+    pub fn with_availability(self, reservations: &[Reservation], now: DateTime<Utc>) -> ItemProgress {
+        match Availability::new(&self.item, reservations, now) {
+            Some(availability) => ItemProgress {
+                funded: availability.purchased >= availability.quantity,
+                availability: Some(availability),
+                ..self
+            },
+            None => self
+        }
+    }
+
+    /// Looks up the item's contributions to see where it stands.
+    pub fn get(item: RegistryItem) -> Result<ItemProgress, RegistryError> {
+        let contributions = Contribution::for_item(&item.id).map_err(RegistryError::Query)?;
+        ItemProgress::new(item, &contributions).map_err(RegistryError::Money)
+    }
+}
+
+impl RegistryItem {
+    pub fn new(title: String, goal: Option<Money>, currency: &str) -> Result<RegistryItem, String> {
+        if title.trim().is_empty() {
+            return Err(String::from("Registry items need a title"));
+        }
+        let currency = parse_currency(currency).map_err(|error| error.to_string())?;
+        if let Some(ref goal) = goal {
+            if !goal.is_positive() {
+                return Err(String::from("Leave the goal out for open ended items"));
+            }
+            if goal.currency() != currency {
+                return Err(format!("The goal is in {}, not {}", goal.currency(), currency));
+            }
+        }
+
+        Ok(RegistryItem {
+            id: Uuid::new_v4().to_string(),
+            title: title.trim().to_string(),
+            description: String::new(),
+            image: String::new(),
+            goal,
+            currency,
+            funded_at: None,
+            position: 0,
+            kind: ItemKind::Fund,
+            url: String::new(),
+            price: None,
+            quantity: None,
+            claimed: 0
+        })
+    }
+
+    /// A gift guests buy themselves, like a set of towels. Guests reserve
+    /// it first so no more than `quantity` are bought.
+    pub fn new_physical(title: String, price: Option<Money>, currency: &str, quantity: u32) -> Result<RegistryItem, String> {
+        if quantity == 0 {
+            return Err(String::from("Physical items need a quantity of at least 1"));
+        }
+        let item = RegistryItem::new(title, None, currency)?;
+        if let Some(ref price) = price {
+            if price.currency() != item.currency {
+                return Err(format!("The price is in {}, not {}", price.currency(), item.currency));
+            }
+        }
+
+        Ok(RegistryItem {
+            kind: ItemKind::Physical,
+            price,
+            quantity: Some(quantity),
+            ..item
+        })
+    }
+
+    pub fn create(&self) -> Result<RegistryItem, PutItemError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let put_item_input = PutItemInput {
+            table_name: env::var("REGISTRY_ITEM_TABLE_NAME").unwrap(),
+            item: serde_dynamodb::to_hashmap(self).unwrap(),
+            ..PutItemInput::default()
+        };
+
+        client.put_item(put_item_input).sync()?;
+        info!("Created registry item {:?}", self);
+        Ok(self.clone())
+    }
+
+    pub fn get(uuid: Uuid) -> Result<RegistryItem, GetItemError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let get_item_input = GetItemInput {
+            table_name: env::var("REGISTRY_ITEM_TABLE_NAME").unwrap(),
+            key: item_key(&uuid.to_string()),
+            ..GetItemInput::default()
+        };
+
+        match client.get_item(get_item_input).sync()?.item {
+            Some(item) => Ok(serde_dynamodb::from_hashmap(item).unwrap()),
+            None => Err(GetItemError::ResourceNotFound(format!("No registry item with id {}", uuid)))
+        }
+    }
+
+    /// Every item, in the order the couple set.
+    pub fn all() -> Result<Vec<RegistryItem>, ScanError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+        let mut items : Vec<RegistryItem> = scan_table(&client, env::var("REGISTRY_ITEM_TABLE_NAME").unwrap())?;
+        items.sort_by(|a, b| (a.position, &a.title).cmp(&(b.position, &b.title)));
+        Ok(items)
+    }
+
+    /// Every item with its progress, adding up all the contributions at once
+    /// rather than querying each item's.
+    pub fn all_with_progress() -> Result<Vec<ItemProgress>, RegistryError> {
+        let items = RegistryItem::all().map_err(RegistryError::Scan)?;
+        let contributions = Contribution::all().map_err(RegistryError::Scan)?;
+        let reservations = Reservation::all().map_err(RegistryError::Scan)?;
+        let now = Utc::now();
+
+        items.into_iter()
+            .map(|item| ItemProgress::new(item, &contributions)
+                .map(|progress| progress.with_availability(&reservations, now))
+                .map_err(RegistryError::Money))
+            .collect()
+    }
+
+    /// How many of a physical item households have claimed. Claims also
+    /// fail if the quantity was changed since the item was read.
+    pub(crate) fn claimed(item_id: &str) -> Counter {
+        Counter {
+            table_name: env::var("REGISTRY_ITEM_TABLE_NAME").unwrap(),
+            key: item_key(item_id),
+            count: "claimed",
+            limit: "quantity"
+        }
+    }
+
+    /// Records when the goal was first reached. Only the first contribution
+    /// over the line sets it.
+    fn mark_funded(client: &DynamoDbClient, item_id: &str, now: DateTime<Utc>) -> Result<(), UpdateItemError> {
+        let mut values = HashMap::new();
+        values.insert(String::from(":funded_at"), string_value(&now.to_rfc3339()));
+
+        client.update_item(UpdateItemInput {
+            table_name: env::var("REGISTRY_ITEM_TABLE_NAME").unwrap(),
+            key: item_key(item_id),
+            update_expression: Some(String::from("SET funded_at = :funded_at")),
+            condition_expression: Some(String::from("attribute_not_exists(funded_at)")),
+            expression_attribute_values: Some(values),
+            ..UpdateItemInput::default()
+        }).sync()?;
+
+        Ok(())
+    }
+}
+
+impl Contribution {
+    pub fn new(item: &RegistryItem, household_id: String, amount: Money, message: String) -> Result<Contribution, String> {
+        if item.kind == ItemKind::Physical {
+            return Err(format!("{} is bought, not contributed to. Reserve it instead.", item.title));
+        }
+        if !amount.is_positive() {
+            return Err(String::from("Contributions must be more than zero"));
+        }
+        if amount.currency() != item.currency {
+            return Err(format!("{} is priced in {}, not {}", item.title, item.currency, amount.currency()));
+        }
+
+        Ok(Contribution {
+            item_id: item.id.clone(),
+            id: Uuid::new_v4().to_string(),
+            household_id,
+            amount,
+            message: message.trim().to_string(),
+            created_at: Utc::now(),
+            payment_id: None,
+            refunded: None,
+            refund_ids: vec!()
+        })
+    }
+
+    /// Ties the contribution to a provider payment, taking its id.
+    pub fn with_payment(self, payment_id: &str) -> Contribution {
+        Contribution {
+            id: payment_id.to_string(),
+            payment_id: Some(payment_id.to_string()),
+            ..self
+        }
+    }
+
+    /// What's left after refunds.
+    pub fn refundable(&self) -> Result<Money, MoneyError> {
+        match self.refunded {
+            Some(ref refunded) => self.amount.saturating_sub(refunded),
+            None => Ok(self.amount.clone())
+        }
+    }
+
+    pub fn get(item_id: &str, contribution_id: &str) -> Result<Contribution, RegistryError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let get_item_input = GetItemInput {
+            table_name: env::var("CONTRIBUTION_TABLE_NAME").unwrap(),
+            key: contribution_key(item_id, contribution_id),
+            ..GetItemInput::default()
+        };
+
+        match client.get_item(get_item_input).sync().map_err(RegistryError::Get)?.item {
+            Some(item) => Ok(serde_dynamodb::from_hashmap(item).unwrap()),
+            None => Err(RegistryError::ContributionNotFound)
+        }
+    }
+
+    /// Looks up the contribution's item and where it stands now.
+    fn item_progress(&self) -> Result<ItemProgress, RegistryError> {
+        let item_id = Uuid::parse_str(&self.item_id).map_err(|_| RegistryError::ItemNotFound)?;
+        let item = match RegistryItem::get(item_id) {
+            Ok(item) => item,
+            Err(GetItemError::ResourceNotFound(_)) => return Err(RegistryError::ItemNotFound),
+            Err(error) => return Err(RegistryError::Get(error))
+        };
+        ItemProgress::get(item)
+    }
+
+    /// Saves the contribution, marking its item funded if this reaches the
+    /// goal. Returns the item's progress afterwards. Recording the same
+    /// contribution again, like a webhook the provider retried, is an
+    /// `AlreadyRecorded` error and isn't counted twice.
+    pub fn record(&self) -> Result<ItemProgress, RegistryError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let put_item_input = PutItemInput {
+            table_name: env::var("CONTRIBUTION_TABLE_NAME").unwrap(),
+            item: serde_dynamodb::to_hashmap(self).unwrap(),
+            condition_expression: Some(String::from("attribute_not_exists(id)")),
+            ..PutItemInput::default()
+        };
+        match client.put_item(put_item_input).sync() {
+            Ok(_) => {},
+            Err(PutItemError::ConditionalCheckFailed(_)) => return Err(RegistryError::AlreadyRecorded),
+            Err(error) => return Err(RegistryError::Put(error))
+        }
+
+        let mut progress = self.item_progress()?;
+        info!("Household {} gave {} towards {}", self.household_id, self.amount, progress.item.title);
+
+        if progress.funded && progress.item.funded_at.is_none() {
+            let now = Utc::now();
+            match RegistryItem::mark_funded(&client, &progress.item.id, now) {
+                Ok(()) => {
+                    info!("{} is fully funded", progress.item.title);
+                    progress.item.funded_at = Some(now);
+                },
+                Err(UpdateItemError::ConditionalCheckFailed(_)) => {},
+                Err(error) => error!("Could not mark {} funded: {:?}", progress.item.id, error)
+            }
+        }
+
+        Ok(progress)
+    }
+
+    /// The total refunded once `amount` is counted as refund `refund_id`.
+    fn refunded_with(&self, refund_id: &str, amount: &Money) -> Result<Money, RegistryError> {
+        if self.refund_ids.iter().any(|id| id == refund_id) {
+            return Err(RegistryError::AlreadyRecorded);
+        }
+        let refundable = self.refundable().map_err(RegistryError::Money)?;
+        if amount.currency() != refundable.currency() {
+            return Err(RegistryError::Invalid(format!("The contribution was in {}, not {}", refundable.currency(), amount.currency())));
+        }
+        if !amount.is_positive() || *amount > refundable {
+            return Err(RegistryError::Invalid(format!("Only {} can be refunded", refundable)));
+        }
+        match self.refunded {
+            Some(ref refunded) => refunded.checked_add(amount).map_err(RegistryError::Money),
+            None => Ok(amount.clone())
+        }
+    }
+
+    /// Sets the refunded total, as long as it's still what this copy of the
+    /// contribution says and the refund hasn't been counted.
+    fn update_refunded(&self, client: &DynamoDbClient, refund_id: &str, refunded: &Money) -> Result<(), UpdateItemError> {
+        let mut values = HashMap::new();
+        values.insert(String::from(":refunded"), AttributeValue {
+            m: Some(serde_dynamodb::to_hashmap(refunded).unwrap()),
+            ..Default::default()
+        });
+        values.insert(String::from(":refund_id"), string_value(refund_id));
+        values.insert(String::from(":refund_ids"), AttributeValue {
+            l: Some(vec!(string_value(refund_id))),
+            ..Default::default()
+        });
+        values.insert(String::from(":none"), AttributeValue {
+            l: Some(vec!()),
+            ..Default::default()
+        });
+        let refunded_condition = match self.refunded {
+            Some(ref previous) => {
+                values.insert(String::from(":previous"), string_value(&previous.amount().to_string()));
+                "refunded.amount = :previous"
+            },
+            None => "attribute_not_exists(refunded)"
+        };
+
+        client.update_item(UpdateItemInput {
+            table_name: env::var("CONTRIBUTION_TABLE_NAME").unwrap(),
+            key: contribution_key(&self.item_id, &self.id),
+            update_expression: Some(String::from(
+                "SET refunded = :refunded, \
+                refund_ids = list_append(if_not_exists(refund_ids, :none), :refund_ids)"
+            )),
+            condition_expression: Some(format!(
+                "(attribute_not_exists(refund_ids) OR NOT contains(refund_ids, :refund_id)) AND {}",
+                refunded_condition
+            )),
+            expression_attribute_values: Some(values),
+            ..UpdateItemInput::default()
+        }).sync()?;
+        Ok(())
+    }
+
+    /// Counts a provider refund against the contribution. Each refund is
+    /// only counted once, whether it comes from the admin API or the
+    /// provider's webhook. When another refund is counted first, the
+    /// contribution is read again and this one is added on top, so refunds
+    /// arriving together are all counted. Returns the item's progress
+    /// afterwards.
+    pub fn record_refund(&self, refund_id: &str, amount: &Money) -> Result<ItemProgress, RegistryError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let mut contribution = self.clone();
+        let mut attempts = 0;
+        loop {
+            let refunded = contribution.refunded_with(refund_id, amount)?;
+            match contribution.update_refunded(&client, refund_id, &refunded) {
+                Ok(()) => break,
+                Err(UpdateItemError::ConditionalCheckFailed(message)) => {
+                    attempts += 1;
+                    if attempts >= REFUND_ATTEMPTS {
+                        return Err(RegistryError::Update(UpdateItemError::ConditionalCheckFailed(message)));
+                    }
+                    info!("Contribution {} changed while counting refund {}, reading it again", self.id, refund_id);
+                    contribution = Contribution::get(&self.item_id, &self.id)?;
+                },
+                Err(error) => return Err(RegistryError::Update(error))
+            }
+        }
+
+        let progress = self.item_progress()?;
+        info!("Refunded {} of contribution {} to {}", amount, self.id, progress.item.title);
+        Ok(progress)
+    }
+
+    /// Every contribution towards an item, oldest first.
+    pub fn for_item(item_id: &str) -> Result<Vec<Contribution>, QueryError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let mut values = HashMap::new();
+        values.insert(String::from(":item_id"), string_value(item_id));
+
+        let query_input = QueryInput {
+            table_name: env::var("CONTRIBUTION_TABLE_NAME").unwrap(),
+            key_condition_expression: Some(String::from("item_id = :item_id")),
+            expression_attribute_values: Some(values),
+            ..QueryInput::default()
+        };
+
+        let mut contributions : Vec<Contribution> = client.query(query_input).sync()?
+            .items
+            .unwrap_or_default()
+            .into_iter()
+            .map(|item| serde_dynamodb::from_hashmap(item).unwrap())
+            .collect();
+        contributions.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        Ok(contributions)
+    }
+
+    pub fn all() -> Result<Vec<Contribution>, ScanError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+        let mut contributions : Vec<Contribution> = scan_table(&client, env::var("CONTRIBUTION_TABLE_NAME").unwrap())?;
+        contributions.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        Ok(contributions)
+    }
+}
+
+
+#[cfg(test)]
+mod registry_tests {
+
+    use super::*;
+    use crate::models::ReservationStatus;
+
+    fn usd(amount: &str) -> Money {
+        Money::parse(amount, "USD").unwrap()
+    }
+
+    fn given(item: &RegistryItem, amount: &str) -> Contribution {
+        Contribution::new(item, "household".to_string(), usd(amount), String::new()).unwrap()
+    }
+
+    #[test]
+    fn test_item_progress() {
+        let item = RegistryItem::new(" Roundtrip Air Fare ".to_string(), Some(usd("218.88")), "usd").unwrap();
+        assert_eq!(item.title, "Roundtrip Air Fare");
+        assert_eq!(item.currency, "USD");
+
+        let progress = ItemProgress::new(item.clone(), &[given(&item, "100.00"), given(&item, "9.44")]).unwrap();
+        assert_eq!(progress.raised, usd("109.44"));
+        assert_eq!(progress.percent, Some(50));
+        assert_eq!(progress.remaining, Some(usd("109.44")));
+        assert!(!progress.funded);
+
+        let refunded = Contribution { refunded: Some(usd("100")), ..given(&item, "300") };
+        let progress = ItemProgress::new(item.clone(), &[given(&item, "18.88"), refunded]).unwrap();
+        assert_eq!(progress.raised, usd("218.88"));
+        assert_eq!(progress.percent, Some(100));
+        assert_eq!(progress.remaining, Some(usd("0")));
+        assert!(progress.funded);
+
+        let envelope = RegistryItem::new("Lucky Envelope".to_string(), None, "USD").unwrap();
+        let progress = ItemProgress::new(envelope.clone(), &[given(&envelope, "50"), given(&item, "20")]).unwrap();
+        assert_eq!(progress.raised, usd("50"));
+        assert_eq!(progress.percent, None);
+        assert!(!progress.funded);
+    }
+
+    #[test]
+    fn test_contribution_new() {
+        let item = RegistryItem::new("Spa for Two".to_string(), Some(usd("68.88")), "USD").unwrap();
+        let contribution = Contribution::new(&item, "household".to_string(), usd("25"), " Enjoy! ".to_string()).unwrap();
+        assert_eq!(contribution.item_id, item.id);
+        assert_eq!(contribution.message, "Enjoy!");
+
+        let euros = Money::parse("25", "EUR").unwrap();
+        assert!(Contribution::new(&item, "household".to_string(), usd("0"), String::new()).is_err());
+        assert!(Contribution::new(&item, "household".to_string(), euros.clone(), String::new()).is_err());
+        assert!(RegistryItem::new("Spa for Two".to_string(), Some(usd("0")), "USD").is_err());
+        assert!(RegistryItem::new("Spa for Two".to_string(), Some(euros), "USD").is_err());
+        assert!(RegistryItem::new("Spa for Two".to_string(), Some(usd("68.88")), "dollars").is_err());
+    }
+
+    #[test]
+    fn test_physical_item() {
+        let item = RegistryItem::new_physical("Dutch Oven".to_string(), Some(usd("89.95")), "USD", 1).unwrap();
+        assert_eq!(item.kind, ItemKind::Physical);
+        assert_eq!(item.quantity, Some(1));
+        assert_eq!(item.goal, None);
+        assert!(Contribution::new(&item, "household".to_string(), usd("25"), String::new()).is_err());
+        assert!(RegistryItem::new_physical("Dutch Oven".to_string(), None, "USD", 0).is_err());
+        assert!(RegistryItem::new_physical("Dutch Oven".to_string(), Some(Money::parse("80", "EUR").unwrap()), "USD", 1).is_err());
+
+        let now = Utc::now();
+        let bought = Reservation::new(&item, "household".to_string(), 1, now).unwrap();
+        let progress = ItemProgress::new(item.clone(), &[]).unwrap().with_availability(&[bought.clone()], now);
+        assert!(!progress.funded);
+        assert_eq!(progress.availability.map(|availability| availability.available), Some(0));
+
+        let bought = Reservation { status: ReservationStatus::Confirmed, confirmed_at: Some(now), ..bought };
+        let progress = ItemProgress::new(item, &[]).unwrap().with_availability(&[bought], now);
+        assert!(progress.funded);
+    }
+
+    #[test]
+    fn test_contribution_with_payment() {
+        let item = RegistryItem::new("Spa for Two".to_string(), Some(usd("68.88")), "USD").unwrap();
+        let contribution = given(&item, "25").with_payment("fake_pay_123");
+        assert_eq!(contribution.id, "fake_pay_123");
+        assert_eq!(contribution.payment_id, Some("fake_pay_123".to_string()));
+
+        let contribution = Contribution { refunded: Some(usd("10")), ..contribution };
+        assert_eq!(contribution.refundable().unwrap(), usd("15"));
+    }
+
+    #[test]
+    fn test_concurrent_refunds() {
+        let item = RegistryItem::new("Spa for Two".to_string(), Some(usd("68.88")), "USD").unwrap();
+        let read = given(&item, "50").with_payment("fake_pay_123");
+        assert_eq!(read.refunded_with("refund_1", &usd("30")).unwrap(), usd("30"));
+
+        // Refund 2 was read alongside refund 1 but lost the race. Reading
+        // the contribution again counts it on top rather than dropping it.
+        let current = Contribution {
+            refunded: Some(usd("30")),
+            refund_ids: vec!("refund_1".to_string()),
+            ..read.clone()
+        };
+        assert_eq!(read.refunded_with("refund_2", &usd("20")).unwrap(), usd("20"));
+        assert_eq!(current.refunded_with("refund_2", &usd("20")).unwrap(), usd("50"));
+
+        match current.refunded_with("refund_1", &usd("30")) {
+            Err(RegistryError::AlreadyRecorded) => {},
+            other => panic!("Expected the refund to already be recorded, got {:?}", other)
+        }
+        match current.refunded_with("refund_3", &usd("25")) {
+            Err(RegistryError::Invalid(message)) => assert_eq!(message, "Only 20.00 USD can be refunded"),
+            other => panic!("Expected too large a refund to be refused, got {:?}", other)
+        }
+    }
+}
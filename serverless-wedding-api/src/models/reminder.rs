@@ -0,0 +1,343 @@
+use serde_derive::{Serialize};
+use std::vec::{Vec};
+use std::env;
+use std::fmt;
+use chrono::{DateTime, Duration, FixedOffset, Timelike, Utc};
+
+use crate::models::{RSVP, Household, Event, EmailContext};
+use super::deadline::{deadline_offset, deadline_has_passed};
+
+/// When the reminder job chases households that haven't answered, and how
+/// often.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReminderSchedule {
+    /// Reminders start this many days before the deadline
+    pub window_days: i64,
+    /// A household is never sent more reminders than this
+    pub max_reminders: u32,
+    /// Days to wait after a reminder before sending another
+    pub interval_days: i64,
+    /// Local hours nothing is sent in, as (start, end). (21, 8) is 9pm to 8am.
+    pub quiet_hours: Option<(u32, u32)>,
+    /// The couple's UTC offset, which quiet hours and dates are read in
+    pub offset: FixedOffset
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReminderError {
+    Config(String)
+}
+
+impl fmt::Display for ReminderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReminderError::Config(message) => write!(f, "{}", message)
+        }
+    }
+}
+
+/// A household that's due a reminder.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReminderTarget {
+    pub household_id: String,
+    pub salutation: String,
+    /// Guests who haven't answered yet
+    pub pending: Vec<String>,
+    pub recipients: Vec<String>,
+    pub reminders_sent: u32,
+    pub last_reminded_at: Option<DateTime<Utc>>,
+    /// What the reminder template is rendered with
+    #[serde(skip)]
+    pub context: EmailContext
+}
+
+/// A household with guests who haven't answered that won't be reminded.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SkippedHousehold {
+    pub household_id: String,
+    pub salutation: String,
+    pub reason: String
+}
+
+/// Who a run of the reminder job contacts. `paused` says why nothing goes
+/// out right now, in which case `due` is who would be contacted otherwise.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReminderPlan {
+    pub paused: Option<String>,
+    pub due: Vec<ReminderTarget>,
+    pub skipped: Vec<SkippedHousehold>
+}
+
+fn env_number<T: std::str::FromStr>(name: &str, default: T) -> Result<T, ReminderError> {
+    match env::var(name) {
+        Ok(ref value) if !value.trim().is_empty() => value.trim().parse()
+            .map_err(|_| ReminderError::Config(format!("{} must be a whole number, not {:?}", name, value))),
+        _ => Ok(default)
+    }
+}
+
+/// Reads quiet hours like "21-8". Blank means there are none.
+pub fn parse_quiet_hours(value: &str) -> Result<Option<(u32, u32)>, ReminderError> {
+    if value.trim().is_empty() {
+        return Ok(None);
+    }
+
+    let invalid = || ReminderError::Config(format!("Quiet hours should look like 21-8, not {:?}", value));
+    let mut hours = value.trim().splitn(2, '-').map(|hour| hour.trim().parse::<u32>());
+    match (hours.next(), hours.next()) {
+        (Some(Ok(start)), Some(Ok(end))) if start < 24 && end < 24 => Ok(Some((start, end))),
+        _ => Err(invalid())
+    }
+}
+
+/// Reads a UTC offset like "-06:00". Blank means none was given.
+pub fn parse_utc_offset(value: &str) -> Result<Option<FixedOffset>, ReminderError> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    let invalid = || ReminderError::Config(format!("REMINDER_TZ should be a UTC offset like -06:00, not {:?}", value));
+    let sign = match value.chars().next() {
+        Some('+') => 1,
+        Some('-') => -1,
+        _ => return Err(invalid())
+    };
+    let mut parts = value[1..].splitn(2, ':').map(|part| part.parse::<i32>());
+    match (parts.next(), parts.next()) {
+        (Some(Ok(hours)), Some(Ok(minutes))) if hours < 24 && minutes < 60 => {
+            FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60)).map(Some).ok_or_else(invalid)
+        },
+        _ => Err(invalid())
+    }
+}
+
+impl Default for ReminderSchedule {
+    fn default() -> ReminderSchedule {
+        ReminderSchedule {
+            window_days: 14,
+            max_reminders: 2,
+            interval_days: 4,
+            quiet_hours: Some((21, 8)),
+            offset: FixedOffset::east(0)
+        }
+    }
+}
+
+impl ReminderSchedule {
+    /// Reads `REMINDER_WINDOW_DAYS`, `REMINDER_MAX`, `REMINDER_INTERVAL_DAYS`,
+    /// `REMINDER_QUIET_HOURS` and `REMINDER_TZ`. Without `REMINDER_TZ`,
+    /// quiet hours are read at the offset `RSVP_DEADLINE` is written with.
+    pub fn from_env() -> Result<ReminderSchedule, ReminderError> {
+        let defaults = ReminderSchedule::default();
+        Ok(ReminderSchedule {
+            window_days: env_number("REMINDER_WINDOW_DAYS", defaults.window_days)?,
+            max_reminders: env_number("REMINDER_MAX", defaults.max_reminders)?,
+            interval_days: env_number("REMINDER_INTERVAL_DAYS", defaults.interval_days)?,
+            quiet_hours: match env::var("REMINDER_QUIET_HOURS") {
+                Ok(value) => parse_quiet_hours(&value)?,
+                Err(_) => defaults.quiet_hours
+            },
+            offset: match env::var("REMINDER_TZ") {
+                Ok(value) => parse_utc_offset(&value)?.unwrap_or_else(deadline_offset),
+                Err(_) => deadline_offset()
+            }
+        })
+    }
+
+    /// Whether `now` falls in quiet hours, which may run past midnight.
+    pub fn is_quiet(&self, now: DateTime<Utc>) -> bool {
+        let hour = now.with_timezone(&self.offset).hour();
+        match self.quiet_hours {
+            None => false,
+            Some((start, end)) if start <= end => start <= hour && hour < end,
+            Some((start, end)) => hour >= start || hour < end
+        }
+    }
+
+    /// Why nothing should be sent at `now`, if anything.
+    fn paused(&self, deadline: Option<DateTime<Utc>>, now: DateTime<Utc>) -> Option<String> {
+        let deadline = match deadline {
+            Some(deadline) => deadline,
+            None => return Some(String::from("No RSVP deadline is set"))
+        };
+        let opens = deadline - Duration::days(self.window_days);
+
+        if deadline_has_passed(Some(deadline), now) {
+            Some(String::from("The RSVP deadline has passed"))
+        } else if now < opens {
+            Some(format!("Reminders start on {}", opens.with_timezone(&self.offset).format("%B %-d, %Y")))
+        } else if self.is_quiet(now) {
+            let (_, end) = self.quiet_hours.unwrap();
+            Some(format!("It's quiet hours until {}:00", end))
+        } else {
+            None
+        }
+    }
+
+    /// Why a household with guests who haven't answered shouldn't be
+    /// reminded at `now`, if anything. Reminders are counted per household,
+    /// since every guest in it is sent the same email.
+    fn skip_reason(&self, recipients: &[String], reminders_sent: u32, last_reminded_at: Option<DateTime<Utc>>, now: DateTime<Utc>) -> Option<String> {
+        if recipients.is_empty() {
+            Some(String::from("No one in the household has an email address"))
+        } else if reminders_sent >= self.max_reminders {
+            Some(format!("Already sent {} reminders", reminders_sent))
+        } else {
+            match last_reminded_at {
+                Some(last) if now < last + Duration::days(self.interval_days) => {
+                    Some(format!("Reminded on {}", last.with_timezone(&self.offset).format("%B %-d")))
+                },
+                _ => None
+            }
+        }
+    }
+
+    /// Works out which households to remind at `now`. Households where
+    /// everyone has answered are left out entirely.
+    pub fn plan(&self, households: &[(Household, Vec<RSVP>)], events: &[Event], deadline: Option<DateTime<Utc>>, now: DateTime<Utc>) -> ReminderPlan {
+        let mut due = vec!();
+        let mut skipped = vec!();
+
+        for (household, rsvps) in households {
+            let pending : Vec<String> = rsvps.iter()
+                .filter(|rsvp| !rsvp.has_responded())
+                .map(|rsvp| rsvp.name.clone())
+                .collect();
+            if pending.is_empty() {
+                continue;
+            }
+
+            let context = EmailContext::new(household, rsvps, events, deadline.map(|deadline| deadline.with_timezone(&self.offset)));
+            let recipients = context.recipients();
+            let reminders_sent = rsvps.iter().map(|rsvp| rsvp.reminders_sent).max().unwrap_or(0);
+            let last_reminded_at = rsvps.iter().filter_map(|rsvp| rsvp.last_reminded_at).max();
+            match self.skip_reason(&recipients, reminders_sent, last_reminded_at, now) {
+                Some(reason) => skipped.push(SkippedHousehold {
+                    household_id: household.id.clone(),
+                    salutation: context.salutation.clone(),
+                    reason
+                }),
+                None => due.push(ReminderTarget {
+                    household_id: household.id.clone(),
+                    salutation: context.salutation.clone(),
+                    pending,
+                    recipients,
+                    reminders_sent,
+                    last_reminded_at,
+                    context
+                })
+            }
+        }
+
+        ReminderPlan {
+            paused: self.paused(deadline, now),
+            due,
+            skipped
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod reminder_tests {
+
+    use super::*;
+    use chrono::TimeZone;
+    use crate::models::{Person, Invitation};
+
+    fn household(names: &[&str]) -> (Household, Vec<RSVP>) {
+        let people = names.iter()
+            .map(|name| Person {
+                name: name.to_string(),
+                email_address: format!("{}@example.com", name.to_lowercase()),
+                events: vec!("estes-park".to_string()),
+                ..Person::default()
+            })
+            .collect();
+        let rsvps = Household::new(people);
+        let household = Household {
+            id: rsvps[0].household_id.clone(),
+            ..Household::default()
+        };
+        (household, rsvps)
+    }
+
+    #[test]
+    fn test_quiet_hours() {
+        let schedule = ReminderSchedule {
+            offset: FixedOffset::west(6 * 3600),
+            ..ReminderSchedule::default()
+        };
+
+        assert!(schedule.is_quiet(Utc.ymd(2019, 7, 25).and_hms(4, 0, 0)));
+        assert!(schedule.is_quiet(Utc.ymd(2019, 7, 25).and_hms(13, 30, 0)));
+        assert!(!schedule.is_quiet(Utc.ymd(2019, 7, 25).and_hms(14, 0, 0)));
+        assert!(!ReminderSchedule { quiet_hours: Some((1, 5)), ..schedule.clone() }.is_quiet(Utc.ymd(2019, 7, 25).and_hms(4, 0, 0)));
+        assert!(!ReminderSchedule { quiet_hours: None, ..schedule }.is_quiet(Utc.ymd(2019, 7, 25).and_hms(4, 0, 0)));
+
+        assert_eq!(parse_quiet_hours("21-8"), Ok(Some((21, 8))));
+        assert_eq!(parse_quiet_hours(" "), Ok(None));
+        assert!(parse_quiet_hours("9pm-8am").is_err());
+        assert!(parse_quiet_hours("21-24").is_err());
+
+        assert_eq!(parse_utc_offset("-06:00"), Ok(Some(FixedOffset::west(6 * 3600))));
+        assert_eq!(parse_utc_offset("+05:30"), Ok(Some(FixedOffset::east(5 * 3600 + 30 * 60))));
+        assert_eq!(parse_utc_offset(""), Ok(None));
+        assert!(parse_utc_offset("America/Denver").is_err());
+        assert!(parse_utc_offset("-6").is_err());
+        assert!(parse_utc_offset("+24:00").is_err());
+    }
+
+    #[test]
+    fn test_reminder_plan() {
+        let schedule = ReminderSchedule { quiet_hours: None, ..ReminderSchedule::default() };
+        let deadline = Utc.ymd(2019, 8, 1).and_hms(23, 59, 59);
+        let now = Utc.ymd(2019, 7, 25).and_hms(17, 0, 0);
+
+        let pending = household(&["Jane", "John"]);
+        let (answered_household, mut answered) = household(&["Ann"]);
+        answered[0].invitations.insert("estes-park".to_string(), Invitation { attending: Some(false) });
+        let (no_email_household, mut no_email) = household(&["Bob"]);
+        no_email[0].email_address = " ".to_string();
+        let (maxed_household, mut maxed) = household(&["Cat"]);
+        maxed[0].reminders_sent = 2;
+        let (recent_household, mut recent) = household(&["Dan", "Eve"]);
+        recent[1].reminders_sent = 1;
+        recent[1].last_reminded_at = Some(now - Duration::days(2));
+        let (stale_household, mut stale) = household(&["Fay"]);
+        stale[0].reminders_sent = 1;
+        stale[0].last_reminded_at = Some(now - Duration::days(5));
+
+        let households = vec!(
+            pending.clone(),
+            (answered_household, answered),
+            (no_email_household, no_email),
+            (maxed_household, maxed),
+            (recent_household, recent),
+            (stale_household, stale)
+        );
+        let plan = schedule.plan(&households, &[], Some(deadline), now);
+
+        assert_eq!(plan.paused, None);
+        let due : Vec<&str> = plan.due.iter().map(|target| target.salutation.as_str()).collect();
+        assert_eq!(due, vec!("Jane & John", "Fay"));
+        assert_eq!(plan.due[0].household_id, pending.0.id);
+        assert_eq!(plan.due[0].recipients, vec!("jane@example.com", "john@example.com"));
+        assert_eq!(plan.due[1].reminders_sent, 1);
+
+        let reasons : Vec<&str> = plan.skipped.iter().map(|skipped| skipped.reason.as_str()).collect();
+        assert_eq!(reasons, vec!(
+            "No one in the household has an email address",
+            "Already sent 2 reminders",
+            "Reminded on July 23"
+        ));
+
+        let early = schedule.plan(&households, &[], Some(deadline), Utc.ymd(2019, 7, 1).and_hms(17, 0, 0));
+        assert_eq!(early.paused, Some("Reminders start on July 18, 2019".to_string()));
+        assert_eq!(early.due[0].household_id, pending.0.id);
+        assert!(schedule.plan(&households, &[], Some(deadline), deadline + Duration::seconds(1)).paused.is_some());
+        assert!(schedule.plan(&households, &[], None, now).paused.is_some());
+        assert!(ReminderSchedule::default().plan(&households, &[], Some(deadline), Utc.ymd(2019, 7, 25).and_hms(23, 0, 0)).paused.is_some());
+    }
+}
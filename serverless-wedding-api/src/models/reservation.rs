@@ -0,0 +1,484 @@
+use serde_derive::{Serialize, Deserialize};
+use std::vec::{Vec};
+use std::collections::{HashMap};
+use std::env;
+use std::fmt;
+use chrono::{DateTime, Duration, Utc};
+use log::{info, error};
+use serde_dynamodb;
+use rusoto_core::Region;
+use rusoto_dynamodb::{
+    DynamoDb,
+    AttributeValue,
+    DynamoDbClient,
+    GetItemInput,
+    GetItemError,
+    PutItemInput,
+    PutItemError,
+    QueryInput,
+    QueryError,
+    UpdateItemInput,
+    UpdateItemError,
+    DeleteItemInput,
+    DeleteItemError,
+    ScanError
+};
+
+use crate::models::{RegistryItem, ItemKind};
+use super::scan::scan_table;
+use super::attributes::string_value;
+
+/// How long a reservation holds an item before it has to be confirmed,
+/// from `RESERVATION_HOURS`.
+pub fn reservation_hours() -> i64 {
+    env::var("RESERVATION_HOURS")
+        .ok()
+        .and_then(|hours| hours.parse().ok())
+        .filter(|hours| *hours > 0)
+        .unwrap_or(48)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReservationStatus {
+    /// Set aside while the guest buys it, until `expires_at`
+    Held,
+    /// The guest has bought it
+    Confirmed
+}
+
+impl ReservationStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ReservationStatus::Held => "held",
+            ReservationStatus::Confirmed => "confirmed"
+        }
+    }
+}
+
+/// A household's claim on some of a physical registry item, so two guests
+/// don't buy the same thing. Keyed on the item, one per household.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Reservation {
+    pub item_id: String,
+    pub household_id: String,
+    pub quantity: u32,
+    pub status: ReservationStatus,
+    pub reserved_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirmed_at: Option<DateTime<Utc>>
+}
+
+/// How much of a physical item is still up for grabs.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Availability {
+    pub quantity: u32,
+    /// Reserved by guests who haven't confirmed yet
+    pub held: u32,
+    pub purchased: u32,
+    pub available: u32
+}
+
+#[derive(Debug)]
+pub enum ReservationError {
+    ItemNotFound,
+    NotFound,
+    Invalid(String),
+    AlreadyReserved(Reservation),
+    /// Only this many are left
+    Unavailable(u32),
+    Expired,
+    Get(GetItemError),
+    Put(PutItemError),
+    Update(UpdateItemError),
+    Delete(DeleteItemError),
+    Query(QueryError)
+}
+
+impl fmt::Display for ReservationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReservationError::ItemNotFound => write!(f, "No registry item was found with that id"),
+            ReservationError::NotFound => write!(f, "You haven't reserved that item"),
+            ReservationError::Invalid(message) => write!(f, "{}", message),
+            ReservationError::AlreadyReserved(_) => write!(f, "You've already reserved that item"),
+            ReservationError::Unavailable(0) => write!(f, "Someone else has already claimed that item"),
+            ReservationError::Unavailable(left) => write!(f, "Only {} of those are left", left),
+            ReservationError::Expired => write!(f, "Your reservation ran out. Please reserve it again."),
+            ReservationError::Get(error) => write!(f, "{}", error),
+            ReservationError::Put(error) => write!(f, "{}", error),
+            ReservationError::Update(error) => write!(f, "{}", error),
+            ReservationError::Delete(error) => write!(f, "{}", error),
+            ReservationError::Query(error) => write!(f, "{}", error)
+        }
+    }
+}
+
+fn reservation_key(item_id: &str, household_id: &str) -> HashMap<String, AttributeValue> {
+    let mut key = HashMap::new();
+    key.insert(String::from("item_id"), string_value(item_id));
+    key.insert(String::from("household_id"), string_value(household_id));
+    key
+}
+
+impl Reservation {
+    pub fn new(item: &RegistryItem, household_id: String, quantity: u32, now: DateTime<Utc>) -> Result<Reservation, String> {
+        let most = match (item.kind, item.quantity) {
+            (ItemKind::Physical, Some(most)) => most,
+            _ => return Err(format!("{} can't be reserved", item.title))
+        };
+        if quantity == 0 || quantity > most {
+            return Err(format!("Reserve between 1 and {}", most));
+        }
+
+        Ok(Reservation {
+            item_id: item.id.clone(),
+            household_id,
+            quantity,
+            status: ReservationStatus::Held,
+            reserved_at: now,
+            expires_at: now + Duration::hours(reservation_hours()),
+            confirmed_at: None
+        })
+    }
+
+    /// A held reservation past its time. It still counts against the item
+    /// until it's released.
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.status == ReservationStatus::Held && now >= self.expires_at
+    }
+
+    /// The stored form of `expires_at`, so updates can check it's the same
+    /// reservation that was read.
+    fn expires_at_value(&self) -> AttributeValue {
+        serde_dynamodb::to_hashmap(self).unwrap().remove("expires_at").unwrap()
+    }
+
+    pub fn get(item_id: &str, household_id: &str) -> Result<Reservation, ReservationError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let get_item_input = GetItemInput {
+            table_name: env::var("RESERVATION_TABLE_NAME").unwrap(),
+            key: reservation_key(item_id, household_id),
+            ..GetItemInput::default()
+        };
+
+        match client.get_item(get_item_input).sync().map_err(ReservationError::Get)?.item {
+            Some(item) => Ok(serde_dynamodb::from_hashmap(item).unwrap()),
+            None => Err(ReservationError::NotFound)
+        }
+    }
+
+    pub fn for_item(item_id: &str) -> Result<Vec<Reservation>, QueryError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let mut values = HashMap::new();
+        values.insert(String::from(":item_id"), string_value(item_id));
+
+        let query_input = QueryInput {
+            table_name: env::var("RESERVATION_TABLE_NAME").unwrap(),
+            key_condition_expression: Some(String::from("item_id = :item_id")),
+            expression_attribute_values: Some(values),
+            ..QueryInput::default()
+        };
+
+        Ok(client.query(query_input).sync()?
+            .items
+            .unwrap_or_default()
+            .into_iter()
+            .map(|item| serde_dynamodb::from_hashmap(item).unwrap())
+            .collect())
+    }
+
+    /// Every item a household has reserved.
+    pub fn for_household(household_id: &str) -> Result<Vec<Reservation>, ScanError> {
+        let mut reservations = Reservation::all()?;
+        reservations.retain(|reservation| reservation.household_id == household_id);
+        Ok(reservations)
+    }
+
+    pub fn all() -> Result<Vec<Reservation>, ScanError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+        scan_table(&client, env::var("RESERVATION_TABLE_NAME").unwrap())
+    }
+
+    /// Sets `quantity` of a physical item aside for a household until the
+    /// reservation expires. The item's count is claimed with a conditional
+    /// write first, so two guests can't both take the last one. When it's
+    /// all claimed, expired reservations are released and it's tried once
+    /// more.
+    pub fn reserve(item: &RegistryItem, household_id: &str, quantity: u32, now: DateTime<Utc>) -> Result<Reservation, ReservationError> {
+        let reservation = Reservation::new(item, household_id.to_string(), quantity, now)
+            .map_err(ReservationError::Invalid)?;
+
+        match Reservation::get(&item.id, household_id) {
+            Ok(ref existing) if existing.is_expired(now) => {
+                existing.release()?;
+            },
+            Ok(existing) => return Err(ReservationError::AlreadyReserved(existing)),
+            Err(ReservationError::NotFound) => {},
+            Err(error) => return Err(error)
+        }
+
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        match RegistryItem::claimed(&item.id).reserve(&client, item.quantity.unwrap_or(0), quantity) {
+            Ok(()) => {},
+            Err(UpdateItemError::ConditionalCheckFailed(_)) => {
+                if Reservation::release_expired(&item.id, now)? == 0 {
+                    return Err(Reservation::unavailable(item, now));
+                }
+                match RegistryItem::claimed(&item.id).reserve(&client, item.quantity.unwrap_or(0), quantity) {
+                    Ok(()) => {},
+                    Err(UpdateItemError::ConditionalCheckFailed(_)) => return Err(Reservation::unavailable(item, now)),
+                    Err(error) => return Err(ReservationError::Update(error))
+                }
+            },
+            Err(error) => return Err(ReservationError::Update(error))
+        }
+
+        let put_item_input = PutItemInput {
+            table_name: env::var("RESERVATION_TABLE_NAME").unwrap(),
+            item: serde_dynamodb::to_hashmap(&reservation).unwrap(),
+            condition_expression: Some(String::from("attribute_not_exists(household_id)")),
+            ..PutItemInput::default()
+        };
+
+        if let Err(put_error) = client.put_item(put_item_input).sync() {
+            if let Err(release_error) = RegistryItem::claimed(&item.id).release(&client, quantity) {
+                error!("Could not give back {} of registry item {}: {:?}", quantity, item.id, release_error);
+            }
+            return Err(match put_error {
+                // Reserved twice at once, e.g. a double click
+                PutItemError::ConditionalCheckFailed(_) => Reservation::get(&item.id, household_id)
+                    .map(ReservationError::AlreadyReserved)
+                    .unwrap_or_else(|error| error),
+                error => ReservationError::Put(error)
+            });
+        }
+
+        info!("Household {} reserved {} of {} until {}", household_id, quantity, item.title, reservation.expires_at);
+        Ok(reservation)
+    }
+
+    /// How many are left, for telling a guest there aren't enough.
+    fn unavailable(item: &RegistryItem, now: DateTime<Utc>) -> ReservationError {
+        let reservations = Reservation::for_item(&item.id).unwrap_or_default();
+        let left = Availability::new(item, &reservations, now).map_or(0, |availability| availability.available);
+        ReservationError::Unavailable(left)
+    }
+
+    /// Marks a held reservation bought, so it no longer expires. Confirming
+    /// twice is fine.
+    pub fn confirm(item_id: &str, household_id: &str, now: DateTime<Utc>) -> Result<Reservation, ReservationError> {
+        let reservation = Reservation::get(item_id, household_id)?;
+        if reservation.status == ReservationStatus::Confirmed {
+            return Ok(reservation);
+        }
+        if reservation.is_expired(now) {
+            return Err(ReservationError::Expired);
+        }
+
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let mut names = HashMap::new();
+        names.insert(String::from("#status"), String::from("status"));
+
+        let mut values = HashMap::new();
+        values.insert(String::from(":held"), string_value(ReservationStatus::Held.as_str()));
+        values.insert(String::from(":confirmed"), string_value(ReservationStatus::Confirmed.as_str()));
+        values.insert(String::from(":confirmed_at"), string_value(&now.to_rfc3339()));
+        values.insert(String::from(":expires_at"), reservation.expires_at_value());
+
+        let update = client.update_item(UpdateItemInput {
+            table_name: env::var("RESERVATION_TABLE_NAME").unwrap(),
+            key: reservation_key(item_id, household_id),
+            update_expression: Some(String::from("SET #status = :confirmed, confirmed_at = :confirmed_at")),
+            condition_expression: Some(String::from("#status = :held AND expires_at = :expires_at")),
+            expression_attribute_names: Some(names),
+            expression_attribute_values: Some(values),
+            ..UpdateItemInput::default()
+        }).sync();
+
+        match update {
+            Ok(_) => {},
+            // Released as expired in the meantime, or confirmed by another tab
+            Err(UpdateItemError::ConditionalCheckFailed(_)) => {
+                return match Reservation::get(item_id, household_id) {
+                    Ok(ref current) if current.status == ReservationStatus::Confirmed => Ok(current.clone()),
+                    Ok(_) | Err(ReservationError::NotFound) => Err(ReservationError::Expired),
+                    Err(error) => Err(error)
+                }
+            },
+            Err(error) => return Err(ReservationError::Update(error))
+        }
+
+        info!("Household {} bought {} of registry item {}", household_id, reservation.quantity, item_id);
+        Ok(Reservation {
+            status: ReservationStatus::Confirmed,
+            confirmed_at: Some(now),
+            ..reservation
+        })
+    }
+
+    /// Gives a household's reservation back, held or confirmed, if they
+    /// had one.
+    pub fn cancel(item_id: &str, household_id: &str) -> Result<Option<Reservation>, ReservationError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let delete_item_input = DeleteItemInput {
+            table_name: env::var("RESERVATION_TABLE_NAME").unwrap(),
+            key: reservation_key(item_id, household_id),
+            return_values: Some(String::from("ALL_OLD")),
+            ..DeleteItemInput::default()
+        };
+
+        let removed : Option<Reservation> = client.delete_item(delete_item_input).sync()
+            .map_err(ReservationError::Delete)?
+            .attributes
+            .map(|item| serde_dynamodb::from_hashmap(item).unwrap());
+
+        if let Some(removed) = &removed {
+            RegistryItem::claimed(item_id).release(&client, removed.quantity)
+                .map_err(ReservationError::Update)?;
+            info!("Household {} gave up {} of registry item {}", household_id, removed.quantity, item_id);
+        }
+
+        Ok(removed)
+    }
+
+    /// Deletes an expired reservation and gives its quantity back. The
+    /// delete only goes through while it's still held with the same expiry,
+    /// so one confirmed at the last moment is kept. Returns whether it was
+    /// released.
+    fn release(&self) -> Result<bool, ReservationError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let mut names = HashMap::new();
+        names.insert(String::from("#status"), String::from("status"));
+
+        let mut values = HashMap::new();
+        values.insert(String::from(":held"), string_value(ReservationStatus::Held.as_str()));
+        values.insert(String::from(":expires_at"), self.expires_at_value());
+
+        let delete = client.delete_item(DeleteItemInput {
+            table_name: env::var("RESERVATION_TABLE_NAME").unwrap(),
+            key: reservation_key(&self.item_id, &self.household_id),
+            condition_expression: Some(String::from("#status = :held AND expires_at = :expires_at")),
+            expression_attribute_names: Some(names),
+            expression_attribute_values: Some(values),
+            ..DeleteItemInput::default()
+        }).sync();
+
+        match delete {
+            Ok(_) => {},
+            Err(DeleteItemError::ConditionalCheckFailed(_)) => return Ok(false),
+            Err(error) => return Err(ReservationError::Delete(error))
+        }
+
+        RegistryItem::claimed(&self.item_id).release(&client, self.quantity)
+            .map_err(ReservationError::Update)?;
+        info!("Released household {}'s expired reservation of registry item {}", self.household_id, self.item_id);
+        Ok(true)
+    }
+
+    /// Releases every expired reservation of an item, returning how many
+    /// were.
+    pub fn release_expired(item_id: &str, now: DateTime<Utc>) -> Result<u32, ReservationError> {
+        let mut released = 0;
+        for reservation in Reservation::for_item(item_id).map_err(ReservationError::Query)? {
+            if reservation.is_expired(now) && reservation.release()? {
+                released += 1;
+            }
+        }
+        Ok(released)
+    }
+}
+
+impl Availability {
+    /// Counts the item's reservations that haven't expired. Only physical
+    /// items have any.
+    pub fn new(item: &RegistryItem, reservations: &[Reservation], now: DateTime<Utc>) -> Option<Availability> {
+        let quantity = match (item.kind, item.quantity) {
+            (ItemKind::Physical, Some(quantity)) => quantity,
+            _ => return None
+        };
+
+        let mut held = 0;
+        let mut purchased = 0;
+        for reservation in reservations.iter().filter(|reservation| reservation.item_id == item.id) {
+            match reservation.status {
+                ReservationStatus::Confirmed => purchased += reservation.quantity,
+                ReservationStatus::Held if !reservation.is_expired(now) => held += reservation.quantity,
+                ReservationStatus::Held => {}
+            }
+        }
+
+        Some(Availability {
+            quantity,
+            held,
+            purchased,
+            available: quantity.saturating_sub(held + purchased)
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod reservation_tests {
+
+    use super::*;
+    use crate::models::Money;
+
+    fn towels(quantity: u32) -> RegistryItem {
+        RegistryItem::new_physical(
+            "Bath Towels".to_string(),
+            Some(Money::parse("24.99", "USD").unwrap()),
+            "USD",
+            quantity
+        ).unwrap()
+    }
+
+    #[test]
+    fn test_reservation_new() {
+        let now = Utc::now();
+        let item = towels(4);
+        let reservation = Reservation::new(&item, "household".to_string(), 2, now).unwrap();
+        assert_eq!(reservation.status, ReservationStatus::Held);
+        assert_eq!(reservation.expires_at, now + Duration::hours(48));
+        assert!(!reservation.is_expired(now));
+        assert!(reservation.is_expired(now + Duration::hours(48)));
+
+        assert!(Reservation::new(&item, "household".to_string(), 0, now).is_err());
+        assert!(Reservation::new(&item, "household".to_string(), 5, now).is_err());
+
+        let fund = RegistryItem::new("Airfare".to_string(), None, "USD").unwrap();
+        assert!(Reservation::new(&fund, "household".to_string(), 1, now).is_err());
+    }
+
+    #[test]
+    fn test_availability() {
+        let now = Utc::now();
+        let item = towels(6);
+        let reserve = |household_id: &str, quantity, at| Reservation::new(&item, household_id.to_string(), quantity, at).unwrap();
+
+        let confirmed = Reservation {
+            status: ReservationStatus::Confirmed,
+            confirmed_at: Some(now),
+            ..reserve("bought", 2, now - Duration::days(5))
+        };
+        let reservations = vec!(
+            confirmed,
+            reserve("holding", 1, now),
+            reserve("expired", 3, now - Duration::days(3)),
+            Reservation { item_id: "another item".to_string(), ..reserve("elsewhere", 1, now) }
+        );
+
+        let availability = Availability::new(&item, &reservations, now).unwrap();
+        assert_eq!(availability, Availability { quantity: 6, held: 1, purchased: 2, available: 3 });
+
+        let fund = RegistryItem::new("Airfare".to_string(), None, "USD").unwrap();
+        assert_eq!(Availability::new(&fund, &reservations, now), None);
+    }
+}
@@ -2,7 +2,9 @@ use serde_derive::{Serialize, Deserialize};
 use std::vec::{Vec};
 use std::collections::{HashMap};
 use std::env;
+use std::fmt;
 use uuid::Uuid;
+use chrono::{DateTime, FixedOffset, Utc};
 use log::{debug, info, error};
 use serde_dynamodb;
 use serde_json::{Value};
@@ -14,10 +16,31 @@ use rusoto_dynamodb::{
     QueryError,
     DynamoDbClient,
     UpdateItemInput,
-    UpdateItemError
+    UpdateItemError,
+    ScanError
 };
 
-use crate::models::{Person};
+use crate::models::{Person, AgeCategory, Allergy, Menu, Travel};
+use super::allergy::parse_allergies;
+use super::menu::NO_MEAL;
+use super::scan::scan_table;
+use super::deadline::{local_deadline, deadline_has_passed};
+
+/// Fields the couple sets while managing invitations and seating. Everything
+/// else a PATCH can change is the guest's response and closes at the deadline.
+pub(crate) const ADMIN_KEYS : [&str; 3] = ["invitation_submitted", "reminder_submitted", "tags"];
+
+/// Details guests may keep updating after the deadline, since flights are
+/// often booked later.
+const OPEN_KEYS : [&str; 1] = ["travel"];
+
+/// A guest's response to one event. `attending` stays `None` until the
+/// guest answers.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Invitation {
+    #[serde(default)]
+    pub attending: Option<bool>
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RSVP {
@@ -30,7 +53,115 @@ pub struct RSVP {
     pub reminder_submitted: bool,
     pub dietary_restrictions: String,
     pub dietary_restrictions_other: String,
-    pub food_preference: String
+    pub food_preference: String,
+    /// Invitations keyed by event id
+    #[serde(default)]
+    pub invitations: HashMap<String, Invitation>,
+    /// Whether a guest added this companion using one of their household's plus-ones
+    #[serde(default)]
+    pub plus_one: bool,
+    #[serde(default)]
+    pub age_category: AgeCategory,
+    #[serde(default)]
+    pub allergies: Vec<Allergy>,
+    /// Labels like "family" or "college" the seating solver groups by
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub travel: Travel,
+    /// How many reminders the guest has been sent
+    #[serde(default)]
+    pub reminders_sent: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_reminded_at: Option<DateTime<Utc>>
+}
+
+/// An updated RSVP along with anything the guest should double check.
+#[derive(Debug, Clone, Serialize)]
+pub struct PatchedRSVP {
+    #[serde(flatten)]
+    pub rsvp: RSVP,
+    pub warnings: Vec<String>
+}
+
+#[derive(Debug)]
+pub enum PatchError {
+    NotFound,
+    Invalid(String),
+    /// Shown at the offset the deadline was set with
+    DeadlinePassed(DateTime<FixedOffset>),
+    Config(String),
+    Menu(ScanError),
+    Update(UpdateItemError)
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PatchError::NotFound => write!(f, "No RSVP was found with that id"),
+            PatchError::Invalid(message) => write!(f, "{}", message),
+            PatchError::DeadlinePassed(deadline) => write!(
+                f,
+                "RSVPs closed on {}. Please submit a change request instead.",
+                deadline.format("%B %-d, %Y")
+            ),
+            PatchError::Config(message) => write!(f, "{}", message),
+            PatchError::Menu(error) => write!(f, "Could not load the menu: {}", error),
+            PatchError::Update(error) => write!(f, "{}", error)
+        }
+    }
+}
+
+/// Converts a JSON value from a PATCH payload into a DynamoDB attribute.
+/// DynamoDB rejects empty strings, so they are stored as a single space.
+fn to_attribute_value(value: &Value) -> AttributeValue {
+    match value {
+        Value::String(string) => {
+            if string != "" {
+                AttributeValue {
+                    s: Some(string.to_string()),
+                    ..Default::default()
+                }
+            } else {
+                AttributeValue {
+                    s: Some(" ".to_string()),
+                    ..Default::default()
+                }
+            }
+        },
+        Value::Bool(boolean) => {
+            AttributeValue {
+                bool: Some(*boolean),
+                ..Default::default()
+            }
+        },
+        Value::Number(number) => {
+            AttributeValue {
+                n: Some(number.to_string()),
+                ..Default::default()
+            }
+        },
+        Value::Array(values) => {
+            AttributeValue {
+                l: Some(values.iter().map(to_attribute_value).collect()),
+                ..Default::default()
+            }
+        },
+        Value::Object(map) => {
+            AttributeValue {
+                m: Some(map.iter()
+                    .map(|(key, value)| (key.clone(), to_attribute_value(value)))
+                    .collect()),
+                ..Default::default()
+            }
+        },
+        Value::Null => {
+            AttributeValue {
+                null: Some(true),
+                ..Default::default()
+            }
+        }
+    }
 }
 
 impl RSVP {
@@ -45,13 +176,121 @@ impl RSVP {
             reminder_submitted: false.into(),
             dietary_restrictions: String::from("None"),
             dietary_restrictions_other: String::from(""),
-            food_preference: String::from("4course")
+            food_preference: String::from(NO_MEAL),
+            invitations: person.events.into_iter()
+                .map(|event_id| (event_id, Invitation::default()))
+                .collect(),
+            plus_one: false,
+            age_category: person.age_category,
+            allergies: vec!(),
+            tags: vec!(),
+            travel: Travel::default(),
+            reminders_sent: 0,
+            last_reminded_at: None
+        }
+    }
+
+    /// Whether the guest has answered for at least one event
+    pub fn has_responded(&self) -> bool {
+        self.attending || self.invitations.values().any(|invitation| invitation.attending.is_some())
+    }
+
+    /// Whether the guest is coming to anything at all
+    pub fn is_attending_any(&self) -> bool {
+        self.attending || self.invitations.values().any(|invitation| invitation.attending == Some(true))
+    }
+
+    /// Primary key of the record in the RSVP table
+    fn key(&self) -> HashMap<String, AttributeValue> {
+        let mut key = HashMap::new();
+        key.insert(String::from("household_id"), AttributeValue {
+            s: Some(self.household_id.clone()),
+            ..Default::default()
+        });
+        key.insert(String::from("name"), AttributeValue {
+            s: Some(self.name.clone()),
+            ..Default::default()
+        });
+        key
+    }
+
+    /// Applies a guest's edits, rejecting changes to their response once
+    /// the RSVP deadline has passed. Choices that clash on the menu are
+    /// saved anyway and come back as warnings.
+    pub fn patch(uuid: Uuid, payload: Value) -> Result<PatchedRSVP, PatchError> {
+        if RSVP::edits_response(&payload) {
+            if let Some(deadline) = local_deadline().map_err(PatchError::Config)? {
+                if deadline_has_passed(Some(deadline.with_timezone(&Utc)), Utc::now()) {
+                    return Err(PatchError::DeadlinePassed(deadline));
+                }
+            }
+        }
+
+        let menu = Menu::get().map_err(PatchError::Menu)?;
+        let rsvp = RSVP::apply(uuid, payload, &menu)?;
+        let warnings = menu.conflict(&rsvp.food_preference, &rsvp.dietary_restrictions)
+            .into_iter()
+            .collect();
+
+        Ok(PatchedRSVP { rsvp, warnings })
+    }
+
+    /// Whether a PATCH payload changes anything besides the admin flags and
+    /// the details that stay open
+    fn edits_response(payload: &Value) -> bool {
+        match payload.as_object() {
+            Some(map) => map.iter().any(|(key, value)| {
+                *value != Value::Null && !ADMIN_KEYS.contains(&key.as_str()) && !OPEN_KEYS.contains(&key.as_str())
+            }),
+            None => false
+        }
+    }
+
+    /// Checks a guest's edits without saving them. Meal and diet choices
+    /// must be on `menu`, with the meal checked against the guest's age and
+    /// events using the current value of whichever the payload leaves
+    /// alone, and responses may only answer events the guest was invited to.
+    pub fn check(&self, payload: &Value, menu: &Menu) -> Result<(), PatchError> {
+        if payload["food_preference"] != Value::Null || payload["age_category"] != Value::Null {
+            let age_category : AgeCategory = match payload.get("age_category") {
+                Some(Value::Null) | None => self.age_category,
+                Some(age_category) => serde_json::from_value(age_category.clone())
+                    .map_err(|_| PatchError::Invalid(format!("Unknown age category: {}", age_category)))?
+            };
+            let food_preference = payload["food_preference"].as_str()
+                .unwrap_or(&self.food_preference);
+            let events : Vec<String> = self.invitations.keys().cloned().collect();
+
+            menu.check_meal(food_preference, age_category, &events).map_err(PatchError::Invalid)?;
+        }
+        if let Some(dietary_restrictions) = payload["dietary_restrictions"].as_str() {
+            menu.check_diet(dietary_restrictions).map_err(PatchError::Invalid)?;
         }
+
+        // Per-event responses arrive as {"invitations": {"<event id>": {"attending": true}}}
+        if let Some(invitations) = payload.get("invitations") {
+            let invitations = invitations.as_object()
+                .ok_or_else(|| PatchError::Invalid(String::from("invitations must be an object keyed by event id")))?;
+
+            for (event_id, response) in invitations {
+                if !self.invitations.contains_key(event_id) {
+                    return Err(PatchError::Invalid(format!("{} is not invited to event {}", self.name, event_id)));
+                }
+                if !response["attending"].is_boolean() {
+                    return Err(PatchError::Invalid(format!("The response to event {} must set attending to true or false", event_id)));
+                }
+            }
+        }
+
+        Ok(())
     }
 
-    pub fn patch(uuid: Uuid, payload: Value) -> Result<RSVP, UpdateItemError> {
+    /// Applies a PATCH payload regardless of the deadline, checking meal
+    /// and diet choices against `menu`. Used directly when the couple
+    /// approves a change request.
+    pub fn apply(uuid: Uuid, payload: Value, menu: &Menu) -> Result<RSVP, PatchError> {
         let client = DynamoDbClient::new(Region::UsEast1);
-        let rsvp = RSVP::get(uuid).unwrap();
+        let rsvp = RSVP::get(uuid).map_err(|_| PatchError::NotFound)?;
 
         debug!("Preparing to update RSVP: {:?}", rsvp);
         
@@ -62,79 +301,83 @@ impl RSVP {
             String::from("reminder_submitted"),
             String::from("dietary_restrictions"),
             String::from("dietary_restrictions_other"),
-            String::from("food_preference")
+            String::from("food_preference"),
+            String::from("age_category")
         ];
 
-        // Create a vector of (String, Value) tuples
-        let mut payload_tuple_vector = Vec::new();
+        rsvp.check(&payload, menu)?;
+
+        // Create update expression assignments and expression attribute
+        // values for each allowable key present in the payload
+        let mut assignments : Vec<String> = vec!();
+        let mut expression_attribute_names = HashMap::new();
+        let mut expression_attribute_values = HashMap::new();
         for key in &patchable_keys {
             if payload[key] != Value::Null {
-                payload_tuple_vector.push((key, &payload[key]))
+                assignments.push(format!("{k} = :{k}", k = key));
+                expression_attribute_values.insert(format!(":{}", key), to_attribute_value(&payload[key]));
             }
         }
 
-        // Get primary key for update operation
-        let mut key = HashMap::new();
-        key.insert(String::from("household_id"), AttributeValue {
-            s: Some(String::from(rsvp.clone().household_id)),
-            ..Default::default()
-        });
-        key.insert(String::from("name"), AttributeValue {
-            s: Some(String::from(rsvp.clone().name)),
-            ..Default::default()
-        });
+        // Allergies replace the whole list, normalized so major allergens
+        // always use the same codes
+        if payload["allergies"] != Value::Null {
+            let allergies = parse_allergies(&payload["allergies"]).map_err(PatchError::Invalid)?;
+            assignments.push(String::from("allergies = :allergies"));
+            expression_attribute_values.insert(String::from(":allergies"), to_attribute_value(&serde_json::to_value(&allergies).unwrap()));
+        }
 
-        // Create update expression and expresion attribute values 
-        // by iterating over payload_tuple_vector
-        let mut expression_attribute_values = HashMap::new();
-        let mut update_expression = String::from("SET ");
-        for (i, item) in payload_tuple_vector.iter().enumerate() {
-            let key = item.0;
-            let value = item.1;
-
-            // Append to Update Expression
-            let mut to_append = format!("{k} = :{k}", k = key);
-            if i + 1 != payload_tuple_vector.len() {
-                to_append.push_str(",");
-            }
+        // Seating tags are stored trimmed and lowercased so "Family" and
+        // "family " group together
+        if payload["tags"] != Value::Null {
+            let tags : Vec<String> = serde_json::from_value(payload["tags"].clone())
+                .map_err(|_| PatchError::Invalid(String::from("Tags must be a list of strings")))?;
+            let mut tags : Vec<String> = tags.iter()
+                .map(|tag| tag.trim().to_lowercase())
+                .filter(|tag| !tag.is_empty())
+                .collect();
+            tags.sort();
+            tags.dedup();
 
-            update_expression.push_str(&to_append);
-            
-            // Append to Attribute Values
-            let attribute_value = match value {
-                Value::String(string) => {
-                    if string != "" {
-                        AttributeValue {
-                            s: Some(string.to_string()),
-                            ..Default::default()
-                        }
-                    } else {
-                        AttributeValue {
-                            s: Some(" ".to_string()),
-                            ..Default::default()
-                        }
-                    }
-                },
-                Value::Bool(boolean) => {
-                    AttributeValue {
-                        bool: Some(*boolean),
-                        ..Default::default()
-                    }
-                },
-                _ => {
-                    AttributeValue {
-                        ..Default::default()
-                    }
-                }
-            };
+            assignments.push(String::from("tags = :tags"));
+            expression_attribute_values.insert(String::from(":tags"), to_attribute_value(&serde_json::to_value(&tags).unwrap()));
+        }
+
+        // Travel replaces the whole object, so guests can clear a field by
+        // leaving it out
+        if payload["travel"] != Value::Null {
+            let travel = Travel::parse(&payload["travel"]).map_err(PatchError::Invalid)?;
+            assignments.push(String::from("travel = :travel"));
+            expression_attribute_values.insert(String::from(":travel"), to_attribute_value(&serde_json::to_value(&travel).unwrap()));
+        }
+
+        // Per-event responses, already checked against the guest's invitations
+        if let Some(invitations) = payload["invitations"].as_object() {
+            for (i, (event_id, response)) in invitations.iter().enumerate() {
+                let attending = response["attending"] == Value::Bool(true);
 
-            expression_attribute_values.insert(String::from(format!(":{}", key.to_string())), attribute_value);
+                assignments.push(format!("invitations.#event{i}.attending = :event{i}_attending", i = i));
+                expression_attribute_names.insert(format!("#event{}", i), event_id.clone());
+                expression_attribute_values.insert(format!(":event{}_attending", i), AttributeValue {
+                    bool: Some(attending),
+                    ..Default::default()
+                });
+            }
+        }
+
+        if assignments.is_empty() {
+            return Ok(rsvp);
         }
 
         // Gather the above into an instance of UpdateItemInput
         let update_item_input = UpdateItemInput {
-            key,
-            update_expression: Some(String::from(update_expression)),
+            key: rsvp.key(),
+            update_expression: Some(format!("SET {}", assignments.join(", "))),
+            expression_attribute_names: if expression_attribute_names.is_empty() {
+                None
+            } else {
+                Some(expression_attribute_names)
+            },
             expression_attribute_values: Some(expression_attribute_values),
             table_name: env::var("RSVP_TABLE_NAME").unwrap(),
             ..Default::default()
@@ -151,11 +394,78 @@ impl RSVP {
             },
             Err(error) => {
                 error!("Error! {:?}", error);
-                Err(error)
+                Err(PatchError::Update(error))
             }
         }
     }
 
+    /// Invites the guest to an event, leaving any existing response alone.
+    pub fn invite(&self, event_id: &str) -> Result<(), UpdateItemError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        // Records written before events existed have no invitations map,
+        // and DynamoDB can't SET a path inside a missing map.
+        let mut empty_map = HashMap::new();
+        empty_map.insert(String::from(":empty"), AttributeValue {
+            m: Some(HashMap::new()),
+            ..Default::default()
+        });
+
+        client.update_item(UpdateItemInput {
+            key: self.key(),
+            update_expression: Some(String::from("SET invitations = if_not_exists(invitations, :empty)")),
+            expression_attribute_values: Some(empty_map),
+            table_name: env::var("RSVP_TABLE_NAME").unwrap(),
+            ..Default::default()
+        }).sync()?;
+
+        let mut names = HashMap::new();
+        names.insert(String::from("#event"), event_id.to_string());
+
+        let mut values = HashMap::new();
+        values.insert(String::from(":invitation"), AttributeValue {
+            m: Some(serde_dynamodb::to_hashmap(&Invitation::default()).unwrap()),
+            ..Default::default()
+        });
+
+        client.update_item(UpdateItemInput {
+            key: self.key(),
+            update_expression: Some(String::from("SET invitations.#event = if_not_exists(invitations.#event, :invitation)")),
+            expression_attribute_names: Some(names),
+            expression_attribute_values: Some(values),
+            table_name: env::var("RSVP_TABLE_NAME").unwrap(),
+            ..Default::default()
+        }).sync()?;
+
+        info!("Invited {} to event {}", self.id, event_id);
+        Ok(())
+    }
+
+    /// Records that the guest was sent a reminder: sets `reminder_submitted`
+    /// and `last_reminded_at`, and counts it.
+    pub fn record_reminder(&self, now: DateTime<Utc>) -> Result<(), UpdateItemError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let mut values = HashMap::new();
+        values.insert(String::from(":sent"), to_attribute_value(&Value::Bool(true)));
+        values.insert(String::from(":now"), to_attribute_value(&serde_json::to_value(now).unwrap()));
+        values.insert(String::from(":zero"), to_attribute_value(&Value::from(0)));
+        values.insert(String::from(":one"), to_attribute_value(&Value::from(1)));
+
+        client.update_item(UpdateItemInput {
+            key: self.key(),
+            update_expression: Some(String::from(
+                "SET reminder_submitted = :sent, last_reminded_at = :now, reminders_sent = if_not_exists(reminders_sent, :zero) + :one"
+            )),
+            expression_attribute_values: Some(values),
+            table_name: env::var("RSVP_TABLE_NAME").unwrap(),
+            ..Default::default()
+        }).sync()?;
+
+        info!("Recorded a reminder to {}", self.id);
+        Ok(())
+    }
+
     pub fn get(uuid: Uuid) -> Result<RSVP, QueryError> {
         let client = DynamoDbClient::new(Region::UsEast1);
         
@@ -205,6 +515,11 @@ impl RSVP {
             Ok(rsvps[0].clone())
         }
     }
+
+    pub fn all() -> Result<Vec<RSVP>, ScanError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+        scan_table(&client, env::var("RSVP_TABLE_NAME").unwrap())
+    }
 }
 
 
@@ -212,15 +527,27 @@ impl RSVP {
 mod rsvp_tests {
 
     use serde_json::{json};
+    use chrono::TimeZone;
     use super::*;
 
+    #[test]
+    fn test_deadline_passed_message() {
+        let deadline = FixedOffset::west(6 * 3600).ymd(2019, 8, 1).and_hms(23, 59, 59);
+
+        assert_eq!(
+            PatchError::DeadlinePassed(deadline).to_string(),
+            "RSVPs closed on August 1, 2019. Please submit a change request instead."
+        );
+    }
+
     #[test]
     fn test_rsvp_new() {
         let household_id = Uuid::new_v4().to_string();
         let result = RSVP::new(
             Person {
                 name: "Blaine Price".to_string(),
-                email_address: "email@example.com".to_string()
+                email_address: "email@example.com".to_string(),
+                ..Person::default()
             },
             household_id.clone()
         );
@@ -231,6 +558,45 @@ mod rsvp_tests {
         assert_eq!(result.attending, false);
         assert_eq!(result.invitation_submitted, false);
         assert_eq!(result.reminder_submitted, false);
+        assert!(result.allergies.is_empty());
+        assert!(result.tags.is_empty());
+    }
+
+    #[test]
+    fn test_rsvp_new_invitations() {
+        let result = RSVP::new(
+            Person {
+                name: "Blaine Price".to_string(),
+                email_address: "email@example.com".to_string(),
+                events: vec!("wedding".to_string(), "bash".to_string()),
+                age_category: AgeCategory::Child
+            },
+            Uuid::new_v4().to_string()
+        );
+
+        assert_eq!(result.invitations.len(), 2);
+        assert_eq!(result.invitations["bash"], Invitation { attending: None });
+        assert_eq!(result.age_category, AgeCategory::Child);
+    }
+
+    #[test]
+    fn test_rsvp_edits_response() {
+        assert!(RSVP::edits_response(&json!({"food_preference": "pizza"})));
+        assert!(RSVP::edits_response(&json!({"reminder_submitted": true, "attending": false})));
+        assert!(!RSVP::edits_response(&json!({"invitation_submitted": true, "reminder_submitted": true})));
+        assert!(!RSVP::edits_response(&json!({"attending": null})));
+        assert!(!RSVP::edits_response(&json!({"tags": ["family"]})));
+        assert!(!RSVP::edits_response(&json!({"travel": {"airport": "DEN"}})));
+    }
+
+    #[test]
+    fn test_to_attribute_value() {
+        let value = to_attribute_value(&json!({"wedding": {"attending": true}, "note": ""}));
+        let map = value.m.unwrap();
+
+        assert_eq!(map["note"].s, Some(" ".to_string()));
+        assert_eq!(map["wedding"].m.as_ref().unwrap()["attending"].bool, Some(true));
+        assert_eq!(to_attribute_value(&json!(3)).n, Some("3".to_string()));
     }
 
     #[test]
@@ -246,7 +612,7 @@ mod rsvp_tests {
         });
 
         match RSVP::patch(uuid, payload.clone()) {
-            Ok(rsvp) => {
+            Ok(PatchedRSVP { rsvp, .. }) => {
                 assert_eq!(&rsvp.attending, payload.get("attending").unwrap());
                 assert_eq!(&rsvp.invitation_submitted, payload.get("invitation_submitted").unwrap());
                 assert_eq!(&rsvp.reminder_submitted, payload.get("reminder_submitted").unwrap());
@@ -0,0 +1,318 @@
+use serde_derive::{Serialize, Deserialize};
+use std::vec::{Vec};
+use std::collections::{BTreeSet, HashMap};
+use serde_dynamodb;
+use rusoto_dynamodb::AttributeValue;
+
+use crate::models::{RSVP, Event, Travel};
+use super::allergy::describe_allergies;
+use super::rsvp::ADMIN_KEYS;
+
+/// A batch of records from the RSVP table's stream.
+#[derive(Debug, Clone, Deserialize)]
+pub struct StreamEvent {
+    #[serde(rename = "Records", default)]
+    pub records: Vec<StreamRecord>
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StreamRecord {
+    /// INSERT, MODIFY or REMOVE
+    #[serde(rename = "eventName")]
+    pub event_name: String,
+    pub dynamodb: StreamImages
+}
+
+/// The record before and after the write. The table has to stream
+/// NEW_AND_OLD_IMAGES for both to be there.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct StreamImages {
+    #[serde(rename = "OldImage", default)]
+    pub old_image: Option<HashMap<String, AttributeValue>>,
+    #[serde(rename = "NewImage", default)]
+    pub new_image: Option<HashMap<String, AttributeValue>>
+}
+
+/// One field that differs between two versions of an RSVP, described for
+/// people rather than as stored.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    /// The attribute that changed. Per-event responses are
+    /// `invitations.<event id>`.
+    pub field: String,
+    pub before: String,
+    pub after: String
+}
+
+impl FieldChange {
+    /// Whether the guest made this change, rather than the couple setting
+    /// one of their flags.
+    pub fn is_guest_change(&self) -> bool {
+        !ADMIN_KEYS.contains(&self.field.as_str())
+    }
+
+    fn label(&self, events: &[Event]) -> String {
+        if self.field.starts_with("invitations.") {
+            let event_id = &self.field["invitations.".len()..];
+            return events.iter()
+                .find(|event| event.id == event_id)
+                .map_or_else(|| String::from("Another event"), |event| event.name.clone());
+        }
+        match self.field.as_str() {
+            "attending" => String::from("Attending"),
+            "food_preference" => String::from("Meal"),
+            "dietary_restrictions" => String::from("Dietary restrictions"),
+            "dietary_restrictions_other" => String::from("Other dietary restrictions"),
+            "allergies" => String::from("Allergies"),
+            "age_category" => String::from("Age"),
+            "travel" => String::from("Travel"),
+            field => field.replace("_", " ")
+        }
+    }
+}
+
+/// What a write to the RSVP table did, read off a stream record.
+#[derive(Debug, Clone)]
+pub enum RsvpChange {
+    Added(RSVP),
+    Modified(RsvpDiff),
+    Removed(RSVP)
+}
+
+/// Every field that differs between the old and new image of an RSVP.
+#[derive(Debug, Clone, Serialize)]
+pub struct RsvpDiff {
+    pub household_id: String,
+    pub name: String,
+    pub changes: Vec<FieldChange>
+}
+
+/// Empty strings are stored as a single space, since DynamoDB rejects them.
+fn text(value: &str) -> String {
+    match value.trim() {
+        "" => String::from("none"),
+        value => value.to_string()
+    }
+}
+
+fn yes_no(value: bool) -> String {
+    String::from(if value { "yes" } else { "no" })
+}
+
+fn response(attending: Option<bool>) -> String {
+    String::from(match attending {
+        Some(true) => "attending",
+        Some(false) => "not attending",
+        None => "no answer"
+    })
+}
+
+fn travel(travel: &Travel) -> String {
+    let mut parts = vec!();
+    if let Some(arrival) = travel.arrival {
+        parts.push(format!("arriving {}", arrival.format("%b %-d at %-I:%M%P")));
+    }
+    if !travel.arrival_flight.is_empty() {
+        parts.push(format!("on {}", travel.arrival_flight));
+    }
+    if !travel.airport.is_empty() {
+        parts.push(format!("into {}", travel.airport));
+    }
+    if let Some(departure) = travel.departure {
+        parts.push(format!("leaving {}", departure.format("%b %-d at %-I:%M%P")));
+    }
+    if !travel.departure_flight.is_empty() {
+        parts.push(format!("on {}", travel.departure_flight));
+    }
+    if travel.needs_shuttle {
+        parts.push(String::from("needs a shuttle"));
+    }
+    if parts.is_empty() {
+        String::from("none")
+    } else {
+        parts.join(", ")
+    }
+}
+
+fn allergies(rsvp: &RSVP) -> String {
+    text(&describe_allergies(&rsvp.allergies))
+}
+
+impl RsvpDiff {
+    pub fn new(old: &RSVP, new: &RSVP) -> RsvpDiff {
+        let mut changes = vec!();
+        let mut compare = |field: &str, before: String, after: String| {
+            if before != after {
+                changes.push(FieldChange { field: field.to_string(), before, after });
+            }
+        };
+
+        compare("attending", yes_no(old.attending), yes_no(new.attending));
+        compare("food_preference", text(&old.food_preference), text(&new.food_preference));
+        compare("dietary_restrictions", text(&old.dietary_restrictions), text(&new.dietary_restrictions));
+        compare("dietary_restrictions_other", text(&old.dietary_restrictions_other), text(&new.dietary_restrictions_other));
+        compare("allergies", allergies(old), allergies(new));
+        compare("age_category", format!("{:?}", old.age_category).to_lowercase(), format!("{:?}", new.age_category).to_lowercase());
+        compare("travel", travel(&old.travel), travel(&new.travel));
+
+        let event_ids : BTreeSet<&String> = old.invitations.keys().chain(new.invitations.keys()).collect();
+        for event_id in event_ids {
+            let before = old.invitations.get(event_id).and_then(|invitation| invitation.attending);
+            let after = new.invitations.get(event_id).and_then(|invitation| invitation.attending);
+            compare(&format!("invitations.{}", event_id), response(before), response(after));
+        }
+
+        compare("invitation_submitted", yes_no(old.invitation_submitted), yes_no(new.invitation_submitted));
+        compare("reminder_submitted", yes_no(old.reminder_submitted), yes_no(new.reminder_submitted));
+        compare("tags", text(&old.tags.join(", ")), text(&new.tags.join(", ")));
+
+        RsvpDiff {
+            household_id: new.household_id.clone(),
+            name: new.name.clone(),
+            changes
+        }
+    }
+
+    /// The changes the couple would want to hear about.
+    pub fn guest_changes(&self) -> Vec<&FieldChange> {
+        self.changes.iter().filter(|change| change.is_guest_change()).collect()
+    }
+
+    /// One line per change, e.g. "Meal: chicken → salmon". `None` when the
+    /// guest didn't change anything.
+    pub fn message(&self, events: &[Event]) -> Option<String> {
+        let changes = self.guest_changes();
+        if changes.is_empty() {
+            return None;
+        }
+
+        let lines : Vec<String> = changes.iter()
+            .map(|change| format!("- {}: {} → {}", change.label(events), change.before, change.after))
+            .collect();
+        Some(format!("{} updated their RSVP:\n{}", self.name, lines.join("\n")))
+    }
+}
+
+impl RsvpChange {
+    /// Deserializes the images on a stream record into RSVPs.
+    pub fn from_record(record: &StreamRecord) -> Result<RsvpChange, String> {
+        let image = |image: &Option<HashMap<String, AttributeValue>>, which: &str| -> Result<RSVP, String> {
+            let image = image.clone()
+                .ok_or_else(|| format!("The {} record has no {} image", record.event_name, which))?;
+            serde_dynamodb::from_hashmap(image)
+                .map_err(|error| format!("Could not read the {} image: {}", which, error))
+        };
+
+        match record.event_name.as_str() {
+            "INSERT" => Ok(RsvpChange::Added(image(&record.dynamodb.new_image, "new")?)),
+            "MODIFY" => Ok(RsvpChange::Modified(RsvpDiff::new(
+                &image(&record.dynamodb.old_image, "old")?,
+                &image(&record.dynamodb.new_image, "new")?
+            ))),
+            "REMOVE" => Ok(RsvpChange::Removed(image(&record.dynamodb.old_image, "old")?)),
+            other => Err(format!("Unknown stream event {}", other))
+        }
+    }
+
+    /// What to tell the couple, if anything. Guests only add and remove
+    /// their own plus-ones; other guests are added by the couple.
+    pub fn message(&self, events: &[Event]) -> Option<String> {
+        match self {
+            RsvpChange::Added(rsvp) if rsvp.plus_one => Some(format!("{} was added as a plus-one", rsvp.name)),
+            RsvpChange::Removed(rsvp) if rsvp.plus_one => Some(format!("{} is no longer coming as a plus-one", rsvp.name)),
+            RsvpChange::Modified(diff) => diff.message(events),
+            _ => None
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod rsvp_change_tests {
+
+    use super::*;
+    use chrono::NaiveDate;
+    use serde_json::json;
+    use crate::models::{Person, Invitation, Allergy, Severity};
+
+    fn rsvp() -> RSVP {
+        let person = Person {
+            name: "Jane Doe".to_string(),
+            email_address: "jane@example.com".to_string(),
+            events: vec!("estes-park".to_string()),
+            ..Person::default()
+        };
+        RSVP::new(person, "household".to_string())
+    }
+
+    #[test]
+    fn test_rsvp_diff() {
+        let old = rsvp();
+        let mut new = RSVP {
+            attending: true,
+            food_preference: "Salmon".to_string(),
+            dietary_restrictions_other: " ".to_string(),
+            reminder_submitted: true,
+            allergies: vec!(Allergy { allergen: "peanuts".to_string(), severity: Severity::Severe, notes: None }),
+            ..old.clone()
+        };
+        new.invitations.insert("estes-park".to_string(), Invitation { attending: Some(true) });
+
+        let diff = RsvpDiff::new(&old, &new);
+        let fields : Vec<&str> = diff.changes.iter().map(|change| change.field.as_str()).collect();
+        assert_eq!(fields, vec!("attending", "food_preference", "allergies", "invitations.estes-park", "reminder_submitted"));
+        assert_eq!(diff.guest_changes().len(), 4);
+
+        let events = vec!(Event {
+            id: "estes-park".to_string(),
+            ..Event::new("Estes Park Wedding".to_string(), NaiveDate::from_ymd(2019, 9, 14), "Della Terra".to_string(), 120)
+        });
+        let message = diff.message(&events).unwrap();
+        assert!(message.starts_with("Jane Doe updated their RSVP:\n"));
+        assert!(message.contains("- Attending: no → yes"));
+        assert!(message.contains(&format!("- Meal: {} → Salmon", text(&old.food_preference))));
+        assert!(message.contains("- Allergies: none → peanuts (severe)"));
+        assert!(message.contains("- Estes Park Wedding: no answer → attending"));
+        assert!(!message.contains("reminder"));
+    }
+
+    #[test]
+    fn test_admin_changes_are_quiet() {
+        let old = rsvp();
+        let new = RSVP {
+            invitation_submitted: true,
+            tags: vec!("college".to_string()),
+            ..old.clone()
+        };
+
+        let diff = RsvpDiff::new(&old, &new);
+        assert_eq!(diff.changes.len(), 2);
+        assert_eq!(diff.message(&[]), None);
+    }
+
+    #[test]
+    fn test_rsvp_change_from_record() {
+        let old = rsvp();
+        let new = RSVP { attending: true, ..old.clone() };
+        let image = |rsvp: &RSVP| serde_json::to_value(serde_dynamodb::to_hashmap(rsvp).unwrap()).unwrap();
+
+        let event : StreamEvent = serde_json::from_value(json!({
+            "Records": [
+                {"eventName": "MODIFY", "dynamodb": {"OldImage": image(&old), "NewImage": image(&new)}},
+                {"eventName": "INSERT", "dynamodb": {"NewImage": image(&RSVP { plus_one: true, ..new.clone() })}},
+                {"eventName": "REMOVE", "dynamodb": {"OldImage": image(&old)}},
+                {"eventName": "MODIFY", "dynamodb": {"NewImage": image(&new)}}
+            ]
+        })).unwrap();
+
+        let messages : Vec<Option<String>> = event.records[..3].iter()
+            .map(|record| RsvpChange::from_record(record).unwrap().message(&[]))
+            .collect();
+        assert_eq!(messages, vec!(
+            Some("Jane Doe updated their RSVP:\n- Attending: no → yes".to_string()),
+            Some("Jane Doe was added as a plus-one".to_string()),
+            None
+        ));
+        assert!(RsvpChange::from_record(&event.records[3]).is_err());
+    }
+}
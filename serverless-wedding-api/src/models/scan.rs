@@ -0,0 +1,42 @@
+use std::vec::{Vec};
+use log::{error};
+use serde::de::DeserializeOwned;
+use serde_dynamodb;
+use rusoto_dynamodb::{
+    DynamoDb,
+    DynamoDbClient,
+    ScanInput,
+    ScanError
+};
+
+/// Reads every item in `table_name`, following `last_evaluated_key` until
+/// DynamoDB reports there is nothing left. Items that no longer deserialize
+/// are logged and skipped rather than failing the whole scan.
+pub fn scan_table<T: DeserializeOwned>(client: &DynamoDbClient, table_name: String) -> Result<Vec<T>, ScanError> {
+    let mut results : Vec<T> = vec!();
+    let mut exclusive_start_key = None;
+
+    loop {
+        let scan_input = ScanInput {
+            table_name: table_name.clone(),
+            exclusive_start_key: exclusive_start_key.clone(),
+            ..ScanInput::default()
+        };
+
+        let response = client.scan(scan_input).sync()?;
+
+        for item in response.items.unwrap_or_default() {
+            match serde_dynamodb::from_hashmap(item) {
+                Ok(record) => results.push(record),
+                Err(err) => error!("Skipping an item in {} that did not deserialize: {}", table_name, err)
+            }
+        }
+
+        match response.last_evaluated_key {
+            Some(key) => exclusive_start_key = Some(key),
+            None => break
+        }
+    }
+
+    Ok(results)
+}
@@ -0,0 +1,551 @@
+use serde_derive::{Serialize, Deserialize};
+use std::vec::{Vec};
+use std::collections::{HashMap};
+use std::env;
+use std::fmt;
+use std::error::Error;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use log::{info, error};
+use serde_dynamodb;
+use rusoto_core::Region;
+use rusoto_dynamodb::{
+    DynamoDb,
+    AttributeValue,
+    DynamoDbClient,
+    GetItemInput,
+    GetItemError,
+    PutItemInput,
+    PutItemError,
+    DeleteItemInput,
+    DeleteItemError,
+    QueryInput,
+    QueryError,
+    UpdateItemError,
+    ScanError
+};
+
+use crate::models::{RSVP, Event, Allergy, describe_allergies};
+use super::scan::scan_table;
+use super::attributes::{Counter, string_value};
+use super::seating_solver::ConstraintKind;
+
+/// A table at one event. `seated` counts the guests assigned to it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SeatingTable {
+    pub id: String,
+    pub event_id: String,
+    pub name: String,
+    pub capacity: u32,
+    #[serde(default)]
+    pub seated: u32
+}
+
+/// Where one guest sits at one event. Keyed on event and guest, so a guest
+/// has at most one seat per event.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SeatAssignment {
+    pub event_id: String,
+    pub rsvp_id: String,
+    pub table_id: String,
+    pub assigned_at: DateTime<Utc>
+}
+
+#[derive(Debug)]
+pub enum SeatingError {
+    TableNotFound,
+    GuestNotFound,
+    NotAttending(String),
+    TableFull(SeatingTable),
+    Changed,
+    Get(GetItemError),
+    Update(UpdateItemError),
+    Put(PutItemError),
+    Delete(DeleteItemError)
+}
+
+impl fmt::Display for SeatingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SeatingError::TableNotFound => write!(f, "No table at this event was found with that id"),
+            SeatingError::GuestNotFound => write!(f, "No RSVP was found with that id"),
+            SeatingError::NotAttending(name) => write!(f, "{} hasn't said they're coming to this event", name),
+            SeatingError::TableFull(table) => write!(f, "{} already seats {} guests", table.name, table.capacity),
+            SeatingError::Changed => write!(f, "This guest's seat was changed while this was being saved. Please try again."),
+            SeatingError::Get(error) => write!(f, "{}", error),
+            SeatingError::Update(error) => write!(f, "{}", error),
+            SeatingError::Put(error) => write!(f, "{}", error),
+            SeatingError::Delete(error) => write!(f, "{}", error)
+        }
+    }
+}
+
+fn table_key(table_id: &str) -> HashMap<String, AttributeValue> {
+    let mut key = HashMap::new();
+    key.insert(String::from("id"), string_value(table_id));
+    key
+}
+
+fn assignment_key(event_id: &str, rsvp_id: &str) -> HashMap<String, AttributeValue> {
+    let mut key = HashMap::new();
+    key.insert(String::from("event_id"), string_value(event_id));
+    key.insert(String::from("rsvp_id"), string_value(rsvp_id));
+    key
+}
+
+impl SeatingTable {
+    pub fn new(event_id: String, name: String, capacity: u32) -> SeatingTable {
+        SeatingTable {
+            id: Uuid::new_v4().to_string(),
+            event_id,
+            name,
+            capacity,
+            seated: 0
+        }
+    }
+
+    pub fn create(&self) -> Result<SeatingTable, PutItemError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let put_item_input = PutItemInput {
+            table_name: env::var("SEATING_TABLE_NAME").unwrap(),
+            item: serde_dynamodb::to_hashmap(self).unwrap(),
+            ..PutItemInput::default()
+        };
+
+        client.put_item(put_item_input).sync()?;
+        info!("Created table {:?}", self);
+        Ok(self.clone())
+    }
+
+    pub fn get(uuid: Uuid) -> Result<SeatingTable, GetItemError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let get_item_input = GetItemInput {
+            table_name: env::var("SEATING_TABLE_NAME").unwrap(),
+            key: table_key(&uuid.to_string()),
+            ..GetItemInput::default()
+        };
+
+        match client.get_item(get_item_input).sync()?.item {
+            Some(item) => Ok(serde_dynamodb::from_hashmap(item).unwrap()),
+            None => Err(GetItemError::ResourceNotFound(format!("No table with id {}", uuid)))
+        }
+    }
+
+    /// The tables at an event, by name.
+    pub fn for_event(event_id: &str) -> Result<Vec<SeatingTable>, ScanError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+        let mut tables : Vec<SeatingTable> = scan_table(&client, env::var("SEATING_TABLE_NAME").unwrap())?;
+        tables.retain(|table| table.event_id == event_id);
+        tables.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(tables)
+    }
+
+    /// The guests seated at a table, counted against its capacity.
+    fn seated(table_id: &str) -> Counter {
+        Counter {
+            table_name: env::var("SEATING_TABLE_NAME").unwrap(),
+            key: table_key(table_id),
+            count: "seated",
+            limit: "capacity"
+        }
+    }
+}
+
+impl SeatAssignment {
+    /// Every seat at an event, following `last_evaluated_key` since a big
+    /// event's seats can span more than one page of results.
+    pub fn for_event(event_id: &str) -> Result<Vec<SeatAssignment>, QueryError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let mut values = HashMap::new();
+        values.insert(String::from(":event_id"), string_value(event_id));
+
+        let mut assignments : Vec<SeatAssignment> = vec!();
+        let mut exclusive_start_key = None;
+
+        loop {
+            let query_input = QueryInput {
+                table_name: env::var("SEAT_ASSIGNMENT_TABLE_NAME").unwrap(),
+                key_condition_expression: Some(String::from("event_id = :event_id")),
+                expression_attribute_values: Some(values.clone()),
+                exclusive_start_key: exclusive_start_key.clone(),
+                ..QueryInput::default()
+            };
+
+            let response = client.query(query_input).sync()?;
+            assignments.extend(
+                response.items
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|item| serde_dynamodb::from_hashmap(item).unwrap())
+            );
+
+            match response.last_evaluated_key {
+                Some(key) => exclusive_start_key = Some(key),
+                None => break
+            }
+        }
+
+        Ok(assignments)
+    }
+
+    fn get(client: &DynamoDbClient, event_id: &str, rsvp_id: &str) -> Result<Option<SeatAssignment>, GetItemError> {
+        let get_item_input = GetItemInput {
+            table_name: env::var("SEAT_ASSIGNMENT_TABLE_NAME").unwrap(),
+            key: assignment_key(event_id, rsvp_id),
+            ..GetItemInput::default()
+        };
+
+        Ok(client.get_item(get_item_input).sync()?
+            .item
+            .map(|item| serde_dynamodb::from_hashmap(item).unwrap()))
+    }
+
+    /// Seats a guest at a table, moving them if they were already seated
+    /// elsewhere at the event. Only guests who said they're coming to the
+    /// event can be seated.
+    pub fn assign(event_id: &str, table_id: Uuid, rsvp_id: Uuid) -> Result<SeatAssignment, SeatingError> {
+        let table = SeatingTable::get(table_id).map_err(|_| SeatingError::TableNotFound)?;
+        if table.event_id != event_id {
+            return Err(SeatingError::TableNotFound);
+        }
+        let rsvp = RSVP::get(rsvp_id).map_err(|_| SeatingError::GuestNotFound)?;
+
+        let attending = rsvp.invitations.get(&table.event_id)
+            .map_or(false, |invitation| invitation.attending == Some(true));
+        if !attending {
+            return Err(SeatingError::NotAttending(rsvp.name));
+        }
+
+        let client = DynamoDbClient::new(Region::UsEast1);
+        let existing = SeatAssignment::get(&client, &table.event_id, &rsvp.id)
+            .map_err(SeatingError::Get)?;
+        if let Some(existing) = &existing {
+            if existing.table_id == table.id {
+                return Ok(existing.clone());
+            }
+        }
+
+        match SeatingTable::seated(&table.id).reserve(&client, table.capacity, 1) {
+            Ok(()) => {},
+            Err(UpdateItemError::ConditionalCheckFailed(_)) => return Err(SeatingError::TableFull(table)),
+            Err(error) => return Err(SeatingError::Update(error))
+        }
+
+        let assignment = SeatAssignment {
+            event_id: table.event_id.clone(),
+            rsvp_id: rsvp.id.clone(),
+            table_id: table.id.clone(),
+            assigned_at: Utc::now()
+        };
+
+        // Only replace the seat that was read, so a concurrent move can't
+        // leave a table counting a guest who isn't there
+        let mut values = HashMap::new();
+        let condition_expression = match &existing {
+            Some(existing) => {
+                values.insert(String::from(":table_id"), string_value(&existing.table_id));
+                String::from("table_id = :table_id")
+            },
+            None => String::from("attribute_not_exists(rsvp_id)")
+        };
+
+        let put_item_input = PutItemInput {
+            table_name: env::var("SEAT_ASSIGNMENT_TABLE_NAME").unwrap(),
+            item: serde_dynamodb::to_hashmap(&assignment).unwrap(),
+            condition_expression: Some(condition_expression),
+            expression_attribute_values: if values.is_empty() { None } else { Some(values) },
+            ..PutItemInput::default()
+        };
+
+        if let Err(error) = client.put_item(put_item_input).sync() {
+            if let Err(release_error) = SeatingTable::seated(&table.id).release(&client, 1) {
+                error!("Could not release a seat at table {}: {:?}", table.id, release_error);
+            }
+            return match error {
+                PutItemError::ConditionalCheckFailed(_) => Err(SeatingError::Changed),
+                error => Err(SeatingError::Put(error))
+            };
+        }
+
+        if let Some(existing) = existing {
+            if let Err(release_error) = SeatingTable::seated(&existing.table_id).release(&client, 1) {
+                error!("Could not release a seat at table {}: {:?}", existing.table_id, release_error);
+            }
+        }
+
+        info!("Seated {} at {}", rsvp.name, table.name);
+        Ok(assignment)
+    }
+
+    /// Removes a guest's seat at an event, if they had one.
+    pub fn unassign(event_id: &str, rsvp_id: &str) -> Result<Option<SeatAssignment>, SeatingError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let delete_item_input = DeleteItemInput {
+            table_name: env::var("SEAT_ASSIGNMENT_TABLE_NAME").unwrap(),
+            key: assignment_key(event_id, rsvp_id),
+            return_values: Some(String::from("ALL_OLD")),
+            ..DeleteItemInput::default()
+        };
+
+        let removed : Option<SeatAssignment> = client.delete_item(delete_item_input).sync()
+            .map_err(SeatingError::Delete)?
+            .attributes
+            .map(|item| serde_dynamodb::from_hashmap(item).unwrap());
+
+        if let Some(removed) = &removed {
+            SeatingTable::seated(&removed.table_id).release(&client, 1)
+                .map_err(SeatingError::Update)?;
+            info!("Removed RSVP {} from table {}", rsvp_id, removed.table_id);
+        }
+
+        Ok(removed)
+    }
+}
+
+/// A rule for the seating solver: these guests must sit together, or no
+/// two of them may share a table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SeatingConstraint {
+    pub id: String,
+    pub event_id: String,
+    pub kind: ConstraintKind,
+    pub rsvp_ids: Vec<String>,
+    #[serde(default)]
+    pub note: Option<String>
+}
+
+impl SeatingConstraint {
+    pub fn new(event_id: String, kind: ConstraintKind, mut rsvp_ids: Vec<String>, note: Option<String>) -> Result<SeatingConstraint, String> {
+        rsvp_ids.sort();
+        rsvp_ids.dedup();
+        if rsvp_ids.len() < 2 {
+            return Err(String::from("A seating constraint needs at least two guests"));
+        }
+
+        Ok(SeatingConstraint {
+            id: Uuid::new_v4().to_string(),
+            event_id,
+            kind,
+            rsvp_ids,
+            note
+        })
+    }
+
+    pub fn create(&self) -> Result<SeatingConstraint, PutItemError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let put_item_input = PutItemInput {
+            table_name: env::var("SEATING_CONSTRAINT_TABLE_NAME").unwrap(),
+            item: serde_dynamodb::to_hashmap(self).unwrap(),
+            ..PutItemInput::default()
+        };
+
+        client.put_item(put_item_input).sync()?;
+        info!("Created seating constraint {:?}", self);
+        Ok(self.clone())
+    }
+
+    pub fn for_event(event_id: &str) -> Result<Vec<SeatingConstraint>, ScanError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+        let mut constraints : Vec<SeatingConstraint> = scan_table(&client, env::var("SEATING_CONSTRAINT_TABLE_NAME").unwrap())?;
+        constraints.retain(|constraint| constraint.event_id == event_id);
+        constraints.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(constraints)
+    }
+
+    /// Removes a constraint from an event, returning it if it existed.
+    pub fn delete(event_id: &str, uuid: Uuid) -> Result<Option<SeatingConstraint>, DeleteItemError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let mut values = HashMap::new();
+        values.insert(String::from(":event_id"), string_value(event_id));
+
+        let delete_item_input = DeleteItemInput {
+            table_name: env::var("SEATING_CONSTRAINT_TABLE_NAME").unwrap(),
+            key: table_key(&uuid.to_string()),
+            condition_expression: Some(String::from("event_id = :event_id")),
+            expression_attribute_values: Some(values),
+            return_values: Some(String::from("ALL_OLD")),
+            ..DeleteItemInput::default()
+        };
+
+        match client.delete_item(delete_item_input).sync() {
+            Ok(output) => Ok(output.attributes.map(|item| serde_dynamodb::from_hashmap(item).unwrap())),
+            Err(DeleteItemError::ConditionalCheckFailed(_)) => Ok(None),
+            Err(error) => Err(error)
+        }
+    }
+}
+
+/// A guest as the seating chart shows them, with what the servers at their
+/// table need to know.
+#[derive(Debug, Clone, Serialize)]
+pub struct SeatedGuest {
+    pub rsvp_id: String,
+    pub household_id: String,
+    pub name: String,
+    /// False when a seated guest has since declined
+    pub attending: bool,
+    pub food_preference: String,
+    pub dietary_restrictions: String,
+    pub dietary_restrictions_other: String,
+    pub allergies: Vec<Allergy>,
+    pub allergy_summary: String,
+    pub severe_allergy: bool
+}
+
+impl SeatedGuest {
+    fn new(rsvp: &RSVP, event_id: &str) -> SeatedGuest {
+        SeatedGuest {
+            rsvp_id: rsvp.id.clone(),
+            household_id: rsvp.household_id.clone(),
+            name: rsvp.name.clone(),
+            attending: rsvp.invitations.get(event_id)
+                .map_or(false, |invitation| invitation.attending == Some(true)),
+            food_preference: rsvp.food_preference.clone(),
+            dietary_restrictions: rsvp.dietary_restrictions.clone(),
+            dietary_restrictions_other: rsvp.dietary_restrictions_other.trim().to_string(),
+            allergies: rsvp.allergies.clone(),
+            allergy_summary: describe_allergies(&rsvp.allergies),
+            severe_allergy: rsvp.allergies.iter().any(|allergy| allergy.is_severe())
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TableView {
+    #[serde(flatten)]
+    pub table: SeatingTable,
+    pub open_seats: u32,
+    pub guests: Vec<SeatedGuest>
+}
+
+/// An event's tables and who sits at each, plus the attending guests who
+/// don't have a seat yet.
+#[derive(Debug, Clone, Serialize)]
+pub struct SeatingChart {
+    pub event: Event,
+    pub tables: Vec<TableView>,
+    pub unseated: Vec<SeatedGuest>
+}
+
+impl SeatingChart {
+    pub fn new(event: Event, tables: Vec<SeatingTable>, assignments: &[SeatAssignment], rsvps: &[RSVP]) -> SeatingChart {
+        let by_id : HashMap<&str, &RSVP> = rsvps.iter()
+            .map(|rsvp| (rsvp.id.as_str(), rsvp))
+            .collect();
+
+        let tables = tables.into_iter()
+            .map(|table| {
+                let mut guests : Vec<SeatedGuest> = assignments.iter()
+                    .filter(|assignment| assignment.table_id == table.id)
+                    .filter_map(|assignment| by_id.get(assignment.rsvp_id.as_str()))
+                    .map(|rsvp| SeatedGuest::new(rsvp, &event.id))
+                    .collect();
+                guests.sort_by(|a, b| (&a.household_id, &a.name).cmp(&(&b.household_id, &b.name)));
+
+                TableView {
+                    open_seats: table.capacity.saturating_sub(guests.len() as u32),
+                    table,
+                    guests
+                }
+            })
+            .collect();
+
+        let mut unseated : Vec<SeatedGuest> = rsvps.iter()
+            .filter(|rsvp| {
+                rsvp.invitations.get(&event.id)
+                    .map_or(false, |invitation| invitation.attending == Some(true))
+            })
+            .filter(|rsvp| !assignments.iter().any(|assignment| assignment.rsvp_id == rsvp.id))
+            .map(|rsvp| SeatedGuest::new(rsvp, &event.id))
+            .collect();
+        unseated.sort_by(|a, b| (&a.household_id, &a.name).cmp(&(&b.household_id, &b.name)));
+
+        SeatingChart { event, tables, unseated }
+    }
+
+    pub fn get(event_id: Uuid) -> Result<SeatingChart, Box<Error>> {
+        let event = Event::get(event_id)?;
+        let tables = SeatingTable::for_event(&event.id)?;
+        let assignments = SeatAssignment::for_event(&event.id)?;
+        let rsvps = RSVP::all()?;
+
+        Ok(SeatingChart::new(event, tables, &assignments, &rsvps))
+    }
+}
+
+
+#[cfg(test)]
+mod seating_tests {
+
+    use super::*;
+    use chrono::NaiveDate;
+    use serde_json::{json};
+    use crate::models::{Person, Invitation, parse_allergies};
+
+    fn guest(name: &str, event: &Event, attending: Option<bool>) -> RSVP {
+        let mut rsvp = RSVP::new(
+            Person {
+                name: name.to_string(),
+                email_address: "email@example.com".to_string(),
+                events: vec!(event.id.clone()),
+                ..Person::default()
+            },
+            "household".to_string()
+        );
+        rsvp.invitations.insert(event.id.clone(), Invitation { attending });
+        rsvp
+    }
+
+    fn seat(table: &SeatingTable, rsvp: &RSVP) -> SeatAssignment {
+        SeatAssignment {
+            event_id: table.event_id.clone(),
+            rsvp_id: rsvp.id.clone(),
+            table_id: table.id.clone(),
+            assigned_at: Utc::now()
+        }
+    }
+
+    #[test]
+    fn test_seating_chart() {
+        let wedding = Event::new("Wedding".to_string(), NaiveDate::from_ymd(2019, 9, 21), "Estes Park".to_string(), 100);
+        let table = SeatingTable::new(wedding.id.clone(), "Table 1".to_string(), 8);
+
+        let mut blaine = guest("Blaine Price", &wedding, Some(true));
+        blaine.allergies = parse_allergies(&json!([{"allergen": "shellfish", "severity": "severe"}])).unwrap();
+        let cynthia = guest("Cynthia Young", &wedding, Some(false));
+        let ling = guest("Ling Ling", &wedding, Some(true));
+        let pending = guest("Pending Guest", &wedding, None);
+
+        let assignments = vec!(seat(&table, &blaine), seat(&table, &cynthia));
+        let chart = SeatingChart::new(wedding, vec!(table), &assignments, &[blaine, cynthia, ling, pending]);
+
+        let table = &chart.tables[0];
+        assert_eq!(table.open_seats, 6);
+        assert_eq!(table.guests[0].name, "Blaine Price");
+        assert!(table.guests[0].severe_allergy);
+        assert_eq!(table.guests[0].allergy_summary, "shellfish (severe)");
+        assert!(!table.guests[1].attending);
+
+        assert_eq!(chart.unseated.len(), 1);
+        assert_eq!(chart.unseated[0].name, "Ling Ling");
+    }
+
+    #[test]
+    fn test_seating_constraint_new() {
+        let constraint = SeatingConstraint::new(
+            "wedding".to_string(),
+            ConstraintKind::Apart,
+            vec!("b".to_string(), "a".to_string(), "b".to_string()),
+            None
+        ).unwrap();
+        assert_eq!(constraint.rsvp_ids, vec!("a".to_string(), "b".to_string()));
+
+        assert!(SeatingConstraint::new("wedding".to_string(), ConstraintKind::Together, vec!("a".to_string(), "a".to_string()), None).is_err());
+    }
+}
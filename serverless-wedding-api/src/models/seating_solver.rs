@@ -0,0 +1,719 @@
+use serde_derive::{Serialize, Deserialize};
+use std::vec::{Vec};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::error::Error;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::models::{RSVP, Event, SeatingTable, SeatingConstraint};
+
+/// Penalties for each way a plan can fall short. Leaving a guest without a
+/// seat only happens when the tables can't hold everyone.
+const UNSEATED_PENALTY : u32 = 1000;
+const TOGETHER_PENALTY : u32 = 100;
+const APART_PENALTY : u32 = 100;
+const HOUSEHOLD_PENALTY : u32 = 20;
+const TAG_PENALTY : u32 = 1;
+
+/// Candidate moves the solver may score before settling on a plan. Big
+/// enough for a few hundred guests to converge.
+pub const DEFAULT_EVALUATIONS : u64 = 2_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConstraintKind {
+    /// The guests should share a table
+    Together,
+    /// No two of the guests should share a table
+    Apart
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SolverGuest {
+    pub id: String,
+    pub household_id: String,
+    pub name: String,
+    #[serde(default)]
+    pub tags: Vec<String>
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SolverTable {
+    pub id: String,
+    pub name: String,
+    pub capacity: u32
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SolverConstraint {
+    pub kind: ConstraintKind,
+    pub guests: Vec<String>
+}
+
+/// Everything the solver needs to seat one event. This is also the export
+/// format the `seating-solver` CLI reads.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SeatingProblem {
+    pub tables: Vec<SolverTable>,
+    pub guests: Vec<SolverGuest>,
+    #[serde(default)]
+    pub constraints: Vec<SolverConstraint>
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProposedSeat {
+    pub guest_id: String,
+    pub name: String,
+    pub table_id: String,
+    pub table: String
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ViolationKind {
+    HouseholdSplit,
+    TogetherSplit,
+    ApartSeatedTogether,
+    TagSplit
+}
+
+/// A soft constraint the plan doesn't meet.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Violation {
+    pub kind: ViolationKind,
+    pub guests: Vec<String>,
+    pub tables: Vec<String>,
+    pub message: String
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SeatingPlan {
+    pub seats: Vec<ProposedSeat>,
+    pub unseated: Vec<String>,
+    pub violations: Vec<Violation>,
+    pub penalty: u32,
+    /// False when the budget ran out before the plan stopped improving
+    pub converged: bool,
+    /// Candidate moves scored while improving the plan
+    pub evaluations: u64,
+    /// The time limit stopped the solver, so the plan depends on how fast
+    /// it ran and may differ between runs
+    pub timed_out: bool
+}
+
+/// How long the solver may keep improving a plan. It stops after
+/// `evaluations` candidate moves, which always gives the same plan for the
+/// same problem; `time` is only a backstop.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SolverBudget {
+    pub evaluations: u64,
+    pub time: Duration
+}
+
+impl Default for SolverBudget {
+    fn default() -> SolverBudget {
+        SolverBudget {
+            evaluations: DEFAULT_EVALUATIONS,
+            time: Duration::from_secs(5)
+        }
+    }
+}
+
+impl SeatingProblem {
+    /// The problem for one event: everyone who accepted it, its tables, and
+    /// the constraints between its guests.
+    pub fn new(event: &Event, tables: &[SeatingTable], rsvps: &[RSVP], constraints: &[SeatingConstraint]) -> SeatingProblem {
+        SeatingProblem {
+            tables: tables.iter()
+                .filter(|table| table.event_id == event.id)
+                .map(|table| SolverTable {
+                    id: table.id.clone(),
+                    name: table.name.clone(),
+                    capacity: table.capacity
+                })
+                .collect(),
+            guests: rsvps.iter()
+                .filter(|rsvp| {
+                    rsvp.invitations.get(&event.id)
+                        .map_or(false, |invitation| invitation.attending == Some(true))
+                })
+                .map(|rsvp| SolverGuest {
+                    id: rsvp.id.clone(),
+                    household_id: rsvp.household_id.clone(),
+                    name: rsvp.name.clone(),
+                    tags: rsvp.tags.clone()
+                })
+                .collect(),
+            constraints: constraints.iter()
+                .filter(|constraint| constraint.event_id == event.id)
+                .map(|constraint| SolverConstraint {
+                    kind: constraint.kind,
+                    guests: constraint.rsvp_ids.clone()
+                })
+                .collect()
+        }
+    }
+
+    pub fn get(event_id: Uuid) -> Result<SeatingProblem, Box<Error>> {
+        let event = Event::get(event_id)?;
+        let tables = SeatingTable::for_event(&event.id)?;
+        let constraints = SeatingConstraint::for_event(&event.id)?;
+        let rsvps = RSVP::all()?;
+
+        Ok(SeatingProblem::new(&event, &tables, &rsvps, &constraints))
+    }
+}
+
+/// Groups of guest indexes, each scored on how many tables it spans.
+struct Groups {
+    households: Vec<Vec<usize>>,
+    together: Vec<Vec<usize>>,
+    apart: Vec<Vec<usize>>,
+    tags: Vec<(String, Vec<usize>)>
+}
+
+/// Working state: the table index each guest sits at, and how full each
+/// table is.
+struct State {
+    seats: Vec<Option<usize>>,
+    loads: Vec<u32>
+}
+
+impl State {
+    fn has_room(&self, capacities: &[u32], table: usize, needed: u32) -> bool {
+        self.loads[table] + needed <= capacities[table]
+    }
+
+    fn set(&mut self, guest: usize, table: Option<usize>) {
+        if let Some(old) = self.seats[guest] {
+            self.loads[old] -= 1;
+        }
+        if let Some(new) = table {
+            self.loads[new] += 1;
+        }
+        self.seats[guest] = table;
+    }
+
+    /// Makes `moves` if that lowers `current`, keeping it up to date.
+    fn try_moves(&mut self, scorer: &Scorer, moves: &[(usize, Option<usize>)], current: &mut u32) -> bool {
+        let moving : Vec<usize> = moves.iter().map(|(guest, _table)| *guest).collect();
+        let previous : Vec<Option<usize>> = moving.iter().map(|guest| self.seats[*guest]).collect();
+
+        let before = scorer.local(&self.seats, &moving);
+        for (guest, table) in moves {
+            self.set(*guest, *table);
+        }
+        let after = scorer.local(&self.seats, &moving);
+
+        if after < before {
+            *current = *current - before + after;
+            true
+        } else {
+            for (guest, seat) in moving.iter().zip(previous) {
+                self.set(*guest, seat);
+            }
+            false
+        }
+    }
+}
+
+fn tables_spanned(seats: &[Option<usize>], group: &[usize]) -> BTreeSet<usize> {
+    group.iter().filter_map(|guest| seats[*guest]).collect()
+}
+
+fn spread(seats: &[Option<usize>], group: &[usize]) -> u32 {
+    (tables_spanned(seats, group).len() as u32).saturating_sub(1)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Scoring {
+    /// `weight` for every table past the first the group spans
+    Spread(u32),
+    /// `APART_PENALTY` for every pair of the group sharing a table
+    Apart
+}
+
+/// Scores plans. A move only changes the score of the groups the moving
+/// guests belong to, so candidates are scored on just those.
+struct Scorer {
+    groups: Vec<(Scoring, Vec<usize>)>,
+    /// The groups each guest belongs to
+    memberships: Vec<Vec<usize>>
+}
+
+impl Scorer {
+    fn new(groups: &Groups, guests: usize) -> Scorer {
+        let spread_by = |weight: u32, groups: &[Vec<usize>]| -> Vec<(Scoring, Vec<usize>)> {
+            groups.iter().map(|group| (Scoring::Spread(weight), group.clone())).collect()
+        };
+
+        let mut scored = spread_by(HOUSEHOLD_PENALTY, &groups.households);
+        scored.extend(spread_by(TOGETHER_PENALTY, &groups.together));
+        scored.extend(groups.apart.iter().map(|group| (Scoring::Apart, group.clone())));
+        scored.extend(groups.tags.iter().map(|(_tag, group)| (Scoring::Spread(TAG_PENALTY), group.clone())));
+
+        let mut memberships = vec!(vec!(); guests);
+        for (index, (_scoring, members)) in scored.iter().enumerate() {
+            for guest in members {
+                memberships[*guest].push(index);
+            }
+        }
+
+        Scorer { groups: scored, memberships }
+    }
+
+    fn group_penalty(&self, group: usize, seats: &[Option<usize>]) -> u32 {
+        let (scoring, members) = &self.groups[group];
+        match scoring {
+            Scoring::Spread(weight) => spread(seats, members) * weight,
+            Scoring::Apart => {
+                let mut at_table : HashMap<usize, u32> = HashMap::new();
+                for table in members.iter().filter_map(|guest| seats[*guest]) {
+                    *at_table.entry(table).or_insert(0) += 1;
+                }
+                at_table.values().map(|count| count * (count - 1) / 2 * APART_PENALTY).sum()
+            }
+        }
+    }
+
+    fn penalty(&self, seats: &[Option<usize>]) -> u32 {
+        let unseated = seats.iter().filter(|seat| seat.is_none()).count() as u32 * UNSEATED_PENALTY;
+        unseated + (0..self.groups.len()).map(|group| self.group_penalty(group, seats)).sum::<u32>()
+    }
+
+    /// The part of the penalty that moving `guests` can change.
+    fn local(&self, seats: &[Option<usize>], guests: &[usize]) -> u32 {
+        let groups : BTreeSet<usize> = guests.iter()
+            .flat_map(|guest| self.memberships[*guest].iter().cloned())
+            .collect();
+        let unseated = guests.iter().filter(|guest| seats[**guest].is_none()).count() as u32 * UNSEATED_PENALTY;
+        unseated + groups.into_iter().map(|group| self.group_penalty(group, seats)).sum::<u32>()
+    }
+}
+
+/// Counts candidate moves against a `SolverBudget`.
+struct Allowance {
+    budget: SolverBudget,
+    deadline: Instant,
+    evaluations: u64,
+    timed_out: bool
+}
+
+impl Allowance {
+    /// Takes one evaluation, or returns false once the budget is spent.
+    fn spend(&mut self) -> bool {
+        if self.evaluations >= self.budget.evaluations {
+            return false;
+        }
+        if Instant::now() >= self.deadline {
+            self.timed_out = true;
+            return false;
+        }
+        self.evaluations += 1;
+        true
+    }
+}
+
+/// Finds the root of `index`, for merging households with guests who must
+/// sit together.
+fn find(parents: &mut [usize], index: usize) -> usize {
+    let mut root = index;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    parents[index] = root;
+    root
+}
+
+/// Proposes a seating plan within `budget`.
+///
+/// Capacity is never exceeded. Everything else is a soft constraint,
+/// weighted so must-sit-together and must-not-sit-together rules matter
+/// most, then keeping households together, then grouping by tag. The plan
+/// starts from a greedy placement of households and improves it by moving
+/// households, moving single guests and swapping pairs, always in the same
+/// order, so the same problem and evaluation budget give the same plan
+/// unless the time limit cuts the search short.
+pub fn solve(problem: &SeatingProblem, budget: SolverBudget) -> SeatingPlan {
+    let mut allowance = Allowance {
+        budget,
+        deadline: Instant::now() + budget.time,
+        evaluations: 0,
+        timed_out: false
+    };
+
+    let mut guests : Vec<&SolverGuest> = problem.guests.iter().collect();
+    guests.sort_by(|a, b| (&a.household_id, &a.name, &a.id).cmp(&(&b.household_id, &b.name, &b.id)));
+    let index_of : HashMap<&str, usize> = guests.iter()
+        .enumerate()
+        .map(|(index, guest)| (guest.id.as_str(), index))
+        .collect();
+    let capacities : Vec<u32> = problem.tables.iter().map(|table| table.capacity).collect();
+
+    let mut households : BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    let mut tags : BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (index, guest) in guests.iter().enumerate() {
+        households.entry(guest.household_id.as_str()).or_insert_with(Vec::new).push(index);
+        for tag in &guest.tags {
+            tags.entry(tag.as_str()).or_insert_with(Vec::new).push(index);
+        }
+    }
+
+    // Constraints naming guests who aren't coming are ignored
+    let constraint_groups = |kind: ConstraintKind| -> Vec<Vec<usize>> {
+        problem.constraints.iter()
+            .filter(|constraint| constraint.kind == kind)
+            .map(|constraint| {
+                let members : BTreeSet<usize> = constraint.guests.iter()
+                    .filter_map(|id| index_of.get(id.as_str()).cloned())
+                    .collect();
+                members.into_iter().collect::<Vec<usize>>()
+            })
+            .filter(|members| members.len() > 1)
+            .collect()
+    };
+
+    let groups = Groups {
+        households: households.values().cloned().collect(),
+        together: constraint_groups(ConstraintKind::Together),
+        apart: constraint_groups(ConstraintKind::Apart),
+        tags: tags.into_iter()
+            .map(|(tag, members)| (tag.to_string(), members))
+            .collect()
+    };
+    let scorer = Scorer::new(&groups, guests.len());
+
+    let mut state = State {
+        seats: vec!(None; guests.len()),
+        loads: vec!(0; capacities.len())
+    };
+
+    // Greedy start: place households and the guests who must sit with them
+    // as units, largest first, wherever the plan scores best
+    let mut parents : Vec<usize> = (0..guests.len()).collect();
+    for group in groups.households.iter().chain(groups.together.iter()) {
+        for member in &group[1..] {
+            let a = find(&mut parents, group[0]);
+            let b = find(&mut parents, *member);
+            if a != b {
+                parents[b.max(a)] = a.min(b);
+            }
+        }
+    }
+    let mut units : BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for guest in 0..guests.len() {
+        let root = find(&mut parents, guest);
+        units.entry(root).or_insert_with(Vec::new).push(guest);
+    }
+    let mut units : Vec<Vec<usize>> = units.into_iter().map(|(_root, members)| members).collect();
+    units.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a[0].cmp(&b[0])));
+
+    // Every table is scored on the same groups, so comparing just those
+    // finds the best one
+    for unit in &units {
+        let mut best : Option<(u32, usize)> = None;
+        for table in 0..capacities.len() {
+            if !state.has_room(&capacities, table, unit.len() as u32) {
+                continue;
+            }
+            for guest in unit {
+                state.set(*guest, Some(table));
+            }
+            let score = scorer.local(&state.seats, unit);
+            if best.map_or(true, |(best_score, _)| score < best_score) {
+                best = Some((score, table));
+            }
+            for guest in unit {
+                state.set(*guest, None);
+            }
+        }
+
+        match best {
+            Some((_score, table)) => {
+                for guest in unit {
+                    state.set(*guest, Some(table));
+                }
+            },
+            // Too big for any table: seat members one at a time
+            None => {
+                for guest in unit {
+                    let mut best : Option<(u32, usize)> = None;
+                    for table in 0..capacities.len() {
+                        if !state.has_room(&capacities, table, 1) {
+                            continue;
+                        }
+                        state.set(*guest, Some(table));
+                        let score = scorer.local(&state.seats, &[*guest]);
+                        if best.map_or(true, |(best_score, _)| score < best_score) {
+                            best = Some((score, table));
+                        }
+                        state.set(*guest, None);
+                    }
+                    if let Some((_score, table)) = best {
+                        state.set(*guest, Some(table));
+                    }
+                }
+            }
+        }
+    }
+
+    // Improve until nothing helps or the budget runs out
+    let mut current = scorer.penalty(&state.seats);
+    let mut converged = false;
+    'search: loop {
+        let mut improved = false;
+
+        for household in &groups.households {
+            for table in 0..capacities.len() {
+                let moves : Vec<(usize, Option<usize>)> = household.iter()
+                    .filter(|guest| state.seats[**guest] != Some(table))
+                    .map(|guest| (*guest, Some(table)))
+                    .collect();
+                if moves.is_empty() || !state.has_room(&capacities, table, moves.len() as u32) {
+                    continue;
+                }
+                if !allowance.spend() {
+                    break 'search;
+                }
+                improved |= state.try_moves(&scorer, &moves, &mut current);
+            }
+        }
+
+        for guest in 0..guests.len() {
+            for table in 0..capacities.len() {
+                if state.seats[guest] == Some(table) || !state.has_room(&capacities, table, 1) {
+                    continue;
+                }
+                if !allowance.spend() {
+                    break 'search;
+                }
+                improved |= state.try_moves(&scorer, &[(guest, Some(table))], &mut current);
+            }
+        }
+
+        for a in 0..guests.len() {
+            for b in (a + 1)..guests.len() {
+                let (seat_a, seat_b) = (state.seats[a], state.seats[b]);
+                if seat_a == seat_b || seat_a.is_none() || seat_b.is_none() {
+                    continue;
+                }
+                if !allowance.spend() {
+                    break 'search;
+                }
+                improved |= state.try_moves(&scorer, &[(a, seat_b), (b, seat_a)], &mut current);
+            }
+        }
+
+        if !improved {
+            converged = true;
+            break;
+        }
+    }
+    debug_assert_eq!(current, scorer.penalty(&state.seats));
+
+    let table_names = |tables: BTreeSet<usize>| -> Vec<String> {
+        tables.into_iter().map(|table| problem.tables[table].name.clone()).collect()
+    };
+    let names = |members: &[usize]| -> Vec<String> {
+        members.iter().map(|guest| guests[*guest].name.clone()).collect()
+    };
+
+    let mut violations = vec!();
+    for household in &groups.households {
+        if spread(&state.seats, household) > 0 {
+            let tables = table_names(tables_spanned(&state.seats, household));
+            violations.push(Violation {
+                kind: ViolationKind::HouseholdSplit,
+                message: format!("{} are split across {}", names(household).join(", "), tables.join(", ")),
+                guests: household.iter().map(|guest| guests[*guest].id.clone()).collect(),
+                tables
+            });
+        }
+    }
+    for group in &groups.together {
+        if spread(&state.seats, group) > 0 {
+            let tables = table_names(tables_spanned(&state.seats, group));
+            violations.push(Violation {
+                kind: ViolationKind::TogetherSplit,
+                message: format!("{} should sit together but are at {}", names(group).join(", "), tables.join(", ")),
+                guests: group.iter().map(|guest| guests[*guest].id.clone()).collect(),
+                tables
+            });
+        }
+    }
+    for group in &groups.apart {
+        let mut at_table : BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for guest in group {
+            if let Some(table) = state.seats[*guest] {
+                at_table.entry(table).or_insert_with(Vec::new).push(*guest);
+            }
+        }
+        for (table, members) in at_table.into_iter().filter(|(_table, members)| members.len() > 1) {
+            violations.push(Violation {
+                kind: ViolationKind::ApartSeatedTogether,
+                message: format!("{} should sit apart but share {}", names(&members).join(", "), problem.tables[table].name),
+                guests: members.iter().map(|guest| guests[*guest].id.clone()).collect(),
+                tables: vec!(problem.tables[table].name.clone())
+            });
+        }
+    }
+    for (tag, group) in &groups.tags {
+        if spread(&state.seats, group) > 0 {
+            let tables = table_names(tables_spanned(&state.seats, group));
+            violations.push(Violation {
+                kind: ViolationKind::TagSplit,
+                message: format!("Guests tagged {} are split across {}", tag, tables.join(", ")),
+                guests: group.iter().map(|guest| guests[*guest].id.clone()).collect(),
+                tables
+            });
+        }
+    }
+
+    let mut seats = vec!();
+    let mut unseated = vec!();
+    for (index, guest) in guests.iter().enumerate() {
+        match state.seats[index] {
+            Some(table) => seats.push(ProposedSeat {
+                guest_id: guest.id.clone(),
+                name: guest.name.clone(),
+                table_id: problem.tables[table].id.clone(),
+                table: problem.tables[table].name.clone()
+            }),
+            None => unseated.push(guest.id.clone())
+        }
+    }
+
+    SeatingPlan {
+        seats,
+        unseated,
+        violations,
+        penalty: current,
+        converged,
+        evaluations: allowance.evaluations,
+        timed_out: allowance.timed_out
+    }
+}
+
+
+#[cfg(test)]
+mod seating_solver_tests {
+
+    use super::*;
+
+    fn guest(id: &str, household_id: &str, tags: Vec<&str>) -> SolverGuest {
+        SolverGuest {
+            id: id.to_string(),
+            household_id: household_id.to_string(),
+            name: id.to_string(),
+            tags: tags.into_iter().map(String::from).collect()
+        }
+    }
+
+    fn table(id: &str, capacity: u32) -> SolverTable {
+        SolverTable {
+            id: id.to_string(),
+            name: id.to_string(),
+            capacity
+        }
+    }
+
+    fn table_of<'a>(plan: &'a SeatingPlan, guest_id: &str) -> &'a str {
+        &plan.seats.iter().find(|seat| seat.guest_id == guest_id).unwrap().table_id
+    }
+
+    #[test]
+    fn test_solve_keeps_households_together() {
+        let problem = SeatingProblem {
+            tables: vec!(table("one", 4), table("two", 4)),
+            guests: vec!(
+                guest("blaine", "price", vec!()),
+                guest("cynthia", "price", vec!()),
+                guest("ling", "ling", vec!()),
+                guest("ling-jr", "ling", vec!()),
+                guest("ling-sr", "ling", vec!())
+            ),
+            constraints: vec!()
+        };
+
+        let plan = solve(&problem, SolverBudget::default());
+
+        assert!(plan.converged);
+        assert!(plan.unseated.is_empty());
+        assert!(plan.violations.is_empty());
+        assert_eq!(table_of(&plan, "blaine"), table_of(&plan, "cynthia"));
+        assert_eq!(table_of(&plan, "ling"), table_of(&plan, "ling-sr"));
+    }
+
+    #[test]
+    fn test_solve_respects_capacity() {
+        let problem = SeatingProblem {
+            tables: vec!(table("one", 2)),
+            guests: vec!(guest("a", "a", vec!()), guest("b", "b", vec!()), guest("c", "c", vec!())),
+            constraints: vec!()
+        };
+
+        let plan = solve(&problem, SolverBudget::default());
+
+        assert_eq!(plan.seats.len(), 2);
+        assert_eq!(plan.unseated.len(), 1);
+    }
+
+    #[test]
+    fn test_solve_honors_constraints() {
+        let problem = SeatingProblem {
+            tables: vec!(table("one", 3), table("two", 3)),
+            guests: vec!(
+                guest("a", "a", vec!("college")),
+                guest("b", "b", vec!("college")),
+                guest("c", "c", vec!("college")),
+                guest("d", "d", vec!()),
+                guest("e", "e", vec!())
+            ),
+            constraints: vec!(
+                SolverConstraint { kind: ConstraintKind::Apart, guests: vec!("a".to_string(), "b".to_string()) },
+                SolverConstraint { kind: ConstraintKind::Together, guests: vec!("d".to_string(), "e".to_string()) }
+            )
+        };
+
+        let plan = solve(&problem, SolverBudget::default());
+
+        assert_ne!(table_of(&plan, "a"), table_of(&plan, "b"));
+        assert_eq!(table_of(&plan, "d"), table_of(&plan, "e"));
+        // The apart rule forces the college friends onto two tables
+        assert_eq!(plan.violations.len(), 1);
+        assert_eq!(plan.violations[0].kind, ViolationKind::TagSplit);
+    }
+
+    #[test]
+    fn test_solve_is_deterministic() {
+        let problem = SeatingProblem {
+            tables: vec!(table("one", 3), table("two", 3), table("three", 3)),
+            guests: (0..8).map(|i| guest(&format!("guest-{}", i), &format!("house-{}", i / 2), vec!())).collect(),
+            constraints: vec!()
+        };
+
+        assert_eq!(solve(&problem, SolverBudget::default()), solve(&problem, SolverBudget::default()));
+    }
+
+    #[test]
+    fn test_solve_stops_at_the_budget() {
+        let problem = SeatingProblem {
+            tables: vec!(table("one", 3), table("two", 3), table("three", 3)),
+            guests: (0..8).map(|i| guest(&format!("guest-{}", i), &format!("house-{}", i / 2), vec!("family"))).collect(),
+            constraints: vec!()
+        };
+
+        let short = SolverBudget { evaluations: 3, ..SolverBudget::default() };
+        let plan = solve(&problem, short);
+        assert!(!plan.converged);
+        assert!(!plan.timed_out);
+        assert_eq!(plan.evaluations, 3);
+        assert_eq!(plan, solve(&problem, short));
+
+        let untimed = solve(&problem, SolverBudget { time: Duration::from_secs(0), ..SolverBudget::default() });
+        assert!(!untimed.converged);
+        assert!(untimed.timed_out);
+        assert_eq!(untimed.evaluations, 0);
+        assert_eq!(untimed.seats.len(), 8);
+    }
+}
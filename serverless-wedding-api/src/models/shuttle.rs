@@ -0,0 +1,447 @@
+use serde_derive::{Serialize, Deserialize};
+use std::vec::{Vec};
+use std::collections::{HashMap};
+use std::env;
+use std::fmt;
+use std::error::Error;
+use uuid::Uuid;
+use chrono::{DateTime, FixedOffset, NaiveDateTime, Utc};
+use log::{info, error};
+use serde_dynamodb;
+use rusoto_core::Region;
+use rusoto_dynamodb::{
+    DynamoDb,
+    AttributeValue,
+    DynamoDbClient,
+    GetItemInput,
+    GetItemError,
+    PutItemInput,
+    PutItemError,
+    DeleteItemInput,
+    DeleteItemError,
+    QueryInput,
+    QueryError,
+    UpdateItemError,
+    ScanError
+};
+
+use crate::models::{RSVP, Household};
+use super::scan::scan_table;
+use super::attributes::{Counter, string_value, number_value};
+use super::deadline::deadline_offset;
+
+/// One trip of a shuttle, e.g. the airport to the hotel on Friday
+/// afternoon. `booked` counts the seats households have claimed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShuttleRun {
+    pub id: String,
+    /// Where it goes, e.g. "Denver Airport to The Stanley Hotel"
+    pub route: String,
+    /// Local time at the pickup, in the couple's time zone
+    pub departs_at: NaiveDateTime,
+    pub pickup: String,
+    pub capacity: u32,
+    #[serde(default)]
+    pub booked: u32
+}
+
+/// A household's seats on one run. Keyed on run and household, so each
+/// household signs up once per run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShuttleSignup {
+    pub run_id: String,
+    pub household_id: String,
+    pub seats: u32,
+    pub signed_up_at: DateTime<Utc>
+}
+
+/// A run as guests see it.
+#[derive(Debug, Clone, Serialize)]
+pub struct ShuttleStatus {
+    #[serde(flatten)]
+    pub run: ShuttleRun,
+    pub seats_left: u32
+}
+
+#[derive(Debug)]
+pub enum ShuttleError {
+    RunNotFound,
+    Invalid(String),
+    Departed(ShuttleRun),
+    Full(ShuttleRun),
+    /// Another change to the household's signup was saved first
+    Changed,
+    Get(GetItemError),
+    Update(UpdateItemError),
+    Put(PutItemError),
+    Delete(DeleteItemError)
+}
+
+impl fmt::Display for ShuttleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ShuttleError::RunNotFound => write!(f, "No shuttle was found with that id"),
+            ShuttleError::Invalid(message) => write!(f, "{}", message),
+            ShuttleError::Departed(run) => write!(f, "The {} shuttle has already left", run.route),
+            ShuttleError::Full(run) => write!(f, "The {} shuttle only has {} seats left", run.route, run.seats_left()),
+            ShuttleError::Changed => write!(f, "Your seats were changed while this was being saved. Please try again."),
+            ShuttleError::Get(error) => write!(f, "{}", error),
+            ShuttleError::Update(error) => write!(f, "{}", error),
+            ShuttleError::Put(error) => write!(f, "{}", error),
+            ShuttleError::Delete(error) => write!(f, "{}", error)
+        }
+    }
+}
+
+fn run_key(run_id: &str) -> HashMap<String, AttributeValue> {
+    let mut key = HashMap::new();
+    key.insert(String::from("id"), string_value(run_id));
+    key
+}
+
+fn signup_key(run_id: &str, household_id: &str) -> HashMap<String, AttributeValue> {
+    let mut key = HashMap::new();
+    key.insert(String::from("run_id"), string_value(run_id));
+    key.insert(String::from("household_id"), string_value(household_id));
+    key
+}
+
+impl ShuttleRun {
+    pub fn new(route: String, departs_at: NaiveDateTime, pickup: String, capacity: u32) -> Result<ShuttleRun, String> {
+        if route.trim().is_empty() || capacity == 0 {
+            return Err(String::from("Shuttles need a route and at least one seat"));
+        }
+
+        Ok(ShuttleRun {
+            id: Uuid::new_v4().to_string(),
+            route: route.trim().to_string(),
+            departs_at,
+            pickup: pickup.trim().to_string(),
+            capacity,
+            booked: 0
+        })
+    }
+
+    /// Whether the run has left by `now`, reading `departs_at` at the
+    /// couple's `offset`.
+    pub fn has_departed(&self, now: DateTime<Utc>, offset: FixedOffset) -> bool {
+        self.departs_at <= now.with_timezone(&offset).naive_local()
+    }
+
+    pub fn seats_left(&self) -> u32 {
+        self.capacity.saturating_sub(self.booked)
+    }
+
+    pub fn status(self) -> ShuttleStatus {
+        ShuttleStatus {
+            seats_left: self.seats_left(),
+            run: self
+        }
+    }
+
+    pub fn create(&self) -> Result<ShuttleRun, PutItemError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let put_item_input = PutItemInput {
+            table_name: env::var("SHUTTLE_RUN_TABLE_NAME").unwrap(),
+            item: serde_dynamodb::to_hashmap(self).unwrap(),
+            ..PutItemInput::default()
+        };
+
+        client.put_item(put_item_input).sync()?;
+        info!("Created shuttle run {:?}", self);
+        Ok(self.clone())
+    }
+
+    pub fn get(uuid: Uuid) -> Result<ShuttleRun, GetItemError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let get_item_input = GetItemInput {
+            table_name: env::var("SHUTTLE_RUN_TABLE_NAME").unwrap(),
+            key: run_key(&uuid.to_string()),
+            ..GetItemInput::default()
+        };
+
+        match client.get_item(get_item_input).sync()?.item {
+            Some(item) => Ok(serde_dynamodb::from_hashmap(item).unwrap()),
+            None => Err(GetItemError::ResourceNotFound(format!("No shuttle run with id {}", uuid)))
+        }
+    }
+
+    /// Every run, in departure order.
+    pub fn all() -> Result<Vec<ShuttleRun>, ScanError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+        let mut runs : Vec<ShuttleRun> = scan_table(&client, env::var("SHUTTLE_RUN_TABLE_NAME").unwrap())?;
+        runs.sort_by(|a, b| (a.departs_at, &a.route).cmp(&(b.departs_at, &b.route)));
+        Ok(runs)
+    }
+
+    /// The seats households have claimed on the run.
+    fn booked(run_id: &str) -> Counter {
+        Counter {
+            table_name: env::var("SHUTTLE_RUN_TABLE_NAME").unwrap(),
+            key: run_key(run_id),
+            count: "booked",
+            limit: "capacity"
+        }
+    }
+}
+
+impl ShuttleSignup {
+    /// Everyone signed up for a run.
+    pub fn for_run(run_id: &str) -> Result<Vec<ShuttleSignup>, QueryError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let mut values = HashMap::new();
+        values.insert(String::from(":run_id"), string_value(run_id));
+
+        let query_input = QueryInput {
+            table_name: env::var("SHUTTLE_SIGNUP_TABLE_NAME").unwrap(),
+            key_condition_expression: Some(String::from("run_id = :run_id")),
+            expression_attribute_values: Some(values),
+            ..QueryInput::default()
+        };
+
+        Ok(client.query(query_input).sync()?
+            .items
+            .unwrap_or_default()
+            .into_iter()
+            .map(|item| serde_dynamodb::from_hashmap(item).unwrap())
+            .collect())
+    }
+
+    /// Every run a household is signed up for.
+    pub fn for_household(household_id: &str) -> Result<Vec<ShuttleSignup>, ScanError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+        let mut signups : Vec<ShuttleSignup> = scan_table(&client, env::var("SHUTTLE_SIGNUP_TABLE_NAME").unwrap())?;
+        signups.retain(|signup| signup.household_id == household_id);
+        Ok(signups)
+    }
+
+    fn get(client: &DynamoDbClient, run_id: &str, household_id: &str) -> Result<Option<ShuttleSignup>, GetItemError> {
+        let get_item_input = GetItemInput {
+            table_name: env::var("SHUTTLE_SIGNUP_TABLE_NAME").unwrap(),
+            key: signup_key(run_id, household_id),
+            ..GetItemInput::default()
+        };
+
+        Ok(client.get_item(get_item_input).sync()?
+            .item
+            .map(|item| serde_dynamodb::from_hashmap(item).unwrap()))
+    }
+
+    /// Holds `seats` on a run for a household, replacing their earlier
+    /// count. Only the extra seats are claimed when they ask for more. If
+    /// their signup changes underneath, the seats claimed are given back and
+    /// it fails with `Changed`.
+    pub fn sign_up(run_id: Uuid, household_id: &str, seats: u32) -> Result<ShuttleSignup, ShuttleError> {
+        if seats == 0 {
+            return Err(ShuttleError::Invalid(String::from("Sign up for at least one seat")));
+        }
+
+        let run = ShuttleRun::get(run_id).map_err(|_| ShuttleError::RunNotFound)?;
+        if run.has_departed(Utc::now(), deadline_offset()) {
+            return Err(ShuttleError::Departed(run));
+        }
+
+        let client = DynamoDbClient::new(Region::UsEast1);
+        let existing = ShuttleSignup::get(&client, &run.id, household_id).map_err(ShuttleError::Get)?;
+        let held = existing.as_ref().map_or(0, |existing| existing.seats);
+
+        if seats > held {
+            match ShuttleRun::booked(&run.id).reserve(&client, run.capacity, seats - held) {
+                Ok(()) => {},
+                Err(UpdateItemError::ConditionalCheckFailed(_)) => {
+                    let current = ShuttleRun::get(run_id).unwrap_or(run);
+                    return Err(ShuttleError::Full(current));
+                },
+                Err(error) => return Err(ShuttleError::Update(error))
+            }
+        }
+
+        let signup = ShuttleSignup {
+            run_id: run.id.clone(),
+            household_id: household_id.to_string(),
+            seats,
+            signed_up_at: Utc::now()
+        };
+
+        // Only save over the signup the seats above were counted from
+        let mut values = HashMap::new();
+        let condition_expression = match &existing {
+            Some(existing) => {
+                values.insert(String::from(":held"), number_value(existing.seats));
+                String::from("seats = :held")
+            },
+            None => String::from("attribute_not_exists(household_id)")
+        };
+        let put_item_input = PutItemInput {
+            table_name: env::var("SHUTTLE_SIGNUP_TABLE_NAME").unwrap(),
+            item: serde_dynamodb::to_hashmap(&signup).unwrap(),
+            condition_expression: Some(condition_expression),
+            expression_attribute_values: if values.is_empty() { None } else { Some(values) },
+            ..PutItemInput::default()
+        };
+
+        if let Err(error) = client.put_item(put_item_input).sync() {
+            if seats > held {
+                if let Err(release_error) = ShuttleRun::booked(&run.id).release(&client, seats - held) {
+                    error!("Could not release {} seats on shuttle {}: {:?}", seats - held, run.id, release_error);
+                }
+            }
+            return Err(match error {
+                PutItemError::ConditionalCheckFailed(_) => ShuttleError::Changed,
+                error => ShuttleError::Put(error)
+            });
+        }
+
+        if seats < held {
+            if let Err(release_error) = ShuttleRun::booked(&run.id).release(&client, held - seats) {
+                error!("Could not release {} seats on shuttle {}: {:?}", held - seats, run.id, release_error);
+            }
+        }
+
+        info!("Household {} holds {} seats on {}", household_id, seats, run.route);
+        Ok(signup)
+    }
+
+    /// Gives a household's seats on a run back, if they had any.
+    pub fn cancel(run_id: &str, household_id: &str) -> Result<Option<ShuttleSignup>, ShuttleError> {
+        let client = DynamoDbClient::new(Region::UsEast1);
+
+        let delete_item_input = DeleteItemInput {
+            table_name: env::var("SHUTTLE_SIGNUP_TABLE_NAME").unwrap(),
+            key: signup_key(run_id, household_id),
+            return_values: Some(String::from("ALL_OLD")),
+            ..DeleteItemInput::default()
+        };
+
+        let removed : Option<ShuttleSignup> = client.delete_item(delete_item_input).sync()
+            .map_err(ShuttleError::Delete)?
+            .attributes
+            .map(|item| serde_dynamodb::from_hashmap(item).unwrap());
+
+        if let Some(removed) = &removed {
+            ShuttleRun::booked(run_id).release(&client, removed.seats)
+                .map_err(ShuttleError::Update)?;
+            info!("Household {} gave up {} seats on shuttle {}", household_id, removed.seats, run_id);
+        }
+
+        Ok(removed)
+    }
+}
+
+/// One household on a run's passenger list.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Passenger {
+    pub household_id: String,
+    pub household: String,
+    pub seats: u32
+}
+
+/// Who to expect on a run, for the driver.
+#[derive(Debug, Clone, Serialize)]
+pub struct ShuttleManifest {
+    pub run: ShuttleRun,
+    pub passengers: Vec<Passenger>,
+    pub seats_booked: u32
+}
+
+impl ShuttleManifest {
+    pub fn new(run: ShuttleRun, signups: &[ShuttleSignup], rsvps: &[RSVP]) -> ShuttleManifest {
+        let mut households : HashMap<&str, Vec<RSVP>> = HashMap::new();
+        for rsvp in rsvps {
+            households.entry(rsvp.household_id.as_str())
+                .or_insert_with(Vec::new)
+                .push(rsvp.clone());
+        }
+
+        let mut passengers : Vec<Passenger> = signups.iter()
+            .filter(|signup| signup.run_id == run.id)
+            .map(|signup| Passenger {
+                household_id: signup.household_id.clone(),
+                household: households.get(signup.household_id.as_str())
+                    .map_or(String::new(), |members| Household::display_name(members)),
+                seats: signup.seats
+            })
+            .collect();
+        passengers.sort_by(|a, b| a.household.cmp(&b.household));
+
+        ShuttleManifest {
+            seats_booked: passengers.iter().map(|passenger| passenger.seats).sum(),
+            run,
+            passengers
+        }
+    }
+
+    pub fn get(run_id: Uuid) -> Result<ShuttleManifest, Box<Error>> {
+        let run = ShuttleRun::get(run_id)?;
+        let signups = ShuttleSignup::for_run(&run.id)?;
+        let rsvps = RSVP::all()?;
+        Ok(ShuttleManifest::new(run, &signups, &rsvps))
+    }
+}
+
+
+#[cfg(test)]
+mod shuttle_tests {
+
+    use super::*;
+    use chrono::{NaiveDate, TimeZone};
+    use crate::models::{Person};
+
+    #[test]
+    fn test_shuttle_run_new() {
+        let departs_at = NaiveDate::from_ymd(2019, 9, 20).and_hms(14, 0, 0);
+        let run = ShuttleRun::new(" DEN to Estes Park ".to_string(), departs_at, "Door 505".to_string(), 14).unwrap();
+        assert_eq!(run.route, "DEN to Estes Park");
+        assert_eq!(run.seats_left(), 14);
+
+        let run = ShuttleRun { booked: 15, ..run };
+        assert_eq!(run.status().seats_left, 0);
+
+        assert!(ShuttleRun::new("DEN to Estes Park".to_string(), departs_at, String::new(), 0).is_err());
+        assert!(ShuttleRun::new(" ".to_string(), departs_at, String::new(), 14).is_err());
+    }
+
+    #[test]
+    fn test_shuttle_run_has_departed() {
+        let departs_at = NaiveDate::from_ymd(2019, 9, 20).and_hms(14, 0, 0);
+        let run = ShuttleRun::new("DEN to Estes Park".to_string(), departs_at, String::new(), 14).unwrap();
+        let denver = FixedOffset::west(6 * 3600);
+
+        // 2pm in Denver is 8pm UTC
+        assert!(!run.has_departed(Utc.ymd(2019, 9, 20).and_hms(19, 59, 0), denver));
+        assert!(run.has_departed(Utc.ymd(2019, 9, 20).and_hms(20, 0, 0), denver));
+        assert!(run.has_departed(Utc.ymd(2019, 9, 20).and_hms(14, 0, 0), FixedOffset::east(0)));
+    }
+
+    #[test]
+    fn test_shuttle_manifest() {
+        let departs_at = NaiveDate::from_ymd(2019, 9, 20).and_hms(14, 0, 0);
+        let run = ShuttleRun::new("DEN to Estes Park".to_string(), departs_at, String::new(), 14).unwrap();
+        let rsvps : Vec<RSVP> = vec!(("Cynthia Young", "young"), ("Blaine Price", "price"))
+            .into_iter()
+            .map(|(name, household_id)| RSVP::new(
+                Person {
+                    name: name.to_string(),
+                    email_address: "email@example.com".to_string(),
+                    ..Person::default()
+                },
+                household_id.to_string()
+            ))
+            .collect();
+        let signup = |household_id: &str, run_id: &str, seats| ShuttleSignup {
+            run_id: run_id.to_string(),
+            household_id: household_id.to_string(),
+            seats,
+            signed_up_at: Utc::now()
+        };
+        let signups = vec!(signup("young", &run.id, 2), signup("price", &run.id, 1), signup("price", "another run", 1));
+
+        let manifest = ShuttleManifest::new(run, &signups, &rsvps);
+        assert_eq!(manifest.seats_booked, 3);
+        assert_eq!(manifest.passengers[0].household, "Blaine Price");
+        assert_eq!(manifest.passengers[1].seats, 2);
+    }
+}
@@ -0,0 +1,216 @@
+use serde_derive::{Serialize};
+use std::vec::{Vec};
+use std::collections::{HashSet};
+use std::error::Error;
+use chrono::NaiveDate;
+
+use crate::models::{RSVP, Event, AgeCategory, SevereAllergy};
+
+/// Head counts by age category, for the caterer.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AgeBreakdown {
+    pub adults: usize,
+    pub children: usize,
+    pub infants: usize
+}
+
+impl AgeBreakdown {
+    pub fn add(&mut self, age_category: AgeCategory) {
+        match age_category {
+            AgeCategory::Adult => self.adults += 1,
+            AgeCategory::Child => self.children += 1,
+            AgeCategory::Infant => self.infants += 1
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.adults + self.children + self.infants
+    }
+
+    pub fn count<'a, I: IntoIterator<Item = &'a RSVP>>(rsvps: I) -> AgeBreakdown {
+        let mut breakdown = AgeBreakdown::default();
+        for rsvp in rsvps {
+            breakdown.add(rsvp.age_category);
+        }
+        breakdown
+    }
+}
+
+/// Response counts for one event.
+#[derive(Debug, Clone, Serialize)]
+pub struct EventSummary {
+    pub event_id: String,
+    pub name: String,
+    pub date: NaiveDate,
+    pub venue: String,
+    pub capacity: u32,
+    pub invited: usize,
+    pub attending: usize,
+    pub declined: usize,
+    pub pending: usize,
+    pub remaining_capacity: i64,
+    pub attending_by_age: AgeBreakdown
+}
+
+impl EventSummary {
+    fn new(event: &Event, rsvps: &[RSVP]) -> EventSummary {
+        let responses : Vec<Option<bool>> = rsvps.iter()
+            .filter_map(|rsvp| rsvp.invitations.get(&event.id))
+            .map(|invitation| invitation.attending)
+            .collect();
+
+        let attending_by_age = AgeBreakdown::count(rsvps.iter().filter(|rsvp| {
+            rsvp.invitations.get(&event.id)
+                .map_or(false, |invitation| invitation.attending == Some(true))
+        }));
+
+        let attending = responses.iter().filter(|r| **r == Some(true)).count();
+        let declined = responses.iter().filter(|r| **r == Some(false)).count();
+
+        EventSummary {
+            event_id: event.id.clone(),
+            name: event.name.clone(),
+            date: event.date,
+            venue: event.venue.clone(),
+            capacity: event.capacity,
+            invited: responses.len(),
+            attending,
+            declined,
+            pending: responses.len() - attending - declined,
+            remaining_capacity: i64::from(event.capacity) - attending as i64,
+            attending_by_age
+        }
+    }
+}
+
+/// Guest counts across the whole guest list, broken down by event.
+#[derive(Debug, Clone, Serialize)]
+pub struct Summary {
+    pub households: usize,
+    pub guests: usize,
+    pub attending: usize,
+    pub attending_by_age: AgeBreakdown,
+    /// Guests coming to anything who listed an allergen as severe
+    pub severe_allergies: Vec<SevereAllergy>,
+    pub guests_with_allergies: usize,
+    pub events: Vec<EventSummary>
+}
+
+impl Summary {
+    pub fn new(rsvps: &[RSVP], events: &[Event]) -> Summary {
+        let households : HashSet<&str> = rsvps.iter()
+            .map(|rsvp| rsvp.household_id.as_str())
+            .collect();
+
+        Summary {
+            households: households.len(),
+            guests: rsvps.len(),
+            attending: rsvps.iter().filter(|rsvp| rsvp.attending).count(),
+            attending_by_age: AgeBreakdown::count(rsvps.iter().filter(|rsvp| rsvp.attending)),
+            severe_allergies: SevereAllergy::collect(rsvps.iter().filter(|rsvp| rsvp.is_attending_any())),
+            guests_with_allergies: rsvps.iter()
+                .filter(|rsvp| rsvp.is_attending_any() && !rsvp.allergies.is_empty())
+                .count(),
+            events: events.iter()
+                .map(|event| EventSummary::new(event, rsvps))
+                .collect()
+        }
+    }
+
+    pub fn get() -> Result<Summary, Box<Error>> {
+        let rsvps = RSVP::all()?;
+        let events = Event::all()?;
+        Ok(Summary::new(&rsvps, &events))
+    }
+}
+
+
+#[cfg(test)]
+mod summary_tests {
+
+    use super::*;
+    use serde_json::{json};
+    use crate::models::{Person, Invitation, parse_allergies};
+
+    fn guest(name: &str, household_id: &str, events: Vec<&Event>) -> RSVP {
+        RSVP::new(
+            Person {
+                name: name.to_string(),
+                email_address: "email@example.com".to_string(),
+                events: events.iter().map(|event| event.id.clone()).collect(),
+                ..Person::default()
+            },
+            household_id.to_string()
+        )
+    }
+
+    #[test]
+    fn test_summary_by_event() {
+        let wedding = Event::new("Wedding".to_string(), NaiveDate::from_ymd(2019, 9, 21), "Estes Park".to_string(), 100);
+        let bash = Event::new("Bash".to_string(), NaiveDate::from_ymd(2019, 10, 12), "Williamsburg".to_string(), 2);
+
+        let mut blaine = guest("Blaine Price", "a", vec!(&wedding, &bash));
+        let mut cynthia = guest("Cynthia Young", "a", vec!(&wedding, &bash));
+        let ling = guest("Ling Ling", "b", vec!(&bash));
+
+        blaine.invitations.insert(wedding.id.clone(), Invitation { attending: Some(true) });
+        blaine.invitations.insert(bash.id.clone(), Invitation { attending: Some(true) });
+        cynthia.invitations.insert(bash.id.clone(), Invitation { attending: Some(false) });
+
+        let summary = Summary::new(&[blaine, cynthia, ling], &[wedding, bash]);
+
+        assert_eq!(summary.households, 2);
+        assert_eq!(summary.guests, 3);
+
+        let wedding = &summary.events[0];
+        assert_eq!((wedding.invited, wedding.attending, wedding.declined, wedding.pending), (2, 1, 0, 1));
+
+        let bash = &summary.events[1];
+        assert_eq!((bash.invited, bash.attending, bash.declined, bash.pending), (3, 1, 1, 1));
+        assert_eq!(bash.remaining_capacity, 1);
+    }
+
+    #[test]
+    fn test_summary_attending_by_age() {
+        let bash = Event::new("Bash".to_string(), NaiveDate::from_ymd(2019, 10, 12), "Williamsburg".to_string(), 2);
+
+        let mut blaine = guest("Blaine Price", "a", vec!(&bash));
+        let mut cynthia = guest("Cynthia Young", "a", vec!(&bash));
+        let mut ling = guest("Ling Ling", "b", vec!(&bash));
+        ling.age_category = AgeCategory::Child;
+
+        blaine.invitations.insert(bash.id.clone(), Invitation { attending: Some(true) });
+        cynthia.invitations.insert(bash.id.clone(), Invitation { attending: Some(false) });
+        ling.invitations.insert(bash.id.clone(), Invitation { attending: Some(true) });
+
+        let summary = Summary::new(&[blaine, cynthia, ling], &[bash]);
+
+        let bash = &summary.events[0];
+        assert_eq!((bash.invited, bash.attending, bash.declined, bash.pending), (3, 2, 1, 0));
+        assert_eq!(bash.remaining_capacity, 0);
+        assert_eq!(bash.attending_by_age, AgeBreakdown { adults: 1, children: 1, infants: 0 });
+    }
+
+    #[test]
+    fn test_summary_flags_severe_allergies() {
+        let wedding = Event::new("Wedding".to_string(), NaiveDate::from_ymd(2019, 9, 21), "Estes Park".to_string(), 100);
+
+        let mut blaine = guest("Blaine Price", "a", vec!(&wedding));
+        blaine.invitations.insert(wedding.id.clone(), Invitation { attending: Some(true) });
+        blaine.allergies = parse_allergies(&json!([
+            {"allergen": "shellfish", "severity": "severe"},
+            {"allergen": "cilantro", "severity": "mild"}
+        ])).unwrap();
+
+        let mut cynthia = guest("Cynthia Young", "a", vec!(&wedding));
+        cynthia.invitations.insert(wedding.id.clone(), Invitation { attending: Some(false) });
+        cynthia.allergies = parse_allergies(&json!([{"allergen": "peanuts", "severity": "severe"}])).unwrap();
+
+        let summary = Summary::new(&[blaine, cynthia], &[wedding]);
+
+        assert_eq!(summary.guests_with_allergies, 1);
+        assert_eq!(summary.severe_allergies.len(), 1);
+        assert_eq!(summary.severe_allergies[0].name, "Blaine Price");
+        assert_eq!(summary.severe_allergies[0].allergen, "shellfish");
+    }
+}
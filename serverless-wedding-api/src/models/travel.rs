@@ -0,0 +1,290 @@
+use serde_derive::{Serialize, Deserialize};
+use std::vec::{Vec};
+use std::collections::{BTreeMap};
+use chrono::{NaiveDate, NaiveDateTime};
+use serde_json::{Value};
+use rusoto_dynamodb::ScanError;
+
+use crate::models::{RSVP};
+
+/// How a guest is getting to and from the wedding. Times are local to the
+/// airport, as they're printed on the ticket.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Travel {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arrival: Option<NaiveDateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub departure: Option<NaiveDateTime>,
+    /// IATA code, e.g. "DEN"
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub airport: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub arrival_flight: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub departure_flight: String,
+    #[serde(default)]
+    pub needs_shuttle: bool
+}
+
+/// Accepts what a browser's datetime-local input sends, with or without
+/// seconds.
+fn parse_time(field: &str, value: &Value) -> Result<Option<NaiveDateTime>, String> {
+    match value {
+        Value::Null => Ok(None),
+        Value::String(string) if string.trim().is_empty() => Ok(None),
+        Value::String(string) => NaiveDateTime::parse_from_str(string.trim(), "%Y-%m-%dT%H:%M")
+            .or_else(|_| NaiveDateTime::parse_from_str(string.trim(), "%Y-%m-%dT%H:%M:%S"))
+            .map(Some)
+            .map_err(|_| format!("The {} time must look like 2019-09-14T15:30", field)),
+        _ => Err(format!("The {} time must be a string", field))
+    }
+}
+
+fn parse_string(field: &str, value: &Value) -> Result<String, String> {
+    match value {
+        Value::Null => Ok(String::new()),
+        Value::String(string) => Ok(string.trim().to_uppercase()),
+        _ => Err(format!("The {} must be a string", field))
+    }
+}
+
+/// "ua 1234" and "UA1234" are the same flight.
+fn parse_flight(field: &str, value: &Value) -> Result<String, String> {
+    let flight : String = parse_string(field, value)?
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+
+    let valid = flight.is_empty() || (
+        flight.len() >= 3 &&
+        flight.len() <= 7 &&
+        flight.chars().all(|c| c.is_ascii_alphanumeric()) &&
+        flight.chars().last().map_or(false, |c| c.is_ascii_digit())
+    );
+
+    if valid {
+        Ok(flight)
+    } else {
+        Err(format!("{} is not a flight number", flight))
+    }
+}
+
+impl Travel {
+    /// Parses and normalizes the `travel` object from a PATCH payload. The
+    /// whole object is replaced, so fields left out are cleared.
+    pub fn parse(value: &Value) -> Result<Travel, String> {
+        let map = value.as_object()
+            .ok_or_else(|| String::from("travel must be an object"))?;
+        let field = |name: &str| map.get(name).unwrap_or(&Value::Null);
+
+        let travel = Travel {
+            arrival: parse_time("arrival", field("arrival"))?,
+            departure: parse_time("departure", field("departure"))?,
+            airport: parse_string("airport", field("airport"))?,
+            arrival_flight: parse_flight("arrival_flight", field("arrival_flight"))?,
+            departure_flight: parse_flight("departure_flight", field("departure_flight"))?,
+            needs_shuttle: match field("needs_shuttle") {
+                Value::Null => false,
+                Value::Bool(needs_shuttle) => *needs_shuttle,
+                _ => return Err(String::from("needs_shuttle must be true or false"))
+            }
+        };
+
+        if !travel.airport.is_empty() && (travel.airport.len() != 3 || !travel.airport.chars().all(|c| c.is_ascii_alphabetic())) {
+            return Err(format!("{} is not a three letter airport code", travel.airport));
+        }
+        if let (Some(arrival), Some(departure)) = (travel.arrival, travel.departure) {
+            if departure <= arrival {
+                return Err(String::from("The departure must be after the arrival"));
+            }
+        }
+
+        Ok(travel)
+    }
+}
+
+/// A guest on an arrivals or departures list.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Traveler {
+    pub rsvp_id: String,
+    pub household_id: String,
+    pub name: String,
+    pub time: NaiveDateTime,
+    pub flight: String,
+    pub needs_shuttle: bool
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AirportGroup {
+    pub airport: String,
+    pub guests: Vec<Traveler>
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TravelDay {
+    pub date: NaiveDate,
+    pub airports: Vec<AirportGroup>
+}
+
+/// Attending guests who haven't said when they arrive.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MissingTravel {
+    pub rsvp_id: String,
+    pub household_id: String,
+    pub name: String
+}
+
+/// When attending guests come and go, grouped by day and then airport, each
+/// group in time order.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ArrivalsReport {
+    pub arrivals: Vec<TravelDay>,
+    pub departures: Vec<TravelDay>,
+    pub needs_shuttle: usize,
+    pub missing: Vec<MissingTravel>
+}
+
+fn group<F>(rsvps: &[&RSVP], when: F) -> Vec<TravelDay>
+    where F: Fn(&Travel) -> Option<(NaiveDateTime, &str)>
+{
+    let mut days : BTreeMap<NaiveDate, BTreeMap<String, Vec<Traveler>>> = BTreeMap::new();
+
+    for rsvp in rsvps {
+        if let Some((time, flight)) = when(&rsvp.travel) {
+            let airport = match rsvp.travel.airport.as_str() {
+                "" => String::from("Unknown"),
+                airport => airport.to_string()
+            };
+            days.entry(time.date())
+                .or_insert_with(BTreeMap::new)
+                .entry(airport)
+                .or_insert_with(Vec::new)
+                .push(Traveler {
+                    rsvp_id: rsvp.id.clone(),
+                    household_id: rsvp.household_id.clone(),
+                    name: rsvp.name.clone(),
+                    time,
+                    flight: flight.to_string(),
+                    needs_shuttle: rsvp.travel.needs_shuttle
+                });
+        }
+    }
+
+    days.into_iter()
+        .map(|(date, airports)| TravelDay {
+            date,
+            airports: airports.into_iter()
+                .map(|(airport, mut guests)| {
+                    guests.sort_by(|a, b| a.time.cmp(&b.time).then_with(|| a.name.cmp(&b.name)));
+                    AirportGroup { airport, guests }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+impl ArrivalsReport {
+    pub fn new(rsvps: &[RSVP]) -> ArrivalsReport {
+        let attending : Vec<&RSVP> = rsvps.iter()
+            .filter(|rsvp| rsvp.is_attending_any())
+            .collect();
+
+        let mut missing : Vec<MissingTravel> = attending.iter()
+            .filter(|rsvp| rsvp.travel.arrival.is_none())
+            .map(|rsvp| MissingTravel {
+                rsvp_id: rsvp.id.clone(),
+                household_id: rsvp.household_id.clone(),
+                name: rsvp.name.clone()
+            })
+            .collect();
+        missing.sort_by(|a, b| a.name.cmp(&b.name));
+
+        ArrivalsReport {
+            arrivals: group(&attending, |travel| travel.arrival.map(|time| (time, travel.arrival_flight.as_str()))),
+            departures: group(&attending, |travel| travel.departure.map(|time| (time, travel.departure_flight.as_str()))),
+            needs_shuttle: attending.iter().filter(|rsvp| rsvp.travel.needs_shuttle).count(),
+            missing
+        }
+    }
+
+    pub fn get() -> Result<ArrivalsReport, ScanError> {
+        Ok(ArrivalsReport::new(&RSVP::all()?))
+    }
+}
+
+
+#[cfg(test)]
+mod travel_tests {
+
+    use super::*;
+    use serde_json::{json};
+    use uuid::Uuid;
+    use crate::models::{Person};
+
+    fn guest(name: &str, attending: bool, travel: Travel) -> RSVP {
+        let mut rsvp = RSVP::new(
+            Person {
+                name: name.to_string(),
+                email_address: "email@example.com".to_string(),
+                ..Person::default()
+            },
+            Uuid::new_v4().to_string()
+        );
+        rsvp.attending = attending;
+        rsvp.travel = travel;
+        rsvp
+    }
+
+    #[test]
+    fn test_parse_travel_normalizes() {
+        let travel = Travel::parse(&json!({
+            "arrival": "2019-09-13T15:30",
+            "departure": "2019-09-15T09:05:00",
+            "airport": " den ",
+            "arrival_flight": "f9 1234",
+            "needs_shuttle": true
+        })).unwrap();
+
+        assert_eq!(travel.arrival, Some(NaiveDate::from_ymd(2019, 9, 13).and_hms(15, 30, 0)));
+        assert_eq!(travel.airport, "DEN");
+        assert_eq!(travel.arrival_flight, "F91234");
+        assert_eq!(travel.departure_flight, "");
+        assert!(travel.needs_shuttle);
+    }
+
+    #[test]
+    fn test_parse_travel_rejects() {
+        assert!(Travel::parse(&json!({"airport": "Denver"})).is_err());
+        assert!(Travel::parse(&json!({"arrival_flight": "F9-1234"})).is_err());
+        assert!(Travel::parse(&json!({"arrival": "Friday"})).is_err());
+        assert!(Travel::parse(&json!({"arrival": "2019-09-15T10:00", "departure": "2019-09-13T10:00"})).is_err());
+        assert!(Travel::parse(&json!("DEN")).is_err());
+    }
+
+    #[test]
+    fn test_arrivals_report_groups_by_day_and_airport() {
+        let arriving = |day, hour, airport: &str| Travel {
+            arrival: Some(NaiveDate::from_ymd(2019, 9, day).and_hms(hour, 0, 0)),
+            airport: airport.to_string(),
+            ..Travel::default()
+        };
+        let rsvps = vec!(
+            guest("Cynthia Young", true, arriving(13, 18, "DEN")),
+            guest("Blaine Price", true, Travel { needs_shuttle: true, ..arriving(13, 9, "DEN") }),
+            guest("Ling Ling", true, arriving(12, 9, "COS")),
+            guest("Declined Guest", false, arriving(12, 9, "DEN")),
+            guest("No Plans Yet", true, Travel::default())
+        );
+
+        let report = ArrivalsReport::new(&rsvps);
+        assert_eq!(report.arrivals.len(), 2);
+        assert_eq!(report.arrivals[0].date, NaiveDate::from_ymd(2019, 9, 12));
+        assert_eq!(report.arrivals[0].airports[0].airport, "COS");
+        let names : Vec<&str> = report.arrivals[1].airports[0].guests.iter().map(|guest| guest.name.as_str()).collect();
+        assert_eq!(names, vec!("Blaine Price", "Cynthia Young"));
+        assert!(report.departures.is_empty());
+        assert_eq!(report.needs_shuttle, 1);
+        assert_eq!(report.missing.len(), 1);
+        assert_eq!(report.missing[0].name, "No Plans Yet");
+    }
+}
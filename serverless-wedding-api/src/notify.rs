@@ -0,0 +1,619 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::rc::Rc;
+use serde_derive::{Serialize, Deserialize};
+use serde_json::json;
+use log::{info, error};
+use rusoto_core::Region;
+use rusoto_sns::{Sns, SnsClient, PublishInput};
+use lettre::{SmtpClient, Transport, ClientSecurity, ClientTlsParameters};
+use lettre::smtp::authentication::Credentials;
+use lettre_email::EmailBuilder;
+use native_tls::{TlsConnector, Protocol};
+
+use crate::models::{RenderedEmail, TemplateKind};
+
+/// What a notification is about. Each kind can be routed to its own
+/// channels, so guest emails and texts to the couple don't have to share.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// A guest changed their RSVP
+    RsvpChanged,
+    /// Inviting a household to respond
+    Invitation,
+    /// Nudging a household that hasn't responded
+    Reminder,
+    /// Letting a guest know their response was received
+    Confirmation
+}
+
+impl NotificationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationKind::RsvpChanged => "rsvp_changed",
+            NotificationKind::Invitation => "invitation",
+            NotificationKind::Reminder => "reminder",
+            NotificationKind::Confirmation => "confirmation"
+        }
+    }
+
+    pub fn parse(kind: &str) -> Result<NotificationKind, NotifyError> {
+        match kind.trim() {
+            "rsvp_changed" => Ok(NotificationKind::RsvpChanged),
+            "invitation" => Ok(NotificationKind::Invitation),
+            "reminder" => Ok(NotificationKind::Reminder),
+            "confirmation" => Ok(NotificationKind::Confirmation),
+            other => Err(NotifyError::Config(format!("Unknown notification type: {}", other)))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Notification {
+    pub kind: NotificationKind,
+    pub subject: String,
+    /// Plain text, which every channel can send
+    pub body: String,
+    /// Sent alongside the text by channels that can show it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub html: Option<String>,
+    /// Email addresses to send to. Left empty, channels send to whoever
+    /// they're set up to tell, normally the couple.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub recipients: Vec<String>
+}
+
+impl Notification {
+    pub fn new(kind: NotificationKind, subject: &str, body: &str) -> Notification {
+        Notification {
+            kind,
+            subject: subject.to_string(),
+            body: body.to_string(),
+            html: None,
+            recipients: vec!()
+        }
+    }
+
+    pub fn to(self, recipients: Vec<String>) -> Notification {
+        Notification { recipients, ..self }
+    }
+
+    pub fn with_html(self, html: String) -> Notification {
+        Notification { html: Some(html), ..self }
+    }
+
+    /// A guest email, to the household's addresses.
+    pub fn email(email: &RenderedEmail) -> Notification {
+        let kind = match email.kind {
+            TemplateKind::Invitation => NotificationKind::Invitation,
+            TemplateKind::Reminder => NotificationKind::Reminder,
+            TemplateKind::Confirmation => NotificationKind::Confirmation
+        };
+        let notification = Notification::new(kind, &email.subject, &email.text).to(email.to.clone());
+
+        if email.html.trim().is_empty() {
+            notification
+        } else {
+            notification.with_html(email.html.clone())
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum NotifyError {
+    /// A channel or route is set up wrong
+    Config(String),
+    /// The channel took the notification but couldn't deliver it
+    Send(&'static str, String)
+}
+
+impl fmt::Display for NotifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NotifyError::Config(message) => write!(f, "{}", message),
+            NotifyError::Send(channel, message) => write!(f, "Could not send through {}: {}", channel, message)
+        }
+    }
+}
+
+/// A way of getting a notification to someone.
+pub trait Notifier {
+    fn name(&self) -> &'static str;
+
+    fn send(&self, notification: &Notification) -> Result<(), NotifyError>;
+}
+
+/// Texts and emails the couple through an SNS topic.
+pub struct SnsNotifier {
+    client: SnsClient,
+    topic_arn: String
+}
+
+impl SnsNotifier {
+    pub fn new(topic_arn: &str) -> SnsNotifier {
+        SnsNotifier {
+            client: SnsClient::new(Region::UsEast1),
+            topic_arn: topic_arn.to_string()
+        }
+    }
+}
+
+impl Notifier for SnsNotifier {
+    fn name(&self) -> &'static str {
+        "sns"
+    }
+
+    fn send(&self, notification: &Notification) -> Result<(), NotifyError> {
+        self.client.publish(PublishInput {
+            topic_arn: Some(self.topic_arn.clone()),
+            subject: Some(notification.subject.clone()),
+            message: notification.body.clone(),
+            ..PublishInput::default()
+        }).sync().map_err(|error| NotifyError::Send(self.name(), error.to_string()))?;
+        Ok(())
+    }
+}
+
+/// Sends email through an SMTP server on `port`. With `tls` it requires
+/// STARTTLS; without, it speaks plain SMTP, which is only meant for a relay
+/// on the same network.
+pub struct SmtpNotifier {
+    pub host: String,
+    pub port: u16,
+    pub tls: bool,
+    pub credentials: Option<(String, String)>,
+    pub from: String,
+    /// Who gets notifications that don't name their recipients
+    pub to: Vec<String>
+}
+
+impl Notifier for SmtpNotifier {
+    fn name(&self) -> &'static str {
+        "smtp"
+    }
+
+    fn send(&self, notification: &Notification) -> Result<(), NotifyError> {
+        let recipients = if notification.recipients.is_empty() { &self.to } else { &notification.recipients };
+        if recipients.is_empty() {
+            return Err(NotifyError::Config(String::from("There's no one to email")));
+        }
+
+        let mut email = EmailBuilder::new()
+            .from(self.from.as_str())
+            .subject(notification.subject.as_str());
+        for recipient in recipients {
+            email = email.to(recipient.as_str());
+        }
+        let email = match notification.html {
+            Some(ref html) => email.alternative(html.as_str(), notification.body.as_str()),
+            None => email.text(notification.body.as_str())
+        }.build().map_err(|error| NotifyError::Send(self.name(), error.to_string()))?;
+
+        let security = if self.tls {
+            let connector = TlsConnector::builder()
+                .min_protocol_version(Some(Protocol::Tlsv12))
+                .build()
+                .map_err(|error| NotifyError::Send(self.name(), error.to_string()))?;
+            ClientSecurity::Required(ClientTlsParameters::new(self.host.clone(), connector))
+        } else {
+            ClientSecurity::None
+        };
+        let client = SmtpClient::new((self.host.as_str(), self.port), security)
+            .map_err(|error| NotifyError::Send(self.name(), error.to_string()))?;
+        let client = match self.credentials {
+            Some((ref username, ref password)) => client.credentials(Credentials::new(username.clone(), password.clone())),
+            None => client
+        };
+
+        client.transport()
+            .send(email.into())
+            .map_err(|error| NotifyError::Send(self.name(), error.to_string()))?;
+        Ok(())
+    }
+}
+
+/// Posts to an incoming webhook as `{"text": ...}`, which Slack and most
+/// chat apps accept.
+pub struct WebhookNotifier {
+    client: reqwest::Client,
+    url: String
+}
+
+impl WebhookNotifier {
+    pub fn new(url: &str) -> WebhookNotifier {
+        WebhookNotifier {
+            client: reqwest::Client::new(),
+            url: url.to_string()
+        }
+    }
+}
+
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    fn send(&self, notification: &Notification) -> Result<(), NotifyError> {
+        let payload = json!({"text": format!("*{}*\n{}", notification.subject, notification.body)});
+
+        self.client.post(&self.url)
+            .json(&payload)
+            .send()
+            .and_then(|response| response.error_for_status())
+            .map_err(|error| NotifyError::Send(self.name(), error.to_string()))?;
+        Ok(())
+    }
+}
+
+/// Only writes notifications to the log, for local development or a
+/// channel that's switched off.
+pub struct LogNotifier;
+
+impl Notifier for LogNotifier {
+    fn name(&self) -> &'static str {
+        "log"
+    }
+
+    fn send(&self, notification: &Notification) -> Result<(), NotifyError> {
+        info!("{} notification to {:?}: {}\n{}", notification.kind.as_str(), notification.recipients, notification.subject, notification.body);
+        Ok(())
+    }
+}
+
+/// Keeps what it's sent in memory, for tests and dry runs. Clones share
+/// the same list, so one can be handed to `Notifiers` and another kept to
+/// look at.
+#[derive(Clone, Default)]
+pub struct RecordingNotifier {
+    sent: Rc<RefCell<Vec<Notification>>>
+}
+
+impl RecordingNotifier {
+    pub fn new() -> RecordingNotifier {
+        RecordingNotifier::default()
+    }
+
+    pub fn sent(&self) -> Vec<Notification> {
+        self.sent.borrow().clone()
+    }
+}
+
+impl Notifier for RecordingNotifier {
+    fn name(&self) -> &'static str {
+        "recording"
+    }
+
+    fn send(&self, notification: &Notification) -> Result<(), NotifyError> {
+        self.sent.borrow_mut().push(notification.clone());
+        Ok(())
+    }
+}
+
+/// Channels by name, and which of them each kind of notification goes
+/// through. Kinds without a route of their own use the default route.
+#[derive(Default)]
+pub struct Notifiers {
+    channels: HashMap<String, Box<Notifier>>,
+    routes: HashMap<NotificationKind, Vec<String>>,
+    default_route: Vec<String>
+}
+
+impl Notifiers {
+    pub fn new() -> Notifiers {
+        Notifiers::default()
+    }
+
+    pub fn channel(mut self, name: &str, notifier: Box<Notifier>) -> Notifiers {
+        self.channels.insert(name.to_string(), notifier);
+        self
+    }
+
+    pub fn route(mut self, kind: NotificationKind, channels: &[&str]) -> Notifiers {
+        self.routes.insert(kind, channels.iter().map(|channel| channel.to_string()).collect());
+        self
+    }
+
+    pub fn default_route(mut self, channels: &[&str]) -> Notifiers {
+        self.default_route = channels.iter().map(|channel| channel.to_string()).collect();
+        self
+    }
+
+    /// The channels a kind of notification goes through.
+    pub fn channels_for(&self, kind: NotificationKind) -> &[String] {
+        self.routes.get(&kind).unwrap_or(&self.default_route)
+    }
+
+    /// Sends through every channel routed for the notification's kind,
+    /// carrying on past any that fail. Returns how many it went through,
+    /// or the first failure.
+    pub fn notify(&self, notification: &Notification) -> Result<usize, NotifyError> {
+        let mut sent = 0;
+        let mut failure = None;
+
+        for name in self.channels_for(notification.kind) {
+            let result = match self.channels.get(name) {
+                Some(notifier) => notifier.send(notification),
+                None => Err(NotifyError::Config(format!("No {} channel is set up", name)))
+            };
+            match result {
+                Ok(()) => sent += 1,
+                Err(err) => {
+                    error!("Could not send {} notification {:?} through {}: {}", notification.kind.as_str(), notification.subject, name, err);
+                    failure = failure.or(Some(err));
+                }
+            }
+        }
+
+        match failure {
+            Some(err) => Err(err),
+            None => Ok(sent)
+        }
+    }
+}
+
+/// Parses routes like `rsvp_changed=sns,webhook;reminder=smtp;default=log`.
+pub fn parse_routes(routes: &str) -> Result<(HashMap<NotificationKind, Vec<String>>, Vec<String>), NotifyError> {
+    let mut parsed = HashMap::new();
+    let mut default_route = vec!();
+
+    for route in routes.split(';').map(str::trim).filter(|route| !route.is_empty()) {
+        let mut parts = route.splitn(2, '=');
+        let (kind, channels) = match (parts.next(), parts.next()) {
+            (Some(kind), Some(channels)) => (kind.trim(), channels),
+            _ => return Err(NotifyError::Config(format!("Routes look like type=channel,channel, not {}", route)))
+        };
+        let channels : Vec<String> = channels.split(',')
+            .map(|channel| channel.trim().to_string())
+            .filter(|channel| !channel.is_empty())
+            .collect();
+
+        if kind == "default" {
+            default_route = channels;
+        } else {
+            parsed.insert(NotificationKind::parse(kind)?, channels);
+        }
+    }
+
+    Ok((parsed, default_route))
+}
+
+fn required(name: &str) -> Result<String, NotifyError> {
+    env::var(name)
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+        .ok_or_else(|| NotifyError::Config(format!("{} is not set", name)))
+}
+
+fn smtp_from_env() -> Result<SmtpNotifier, NotifyError> {
+    let port = match env::var("SMTP_PORT") {
+        Ok(ref port) if !port.is_empty() => port.parse()
+            .map_err(|_| NotifyError::Config(format!("SMTP_PORT must be a port number, not {}", port)))?,
+        _ => 587
+    };
+    let credentials = match (env::var("SMTP_USERNAME"), env::var("SMTP_PASSWORD")) {
+        (Ok(username), Ok(password)) if !username.is_empty() => Some((username, password)),
+        _ => None
+    };
+
+    Ok(SmtpNotifier {
+        host: required("SMTP_HOST")?,
+        port,
+        tls: env::var("SMTP_TLS").map(|tls| tls != "false").unwrap_or(true),
+        credentials,
+        from: required("NOTIFY_EMAIL_FROM")?,
+        to: env::var("NOTIFY_EMAIL_TO")
+            .unwrap_or_default()
+            .split(',')
+            .map(|address| address.trim().to_string())
+            .filter(|address| !address.is_empty())
+            .collect()
+    })
+}
+
+/// Sets up the channels `NOTIFY_ROUTES` uses, each from its own settings.
+/// Without any routes everything is only logged.
+pub fn from_env() -> Result<Notifiers, NotifyError> {
+    let routes = env::var("NOTIFY_ROUTES")
+        .ok()
+        .filter(|routes| !routes.trim().is_empty())
+        .unwrap_or_else(|| String::from("default=log"));
+    let (routes, default_route) = parse_routes(&routes)?;
+
+    let mut notifiers = Notifiers {
+        routes,
+        default_route,
+        ..Notifiers::default()
+    };
+
+    let mut names : Vec<String> = notifiers.routes.values()
+        .chain(Some(&notifiers.default_route))
+        .flat_map(|channels| channels.iter().cloned())
+        .collect();
+    names.sort();
+    names.dedup();
+
+    for name in names {
+        let notifier : Box<Notifier> = match name.as_str() {
+            "sns" => Box::new(SnsNotifier::new(&required("NOTIFY_SNS_TOPIC_ARN")?)),
+            "smtp" => Box::new(smtp_from_env()?),
+            "webhook" => Box::new(WebhookNotifier::new(&required("NOTIFY_WEBHOOK_URL")?)),
+            "log" => Box::new(LogNotifier),
+            other => return Err(NotifyError::Config(format!("Unknown notification channel: {}", other)))
+        };
+        notifiers.channels.insert(name, notifier);
+    }
+
+    Ok(notifiers)
+}
+
+
+#[cfg(test)]
+mod notify_tests {
+
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::thread;
+
+    struct FailingNotifier;
+
+    impl Notifier for FailingNotifier {
+        fn name(&self) -> &'static str {
+            "failing"
+        }
+
+        fn send(&self, _notification: &Notification) -> Result<(), NotifyError> {
+            Err(NotifyError::Send(self.name(), String::from("down")))
+        }
+    }
+
+    #[test]
+    fn test_parse_routes() {
+        let (routes, default_route) = parse_routes(" rsvp_changed = sns, webhook ; reminder=smtp;default=log;").unwrap();
+        assert_eq!(routes[&NotificationKind::RsvpChanged], vec!("sns", "webhook"));
+        assert_eq!(routes[&NotificationKind::Reminder], vec!("smtp"));
+        assert_eq!(default_route, vec!("log"));
+
+        assert!(parse_routes("gossip=sns").is_err());
+        assert!(parse_routes("reminder").is_err());
+    }
+
+    #[test]
+    fn test_notifiers_route() {
+        let couple = RecordingNotifier::new();
+        let guests = RecordingNotifier::new();
+        let notifiers = Notifiers::new()
+            .channel("couple", Box::new(couple.clone()))
+            .channel("guests", Box::new(guests.clone()))
+            .route(NotificationKind::Reminder, &["guests"])
+            .default_route(&["couple"]);
+
+        let changed = Notification::new(NotificationKind::RsvpChanged, "RSVP Notification", "Jane Doe updated their RSVP");
+        let reminder = Notification::new(NotificationKind::Reminder, "Are you coming?", "Please RSVP")
+            .to(vec!("jane@example.com".to_string()));
+        assert_eq!(notifiers.notify(&changed).unwrap(), 1);
+        assert_eq!(notifiers.notify(&reminder).unwrap(), 1);
+        assert_eq!(couple.sent(), vec!(changed));
+        assert_eq!(guests.sent(), vec!(reminder));
+    }
+
+    #[test]
+    fn test_notifiers_carry_on_past_failures() {
+        let recording = RecordingNotifier::new();
+        let notifiers = Notifiers::new()
+            .channel("failing", Box::new(FailingNotifier))
+            .channel("recording", Box::new(recording.clone()))
+            .default_route(&["failing", "missing", "recording"]);
+
+        let notification = Notification::new(NotificationKind::Confirmation, "Thanks!", "See you there");
+        match notifiers.notify(&notification) {
+            Err(NotifyError::Send("failing", _)) => {},
+            other => panic!("Expected the first failure, got {:?}", other)
+        }
+        assert_eq!(recording.sent().len(), 1);
+    }
+
+    #[test]
+    fn test_webhook_notifier() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hooks/wedding", listener.local_addr().unwrap());
+        let (received, receive) = mpsc::channel();
+
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.to_lowercase().starts_with("content-length:") {
+                    length = line[15..].trim().parse().unwrap();
+                }
+                if line == "\r\n" {
+                    break;
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            (&stream).write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok").unwrap();
+            received.send(body).unwrap();
+        });
+
+        let notification = Notification::new(NotificationKind::RsvpChanged, "RSVP Notification", "Jane Doe updated their RSVP");
+        WebhookNotifier::new(&url).send(&notification).unwrap();
+
+        let body : serde_json::Value = serde_json::from_slice(&receive.recv().unwrap()).unwrap();
+        assert_eq!(body, json!({"text": "*RSVP Notification*\nJane Doe updated their RSVP"}));
+    }
+
+    #[test]
+    fn test_smtp_notifier() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (received, receive) = mpsc::channel();
+
+        // Just enough of an SMTP server to take one message
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut writer = stream;
+            let mut commands = vec!();
+            let mut data = String::new();
+            writer.write_all(b"220 localhost ESMTP\r\n").unwrap();
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap() == 0 {
+                    break;
+                }
+                let command = line.trim_end().to_string();
+                let reply : &[u8] = if command.starts_with("EHLO") {
+                    b"250 localhost\r\n"
+                } else if command == "DATA" {
+                    writer.write_all(b"354 Go ahead\r\n").unwrap();
+                    loop {
+                        let mut line = String::new();
+                        reader.read_line(&mut line).unwrap();
+                        if line == ".\r\n" {
+                            break;
+                        }
+                        data.push_str(&line);
+                    }
+                    b"250 Queued\r\n"
+                } else if command == "QUIT" {
+                    writer.write_all(b"221 Bye\r\n").unwrap();
+                    commands.push(command);
+                    break;
+                } else {
+                    b"250 OK\r\n"
+                };
+                commands.push(command);
+                writer.write_all(reply).unwrap();
+            }
+            received.send((commands, data)).unwrap();
+        });
+
+        let smtp = SmtpNotifier {
+            host: String::from("127.0.0.1"),
+            port,
+            tls: false,
+            credentials: None,
+            from: String::from("couple@slswedding.com"),
+            to: vec!(String::from("couple@slswedding.com"))
+        };
+        let notification = Notification::new(NotificationKind::Reminder, "Are you coming?", "Please RSVP by August 1")
+            .to(vec!(String::from("jane@example.com")));
+        smtp.send(&notification).unwrap();
+        drop(smtp);
+
+        let (commands, data) = receive.recv().unwrap();
+        assert!(commands.iter().any(|command| command == "MAIL FROM:<couple@slswedding.com>"));
+        assert!(commands.iter().any(|command| command == "RCPT TO:<jane@example.com>"));
+        assert!(!commands.iter().any(|command| command == "RCPT TO:<couple@slswedding.com>"));
+        assert!(data.contains("Subject: Are you coming?"));
+        assert!(data.contains("Please RSVP by August 1"));
+    }
+}
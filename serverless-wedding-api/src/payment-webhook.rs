@@ -0,0 +1,130 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::{json};
+use std::ops::Deref;
+use chrono::Utc;
+use log::{info, warn, error};
+use uuid::Uuid;
+
+mod models;
+mod payments;
+use crate::models::{RegistryItem, Contribution, RegistryError};
+use crate::payments::{WebhookEvent, CompletedPayment, Refund, PaymentError, SIGNATURE_HEADER};
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// Records a finished payment as a contribution.
+fn completed(payment: CompletedPayment) -> http::Response<String> {
+    let item = match Uuid::parse_str(&payment.item_id).ok().and_then(|item_id| RegistryItem::get(item_id).ok()) {
+        Some(item) => item,
+        None => {
+            error!("Payment {} is for unknown registry item {}", payment.payment_id, payment.item_id);
+            return respond(404, json!({"message": RegistryError::ItemNotFound.to_string()}).to_string())
+        }
+    };
+
+    let contribution = match Contribution::new(&item, payment.household_id.clone(), payment.amount.clone(), payment.message.clone()) {
+        Ok(contribution) => contribution.with_payment(&payment.payment_id),
+        Err(message) => {
+            error!("Could not record payment {:?}: {}", payment, message);
+            return respond(400, json!({"message": message}).to_string())
+        }
+    };
+
+    match contribution.record() {
+        Ok(_progress) => respond(200, json!({"recorded": true}).to_string()),
+        Err(RegistryError::AlreadyRecorded) => {
+            info!("Payment {} was already recorded", payment.payment_id);
+            respond(200, json!({"recorded": false}).to_string())
+        },
+        Err(err) => {
+            error!("Could not record contribution {:?}: {:?}", contribution, err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    }
+}
+
+/// Counts a refund made from the provider's side against its contribution.
+fn refunded(refund: Refund) -> http::Response<String> {
+    let result = Contribution::get(&refund.item_id, &refund.payment_id)
+        .and_then(|contribution| contribution.record_refund(&refund.id, &refund.amount));
+
+    match result {
+        Ok(_progress) => respond(200, json!({"recorded": true}).to_string()),
+        Err(RegistryError::AlreadyRecorded) => respond(200, json!({"recorded": false}).to_string()),
+        Err(RegistryError::ContributionNotFound) => {
+            error!("Refund {} is for unknown payment {}", refund.id, refund.payment_id);
+            respond(404, json!({"message": RegistryError::ContributionNotFound.to_string()}).to_string())
+        },
+        Err(err) => {
+            error!("Could not record refund {:?}: {:?}", refund, err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    }
+}
+
+/// Where the payment provider reports finished payments and refunds. The
+/// signature is checked before anything is recorded, and providers retry
+/// webhooks, so recording the same event twice is a no-op.
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let signature = request.headers()
+        .get(SIGNATURE_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+
+    let provider = match payments::from_env() {
+        Ok(provider) => provider,
+        Err(err) => {
+            error!("Could not set up the payment provider: {}", err);
+            return Ok(respond(500, json!({"message": "Something went wrong!"}).to_string()))
+        }
+    };
+
+    Ok(match provider.handle_webhook(request.body().deref(), &signature, Utc::now()) {
+        Ok(WebhookEvent::CheckoutCompleted(payment)) => completed(payment),
+        Ok(WebhookEvent::Refunded(refund)) => refunded(refund),
+        Ok(WebhookEvent::Ignored(kind)) => {
+            info!("Ignoring {} webhook {}", provider.name(), kind);
+            respond(200, json!({"recorded": false}).to_string())
+        },
+        Err(PaymentError::InvalidSignature) => {
+            warn!("Refused a {} webhook with a bad signature", provider.name());
+            respond(401, json!({"message": PaymentError::InvalidSignature.to_string()}).to_string())
+        },
+        Err(err) => respond(400, json!({"message": err.to_string()}).to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lambda_http::Body;
+
+    #[test]
+    #[ignore]
+    fn payment_webhook_handler_handles() {
+
+        let mut request = Request::new(Body::from(json!({"type": "checkout.expired", "data": {}}).to_string()));
+        *request.uri_mut() = "https://api.slswedding.com/payments/webhook".parse().unwrap();
+
+        handler(request, Context::default()).expect("Expected an OK response");
+    }
+}
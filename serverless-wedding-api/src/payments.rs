@@ -0,0 +1,379 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use serde_derive::{Serialize, Deserialize};
+use serde_json::{json, Value};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::models::Money;
+
+/// The header providers sign webhooks in, as `t=<unix time>,v1=<hex HMAC>`.
+pub const SIGNATURE_HEADER : &str = "Payment-Signature";
+
+/// Webhooks signed longer ago than this are refused, so a captured request
+/// can't be replayed later.
+const TOLERANCE_SECONDS : i64 = 5 * 60;
+
+/// What a guest wants to give, sent to the provider to start paying.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckoutRequest {
+    pub item_id: String,
+    pub item_title: String,
+    pub household_id: String,
+    pub amount: Money,
+    #[serde(default)]
+    pub message: String,
+    /// Where the provider sends the guest once they're done
+    pub return_url: String
+}
+
+/// A started payment. Guests are sent to `url` to finish it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Checkout {
+    pub id: String,
+    pub url: String
+}
+
+/// A finished payment, echoing what the checkout was for.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompletedPayment {
+    pub payment_id: String,
+    pub checkout_id: String,
+    pub item_id: String,
+    pub household_id: String,
+    pub amount: Money,
+    #[serde(default)]
+    pub message: String
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RefundRequest {
+    pub item_id: String,
+    pub payment_id: String,
+    pub amount: Money
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Refund {
+    pub id: String,
+    pub item_id: String,
+    pub payment_id: String,
+    pub amount: Money
+}
+
+/// A webhook, once its signature has been checked.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WebhookEvent {
+    CheckoutCompleted(CompletedPayment),
+    Refunded(Refund),
+    /// Something we don't act on, by its type
+    Ignored(String)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PaymentError {
+    InvalidSignature,
+    Malformed(String),
+    Invalid(String),
+    NotFound(String),
+    Provider(String),
+    Config(String)
+}
+
+impl fmt::Display for PaymentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PaymentError::InvalidSignature => write!(f, "The webhook signature is missing, invalid or too old"),
+            PaymentError::Malformed(message) => write!(f, "Malformed webhook: {}", message),
+            PaymentError::Invalid(message) => write!(f, "{}", message),
+            PaymentError::NotFound(payment_id) => write!(f, "No payment was found with id {}", payment_id),
+            PaymentError::Provider(message) => write!(f, "The payment provider failed: {}", message),
+            PaymentError::Config(message) => write!(f, "{}", message)
+        }
+    }
+}
+
+/// Takes payments for registry contributions. Each provider checks its
+/// own webhooks, so handlers only ever see verified events.
+pub trait PaymentProvider {
+    fn name(&self) -> &'static str;
+
+    fn create_checkout(&self, request: &CheckoutRequest) -> Result<Checkout, PaymentError>;
+
+    /// Verifies `signature` against the raw request body and parses it.
+    fn handle_webhook(&self, body: &[u8], signature: &str, now: DateTime<Utc>) -> Result<WebhookEvent, PaymentError>;
+
+    fn refund(&self, request: &RefundRequest) -> Result<Refund, PaymentError>;
+}
+
+/// The provider named by `PAYMENT_PROVIDER`, checking webhooks with
+/// `PAYMENT_WEBHOOK_SECRET`. There's no default: the fake provider forgets
+/// everything between invocations, so it has to be asked for by name.
+pub fn from_env() -> Result<Box<PaymentProvider>, PaymentError> {
+    let required = |name: &str| env::var(name)
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+        .ok_or_else(|| PaymentError::Config(format!("{} is not set", name)));
+
+    let provider = required("PAYMENT_PROVIDER")?;
+    let secret = required("PAYMENT_WEBHOOK_SECRET")?;
+
+    match provider.trim() {
+        "fake" => Ok(Box::new(FakeProvider::new(secret.as_bytes()))),
+        other => Err(PaymentError::Config(format!("Unknown payment provider: {}", other)))
+    }
+}
+
+fn mac(secret: &[u8], timestamp: i64, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_varkey(secret).expect("HMAC takes keys of any length");
+    mac.input(timestamp.to_string().as_bytes());
+    mac.input(b".");
+    mac.input(body);
+    mac
+}
+
+/// The signature header for `body` sent at `timestamp`.
+pub fn sign(secret: &[u8], timestamp: i64, body: &[u8]) -> String {
+    format!("t={},v1={}", timestamp, hex::encode(mac(secret, timestamp, body).result().code()))
+}
+
+/// Checks a signature header made by `sign`, in constant time, and that it
+/// isn't stale.
+pub fn verify_signature(secret: &[u8], header: &str, body: &[u8], now: DateTime<Utc>) -> Result<(), PaymentError> {
+    let mut timestamp = None;
+    let mut signatures = vec!();
+    for part in header.split(',') {
+        let mut pieces = part.trim().splitn(2, '=');
+        match (pieces.next(), pieces.next()) {
+            (Some("t"), Some(value)) => timestamp = value.parse::<i64>().ok(),
+            (Some("v1"), Some(value)) => signatures.extend(hex::decode(value).ok()),
+            _ => {}
+        }
+    }
+
+    let timestamp = timestamp.ok_or(PaymentError::InvalidSignature)?;
+    if (now.timestamp() - timestamp).abs() > TOLERANCE_SECONDS {
+        return Err(PaymentError::InvalidSignature);
+    }
+
+    // Providers send more than one signature while rotating secrets
+    if signatures.iter().any(|signature| mac(secret, timestamp, body).verify(signature).is_ok()) {
+        Ok(())
+    } else {
+        Err(PaymentError::InvalidSignature)
+    }
+}
+
+/// A provider that never leaves the process, for tests and local
+/// development. Checkouts and payments are kept in memory, and `complete`
+/// stands in for the guest paying, returning the signed webhook the
+/// provider would send.
+pub struct FakeProvider {
+    secret: Vec<u8>,
+    checkouts: RefCell<HashMap<String, CheckoutRequest>>,
+    payments: RefCell<HashMap<String, CompletedPayment>>,
+    refunded: RefCell<HashMap<String, Money>>
+}
+
+impl FakeProvider {
+    pub fn new(secret: &[u8]) -> FakeProvider {
+        FakeProvider {
+            secret: secret.to_vec(),
+            checkouts: RefCell::new(HashMap::new()),
+            payments: RefCell::new(HashMap::new()),
+            refunded: RefCell::new(HashMap::new())
+        }
+    }
+
+    fn webhook(&self, event: Value, now: DateTime<Utc>) -> (Vec<u8>, String) {
+        let body = event.to_string().into_bytes();
+        let signature = sign(&self.secret, now.timestamp(), &body);
+        (body, signature)
+    }
+
+    /// Pays for a checkout, returning the webhook body and signature header.
+    pub fn complete(&self, checkout_id: &str, now: DateTime<Utc>) -> Result<(Vec<u8>, String), PaymentError> {
+        let checkout = self.checkouts.borrow_mut()
+            .remove(checkout_id)
+            .ok_or_else(|| PaymentError::NotFound(checkout_id.to_string()))?;
+
+        let payment = CompletedPayment {
+            payment_id: format!("fake_pay_{}", Uuid::new_v4().to_simple()),
+            checkout_id: checkout_id.to_string(),
+            item_id: checkout.item_id,
+            household_id: checkout.household_id,
+            amount: checkout.amount,
+            message: checkout.message
+        };
+        self.payments.borrow_mut().insert(payment.payment_id.clone(), payment.clone());
+
+        Ok(self.webhook(json!({"type": "checkout.completed", "data": payment}), now))
+    }
+
+    /// The webhook the provider sends after a refund.
+    pub fn refund_webhook(&self, refund: &Refund, now: DateTime<Utc>) -> (Vec<u8>, String) {
+        self.webhook(json!({"type": "refund.completed", "data": refund}), now)
+    }
+}
+
+impl PaymentProvider for FakeProvider {
+    fn name(&self) -> &'static str {
+        "fake"
+    }
+
+    fn create_checkout(&self, request: &CheckoutRequest) -> Result<Checkout, PaymentError> {
+        if !request.amount.is_positive() {
+            return Err(PaymentError::Invalid(String::from("Payments must be more than zero")));
+        }
+
+        let id = format!("fake_cs_{}", Uuid::new_v4().to_simple());
+        self.checkouts.borrow_mut().insert(id.clone(), request.clone());
+        Ok(Checkout {
+            url: format!("https://checkout.fake.test/{}", id),
+            id
+        })
+    }
+
+    fn handle_webhook(&self, body: &[u8], signature: &str, now: DateTime<Utc>) -> Result<WebhookEvent, PaymentError> {
+        verify_signature(&self.secret, signature, body, now)?;
+
+        let event : Value = serde_json::from_slice(body)
+            .map_err(|error| PaymentError::Malformed(error.to_string()))?;
+        let data = event["data"].clone();
+
+        match event["type"].as_str() {
+            Some("checkout.completed") => serde_json::from_value(data)
+                .map(WebhookEvent::CheckoutCompleted)
+                .map_err(|error| PaymentError::Malformed(error.to_string())),
+            Some("refund.completed") => serde_json::from_value(data)
+                .map(WebhookEvent::Refunded)
+                .map_err(|error| PaymentError::Malformed(error.to_string())),
+            Some(other) => Ok(WebhookEvent::Ignored(other.to_string())),
+            None => Err(PaymentError::Malformed(String::from("Webhooks need a type")))
+        }
+    }
+
+    fn refund(&self, request: &RefundRequest) -> Result<Refund, PaymentError> {
+        let paid = self.payments.borrow()
+            .get(&request.payment_id)
+            .map(|payment| payment.amount.clone())
+            .ok_or_else(|| PaymentError::NotFound(request.payment_id.clone()))?;
+
+        let mut refunded = self.refunded.borrow_mut();
+        let already = match refunded.get(&request.payment_id) {
+            Some(already) => already.clone(),
+            None => Money::zero(paid.currency()).map_err(|error| PaymentError::Invalid(error.to_string()))?
+        };
+        let left = paid.checked_sub(&already).map_err(|error| PaymentError::Invalid(error.to_string()))?;
+        if !request.amount.is_positive() || request.amount > left {
+            return Err(PaymentError::Invalid(format!("Only {} of {} can still be refunded", left, paid)));
+        }
+        let total = already.checked_add(&request.amount).map_err(|error| PaymentError::Invalid(error.to_string()))?;
+        refunded.insert(request.payment_id.clone(), total);
+
+        Ok(Refund {
+            id: format!("fake_re_{}", Uuid::new_v4().to_simple()),
+            item_id: request.item_id.clone(),
+            payment_id: request.payment_id.clone(),
+            amount: request.amount.clone()
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod payments_tests {
+
+    use super::*;
+    use chrono::Duration;
+
+    fn checkout_request() -> CheckoutRequest {
+        CheckoutRequest {
+            item_id: "airfare".to_string(),
+            item_title: "Roundtrip Air Fare".to_string(),
+            household_id: "household".to_string(),
+            amount: Money::parse("50", "USD").unwrap(),
+            message: "Safe travels!".to_string(),
+            return_url: "https://slswedding.com/registry".to_string()
+        }
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        let now = Utc::now();
+        let header = sign(b"secret", now.timestamp(), b"{}");
+
+        assert_eq!(verify_signature(b"secret", &header, b"{}", now), Ok(()));
+        assert_eq!(verify_signature(b"secret", &format!("{},v1=00", header), b"{}", now), Ok(()));
+        assert_eq!(verify_signature(b"other secret", &header, b"{}", now), Err(PaymentError::InvalidSignature));
+        assert_eq!(verify_signature(b"secret", &header, b"{\"tampered\":1}", now), Err(PaymentError::InvalidSignature));
+        assert_eq!(verify_signature(b"secret", &header, b"{}", now + Duration::minutes(10)), Err(PaymentError::InvalidSignature));
+        assert_eq!(verify_signature(b"secret", "v1=zz", b"{}", now), Err(PaymentError::InvalidSignature));
+    }
+
+    #[test]
+    fn test_fake_checkout_and_webhook() {
+        let now = Utc::now();
+        let provider = FakeProvider::new(b"secret");
+        let checkout = provider.create_checkout(&checkout_request()).unwrap();
+        assert!(checkout.url.ends_with(&checkout.id));
+
+        let (body, signature) = provider.complete(&checkout.id, now).unwrap();
+        let payment = match provider.handle_webhook(&body, &signature, now).unwrap() {
+            WebhookEvent::CheckoutCompleted(payment) => payment,
+            other => panic!("Expected a completed checkout, got {:?}", other)
+        };
+        assert_eq!(payment.checkout_id, checkout.id);
+        assert_eq!(payment.amount, Money::parse("50.00", "USD").unwrap());
+        assert_eq!(payment.message, "Safe travels!");
+
+        assert!(provider.complete(&checkout.id, now).is_err());
+        assert_eq!(
+            FakeProvider::new(b"another secret").handle_webhook(&body, &signature, now),
+            Err(PaymentError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn test_fake_refund() {
+        let now = Utc::now();
+        let provider = FakeProvider::new(b"secret");
+        let checkout = provider.create_checkout(&checkout_request()).unwrap();
+        let (body, signature) = provider.complete(&checkout.id, now).unwrap();
+        let payment = match provider.handle_webhook(&body, &signature, now).unwrap() {
+            WebhookEvent::CheckoutCompleted(payment) => payment,
+            other => panic!("Expected a completed checkout, got {:?}", other)
+        };
+
+        let request = |amount| RefundRequest {
+            item_id: payment.item_id.clone(),
+            payment_id: payment.payment_id.clone(),
+            amount: Money::parse(amount, "USD").unwrap()
+        };
+        let refund = provider.refund(&request("30")).unwrap();
+        assert!(provider.refund(&request("30")).is_err());
+        assert!(provider.refund(&request("0")).is_err());
+        assert!(provider.refund(&request("20")).is_ok());
+
+        let (body, signature) = provider.refund_webhook(&refund, now);
+        assert_eq!(provider.handle_webhook(&body, &signature, now), Ok(WebhookEvent::Refunded(refund)));
+    }
+
+    #[test]
+    fn test_provider_must_be_chosen() {
+        env::set_var("PAYMENT_WEBHOOK_SECRET", "secret");
+        env::remove_var("PAYMENT_PROVIDER");
+        assert_eq!(from_env().err(), Some(PaymentError::Config("PAYMENT_PROVIDER is not set".to_string())));
+
+        env::set_var("PAYMENT_PROVIDER", "stripe");
+        assert!(from_env().is_err());
+
+        env::set_var("PAYMENT_PROVIDER", "fake");
+        assert_eq!(from_env().unwrap().name(), "fake");
+        env::remove_var("PAYMENT_PROVIDER");
+    }
+}
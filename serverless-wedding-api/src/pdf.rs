@@ -0,0 +1,140 @@
+use std::fmt;
+use std::io::BufWriter;
+use std::str::FromStr;
+use printpdf::{
+    PdfDocument,
+    PdfDocumentReference,
+    PdfLayerReference,
+    IndirectFontRef,
+    BuiltinFont,
+    Mm,
+    Point,
+    Line
+};
+
+/// Points to millimeters
+pub const PT : f64 = 0.3528;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PaperSize {
+    pub width: f64,
+    pub height: f64
+}
+
+impl PaperSize {
+    pub const LETTER : PaperSize = PaperSize { width: 215.9, height: 279.4 };
+    pub const A4 : PaperSize = PaperSize { width: 210.0, height: 297.0 };
+}
+
+impl fmt::Display for PaperSize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}x{}mm", self.width, self.height)
+    }
+}
+
+impl FromStr for PaperSize {
+    type Err = String;
+
+    /// "letter", "legal", "a4", "a5", or a custom size in millimeters such
+    /// as "100x150".
+    fn from_str(value: &str) -> Result<PaperSize, String> {
+        match value.to_lowercase().as_str() {
+            "letter" => Ok(PaperSize::LETTER),
+            "legal" => Ok(PaperSize { width: 215.9, height: 355.6 }),
+            "a4" => Ok(PaperSize::A4),
+            "a5" => Ok(PaperSize { width: 148.0, height: 210.0 }),
+            other => {
+                let error = || format!("Unsupported paper size: {}. Use letter, legal, a4, a5 or WIDTHxHEIGHT in mm", value);
+                let mut parts = other.trim_end_matches("mm").splitn(2, 'x');
+                let width : f64 = parts.next().and_then(|part| part.trim().parse().ok()).ok_or_else(error)?;
+                let height : f64 = parts.next().and_then(|part| part.trim().parse().ok()).ok_or_else(error)?;
+                if width < 50.0 || height < 30.0 || width > 1000.0 || height > 1000.0 {
+                    return Err(error());
+                }
+                Ok(PaperSize { width, height })
+            }
+        }
+    }
+}
+
+/// Rough width of Helvetica text, which is enough to center a line since
+/// the built-in fonts can't be measured.
+pub fn text_width(text: &str, size: f64) -> f64 {
+    text.chars().count() as f64 * size * 0.5 * PT
+}
+
+/// Shrinks the font until the text fits `width`.
+pub fn fit(text: &str, size: f64, width: f64) -> f64 {
+    let natural = text_width(text, size);
+    if natural <= width { size } else { size * width / natural }
+}
+
+pub fn centered(layer: &PdfLayerReference, text: &str, size: f64, center_x: f64, y: f64, width: f64, font: &IndirectFontRef) {
+    let size = fit(text, size, width);
+    layer.use_text(text, size, Mm(center_x - text_width(text, size) / 2.0), Mm(y), font);
+}
+
+pub fn rectangle(layer: &PdfLayerReference, x: f64, y: f64, width: f64, height: f64) {
+    layer.add_shape(Line {
+        points: vec!(
+            (Point::new(Mm(x), Mm(y)), false),
+            (Point::new(Mm(x + width), Mm(y)), false),
+            (Point::new(Mm(x + width), Mm(y + height)), false),
+            (Point::new(Mm(x), Mm(y + height)), false)
+        ),
+        is_closed: true,
+        has_fill: false,
+        has_stroke: true,
+        is_clipping_path: false
+    });
+}
+
+pub struct Fonts {
+    pub regular: IndirectFontRef,
+    pub bold: IndirectFontRef
+}
+
+pub fn new_document(title: &str, paper: PaperSize) -> Result<(PdfDocumentReference, PdfLayerReference, Fonts), String> {
+    let (document, page, layer) = PdfDocument::new(title, Mm(paper.width), Mm(paper.height), "Page");
+    let fonts = Fonts {
+        regular: document.add_builtin_font(BuiltinFont::Helvetica).map_err(|err| err.to_string())?,
+        bold: document.add_builtin_font(BuiltinFont::HelveticaBold).map_err(|err| err.to_string())?
+    };
+    let layer = document.get_page(page).get_layer(layer);
+    Ok((document, layer, fonts))
+}
+
+pub fn next_page(document: &PdfDocumentReference, paper: PaperSize) -> PdfLayerReference {
+    let (page, layer) = document.add_page(Mm(paper.width), Mm(paper.height), "Page");
+    document.get_page(page).get_layer(layer)
+}
+
+pub fn finish(document: PdfDocumentReference) -> Result<Vec<u8>, String> {
+    let mut bytes = vec!();
+    {
+        let mut writer = BufWriter::new(&mut bytes);
+        document.save(&mut writer).map_err(|err| err.to_string())?;
+    }
+    Ok(bytes)
+}
+
+
+#[cfg(test)]
+mod pdf_tests {
+
+    use super::*;
+
+    #[test]
+    fn test_paper_size_from_str() {
+        assert_eq!("Letter".parse::<PaperSize>(), Ok(PaperSize::LETTER));
+        assert_eq!("100x150mm".parse::<PaperSize>(), Ok(PaperSize { width: 100.0, height: 150.0 }));
+        assert!("tabloid".parse::<PaperSize>().is_err());
+        assert!("10x10".parse::<PaperSize>().is_err());
+    }
+
+    #[test]
+    fn test_fit() {
+        assert_eq!(fit("Short", 12.0, 100.0), 12.0);
+        assert!(fit("A much longer line of text than fits", 12.0, 20.0) < 12.0);
+    }
+}
@@ -0,0 +1,63 @@
+extern crate log;
+extern crate simple_logger;
+
+use std::fs;
+use std::process;
+use clap::{App, Arg};
+use uuid::Uuid;
+use log::{info, error};
+
+mod models;
+mod pdf;
+mod cards;
+use crate::cards::CardKind;
+use crate::pdf::PaperSize;
+
+fn run() -> Result<(), String> {
+    let matches = App::new("print-cards")
+        .about("Prints escort cards, place cards or table sheets for an event as a PDF")
+        .arg(Arg::with_name("event")
+            .long("event")
+            .value_name("ID")
+            .help("Event to print cards for")
+            .takes_value(true)
+            .required(true))
+        .arg(Arg::with_name("kind")
+            .long("kind")
+            .value_name("KIND")
+            .help("What to print")
+            .possible_values(&["escort", "place", "tables"])
+            .default_value("escort"))
+        .arg(Arg::with_name("paper")
+            .long("paper")
+            .value_name("SIZE")
+            .help("letter, legal, a4, a5 or WIDTHxHEIGHT in millimeters")
+            .default_value("letter"))
+        .arg(Arg::with_name("out")
+            .long("out")
+            .value_name("FILE")
+            .help("Where to write the PDF, named after the kind by default")
+            .takes_value(true))
+        .get_matches();
+
+    let id = matches.value_of("event").unwrap();
+    let event_id = Uuid::parse_str(id).map_err(|_| format!("Invalid event id: {}", id))?;
+    let kind : CardKind = matches.value_of("kind").unwrap().parse()?;
+    let paper : PaperSize = matches.value_of("paper").unwrap().parse()?;
+    let out = matches.value_of("out").unwrap_or_else(|| kind.file_name());
+
+    let bytes = cards::for_event(event_id, kind, paper).map_err(|err| err.to_string())?;
+    fs::write(out, bytes).map_err(|err| format!("Could not write {}: {}", out, err))?;
+    info!("Wrote {} on {} paper", out, paper);
+
+    Ok(())
+}
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+
+    if let Err(message) = run() {
+        error!("{}", message);
+        process::exit(1);
+    }
+}
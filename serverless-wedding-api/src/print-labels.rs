@@ -0,0 +1,57 @@
+extern crate log;
+extern crate simple_logger;
+
+use std::fs;
+use std::process;
+use clap::{App, Arg};
+use log::{info, warn, error};
+
+mod models;
+mod pdf;
+mod labels;
+use crate::labels::LabelLayout;
+
+fn run() -> Result<(), String> {
+    let matches = App::new("print-labels")
+        .about("Prints mailing labels for every household with an address as a PDF")
+        .arg(Arg::with_name("layout")
+            .long("layout")
+            .value_name("SHEET")
+            .help("Avery label sheet: 5160, 5162, 5163, L7160 or L7163")
+            .default_value("5160"))
+        .arg(Arg::with_name("skip")
+            .long("skip")
+            .value_name("COUNT")
+            .help("Labels already used on the first sheet")
+            .default_value("0"))
+        .arg(Arg::with_name("out")
+            .long("out")
+            .value_name("FILE")
+            .help("Where to write the PDF")
+            .default_value("address-labels.pdf"))
+        .get_matches();
+
+    let layout : LabelLayout = matches.value_of("layout").unwrap().parse()?;
+    let skip : usize = matches.value_of("skip").unwrap().parse()
+        .map_err(|_| String::from("--skip must be a whole number of labels"))?;
+    let out = matches.value_of("out").unwrap();
+
+    let (bytes, missing) = labels::for_all(layout, skip).map_err(|err| err.to_string())?;
+    fs::write(out, bytes).map_err(|err| format!("Could not write {}: {}", out, err))?;
+    info!("Wrote {}", out);
+
+    for name in &missing {
+        warn!("No mailing address for {}", name);
+    }
+
+    Ok(())
+}
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+
+    if let Err(message) = run() {
+        error!("{}", message);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,117 @@
+extern crate log;
+extern crate simple_logger;
+
+use std::collections::{HashSet};
+use std::fs;
+use std::path::{Path};
+use std::process;
+use clap::{App, Arg};
+use uuid::Uuid;
+use log::{info, error};
+
+mod models;
+mod qr;
+use crate::models::{Household, RSVP};
+
+/// Writes the QR code for one household into `out_dir`, naming the file after
+/// the household's display name. Names already handed out in this run get a
+/// numeric suffix so two "Smith Family" invitations don't overwrite each other.
+fn write_household(rsvps: &[RSVP], options: &qr::Options, out_dir: &Path, used_names: &mut HashSet<String>) -> Result<(), String> {
+    let household_id = &rsvps[0].household_id;
+    let link = Household::rsvp_link(household_id);
+    let bytes = qr::render(&link, options).map_err(|err| err.to_string())?;
+
+    let base_name = qr::file_name(&Household::display_name(rsvps), options.format);
+    let mut file_name = base_name.clone();
+    let mut suffix = 2;
+    while used_names.contains(&file_name) {
+        let stem = base_name.trim_end_matches(&format!(".{}", options.format.extension()));
+        file_name = format!("{}-{}.{}", stem, suffix, options.format.extension());
+        suffix += 1;
+    }
+
+    let path = out_dir.join(&file_name);
+    fs::write(&path, bytes).map_err(|err| format!("Could not write {}: {}", path.display(), err))?;
+    info!("Wrote {} for household {}", path.display(), household_id);
+
+    used_names.insert(file_name);
+    Ok(())
+}
+
+fn run() -> Result<(), String> {
+    let matches = App::new("qr-codes")
+        .about("Renders household RSVP links as QR codes for printed invitations")
+        .arg(Arg::with_name("household")
+            .long("household")
+            .value_name("ID")
+            .help("Household id to render, may be repeated")
+            .takes_value(true)
+            .multiple(true)
+            .required_unless("all"))
+        .arg(Arg::with_name("all")
+            .long("all")
+            .help("Render one file per household in the RSVP table")
+            .conflicts_with("household"))
+        .arg(Arg::with_name("format")
+            .long("format")
+            .possible_values(&["svg", "png"])
+            .default_value("svg"))
+        .arg(Arg::with_name("size")
+            .long("size")
+            .value_name("PIXELS")
+            .help("Minimum width and height of the code")
+            .default_value("256"))
+        .arg(Arg::with_name("ec")
+            .long("ec")
+            .help("Error correction level")
+            .possible_values(&["L", "M", "Q", "H"])
+            .case_insensitive(true)
+            .default_value("M"))
+        .arg(Arg::with_name("out")
+            .long("out")
+            .value_name("DIR")
+            .default_value("."))
+        .get_matches();
+
+    let size : u32 = matches.value_of("size").unwrap().parse()
+        .map_err(|_| String::from("--size must be a whole number of pixels"))?;
+    let options = qr::Options {
+        format: matches.value_of("format").unwrap().parse()?,
+        error_correction: qr::parse_error_correction(matches.value_of("ec").unwrap())?,
+        ..qr::Options::default()
+    }.with_size(size);
+
+    let households : Vec<Vec<RSVP>> = if matches.is_present("all") {
+        Household::list().map_err(|err| format!("Could not list households: {}", err))?
+    } else {
+        let mut households = vec!();
+        for id in matches.values_of("household").unwrap() {
+            let uuid = Uuid::parse_str(id).map_err(|_| format!("Invalid household id: {}", id))?;
+            let rsvps = Household::get(uuid).map_err(|err| err.to_string())?;
+            if rsvps.is_empty() {
+                return Err(format!("No household was found with id {}", id));
+            }
+            households.push(rsvps);
+        }
+        households
+    };
+
+    let out_dir = Path::new(matches.value_of("out").unwrap());
+    fs::create_dir_all(out_dir).map_err(|err| format!("Could not create {}: {}", out_dir.display(), err))?;
+
+    let mut used_names = HashSet::new();
+    for rsvps in households.iter().filter(|rsvps| !rsvps.is_empty()) {
+        write_household(rsvps, &options, out_dir, &mut used_names)?;
+    }
+
+    Ok(())
+}
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+
+    if let Err(message) = run() {
+        error!("{}", message);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,190 @@
+use std::fmt;
+use std::str::FromStr;
+use qrcode::{QrCode, EcLevel};
+use qrcode::render::svg;
+use qrcode::types::QrError;
+use image::{DynamicImage, ImageOutputFormat, Luma};
+
+pub const DEFAULT_SIZE: u32 = 256;
+pub const MIN_SIZE: u32 = 64;
+pub const MAX_SIZE: u32 = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Svg,
+    Png
+}
+
+impl Format {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Format::Svg => "image/svg+xml",
+            Format::Png => "image/png"
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Svg => "svg",
+            Format::Png => "png"
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Format, String> {
+        match value.to_lowercase().as_str() {
+            "svg" => Ok(Format::Svg),
+            "png" => Ok(Format::Png),
+            other => Err(format!("Unsupported QR code format: {}", other))
+        }
+    }
+}
+
+/// Accepts the usual single letter names (L, M, Q, H) for the error
+/// correction level, case insensitively.
+pub fn parse_error_correction(value: &str) -> Result<EcLevel, String> {
+    match value.to_uppercase().as_str() {
+        "L" => Ok(EcLevel::L),
+        "M" => Ok(EcLevel::M),
+        "Q" => Ok(EcLevel::Q),
+        "H" => Ok(EcLevel::H),
+        other => Err(format!("Unsupported error correction level: {}", other))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Options {
+    pub format: Format,
+    pub size: u32,
+    pub error_correction: EcLevel
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            format: Format::Svg,
+            size: DEFAULT_SIZE,
+            error_correction: EcLevel::M
+        }
+    }
+}
+
+impl Options {
+    pub fn with_size(mut self, size: u32) -> Options {
+        self.size = size.max(MIN_SIZE).min(MAX_SIZE);
+        self
+    }
+}
+
+#[derive(Debug)]
+pub enum RenderError {
+    Encode(QrError),
+    Image(String)
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RenderError::Encode(error) => write!(f, "Could not encode QR code: {}", error),
+            RenderError::Image(error) => write!(f, "Could not write QR code image: {}", error)
+        }
+    }
+}
+
+/// Renders `data` as a QR code, returning the bytes of the SVG document or
+/// PNG image. `size` is the minimum width and height in pixels.
+pub fn render(data: &str, options: &Options) -> Result<Vec<u8>, RenderError> {
+    let code = QrCode::with_error_correction_level(data, options.error_correction)
+        .map_err(RenderError::Encode)?;
+
+    match options.format {
+        Format::Svg => {
+            let document = code.render()
+                .min_dimensions(options.size, options.size)
+                .dark_color(svg::Color("#000000"))
+                .light_color(svg::Color("#ffffff"))
+                .build();
+            Ok(document.into_bytes())
+        },
+        Format::Png => {
+            let image = code.render::<Luma<u8>>()
+                .min_dimensions(options.size, options.size)
+                .build();
+
+            let mut bytes : Vec<u8> = vec!();
+            DynamicImage::ImageLuma8(image)
+                .write_to(&mut bytes, ImageOutputFormat::Png)
+                .map_err(|error| RenderError::Image(error.to_string()))?;
+            Ok(bytes)
+        }
+    }
+}
+
+/// Builds a file name safe for the print shop's file systems from a
+/// household display name, e.g. "Blaine Price & Cynthia Young" becomes
+/// "blaine-price-and-cynthia-young.svg".
+pub fn file_name(display_name: &str, format: Format) -> String {
+    let mut slug = String::new();
+    for c in display_name.replace("&", " and ").chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    let slug = slug.trim_end_matches('-');
+    let slug = if slug.is_empty() { "household" } else { slug };
+
+    format!("{}.{}", slug, format.extension())
+}
+
+
+#[cfg(test)]
+mod qr_tests {
+
+    use super::*;
+
+    #[test]
+    fn test_render_svg() {
+        let options = Options::default();
+        let bytes = render("https://slswedding.com/rsvp/3eb28445-7698-4a00-b071-49da8eaac944", &options).unwrap();
+        let document = String::from_utf8(bytes).unwrap();
+        assert!(document.contains("<svg"));
+    }
+
+    #[test]
+    fn test_render_png() {
+        let options = Options {
+            format: Format::Png,
+            error_correction: EcLevel::H,
+            ..Options::default()
+        }.with_size(128);
+        let bytes = render("https://slswedding.com/rsvp/3eb28445-7698-4a00-b071-49da8eaac944", &options).unwrap();
+        assert_eq!(&bytes[0..8], &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]);
+    }
+
+    #[test]
+    fn test_options_clamp_size() {
+        assert_eq!(Options::default().with_size(1).size, MIN_SIZE);
+        assert_eq!(Options::default().with_size(100_000).size, MAX_SIZE);
+    }
+
+    #[test]
+    fn test_parse_options() {
+        assert_eq!(parse_error_correction("h").unwrap(), EcLevel::H);
+        assert!(parse_error_correction("x").is_err());
+        assert_eq!("PNG".parse::<Format>().unwrap(), Format::Png);
+        assert!("gif".parse::<Format>().is_err());
+    }
+
+    #[test]
+    fn test_file_name() {
+        assert_eq!(file_name("Blaine Price & Cynthia Young", Format::Svg), "blaine-price-and-cynthia-young.svg");
+        assert_eq!(file_name("  O'Brien / Family ", Format::Png), "o-brien-family.png");
+        assert_eq!(file_name("", Format::Png), "household.png");
+    }
+}
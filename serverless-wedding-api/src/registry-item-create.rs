@@ -0,0 +1,96 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_derive::Deserialize;
+use serde_json::{json};
+use std::ops::Deref;
+use log::{error};
+
+mod models;
+use crate::models::{RegistryItem, ItemKind, ItemProgress, Money, default_currency};
+
+#[derive(Debug, Deserialize)]
+struct RegistryItemPayload {
+    title: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    image: String,
+    /// A decimal string like "129.99", so it never passes through a float
+    goal: Option<String>,
+    currency: Option<String>,
+    #[serde(default)]
+    position: u32,
+    #[serde(default)]
+    kind: ItemKind,
+    /// How many of a physical item are wanted
+    quantity: Option<u32>,
+    /// Where to buy a physical item
+    #[serde(default)]
+    url: String,
+    /// What a physical item costs, as a decimal string
+    price: Option<String>
+}
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let body = request.body().deref();
+    let payload : RegistryItemPayload = match serde_json::from_slice(body) {
+        Ok(payload) => payload,
+        Err(error) => return Ok(respond(400, json!({"message": format!("Invalid registry item: {}", error)}).to_string()))
+    };
+
+    let currency = payload.currency.unwrap_or_else(default_currency);
+    let goal = match payload.goal.map(|goal| Money::parse(&goal, &currency)) {
+        Some(Ok(goal)) => Some(goal),
+        Some(Err(err)) => return Ok(respond(400, json!({"message": err.to_string()}).to_string())),
+        None => None
+    };
+    let price = match payload.price.map(|price| Money::parse(&price, &currency)) {
+        Some(Ok(price)) => Some(price),
+        Some(Err(err)) => return Ok(respond(400, json!({"message": err.to_string()}).to_string())),
+        None => None
+    };
+    let item = match payload.kind {
+        ItemKind::Fund => RegistryItem::new(payload.title, goal, &currency),
+        ItemKind::Physical => RegistryItem::new_physical(payload.title, price, &currency, payload.quantity.unwrap_or(1))
+    };
+    let item = match item {
+        Ok(item) => RegistryItem {
+            description: payload.description.trim().to_string(),
+            image: payload.image.trim().to_string(),
+            url: payload.url.trim().to_string(),
+            position: payload.position,
+            ..item
+        },
+        Err(message) => return Ok(respond(400, json!({"message": message}).to_string()))
+    };
+
+    Ok(match item.create() {
+        Ok(item) => match ItemProgress::new(item, &[]) {
+            Ok(progress) => respond(200, json!(progress).to_string()),
+            Err(err) => respond(400, json!({"message": err.to_string()}).to_string())
+        },
+        Err(err) => {
+            error!("Could not create registry item {:?}: {}", item, err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
@@ -0,0 +1,61 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::{json};
+use chrono::Utc;
+use log::{error};
+
+mod models;
+use crate::models::{Reservation, ReservationError};
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// Guests confirm they bought the item they reserved, so it's no longer
+/// released when the hold runs out.
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let household_id = path_parameters.get("id").unwrap();
+    let item_id = path_parameters.get("item_id").unwrap();
+
+    Ok(match Reservation::confirm(item_id, household_id, Utc::now()) {
+        Ok(reservation) => respond(200, json!(reservation).to_string()),
+        Err(ReservationError::NotFound) => respond(404, json!({"message": ReservationError::NotFound.to_string()}).to_string()),
+        Err(ReservationError::Expired) => respond(410, json!({"message": ReservationError::Expired.to_string()}).to_string()),
+        Err(err) => {
+            error!("Could not confirm household {}'s reservation of registry item {}: {:?}", household_id, item_id, err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lambda_http::Body;
+
+    #[test]
+    #[ignore]
+    fn registry_reservation_confirm_handler_handles() {
+
+        let mut request = Request::new(Body::from(""));
+        *request.uri_mut() = "https://api.slswedding.com/household/3eb28445-7698-4a00-b071-49da8eaac944/registry/9b1f0a3c-2d4e-4f5a-8b6c-7d8e9f0a1b2c/reservation/confirm".parse().unwrap();
+
+        handler(request, Context::default()).expect("Expected an OK response");
+    }
+}
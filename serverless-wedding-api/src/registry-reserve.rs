@@ -0,0 +1,116 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_derive::Deserialize;
+use serde_json::{json};
+use std::ops::Deref;
+use chrono::Utc;
+use log::{error};
+use uuid::Uuid;
+use rusoto_dynamodb::GetItemError;
+
+mod models;
+use crate::models::{Household, RegistryItem, Reservation, ReservationError};
+
+#[derive(Debug, Default, Deserialize)]
+struct ReservePayload {
+    quantity: Option<u32>
+}
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// Guests reserve a physical registry item from their household link, so
+/// no one else buys it too. The reservation is held until it expires
+/// unless they confirm they bought it.
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let uuid : Uuid = match Uuid::parse_str(path_parameters.get("id").unwrap()) {
+        Ok(uuid) => uuid,
+        Err(_error) => return Ok(respond(404, json!({"message": "No household was found with that id"}).to_string()))
+    };
+    let item_id : Uuid = match Uuid::parse_str(path_parameters.get("item_id").unwrap()) {
+        Ok(item_id) => item_id,
+        Err(_error) => return Ok(respond(404, json!({"message": ReservationError::ItemNotFound.to_string()}).to_string()))
+    };
+
+    let body = request.body().deref();
+    let payload : ReservePayload = if body.is_empty() {
+        ReservePayload::default()
+    } else {
+        match serde_json::from_slice(body) {
+            Ok(payload) => payload,
+            Err(error) => return Ok(respond(400, json!({"message": format!("Invalid reservation: {}", error)}).to_string()))
+        }
+    };
+
+    match Household::get(uuid) {
+        Ok(ref rsvps) if rsvps.is_empty() => {
+            return Ok(respond(404, json!({"message": "No household was found with that id"}).to_string()))
+        },
+        Ok(_rsvps) => {},
+        Err(err) => {
+            error!("Could not look up household {}: {}", uuid, err);
+            return Ok(respond(500, json!({"message": "Something went wrong!"}).to_string()))
+        }
+    }
+
+    let item = match RegistryItem::get(item_id) {
+        Ok(item) => item,
+        Err(GetItemError::ResourceNotFound(_)) => {
+            return Ok(respond(404, json!({"message": ReservationError::ItemNotFound.to_string()}).to_string()))
+        },
+        Err(err) => {
+            error!("Could not look up registry item {}: {}", item_id, err);
+            return Ok(respond(500, json!({"message": "Something went wrong!"}).to_string()))
+        }
+    };
+
+    Ok(match Reservation::reserve(&item, &uuid.to_string(), payload.quantity.unwrap_or(1), Utc::now()) {
+        Ok(reservation) => respond(200, json!(reservation).to_string()),
+        Err(ReservationError::Invalid(message)) => respond(400, json!({"message": message}).to_string()),
+        Err(ReservationError::AlreadyReserved(reservation)) => respond(409, json!({
+            "message": ReservationError::AlreadyReserved(reservation.clone()).to_string(),
+            "reservation": reservation
+        }).to_string()),
+        Err(ReservationError::Unavailable(left)) => respond(409, json!({
+            "message": ReservationError::Unavailable(left).to_string(),
+            "available": left
+        }).to_string()),
+        Err(err) => {
+            error!("Could not reserve registry item {} for household {}: {:?}", item_id, uuid, err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lambda_http::Body;
+
+    #[test]
+    #[ignore]
+    fn registry_reserve_handler_handles() {
+
+        let mut request = Request::new(Body::from(json!({"quantity": 1}).to_string()));
+        *request.uri_mut() = "https://api.slswedding.com/household/3eb28445-7698-4a00-b071-49da8eaac944/registry/9b1f0a3c-2d4e-4f5a-8b6c-7d8e9f0a1b2c/reservation".parse().unwrap();
+
+        handler(request, Context::default()).expect("Expected an OK response");
+    }
+}
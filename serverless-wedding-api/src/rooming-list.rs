@@ -0,0 +1,67 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::json;
+use log::{error};
+use uuid::Uuid;
+
+mod models;
+use crate::models::{RoomingList, LodgingError};
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// Everyone booked into a hotel's block, as a CSV to send to the hotel, or
+/// as JSON with `?format=json`.
+fn handler(
+    request: Request,
+    _: Context,
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let hotel_id : Uuid = match Uuid::parse_str(path_parameters.get("id").unwrap()) {
+        Ok(hotel_id) => hotel_id,
+        Err(_error) => return Ok(respond(404, json!({"message": "No hotel was found with that id"}).to_string()))
+    };
+    let as_json = request.query_string_parameters().get("format") == Some("json");
+
+    let list = match RoomingList::get(hotel_id) {
+        Ok(list) => list,
+        Err(LodgingError::HotelNotFound) => return Ok(respond(404, json!({"message": LodgingError::HotelNotFound.to_string()}).to_string())),
+        Err(err) => {
+            error!("Could not build the rooming list for hotel {}: {}", hotel_id, err);
+            return Ok(respond(500, json!({"message": "Something went wrong!"}).to_string()))
+        }
+    };
+    let body = if as_json {
+        Ok(json!(list).to_string())
+    } else {
+        list.csv()
+    };
+
+    Ok(match body {
+        Ok(body) => {
+            http::Response::builder()
+                .header("Access-Control-Allow-Origin", "*")
+                .header("Content-Type", if as_json { "application/json" } else { "text/csv" })
+                .status(200)
+                .body(body)
+                .unwrap()
+        },
+        Err(err) => {
+            error!("Could not write the rooming list for hotel {}: {}", hotel_id, err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
@@ -0,0 +1,78 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_runtime::{lambda, error::HandlerError, Context};
+use serde_json::{json, Value};
+use log::{info, error};
+
+mod models;
+mod notify;
+use crate::models::{Event, RsvpChange, StreamEvent};
+use crate::notify::{Notification, NotificationKind};
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+/// Reads the RSVP table's stream and tells the couple what a guest
+/// changed, through whichever channels `rsvp_changed` is routed to. Writes
+/// that only touch the couple's own flags, like marking an invitation
+/// sent, are skipped.
+fn handler(
+    event: StreamEvent,
+    _: Context
+) -> Result<Value, HandlerError> {
+    let events = Event::all().unwrap_or_else(|err| {
+        error!("Could not load events to name them: {}", err);
+        vec!()
+    });
+    let notifiers = match notify::from_env() {
+        Ok(notifiers) => notifiers,
+        Err(err) => {
+            error!("Could not set up notifications: {}", err);
+            return Ok(json!({"records": event.records.len(), "notified": 0}))
+        }
+    };
+
+    let mut notified = 0;
+    for record in &event.records {
+        let change = match RsvpChange::from_record(record) {
+            Ok(change) => change,
+            Err(message) => {
+                error!("Skipping a stream record: {}", message);
+                continue
+            }
+        };
+
+        let message = match change.message(&events) {
+            Some(message) => message,
+            None => {
+                info!("Nothing to tell the couple about {:?}", change);
+                continue
+            }
+        };
+
+        let notification = Notification::new(NotificationKind::RsvpChanged, "RSVP Notification", &message);
+        if notifiers.notify(&notification).is_ok() {
+            info!("Sent: {}", message);
+            notified += 1;
+        }
+    }
+
+    Ok(json!({"records": event.records.len(), "notified": notified}))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[ignore]
+    fn rsvp_notify_handler_handles() {
+
+        let event : StreamEvent = serde_json::from_value(json!({"Records": []})).unwrap();
+
+        handler(event, Context::default()).expect("Expected an OK response");
+    }
+}
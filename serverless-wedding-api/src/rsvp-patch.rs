@@ -4,11 +4,11 @@ extern crate simple_logger;
 use lambda_http::{lambda, IntoResponse, Request, http, RequestExt};
 use lambda_runtime::{error::HandlerError, Context};
 use serde_json::{json, Value};
-use log::{debug};
+use log::{debug, error};
 use uuid::Uuid;
 
 mod models;
-use crate::models::RSVP;
+use crate::models::{RSVP, PatchError};
 
 fn main() {
     simple_logger::init_with_level(log::Level::Debug).unwrap();
@@ -40,7 +40,32 @@ fn handler(
                 .body(json!(response).to_string())
                 .unwrap()
         },
-        Err(error) => {
+        Err(PatchError::NotFound) => {
+            http::Response::builder()
+                .header("Access-Control-Allow-Origin", "*")
+                .status(404)
+                .body(json!({"message": PatchError::NotFound.to_string()}).to_string())
+                .unwrap()
+        },
+        Err(PatchError::Invalid(message)) => {
+            http::Response::builder()
+                .header("Access-Control-Allow-Origin", "*")
+                .status(400)
+                .body(json!({"message": message}).to_string())
+                .unwrap()
+        },
+        Err(PatchError::DeadlinePassed(deadline)) => {
+            http::Response::builder()
+                .header("Access-Control-Allow-Origin", "*")
+                .status(403)
+                .body(json!({
+                    "message": PatchError::DeadlinePassed(deadline).to_string(),
+                    "deadline": deadline
+                }).to_string())
+                .unwrap()
+        },
+        Err(error @ PatchError::Config(_)) | Err(error @ PatchError::Menu(_)) | Err(error @ PatchError::Update(_)) => {
+            error!("Could not update RSVP {}: {}", uuid, error);
             http::Response::builder()
                 .header("Access-Control-Allow-Origin", "*")
                 .status(500)
@@ -0,0 +1,130 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_runtime::{lambda, error::HandlerError, Context};
+use serde_derive::Deserialize;
+use serde_json::{json, Value};
+use chrono::Utc;
+use log::{info, error};
+
+mod models;
+mod notify;
+use crate::models::{Household, Event, EmailTemplate, TemplateKind, ReminderSchedule, rsvp_deadline};
+use crate::notify::Notification;
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+/// Logs why the job stopped and reports it as the result.
+fn failure(dry_run: bool, message: String) -> Value {
+    error!("{}", message);
+    json!({"dry_run": dry_run, "sent": 0, "error": message})
+}
+
+/// The scheduled event, or `{"dry_run": true}` when invoked by hand.
+#[derive(Debug, Deserialize)]
+struct ReminderRequest {
+    #[serde(default)]
+    dry_run: bool
+}
+
+/// Reminds households that haven't answered as the deadline nears, through
+/// whichever channels `reminder` is routed to. Each household gets at most
+/// `REMINDER_MAX` reminders, a few days apart, and none during quiet hours.
+/// Each reminder is recorded before it's sent, so a household whose record
+/// can't be saved is skipped rather than reminded every hour. A dry run
+/// sends nothing and lists who would be contacted.
+fn handler(
+    request: ReminderRequest,
+    _: Context
+) -> Result<Value, HandlerError> {
+    let now = Utc::now();
+    let schedule = match ReminderSchedule::from_env() {
+        Ok(schedule) => schedule,
+        Err(err) => return Ok(failure(request.dry_run, format!("Could not read the reminder schedule: {}", err)))
+    };
+
+    let (households, events) = match (Household::list(), Household::records(), Event::all()) {
+        (Ok(households), Ok(records), Ok(events)) => {
+            let households : Vec<(Household, Vec<_>)> = households.into_iter()
+                .map(|rsvps| {
+                    let household_id = rsvps[0].household_id.clone();
+                    let household = records.iter()
+                        .find(|record| record.id == household_id)
+                        .cloned()
+                        .unwrap_or_else(|| Household { id: household_id, ..Household::default() });
+                    (household, rsvps)
+                })
+                .collect();
+            (households, events)
+        },
+        (Err(err), _, _) => return Ok(failure(request.dry_run, format!("Could not load households: {}", err))),
+        (_, Err(err), _) => return Ok(failure(request.dry_run, format!("Could not load household records: {}", err))),
+        (_, _, Err(err)) => return Ok(failure(request.dry_run, format!("Could not load events: {}", err)))
+    };
+
+    let deadline = match rsvp_deadline() {
+        Ok(deadline) => deadline,
+        Err(err) => return Ok(failure(request.dry_run, format!("Could not read the RSVP deadline: {}", err)))
+    };
+    let plan = schedule.plan(&households, &events, deadline, now);
+    if request.dry_run || plan.paused.is_some() {
+        info!("Not sending reminders: {}", plan.paused.clone().unwrap_or_else(|| String::from("dry run")));
+        return Ok(json!({"dry_run": request.dry_run, "sent": 0, "plan": plan}));
+    }
+
+    let template = match EmailTemplate::get(TemplateKind::Reminder) {
+        Ok(template) => template,
+        Err(err) => return Ok(failure(false, format!("Could not load the reminder template: {}", err)))
+    };
+    let notifiers = match notify::from_env() {
+        Ok(notifiers) => notifiers,
+        Err(err) => return Ok(failure(false, format!("Could not set up notifications: {}", err)))
+    };
+
+    let mut sent = 0;
+    let mut failed = vec!();
+    for target in &plan.due {
+        let rsvps = households.iter()
+            .find(|(household, _)| household.id == target.household_id)
+            .map_or(&[][..], |(_, rsvps)| &rsvps[..]);
+        let recorded = rsvps.iter()
+            .map(|rsvp| rsvp.record_reminder(now).map_err(|err| format!("RSVP {}: {}", rsvp.id, err)))
+            .collect::<Result<Vec<()>, String>>();
+        if let Err(err) = recorded {
+            error!("Not reminding household {}, could not record the reminder on {}", target.household_id, err);
+            failed.push(target.household_id.clone());
+            continue;
+        }
+
+        let delivered = template.render(&target.context)
+            .map_err(|err| err.to_string())
+            .and_then(|email| notifiers.notify(&Notification::email(&email)).map_err(|err| err.to_string()));
+        if let Err(err) = delivered {
+            error!("Could not remind household {}: {}", target.household_id, err);
+            failed.push(target.household_id.clone());
+            continue;
+        }
+
+        info!("Reminded {} about {:?}", target.salutation, target.pending);
+        sent += 1;
+    }
+
+    Ok(json!({"dry_run": false, "sent": sent, "failed": failed, "plan": plan}))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[ignore]
+    fn rsvp_reminders_handler_handles() {
+
+        let request : ReminderRequest = serde_json::from_value(json!({"dry_run": true})).unwrap();
+
+        handler(request, Context::default()).expect("Expected an OK response");
+    }
+}
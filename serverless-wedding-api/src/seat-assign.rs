@@ -0,0 +1,62 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_derive::Deserialize;
+use serde_json::{json};
+use std::ops::Deref;
+use log::{error};
+use uuid::Uuid;
+
+mod models;
+use crate::models::{SeatAssignment, SeatingError};
+
+#[derive(Debug, Deserialize)]
+struct SeatPayload {
+    table_id: Uuid
+}
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let event_id = path_parameters.get("id").unwrap().to_string();
+    let rsvp_id : Uuid = match Uuid::parse_str(path_parameters.get("rsvp_id").unwrap()) {
+        Ok(rsvp_id) => rsvp_id,
+        Err(_error) => return Ok(respond(404, json!({"message": SeatingError::GuestNotFound.to_string()}).to_string()))
+    };
+
+    let body = request.body().deref();
+    let payload : SeatPayload = match serde_json::from_slice(body) {
+        Ok(payload) => payload,
+        Err(error) => return Ok(respond(400, json!({"message": format!("Invalid seat: {}", error)}).to_string()))
+    };
+
+    Ok(match SeatAssignment::assign(&event_id, payload.table_id, rsvp_id) {
+        Ok(assignment) => respond(200, json!(assignment).to_string()),
+        Err(error @ SeatingError::TableNotFound) => respond(404, json!({"message": error.to_string()}).to_string()),
+        Err(error @ SeatingError::GuestNotFound) => respond(404, json!({"message": error.to_string()}).to_string()),
+        Err(error @ SeatingError::NotAttending(_)) => respond(422, json!({"message": error.to_string()}).to_string()),
+        Err(error @ SeatingError::TableFull(_)) => respond(409, json!({"message": error.to_string()}).to_string()),
+        Err(error @ SeatingError::Changed) => respond(409, json!({"message": error.to_string()}).to_string()),
+        Err(error) => {
+            error!("Could not seat RSVP {}: {:?}", rsvp_id, error);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
@@ -0,0 +1,45 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::{json};
+use log::{error};
+use uuid::Uuid;
+
+mod models;
+use crate::models::SeatingChart;
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// The event's tables with each guest's meal and allergies, plus who
+/// still needs a seat.
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let event_id : Uuid = match Uuid::parse_str(path_parameters.get("id").unwrap()) {
+        Ok(event_id) => event_id,
+        Err(_error) => return Ok(respond(404, json!({"message": "No event was found with that id"}).to_string()))
+    };
+
+    Ok(match SeatingChart::get(event_id) {
+        Ok(chart) => respond(200, json!(chart).to_string()),
+        Err(err) => {
+            error!("Could not build the seating chart for {}: {}", event_id, err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
@@ -0,0 +1,69 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_derive::Deserialize;
+use serde_json::{json};
+use std::ops::Deref;
+use log::{error};
+use uuid::Uuid;
+
+mod models;
+use crate::models::{Event, SeatingConstraint, ConstraintKind};
+
+#[derive(Debug, Deserialize)]
+struct ConstraintPayload {
+    kind: ConstraintKind,
+    rsvp_ids: Vec<String>,
+    #[serde(default)]
+    note: Option<String>
+}
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let event_id : Uuid = match Uuid::parse_str(path_parameters.get("id").unwrap()) {
+        Ok(event_id) => event_id,
+        Err(_error) => return Ok(respond(404, json!({"message": "No event was found with that id"}).to_string()))
+    };
+
+    let body = request.body().deref();
+    let payload : ConstraintPayload = match serde_json::from_slice(body) {
+        Ok(payload) => payload,
+        Err(error) => return Ok(respond(400, json!({"message": format!("Invalid seating constraint: {}", error)}).to_string()))
+    };
+
+    let event = match Event::get(event_id) {
+        Ok(event) => event,
+        Err(_error) => return Ok(respond(404, json!({"message": "No event was found with that id"}).to_string()))
+    };
+
+    let constraint = match SeatingConstraint::new(event.id, payload.kind, payload.rsvp_ids, payload.note) {
+        Ok(constraint) => constraint,
+        Err(message) => return Ok(respond(400, json!({"message": message}).to_string()))
+    };
+
+    Ok(match constraint.create() {
+        Ok(constraint) => respond(200, json!(constraint).to_string()),
+        Err(err) => {
+            error!("Could not create seating constraint {:?}: {}", constraint, err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
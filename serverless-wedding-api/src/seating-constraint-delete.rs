@@ -0,0 +1,46 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::{json};
+use log::{error};
+use uuid::Uuid;
+
+mod models;
+use crate::models::SeatingConstraint;
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let event_id = path_parameters.get("id").unwrap();
+    let not_found = json!({"message": "No seating constraint at this event was found with that id"}).to_string();
+    let uuid : Uuid = match Uuid::parse_str(path_parameters.get("constraint_id").unwrap()) {
+        Ok(uuid) => uuid,
+        Err(_error) => return Ok(respond(404, not_found))
+    };
+
+    Ok(match SeatingConstraint::delete(event_id, uuid) {
+        Ok(Some(constraint)) => respond(200, json!(constraint).to_string()),
+        Ok(None) => respond(404, not_found),
+        Err(err) => {
+            error!("Could not delete seating constraint {}: {}", uuid, err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
@@ -0,0 +1,66 @@
+extern crate log;
+extern crate simple_logger;
+
+use std::time::Duration;
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::{json};
+use log::{error};
+use uuid::Uuid;
+
+mod models;
+use crate::models::{SeatingProblem, SolverBudget, DEFAULT_EVALUATIONS, solve};
+
+/// Leaves room within the Lambda timeout to load the event and respond
+const MAX_BUDGET_MS : u64 = 4000;
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// Proposes a seating plan for the event without saving it, along with the
+/// soft constraints it breaks. ?evaluations= limits how many moves the
+/// solver tries, and ?budget_ms= how long it may run.
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let event_id : Uuid = match Uuid::parse_str(path_parameters.get("id").unwrap()) {
+        Ok(event_id) => event_id,
+        Err(_error) => return Ok(respond(404, json!({"message": "No event was found with that id"}).to_string()))
+    };
+
+    let budget : u64 = match request.query_string_parameters().get("budget_ms") {
+        Some(budget) => match budget.parse() {
+            Ok(budget) => u64::min(budget, MAX_BUDGET_MS),
+            Err(_error) => return Ok(respond(400, json!({"message": format!("Invalid budget: {}", budget)}).to_string()))
+        },
+        None => MAX_BUDGET_MS
+    };
+    let evaluations : u64 = match request.query_string_parameters().get("evaluations") {
+        Some(evaluations) => match evaluations.parse() {
+            Ok(evaluations) => u64::min(evaluations, DEFAULT_EVALUATIONS),
+            Err(_error) => return Ok(respond(400, json!({"message": format!("Invalid evaluations: {}", evaluations)}).to_string()))
+        },
+        None => DEFAULT_EVALUATIONS
+    };
+    let budget = SolverBudget { evaluations, time: Duration::from_millis(budget) };
+
+    Ok(match SeatingProblem::get(event_id) {
+        Ok(problem) => respond(200, json!(solve(&problem, budget)).to_string()),
+        Err(err) => {
+            error!("Could not load seating for {}: {}", event_id, err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
@@ -0,0 +1,96 @@
+extern crate log;
+extern crate simple_logger;
+
+use std::fs;
+use std::process;
+use std::time::Duration;
+use clap::{App, Arg};
+use uuid::Uuid;
+use log::{info, error};
+
+mod models;
+use crate::models::{SeatingProblem, SolverBudget, solve};
+
+fn run() -> Result<(), String> {
+    let matches = App::new("seating-solver")
+        .about("Proposes a seating plan for an event, from the live tables or a seating export")
+        .arg(Arg::with_name("input")
+            .long("input")
+            .value_name("FILE")
+            .help("Seating export to solve offline, as saved from /admin/events/{id}/seating-export")
+            .takes_value(true)
+            .required_unless("event"))
+        .arg(Arg::with_name("event")
+            .long("event")
+            .value_name("ID")
+            .help("Event to read from the tables instead")
+            .takes_value(true)
+            .conflicts_with("input"))
+        .arg(Arg::with_name("evaluations")
+            .long("evaluations")
+            .value_name("COUNT")
+            .help("How many candidate moves the solver may try while improving the plan"))
+        .arg(Arg::with_name("budget")
+            .long("budget-ms")
+            .value_name("MILLISECONDS")
+            .help("Stops the solver early if it runs this long, at the cost of a repeatable plan")
+            .default_value("5000"))
+        .arg(Arg::with_name("out")
+            .long("out")
+            .value_name("FILE")
+            .help("Write the plan here instead of printing it")
+            .takes_value(true))
+        .get_matches();
+
+    let budget : u64 = matches.value_of("budget").unwrap().parse()
+        .map_err(|_| String::from("--budget-ms must be a whole number of milliseconds"))?;
+    let evaluations : u64 = match matches.value_of("evaluations") {
+        Some(evaluations) => evaluations.parse()
+            .map_err(|_| String::from("--evaluations must be a whole number"))?,
+        None => SolverBudget::default().evaluations
+    };
+
+    let problem : SeatingProblem = match matches.value_of("input") {
+        Some(path) => {
+            let contents = fs::read_to_string(path).map_err(|err| format!("Could not read {}: {}", path, err))?;
+            serde_json::from_str(&contents).map_err(|err| format!("{} is not a seating export: {}", path, err))?
+        },
+        None => {
+            let id = matches.value_of("event").unwrap();
+            let uuid = Uuid::parse_str(id).map_err(|_| format!("Invalid event id: {}", id))?;
+            SeatingProblem::get(uuid).map_err(|err| err.to_string())?
+        }
+    };
+
+    let plan = solve(&problem, SolverBudget { evaluations, time: Duration::from_millis(budget) });
+    info!(
+        "Seated {} of {} guests with {} violations{}",
+        plan.seats.len(),
+        problem.guests.len(),
+        plan.violations.len(),
+        if plan.timed_out {
+            " before running out of time"
+        } else if !plan.converged {
+            " before running out of evaluations"
+        } else {
+            ""
+        }
+    );
+
+    let output = serde_json::to_string_pretty(&plan).unwrap();
+    match matches.value_of("out") {
+        Some(path) => fs::write(path, output).map_err(|err| format!("Could not write {}: {}", path, err))?,
+        None => println!("{}", output)
+    }
+
+    Ok(())
+}
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+
+    if let Err(message) = run() {
+        error!("{}", message);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,42 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::{json};
+use log::{error};
+
+mod models;
+use crate::models::ShuttleSignup;
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// Gives a household's seats on a shuttle run back.
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let household_id = path_parameters.get("id").unwrap();
+    let run_id = path_parameters.get("run_id").unwrap();
+
+    Ok(match ShuttleSignup::cancel(run_id, household_id) {
+        Ok(Some(signup)) => respond(200, json!(signup).to_string()),
+        Ok(None) => respond(404, json!({"message": "That household isn't signed up for this shuttle"}).to_string()),
+        Err(err) => {
+            error!("Could not cancel shuttle {} for household {}: {:?}", run_id, household_id, err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
@@ -0,0 +1,59 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_derive::Deserialize;
+use serde_json::{json};
+use std::ops::Deref;
+use chrono::NaiveDateTime;
+use log::{error};
+
+mod models;
+use crate::models::ShuttleRun;
+
+#[derive(Debug, Deserialize)]
+struct ShuttlePayload {
+    route: String,
+    departs_at: NaiveDateTime,
+    #[serde(default)]
+    pickup: String,
+    capacity: u32
+}
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let body = request.body().deref();
+    let payload : ShuttlePayload = match serde_json::from_slice(body) {
+        Ok(payload) => payload,
+        Err(error) => return Ok(respond(400, json!({"message": format!("Invalid shuttle: {}", error)}).to_string()))
+    };
+
+    let run = match ShuttleRun::new(payload.route, payload.departs_at, payload.pickup, payload.capacity) {
+        Ok(run) => run,
+        Err(message) => return Ok(respond(400, json!({"message": message}).to_string()))
+    };
+
+    Ok(match run.create() {
+        Ok(run) => respond(200, json!(run).to_string()),
+        Err(err) => {
+            error!("Could not create shuttle run {:?}: {}", run, err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
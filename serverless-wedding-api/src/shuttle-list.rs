@@ -0,0 +1,43 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::{json};
+use log::{error};
+
+mod models;
+use crate::models::{ShuttleRun, ShuttleStatus};
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+/// Every shuttle run in departure order, with the seats still open.
+fn handler(
+    _: Request,
+    _: Context,
+) -> Result<impl IntoResponse, HandlerError> {
+    Ok(match ShuttleRun::all() {
+        Ok(runs) => {
+            let statuses : Vec<ShuttleStatus> = runs.into_iter()
+                .map(|run| run.status())
+                .collect();
+
+            http::Response::builder()
+                .header("Access-Control-Allow-Origin", "*")
+                .status(200)
+                .body(json!(statuses).to_string())
+                .unwrap()
+        },
+        Err(err) => {
+            error!("Could not list shuttle runs: {}", err);
+            http::Response::builder()
+                .header("Access-Control-Allow-Origin", "*")
+                .status(500)
+                .body(json!({"message": "Something went wrong!"}).to_string())
+                .unwrap()
+        }
+    })
+}
@@ -0,0 +1,44 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::json;
+use log::{error};
+use uuid::Uuid;
+
+mod models;
+use crate::models::ShuttleManifest;
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// The households signed up for a run, for the driver.
+fn handler(
+    request: Request,
+    _: Context,
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let run_id : Uuid = match Uuid::parse_str(path_parameters.get("id").unwrap()) {
+        Ok(run_id) => run_id,
+        Err(_error) => return Ok(respond(404, json!({"message": "No shuttle was found with that id"}).to_string()))
+    };
+
+    Ok(match ShuttleManifest::get(run_id) {
+        Ok(manifest) => respond(200, json!(manifest).to_string()),
+        Err(err) => {
+            error!("Could not build the manifest for shuttle {}: {}", run_id, err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
@@ -0,0 +1,94 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_derive::Deserialize;
+use serde_json::{json};
+use std::ops::Deref;
+use log::{error};
+use uuid::Uuid;
+
+mod models;
+use crate::models::{Household, ShuttleSignup, ShuttleError};
+
+#[derive(Debug, Deserialize)]
+struct SignupPayload {
+    seats: u32
+}
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// Guests hold seats on a shuttle run from their RSVP link. Sending a new
+/// count replaces the old one.
+fn handler(
+    request: Request,
+    _: Context
+) -> Result<impl IntoResponse, HandlerError> {
+    let path_parameters = request.path_parameters();
+    let uuid : Uuid = match Uuid::parse_str(path_parameters.get("id").unwrap()) {
+        Ok(uuid) => uuid,
+        Err(_error) => return Ok(respond(404, json!({"message": "No household was found with that id"}).to_string()))
+    };
+    let run_id : Uuid = match Uuid::parse_str(path_parameters.get("run_id").unwrap()) {
+        Ok(run_id) => run_id,
+        Err(_error) => return Ok(respond(404, json!({"message": ShuttleError::RunNotFound.to_string()}).to_string()))
+    };
+
+    let body = request.body().deref();
+    let payload : SignupPayload = match serde_json::from_slice(body) {
+        Ok(payload) => payload,
+        Err(error) => return Ok(respond(400, json!({"message": format!("Invalid sign up: {}", error)}).to_string()))
+    };
+
+    match Household::get(uuid) {
+        Ok(ref rsvps) if rsvps.is_empty() => {
+            return Ok(respond(404, json!({"message": "No household was found with that id"}).to_string()))
+        },
+        Ok(_rsvps) => {},
+        Err(err) => {
+            error!("Could not look up household {}: {}", uuid, err);
+            return Ok(respond(500, json!({"message": "Something went wrong!"}).to_string()))
+        }
+    }
+
+    Ok(match ShuttleSignup::sign_up(run_id, &uuid.to_string(), payload.seats) {
+        Ok(signup) => respond(200, json!(signup).to_string()),
+        Err(ShuttleError::RunNotFound) => respond(404, json!({"message": ShuttleError::RunNotFound.to_string()}).to_string()),
+        Err(ShuttleError::Invalid(message)) => respond(400, json!({"message": message}).to_string()),
+        Err(ShuttleError::Departed(run)) => respond(403, json!({"message": ShuttleError::Departed(run).to_string()}).to_string()),
+        Err(ShuttleError::Full(run)) => respond(409, json!({"message": ShuttleError::Full(run).to_string()}).to_string()),
+        Err(ShuttleError::Changed) => respond(409, json!({"message": ShuttleError::Changed.to_string()}).to_string()),
+        Err(err) => {
+            error!("Could not sign household {} up for shuttle {}: {:?}", uuid, run_id, err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lambda_http::Body;
+
+    #[test]
+    #[ignore]
+    fn shuttle_signup_handler_handles() {
+
+        let mut request = Request::new(Body::from(json!({"seats": 2}).to_string()));
+        *request.uri_mut() = "https://api.slswedding.com/household/3eb28445-7698-4a00-b071-49da8eaac944/shuttles/5ba2b0fc-5fa1-4bd6-9d1c-2b6f3d3c8a57".parse().unwrap();
+
+        handler(request, Context::default()).expect("Expected an OK response");
+    }
+}
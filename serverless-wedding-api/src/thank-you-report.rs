@@ -0,0 +1,57 @@
+extern crate log;
+extern crate simple_logger;
+
+use lambda_http::{lambda, IntoResponse, http, Request, RequestExt};
+use lambda_runtime::{error::HandlerError, Context};
+use serde_json::json;
+use log::{error};
+
+mod models;
+use crate::models::ThankYouReport;
+
+fn main() {
+    simple_logger::init_with_level(log::Level::Info).unwrap();
+    lambda!(handler)
+}
+
+fn respond(status: u16, body: String) -> http::Response<String> {
+    http::Response::builder()
+        .header("Access-Control-Allow-Origin", "*")
+        .status(status)
+        .body(body)
+        .unwrap()
+}
+
+/// Every thank-you note still to write or mail, oldest gift first, with
+/// where to send it. `?format=csv` gives just the rows, to print or work
+/// through in a spreadsheet.
+fn handler(
+    request: Request,
+    _: Context,
+) -> Result<impl IntoResponse, HandlerError> {
+    let as_csv = request.query_string_parameters().get("format") == Some("csv");
+
+    let report = ThankYouReport::get();
+    let body = report.and_then(|report| {
+        if as_csv {
+            report.csv()
+        } else {
+            Ok(json!(report).to_string())
+        }
+    });
+
+    Ok(match body {
+        Ok(body) => {
+            http::Response::builder()
+                .header("Access-Control-Allow-Origin", "*")
+                .header("Content-Type", if as_csv { "text/csv" } else { "application/json" })
+                .status(200)
+                .body(body)
+                .unwrap()
+        },
+        Err(err) => {
+            error!("Could not build the thank-you report: {}", err);
+            respond(500, json!({"message": "Something went wrong!"}).to_string())
+        }
+    })
+}
@@ -6,14 +6,35 @@ export const state = () => ({
     status_code: null,
     message: null
   },
-  household: []
+  household: [],
+  events: [],
+  deadline: null,
+  // Guests whose travel plans were edited, by id
+  travel_changed: [],
+  details: {
+    salutation: ''
+  },
+  address: {
+    line1: '',
+    line2: '',
+    city: '',
+    region: '',
+    postal_code: '',
+    country: 'US'
+  },
+  address_error: null,
+  menu: {
+    meals: [],
+    dietary_restrictions: []
+  }
 })
 
 const editableKeys = [
   'attending',
   'dietary_restrictions',
   'dietary_restrictions_other',
-  'food_preference'
+  'food_preference',
+  'travel'
 ]
 
 function set_person_state(state, id, callback) {
@@ -22,6 +43,11 @@ function set_person_state(state, id, callback) {
   return callback(person)
 }
 
+// Only travel plans can change once the RSVP deadline has passed
+export function deadline_passed(state) {
+  return state.deadline != null && new Date() > new Date(state.deadline)
+}
+
 function get_patch_rsvp_request(axios, rsvp) {
   const payload = editableKeys.reduce((memo, item) => {
     if (rsvp.hasOwnProperty(item)) {
@@ -47,7 +73,14 @@ export const mutations = {
       fetching: false,
       status_code: 200
     }
-    state.household = response
+    state.household = response.household
+    state.events = response.events
+    state.deadline = response.deadline
+    state.travel_changed = []
+    state.details = response.details
+    if (response.details.address) {
+      state.address = response.details.address
+    }
   },
 
   fetch_household_failure(state) {
@@ -71,14 +104,36 @@ export const mutations = {
     state.household = response
   },
 
-  patch_household_failure(state, error) {
+  patch_household_failure(state, { status_code, message }) {
     state.request = {
       fetching: false,
-      status_code: 500,
-      message: 'Something went wrong'
+      status_code,
+      message
+    }
+  },
+
+  set_address_field(state, { field, value }) {
+    if (field == 'salutation') {
+      state.details.salutation = value
+    } else {
+      state.address[field] = value
     }
   },
 
+  put_address_success(state, response) {
+    state.details = response
+    state.address = response.address
+    state.address_error = null
+  },
+
+  put_address_failure(state, message) {
+    state.address_error = message
+  },
+
+  fetch_menu_success(state, response) {
+    state.menu = response
+  },
+
   toggle_attending(state, { id, attending }) {
     set_person_state(state, id, person => {
       person.attending = attending
@@ -100,6 +155,15 @@ export const mutations = {
     })
   },
 
+  set_travel_field(state, { id, field, value }) {
+    set_person_state(state, id, person => {
+      person.travel = { ...person.travel, [field]: value }
+    })
+    if (!state.travel_changed.includes(id)) {
+      state.travel_changed.push(id)
+    }
+  },
+
   set_food_preference(state, { id, value }) {
     set_person_state(state, id, person => {
       person.food_preference = value
@@ -121,17 +185,74 @@ export const actions = {
       })
   },
 
-  patch_household({ commit }, household) {
+  fetch_menu({ commit }) {
+    return this.$axios.$get(`${API_URL_ROOT}/menu`).then(response => {
+      commit('fetch_menu_success', response)
+    })
+  },
+
+  put_address({ commit, state }, householdId) {
+    // Guests can skip the address until they have one to give
+    if (!state.address.line1) {
+      return Promise.resolve()
+    }
+    return this.$axios
+      .$put(
+        `${API_URL_ROOT}/household/${householdId}/address`,
+        {
+          salutation: state.details.salutation,
+          address: state.address
+        },
+        {
+          headers: {
+            'Content-Type': 'application/json'
+          }
+        }
+      )
+      .then(response => {
+        commit('put_address_success', response)
+      })
+      .catch(error => {
+        const message =
+          error.response && error.response.data
+            ? error.response.data.message
+            : 'Something went wrong'
+        commit('put_address_failure', message)
+        throw error
+      })
+  },
+
+  patch_household({ commit, state }, household) {
     commit('patch_household_request')
-    const requests = household.map(rsvp =>
-      get_patch_rsvp_request(this.$axios, rsvp)
-    )
-    Promise.all(requests)
+    const closed = deadline_passed(state)
+    const requests = household.map(rsvp => {
+      if (!closed) {
+        return get_patch_rsvp_request(this.$axios, rsvp)
+      }
+      // The API turns away anything but travel after the deadline, so
+      // only send travel, and only for guests who changed it
+      if (!state.travel_changed.includes(rsvp.id)) {
+        return Promise.resolve(rsvp)
+      }
+      return get_patch_rsvp_request(this.$axios, {
+        id: rsvp.id,
+        travel: rsvp.travel
+      })
+    })
+    return Promise.all(requests)
       .then(responses => {
         commit('patch_household_success', responses)
       })
       .catch(error => {
-        commit('patch_household_failure', error)
+        const response = error.response || {}
+        commit('patch_household_failure', {
+          status_code: response.status || 500,
+          message:
+            response.data && response.data.message
+              ? response.data.message
+              : 'Something went wrong'
+        })
+        throw error
       })
   }
 }
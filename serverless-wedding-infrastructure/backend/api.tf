@@ -14,6 +14,26 @@ data "template_file" "serverless_wedding_api_iam_policy" {
     vars = {
         rsvp_table_arn = "${aws_dynamodb_table.rsvp_table.arn}"
         rsvp_table_id_index_path = "${aws_dynamodb_table.rsvp_table.arn}/index/${local.rsvp_table_id_index_name}"
+        event_table_arn = "${aws_dynamodb_table.event_table.arn}"
+        change_request_table_arn = "${aws_dynamodb_table.change_request_table.arn}"
+        household_table_arn = "${aws_dynamodb_table.household_table.arn}"
+        menu_table_arn = "${aws_dynamodb_table.menu_table.arn}"
+        seating_table_arn = "${aws_dynamodb_table.seating_table.arn}"
+        seat_assignment_table_arn = "${aws_dynamodb_table.seat_assignment_table.arn}"
+        seating_constraint_table_arn = "${aws_dynamodb_table.seating_constraint_table.arn}"
+        hotel_table_arn = "${aws_dynamodb_table.hotel_table.arn}"
+        lodging_choice_table_arn = "${aws_dynamodb_table.lodging_choice_table.arn}"
+        shuttle_run_table_arn = "${aws_dynamodb_table.shuttle_run_table.arn}"
+        shuttle_signup_table_arn = "${aws_dynamodb_table.shuttle_signup_table.arn}"
+        carpool_table_arn = "${aws_dynamodb_table.carpool_table.arn}"
+        activity_table_arn = "${aws_dynamodb_table.activity_table.arn}"
+        activity_signup_table_arn = "${aws_dynamodb_table.activity_signup_table.arn}"
+        registry_item_table_arn = "${aws_dynamodb_table.registry_item_table.arn}"
+        contribution_table_arn = "${aws_dynamodb_table.contribution_table.arn}"
+        gift_table_arn = "${aws_dynamodb_table.gift_table.arn}"
+        reservation_table_arn = "${aws_dynamodb_table.reservation_table.arn}"
+        email_template_table_arn = "${aws_dynamodb_table.email_template_table.arn}"
+        rsvp_update_topic_arn = "${aws_sns_topic.rsvp_update_notifications.arn}"
     }
 }
 
@@ -10,7 +10,7 @@ resource "aws_dynamodb_table" "rsvp_table" {
     hash_key = "household_id"
     range_key = "name"
     stream_enabled = true
-    stream_view_type = "NEW_IMAGE"
+    stream_view_type = "NEW_AND_OLD_IMAGES"
 
     global_secondary_index {
         name               = "${local.rsvp_table_id_index_name}"
@@ -34,4 +34,268 @@ resource "aws_dynamodb_table" "rsvp_table" {
         name = "id"
         type = "S"
     }
+}
+
+resource "aws_dynamodb_table" "event_table" {
+    name = "event-${var.environment_code}-table"
+    read_capacity = 5
+    write_capacity = 5
+    hash_key = "id"
+
+    attribute {
+        name = "id"
+        type = "S"
+    }
+}
+
+resource "aws_dynamodb_table" "change_request_table" {
+    name = "change-request-${var.environment_code}-table"
+    read_capacity = 5
+    write_capacity = 5
+    hash_key = "id"
+
+    attribute {
+        name = "id"
+        type = "S"
+    }
+}
+
+resource "aws_dynamodb_table" "household_table" {
+    name = "household-${var.environment_code}-table"
+    read_capacity = 5
+    write_capacity = 5
+    hash_key = "id"
+
+    attribute {
+        name = "id"
+        type = "S"
+    }
+}
+
+resource "aws_dynamodb_table" "menu_table" {
+    name = "menu-${var.environment_code}-table"
+    read_capacity = 5
+    write_capacity = 5
+    hash_key = "code"
+
+    attribute {
+        name = "code"
+        type = "S"
+    }
+}
+
+resource "aws_dynamodb_table" "seating_table" {
+    name = "seating-${var.environment_code}-table"
+    read_capacity = 5
+    write_capacity = 5
+    hash_key = "id"
+
+    attribute {
+        name = "id"
+        type = "S"
+    }
+}
+
+resource "aws_dynamodb_table" "seat_assignment_table" {
+    name = "seat-assignment-${var.environment_code}-table"
+    read_capacity = 5
+    write_capacity = 5
+    hash_key = "event_id"
+    range_key = "rsvp_id"
+
+    attribute {
+        name = "event_id"
+        type = "S"
+    }
+
+    attribute {
+        name = "rsvp_id"
+        type = "S"
+    }
+}
+
+resource "aws_dynamodb_table" "seating_constraint_table" {
+    name = "seating-constraint-${var.environment_code}-table"
+    read_capacity = 5
+    write_capacity = 5
+    hash_key = "id"
+
+    attribute {
+        name = "id"
+        type = "S"
+    }
+}
+
+resource "aws_dynamodb_table" "hotel_table" {
+    name = "hotel-${var.environment_code}-table"
+    read_capacity = 5
+    write_capacity = 5
+    hash_key = "id"
+
+    attribute {
+        name = "id"
+        type = "S"
+    }
+}
+
+resource "aws_dynamodb_table" "lodging_choice_table" {
+    name = "lodging-choice-${var.environment_code}-table"
+    read_capacity = 5
+    write_capacity = 5
+    hash_key = "household_id"
+
+    attribute {
+        name = "household_id"
+        type = "S"
+    }
+}
+
+resource "aws_dynamodb_table" "shuttle_run_table" {
+    name = "shuttle-run-${var.environment_code}-table"
+    read_capacity = 5
+    write_capacity = 5
+    hash_key = "id"
+
+    attribute {
+        name = "id"
+        type = "S"
+    }
+}
+
+resource "aws_dynamodb_table" "shuttle_signup_table" {
+    name = "shuttle-signup-${var.environment_code}-table"
+    read_capacity = 5
+    write_capacity = 5
+    hash_key = "run_id"
+    range_key = "household_id"
+
+    attribute {
+        name = "run_id"
+        type = "S"
+    }
+
+    attribute {
+        name = "household_id"
+        type = "S"
+    }
+}
+
+resource "aws_dynamodb_table" "carpool_table" {
+    name = "carpool-${var.environment_code}-table"
+    read_capacity = 5
+    write_capacity = 5
+    hash_key = "rsvp_id"
+
+    attribute {
+        name = "rsvp_id"
+        type = "S"
+    }
+}
+
+resource "aws_dynamodb_table" "activity_table" {
+    name = "activity-${var.environment_code}-table"
+    read_capacity = 5
+    write_capacity = 5
+    hash_key = "id"
+
+    attribute {
+        name = "id"
+        type = "S"
+    }
+}
+
+resource "aws_dynamodb_table" "activity_signup_table" {
+    name = "activity-signup-${var.environment_code}-table"
+    read_capacity = 5
+    write_capacity = 5
+    hash_key = "activity_id"
+    range_key = "rsvp_id"
+
+    attribute {
+        name = "activity_id"
+        type = "S"
+    }
+
+    attribute {
+        name = "rsvp_id"
+        type = "S"
+    }
+}
+
+resource "aws_dynamodb_table" "registry_item_table" {
+    name = "registry-item-${var.environment_code}-table"
+    read_capacity = 5
+    write_capacity = 5
+    hash_key = "id"
+
+    attribute {
+        name = "id"
+        type = "S"
+    }
+}
+
+resource "aws_dynamodb_table" "contribution_table" {
+    name = "contribution-${var.environment_code}-table"
+    read_capacity = 5
+    write_capacity = 5
+    hash_key = "item_id"
+    range_key = "id"
+
+    attribute {
+        name = "item_id"
+        type = "S"
+    }
+
+    attribute {
+        name = "id"
+        type = "S"
+    }
+}
+
+resource "aws_dynamodb_table" "gift_table" {
+    name = "gift-${var.environment_code}-table"
+    read_capacity = 5
+    write_capacity = 5
+    hash_key = "household_id"
+    range_key = "id"
+
+    attribute {
+        name = "household_id"
+        type = "S"
+    }
+
+    attribute {
+        name = "id"
+        type = "S"
+    }
+}
+
+resource "aws_dynamodb_table" "reservation_table" {
+    name = "reservation-${var.environment_code}-table"
+    read_capacity = 5
+    write_capacity = 5
+    hash_key = "item_id"
+    range_key = "household_id"
+
+    attribute {
+        name = "item_id"
+        type = "S"
+    }
+
+    attribute {
+        name = "household_id"
+        type = "S"
+    }
+}
+
+resource "aws_dynamodb_table" "email_template_table" {
+    name = "email_template-${var.environment_code}-table"
+    read_capacity = 5
+    write_capacity = 5
+    hash_key = "kind"
+
+    attribute {
+        name = "kind"
+        type = "S"
+    }
 }
\ No newline at end of file
@@ -15,6 +15,82 @@ output "rsvp_table_stream_arn" {
     value = "${aws_dynamodb_table.rsvp_table.stream_arn}"
 }
 
+output "event_table_name" {
+    value = "${aws_dynamodb_table.event_table.name}"
+}
+
+output "change_request_table_name" {
+    value = "${aws_dynamodb_table.change_request_table.name}"
+}
+
+output "household_table_name" {
+    value = "${aws_dynamodb_table.household_table.name}"
+}
+
+output "menu_table_name" {
+    value = "${aws_dynamodb_table.menu_table.name}"
+}
+
+output "seating_table_name" {
+    value = "${aws_dynamodb_table.seating_table.name}"
+}
+
+output "seat_assignment_table_name" {
+    value = "${aws_dynamodb_table.seat_assignment_table.name}"
+}
+
+output "seating_constraint_table_name" {
+    value = "${aws_dynamodb_table.seating_constraint_table.name}"
+}
+
+output "hotel_table_name" {
+    value = "${aws_dynamodb_table.hotel_table.name}"
+}
+
+output "lodging_choice_table_name" {
+    value = "${aws_dynamodb_table.lodging_choice_table.name}"
+}
+
+output "shuttle_run_table_name" {
+    value = "${aws_dynamodb_table.shuttle_run_table.name}"
+}
+
+output "shuttle_signup_table_name" {
+    value = "${aws_dynamodb_table.shuttle_signup_table.name}"
+}
+
+output "carpool_table_name" {
+    value = "${aws_dynamodb_table.carpool_table.name}"
+}
+
+output "activity_table_name" {
+    value = "${aws_dynamodb_table.activity_table.name}"
+}
+
+output "activity_signup_table_name" {
+    value = "${aws_dynamodb_table.activity_signup_table.name}"
+}
+
+output "registry_item_table_name" {
+    value = "${aws_dynamodb_table.registry_item_table.name}"
+}
+
+output "contribution_table_name" {
+    value = "${aws_dynamodb_table.contribution_table.name}"
+}
+
+output "gift_table_name" {
+    value = "${aws_dynamodb_table.gift_table.name}"
+}
+
+output "reservation_table_name" {
+    value = "${aws_dynamodb_table.reservation_table.name}"
+}
+
+output "email_template_table_name" {
+    value = "${aws_dynamodb_table.email_template_table.name}"
+}
+
 output "api_role_arn" {
     value = "${aws_iam_role.serverless_wedding_api_iam_role.arn}"
 }
@@ -13,6 +13,7 @@ data "template_file" "serverless_wedding_rsvp_handler_iam_policy" {
 
     vars = {
         rsvp_table_stream_arn = "${aws_dynamodb_table.rsvp_table.stream_arn}"
+        event_table_arn = "${aws_dynamodb_table.event_table.arn}"
         rsvp_handler_sns_topic = "${aws_sns_topic.rsvp_update_notifications.arn}"
     }
 }
@@ -27,6 +27,82 @@ output "rsvp_table_stream_arn" {
   value = "${module.backend.rsvp_table_stream_arn}"
 }
 
+output "event_table_name" {
+    value = "${module.backend.event_table_name}"
+}
+
+output "change_request_table_name" {
+    value = "${module.backend.change_request_table_name}"
+}
+
+output "household_table_name" {
+    value = "${module.backend.household_table_name}"
+}
+
+output "menu_table_name" {
+    value = "${module.backend.menu_table_name}"
+}
+
+output "seating_table_name" {
+    value = "${module.backend.seating_table_name}"
+}
+
+output "seat_assignment_table_name" {
+    value = "${module.backend.seat_assignment_table_name}"
+}
+
+output "seating_constraint_table_name" {
+    value = "${module.backend.seating_constraint_table_name}"
+}
+
+output "hotel_table_name" {
+    value = "${module.backend.hotel_table_name}"
+}
+
+output "lodging_choice_table_name" {
+    value = "${module.backend.lodging_choice_table_name}"
+}
+
+output "shuttle_run_table_name" {
+    value = "${module.backend.shuttle_run_table_name}"
+}
+
+output "shuttle_signup_table_name" {
+    value = "${module.backend.shuttle_signup_table_name}"
+}
+
+output "carpool_table_name" {
+    value = "${module.backend.carpool_table_name}"
+}
+
+output "activity_table_name" {
+    value = "${module.backend.activity_table_name}"
+}
+
+output "activity_signup_table_name" {
+    value = "${module.backend.activity_signup_table_name}"
+}
+
+output "registry_item_table_name" {
+    value = "${module.backend.registry_item_table_name}"
+}
+
+output "contribution_table_name" {
+    value = "${module.backend.contribution_table_name}"
+}
+
+output "gift_table_name" {
+    value = "${module.backend.gift_table_name}"
+}
+
+output "reservation_table_name" {
+    value = "${module.backend.reservation_table_name}"
+}
+
+output "email_template_table_name" {
+    value = "${module.backend.email_template_table_name}"
+}
+
 output "api_role_arn" {
     value = "${module.backend.api_role_arn}"
 }